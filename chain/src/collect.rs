@@ -27,6 +27,23 @@ pub struct Chains {
 
     /// Contains all image chains.
     pub images: ImageChains,
+
+    /// Contains explicit dependencies between nodes.
+    pub dependencies: Vec<Dependency>,
+}
+
+/// Explicit dependency between two nodes.
+/// Unlike resource chains it carries no memory access,
+/// only the order of execution.
+#[derive(Clone, Debug)]
+pub struct Dependency {
+    /// Submission of the dependency node to the submission of the dependent node.
+    pub submissions: Range<SubmissionId>,
+
+    /// Stages at which dependent node waits for the dependency.
+    /// Union of stages at which dependent node accesses its resources,
+    /// or `TOP_OF_PIPE` if it declares none.
+    pub stages: rendy_core::hal::pso::PipelineStage,
 }
 
 #[derive(PartialEq, PartialOrd, Eq, Ord)]
@@ -115,12 +132,13 @@ where
         }
     }
 
+    let mut submissions = vec![None; nodes.nodes.len()];
     let mut scheduled = 0;
     if nodes.queues.len() == 1 {
         // With a single queue, wait_factor is always the number of scheduled nodes, and
         // transfers is always zero. Thus, we only need dependency resolution.
         while let Some(node) = ready_nodes.pop() {
-            schedule_node(
+            submissions[node.id] = Some(schedule_node(
                 &mut ready_nodes,
                 &mut unscheduled_nodes,
                 &nodes,
@@ -131,7 +149,7 @@ where
                 &mut schedule,
                 &mut images,
                 &mut buffers,
-            );
+            ));
             scheduled += 1;
         }
     } else {
@@ -148,7 +166,7 @@ where
                 .unwrap();
 
             let node = ready_nodes.swap_remove(index);
-            schedule_node(
+            submissions[node.id] = Some(schedule_node(
                 &mut ready_nodes,
                 &mut unscheduled_nodes,
                 &nodes,
@@ -159,7 +177,7 @@ where
                 &mut schedule,
                 &mut images,
                 &mut buffers,
-            );
+            ));
             scheduled += 1;
         }
     }
@@ -169,6 +187,7 @@ where
         schedule: reify_schedule(schedule),
         buffers: reify_chain(&nodes.buffers, buffers),
         images: reify_chain(&nodes.images, images),
+        dependencies: reify_dependencies(&nodes, &submissions),
     }
 }
 
@@ -249,13 +268,16 @@ where
         reified_nodes[id].id = id;
         reified_nodes[id].family = node.family;
         reified_nodes[id].queues = family_full[&family].clone();
-        reified_nodes[id].buffers = node
-            .buffers
+        // Resources are numbered in order of ids regardless of `HashMap` iteration order.
+        let mut node_buffers: Vec<_> = node.buffers.into_iter().collect();
+        node_buffers.sort_unstable_by_key(|&(k, _)| k);
+        reified_nodes[id].buffers = node_buffers
             .into_iter()
             .map(|(k, v)| (buffers.forward(k), v))
             .collect();
-        reified_nodes[id].images = node
-            .images
+        let mut node_images: Vec<_> = node.images.into_iter().collect();
+        node_images.sort_unstable_by_key(|&(k, _)| k);
+        reified_nodes[id].images = node_images
             .into_iter()
             .map(|(k, v)| (images.forward(k), v))
             .collect();
@@ -288,6 +310,37 @@ fn reify_schedule(vec: Vec<QueueData>) -> Schedule<Unsynchronized> {
     schedule
 }
 
+fn reify_dependencies(
+    nodes: &ResolvedNodeSet,
+    submissions: &[Option<SubmissionId>],
+) -> Vec<Dependency> {
+    let mut dependencies = Vec::new();
+    for node in &nodes.nodes {
+        let start = submissions[node.id].unwrap();
+        for &rev_dep in &node.rev_deps {
+            let dependent = &nodes.nodes[rev_dep];
+            let stages = dependent
+                .buffers
+                .iter()
+                .map(|(_, state)| state.stages)
+                .chain(dependent.images.iter().map(|(_, state)| state.stages))
+                .fold(
+                    rendy_core::hal::pso::PipelineStage::empty(),
+                    |acc, stages| acc | stages,
+                );
+            dependencies.push(Dependency {
+                submissions: start..submissions[rev_dep].unwrap(),
+                stages: if stages.is_empty() {
+                    rendy_core::hal::pso::PipelineStage::TOP_OF_PIPE
+                } else {
+                    stages
+                },
+            });
+        }
+    }
+    dependencies
+}
+
 fn fitness(
     node: &ResolvedNode,
     images: &mut Vec<ChainData<Image>>,
@@ -346,7 +399,7 @@ fn schedule_node<'a>(
     schedule: &mut Vec<QueueData>,
     images: &mut Vec<ChainData<Image>>,
     buffers: &mut Vec<ChainData<Buffer>>,
-) {
+) -> SubmissionId {
     let queue_data = &mut schedule[queue];
     queue_data.wait_factor = max(queue_data.wait_factor, wait_factor + 1);
     let sid = queue_data
@@ -383,6 +436,8 @@ fn schedule_node<'a>(
             ready_nodes.push(&nodes.nodes[rev_dep]);
         }
     }
+
+    sid
 }
 
 fn add_to_chain<R, S>(
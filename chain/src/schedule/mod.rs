@@ -12,7 +12,7 @@ mod family;
 mod queue;
 mod submission;
 
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::ops::{Index, IndexMut};
 
 pub use self::{
@@ -22,9 +22,10 @@ pub use self::{
 };
 
 /// Whole passes schedule.
+/// Families are kept sorted by id so that iteration order is stable.
 #[derive(Clone, Debug)]
 pub struct Schedule<S> {
-    map: HashMap<rendy_core::hal::queue::QueueFamilyId, Family<S>>,
+    map: BTreeMap<usize, Family<S>>,
     ordered: Vec<SubmissionId>,
 }
 
@@ -32,7 +33,7 @@ impl<S> Schedule<S> {
     /// Create new empty `Schedule`
     pub fn new() -> Self {
         Schedule {
-            map: HashMap::default(),
+            map: BTreeMap::new(),
             ordered: Vec::new(),
         }
     }
@@ -48,7 +49,7 @@ impl<S> Schedule<S> {
 
         self.ordered
             .iter()
-            .map(move |&sid| map[&sid.family()].submission(sid).unwrap())
+            .map(move |&sid| map[&sid.family().0].submission(sid).unwrap())
     }
 
     /// The number of families in this schedule.
@@ -73,7 +74,7 @@ impl<S> Schedule<S> {
 
     /// Get reference to `Family` instance by the id.
     pub fn family(&self, fid: rendy_core::hal::queue::QueueFamilyId) -> Option<&Family<S>> {
-        self.map.get(&fid)
+        self.map.get(&fid.0)
     }
 
     /// Get mutable reference to `Family` instance by the id.
@@ -81,7 +82,7 @@ impl<S> Schedule<S> {
         &mut self,
         fid: rendy_core::hal::queue::QueueFamilyId,
     ) -> Option<&mut Family<S>> {
-        self.map.get_mut(&fid)
+        self.map.get_mut(&fid.0)
     }
 
     /// Get reference to `Queue` instance by the id.
@@ -111,7 +112,7 @@ impl<S> Schedule<S> {
     /// Get mutable reference to `Family` instance by the id.
     /// This function will add empty `Family` if id is not present.
     pub fn ensure_family(&mut self, fid: rendy_core::hal::queue::QueueFamilyId) -> &mut Family<S> {
-        self.map.entry(fid).or_insert_with(|| Family::new(fid))
+        self.map.entry(fid.0).or_insert_with(|| Family::new(fid))
     }
 
     /// Get mutable reference to `Queue` instance by the id.
@@ -6,7 +6,7 @@ use std::ops::{Range, RangeFrom, RangeTo};
 
 use crate::{
     chain::{Chain, Link},
-    collect::Chains,
+    collect::{Chains, Dependency},
     node::State,
     resource::{AccessFlags, Buffer, Image, Resource},
     schedule::{Queue, QueueId, Schedule, SubmissionId},
@@ -16,14 +16,15 @@ use crate::{
 /// Semaphore identifier.
 /// It allows to distinguish different semaphores to be later replaced in `Signal`s and `Wait`s
 /// for references to semaphores (or tokens associated with real semaphores).
+/// `id` is `None` for semaphores generated from explicit dependencies between nodes.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 struct Semaphore {
-    id: Id,
+    id: Option<Id>,
     points: Range<SubmissionId>,
 }
 
 impl Semaphore {
-    fn new(id: Id, points: Range<SubmissionId>) -> Self {
+    fn new(id: Option<Id>, points: Range<SubmissionId>) -> Self {
         Semaphore { id, points }
     }
 }
@@ -266,12 +267,21 @@ where
     let buffers = &chains.buffers;
     let images = &chains.images;
 
+    // Chains are visited in order of ids so that the same graph
+    // always yields the same barriers and semaphores.
     let mut sync = SyncTemp(HashMap::default());
-    for (&id, chain) in buffers {
-        sync_chain(id, chain, schedule, &mut sync);
+    let mut buffer_ids: Vec<_> = buffers.keys().copied().collect();
+    buffer_ids.sort_unstable();
+    for id in buffer_ids {
+        sync_chain(id, &buffers[&id], schedule, &mut sync);
+    }
+    let mut image_ids: Vec<_> = images.keys().copied().collect();
+    image_ids.sort_unstable();
+    for id in image_ids {
+        sync_chain(id, &images[&id], schedule, &mut sync);
     }
-    for (&id, chain) in images {
-        sync_chain(id, chain, schedule, &mut sync);
+    for dependency in &chains.dependencies {
+        sync_dependency(dependency, &mut sync);
     }
     if schedule.queue_count() > 1 {
         optimize(schedule, &mut sync);
@@ -358,7 +368,7 @@ fn generate_semaphore_pair<R: Resource>(
     range: Range<SubmissionId>,
 ) {
     if range.start.queue() != range.end.queue() {
-        let semaphore = Semaphore::new(id, range.clone());
+        let semaphore = Semaphore::new(Some(id), range.clone());
         sync.get_sync(range.start)
             .signal
             .push(Signal::new(semaphore.clone()));
@@ -458,6 +468,20 @@ where
     }
 }
 
+// Dependencies on the same queue are satisfied by submission order.
+fn sync_dependency(dependency: &Dependency, sync: &mut SyncTemp) {
+    let range = dependency.submissions.clone();
+    if range.start.queue() != range.end.queue() {
+        let semaphore = Semaphore::new(None, range.clone());
+        sync.get_sync(range.start)
+            .signal
+            .push(Signal::new(semaphore.clone()));
+        sync.get_sync(range.end)
+            .wait
+            .push(Wait::new(semaphore, dependency.stages));
+    }
+}
+
 fn optimize_submission(
     sid: SubmissionId,
     found: &mut HashMap<QueueId, usize>,
@@ -505,3 +529,95 @@ fn optimize<S>(schedule: &Schedule<S>, sync: &mut SyncTemp) {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::{collect, BufferState, Node},
+        rendy_core::hal::{
+            buffer::{Access, Usage},
+            pso::PipelineStage,
+            queue::QueueFamilyId,
+        },
+    };
+
+    fn node(id: usize, family: usize, dependencies: Vec<usize>) -> Node {
+        Node {
+            id,
+            family: QueueFamilyId(family),
+            dependencies,
+            buffers: (0..8)
+                .map(|index| {
+                    (
+                        Id(index),
+                        BufferState {
+                            access: Access::SHADER_WRITE,
+                            stages: PipelineStage::COMPUTE_SHADER,
+                            layout: (),
+                            usage: Usage::STORAGE,
+                        },
+                    )
+                })
+                .collect(),
+            images: HashMap::default(),
+        }
+    }
+
+    type Plan = Vec<(usize, Vec<Wait<usize>>, Vec<Signal<usize>>)>;
+
+    fn plan() -> Plan {
+        let chains = collect(
+            vec![node(0, 0, vec![]), node(1, 1, vec![0]), node(2, 0, vec![1])],
+            |_| 1,
+        );
+        let mut semaphores = 0..;
+        let mut schedule = sync(&chains, || {
+            let id = semaphores.next().unwrap();
+            (id, id)
+        });
+        schedule.build_order();
+        schedule
+            .ordered()
+            .map(|submission| {
+                (
+                    submission.node(),
+                    submission.sync().wait.clone(),
+                    submission.sync().signal.clone(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_dependency_without_resources() {
+        let mut nodes = vec![node(0, 0, vec![]), node(1, 1, vec![0])];
+        for node in &mut nodes {
+            node.buffers.clear();
+        }
+        let chains = collect(nodes, |_| 1);
+        let mut semaphores = 0..;
+        let schedule = sync(&chains, || {
+            let id = semaphores.next().unwrap();
+            (id, id)
+        });
+        let submission = schedule
+            .iter()
+            .flat_map(|family| family.iter())
+            .flat_map(|queue| queue.iter())
+            .find(|submission| submission.node() == 1)
+            .unwrap();
+        assert_eq!(
+            submission.sync().wait,
+            vec![Wait::new(0, PipelineStage::TOP_OF_PIPE)]
+        );
+    }
+
+    #[test]
+    fn test_sync_is_deterministic() {
+        let expected = plan();
+        for _ in 0..16 {
+            assert_eq!(plan(), expected);
+        }
+    }
+}
@@ -7,8 +7,10 @@ use {
         CommandBuffer,
     },
     crate::{
-        capability::{Capability, Compute, Graphics, Supports, Transfer},
+        capability::{Capability, Compute, Execute, Graphics, Supports, Transfer},
+        event::Event,
         family::FamilyId,
+        query::{result_stride, QueryPool, QueryType},
     },
 };
 
@@ -67,7 +69,149 @@ pub struct DispatchCommand {
     pub z: u32,
 }
 
+/// Error returned when pushed constants don't match push constant ranges
+/// of the pipeline layout.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PushConstantsError {
+    /// Offset is not multiple of 4.
+    Misaligned(u32),
+    /// Pushed bytes are not within a range declared for the stage.
+    OutOfRange {
+        /// Stage which doesn't declare the range.
+        stage: rendy_core::hal::pso::ShaderStageFlags,
+        /// Pushed bytes.
+        range: std::ops::Range<u32>,
+    },
+    /// Pushed bytes overlap declared range but stages don't include all its stages.
+    StagesMismatch {
+        /// Stages specified for push.
+        stages: rendy_core::hal::pso::ShaderStageFlags,
+        /// Stages of the overlapped range.
+        expected: rendy_core::hal::pso::ShaderStageFlags,
+    },
+}
+
+impl std::fmt::Display for PushConstantsError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PushConstantsError::Misaligned(offset) => {
+                write!(fmt, "Push constants offset {} is not multiple of 4", offset)
+            }
+            PushConstantsError::OutOfRange { stage, range } => write!(
+                fmt,
+                "Push constants bytes {:?} are not declared for stage {:?}",
+                range, stage
+            ),
+            PushConstantsError::StagesMismatch { stages, expected } => write!(
+                fmt,
+                "Push constants stages {:?} don't include stages {:?} of overlapped range",
+                stages, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PushConstantsError {}
+
+/// Check that pushing `constants` at `offset` for `stages` is valid
+/// for pipeline layout with specified push constant `ranges`.
+///
+/// Every stage must have a declared range that contains pushed bytes
+/// and every declared range overlapped by pushed bytes
+/// must have all its stages included in `stages`.
+pub fn validate_push_constants(
+    ranges: &[(rendy_core::hal::pso::ShaderStageFlags, std::ops::Range<u32>)],
+    stages: rendy_core::hal::pso::ShaderStageFlags,
+    offset: u32,
+    constants: &[u32],
+) -> Result<(), PushConstantsError> {
+    if offset & 3 != 0 {
+        return Err(PushConstantsError::Misaligned(offset));
+    }
+
+    let range = offset..offset + constants.len() as u32 * 4;
+
+    for bit in 0..32 {
+        let stage = rendy_core::hal::pso::ShaderStageFlags::from_bits_truncate(1 << bit);
+        if stage.is_empty() || !stages.contains(stage) {
+            continue;
+        }
+        let declared = ranges
+            .iter()
+            .any(|(s, r)| s.contains(stage) && r.start <= range.start && range.end <= r.end);
+        if !declared {
+            return Err(PushConstantsError::OutOfRange { stage, range });
+        }
+    }
+
+    for (s, r) in ranges {
+        if r.start < range.end && range.start < r.end && !stages.contains(*s) {
+            return Err(PushConstantsError::StagesMismatch {
+                stages,
+                expected: *s,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// `DRAW_INDIRECT_COUNT` feature required for indirect draws with count read from a buffer
+/// is not enabled on the device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DrawIndirectCountUnsupported;
+
+impl std::fmt::Display for DrawIndirectCountUnsupported {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            fmt,
+            "Indirect draw with count requires `DRAW_INDIRECT_COUNT` feature"
+        )
+    }
+}
+
+impl std::error::Error for DrawIndirectCountUnsupported {}
+
+/// Check that indirect draws with count read from a buffer can be recorded
+/// on the device with `features` enabled.
+/// Pipelines and nodes that record [`draw_indirect_count`] or [`draw_indexed_indirect_count`]
+/// should call this once when they are built.
+///
+/// [`draw_indirect_count`]: struct.RenderPassEncoder.html#method.draw_indirect_count
+/// [`draw_indexed_indirect_count`]: struct.RenderPassEncoder.html#method.draw_indexed_indirect_count
+pub fn check_draw_indirect_count(
+    features: rendy_core::hal::Features,
+) -> Result<(), DrawIndirectCountUnsupported> {
+    if features.contains(rendy_core::hal::Features::DRAW_INDIRECT_COUNT) {
+        Ok(())
+    } else {
+        Err(DrawIndirectCountUnsupported)
+    }
+}
+
+/// Debug check of offsets and stride of indirect draw with count.
+fn debug_assert_draw_indirect_count(offset: u64, count_offset: u64, stride: u32, size: usize) {
+    debug_assert_eq!(
+        offset & 3,
+        0,
+        "Indirect buffer offset must be multiple of 4"
+    );
+    debug_assert_eq!(
+        count_offset & 3,
+        0,
+        "Count buffer offset must be multiple of 4"
+    );
+    debug_assert!(
+        stride & 3 == 0 && stride as usize >= size,
+        "Indirect draw stride must be multiple of 4 and not less than draw command"
+    );
+}
+
 /// Encoder for recording commands inside or outside renderpass.
+///
+/// Conditional rendering (`VK_EXT_conditional_rendering`) is not available,
+/// as gfx-hal 0.5 doesn't expose it on any backend.
+/// To skip draws on the GPU, write zero counts to arguments of indirect draws instead.
 #[derive(Debug)]
 pub struct EncoderCommon<'a, B: rendy_core::hal::Backend, C> {
     raw: &'a mut B::CommandBuffer,
@@ -265,6 +409,29 @@ where
         );
     }
 
+    /// Push graphics constants after checking them against
+    /// push constant `ranges` of the `layout`.
+    /// Pushed bytes must be within ranges declared for all `stages`,
+    /// see [`validate_push_constants`].
+    ///
+    /// # Safety
+    ///
+    /// `layout` must be created with push constant `ranges`.
+    ///
+    /// [`validate_push_constants`]: fn.validate_push_constants.html
+    pub unsafe fn push_constants_checked(
+        &mut self,
+        layout: &B::PipelineLayout,
+        ranges: &[(rendy_core::hal::pso::ShaderStageFlags, std::ops::Range<u32>)],
+        stages: rendy_core::hal::pso::ShaderStageFlags,
+        offset: u32,
+        constants: &[u32],
+    ) -> Result<(), PushConstantsError> {
+        validate_push_constants(ranges, stages, offset, constants)?;
+        self.push_constants(layout, stages, offset, constants);
+        Ok(())
+    }
+
     /// Set viewports
     ///
     /// See: https://www.khronos.org/registry/vulkan/specs/1.1-extensions/man/html/vkCmdSetViewport.html
@@ -384,6 +551,53 @@ where
         rendy_core::hal::command::CommandBuffer::set_depth_bias(self.raw, depth_bias);
     }
 
+    /// Begin query `id` from the `pool`.
+    /// Occlusion queries are precise if pool was created with precise type.
+    ///
+    /// # Safety
+    ///
+    /// Query must be reset and not active.
+    /// Query must be ended in the same subpass or outside of render pass
+    /// if it was begun outside of render pass.
+    ///
+    /// See: https://www.khronos.org/registry/vulkan/specs/1.1-extensions/man/html/vkCmdBeginQuery.html
+    pub unsafe fn begin_query(&mut self, pool: &QueryPool<B>, id: u32) {
+        rendy_core::hal::command::CommandBuffer::begin_query(
+            self.raw,
+            pool.query(id),
+            pool.ty().control_flags(),
+        )
+    }
+
+    /// End query `id` from the `pool`.
+    ///
+    /// # Safety
+    ///
+    /// Query must be active.
+    ///
+    /// See: https://www.khronos.org/registry/vulkan/specs/1.1-extensions/man/html/vkCmdEndQuery.html
+    pub unsafe fn end_query(&mut self, pool: &QueryPool<B>, id: u32) {
+        rendy_core::hal::command::CommandBuffer::end_query(self.raw, pool.query(id))
+    }
+
+    /// Write timestamp to query `id` from the `pool` after `stage` is complete.
+    ///
+    /// # Safety
+    ///
+    /// `pool` must be created with timestamp type.
+    /// Query must be reset.
+    ///
+    /// See: https://www.khronos.org/registry/vulkan/specs/1.1-extensions/man/html/vkCmdWriteTimestamp.html
+    pub unsafe fn write_timestamp(
+        &mut self,
+        stage: rendy_core::hal::pso::PipelineStage,
+        pool: &QueryPool<B>,
+        id: u32,
+    ) {
+        debug_assert_eq!(pool.ty(), QueryType::Timestamp);
+        rendy_core::hal::command::CommandBuffer::write_timestamp(self.raw, stage, pool.query(id))
+    }
+
     /// Reborrow encoder.
     pub fn reborrow<K>(&mut self) -> EncoderCommon<'_, B, K>
     where
@@ -453,6 +667,37 @@ where
         rendy_core::hal::command::CommandBuffer::draw(self.inner.raw, vertices, instances)
     }
 
+    /// Draw single triangle that covers whole viewport.
+    /// Useful for post-processing and other full-screen passes.
+    ///
+    /// This is `draw(0..3, 0..1)` without any vertex buffers.
+    /// Vertex shader generates positions from vertex index, so there is nothing
+    /// to allocate, and a single triangle avoids the diagonal seam a quad has
+    /// where fragments along it are shaded twice.
+    ///
+    /// ```glsl
+    /// #version 450
+    ///
+    /// layout(location = 0) out vec2 uv;
+    ///
+    /// void main() {
+    ///     uv = vec2((gl_VertexIndex << 1) & 2, gl_VertexIndex & 2);
+    ///     gl_Position = vec4(uv * 2.0 - 1.0, 0.0, 1.0);
+    /// }
+    /// ```
+    ///
+    /// Flip `uv.y` if the pipeline expects texture coordinates with origin in the bottom-left corner.
+    ///
+    /// # Safety
+    ///
+    /// Bound graphics pipeline must have no vertex input attributes
+    /// and use triangle list primitive topology.
+    ///
+    /// See: https://www.khronos.org/registry/vulkan/specs/1.1-extensions/man/html/vkCmdDraw.html
+    pub unsafe fn draw_fullscreen(&mut self) {
+        self.draw(0..3, 0..1)
+    }
+
     /// Draw indexed, with `base_vertex` specifying an offset that is treated as
     /// vertex number 0.
     ///
@@ -532,6 +777,93 @@ where
         )
     }
 
+    /// Draw indirect with draw count read from `count_buffer`.
+    /// Similar to [`draw_indirect`] except that number of draws is taken from `count_buffer` at `count_offset`
+    /// and clamped to `max_draw_count`.
+    /// Useful when draws are produced on the device, e.g. by culling in compute shader.
+    ///
+    /// [`draw_indirect`]: #method.draw_indirect
+    ///
+    /// # Safety
+    ///
+    /// Similar to `draw_indirect()`.
+    /// `DRAW_INDIRECT_COUNT` feature must be enabled, see [`check_draw_indirect_count`].
+    /// `offset` and `count_offset` must be multiples of 4.
+    /// `stride` must be multiple of 4 and not less than size of [`DrawCommand`].
+    ///
+    /// [`check_draw_indirect_count`]: fn.check_draw_indirect_count.html
+    /// [`DrawCommand`]: struct.DrawCommand.html
+    ///
+    /// See: https://www.khronos.org/registry/vulkan/specs/1.2-extensions/man/html/vkCmdDrawIndirectCount.html
+    pub unsafe fn draw_indirect_count(
+        &mut self,
+        buffer: &B::Buffer,
+        offset: u64,
+        count_buffer: &B::Buffer,
+        count_offset: u64,
+        max_draw_count: u32,
+        stride: u32,
+    ) {
+        debug_assert_draw_indirect_count(
+            offset,
+            count_offset,
+            stride,
+            std::mem::size_of::<DrawCommand>(),
+        );
+        rendy_core::hal::command::CommandBuffer::draw_indirect_count(
+            self.inner.raw,
+            buffer,
+            offset,
+            count_buffer,
+            count_offset,
+            max_draw_count,
+            stride,
+        )
+    }
+
+    /// Draw indirect with indices with draw count read from `count_buffer`.
+    /// Similar to [`draw_indexed_indirect`] except that number of draws is taken from `count_buffer` at `count_offset`
+    /// and clamped to `max_draw_count`.
+    ///
+    /// [`draw_indexed_indirect`]: #method.draw_indexed_indirect
+    ///
+    /// # Safety
+    ///
+    /// Similar to `draw_indexed_indirect()`.
+    /// `DRAW_INDIRECT_COUNT` feature must be enabled, see [`check_draw_indirect_count`].
+    /// `offset` and `count_offset` must be multiples of 4.
+    /// `stride` must be multiple of 4 and not less than size of [`DrawIndexedCommand`].
+    ///
+    /// [`check_draw_indirect_count`]: fn.check_draw_indirect_count.html
+    /// [`DrawIndexedCommand`]: struct.DrawIndexedCommand.html
+    ///
+    /// See: https://www.khronos.org/registry/vulkan/specs/1.2-extensions/man/html/vkCmdDrawIndexedIndirectCount.html
+    pub unsafe fn draw_indexed_indirect_count(
+        &mut self,
+        buffer: &B::Buffer,
+        offset: u64,
+        count_buffer: &B::Buffer,
+        count_offset: u64,
+        max_draw_count: u32,
+        stride: u32,
+    ) {
+        debug_assert_draw_indirect_count(
+            offset,
+            count_offset,
+            stride,
+            std::mem::size_of::<DrawIndexedCommand>(),
+        );
+        rendy_core::hal::command::CommandBuffer::draw_indexed_indirect_count(
+            self.inner.raw,
+            buffer,
+            offset,
+            count_buffer,
+            count_offset,
+            max_draw_count,
+            stride,
+        )
+    }
+
     /// Reborrow encoder.
     pub fn reborrow(&mut self) -> RenderPassEncoder<'_, B> {
         RenderPassEncoder {
@@ -788,6 +1120,71 @@ where
     }
 }
 
+impl<'a, B, C, L> Encoder<'a, B, C, L>
+where
+    B: rendy_core::hal::Backend,
+    C: Supports<Execute>,
+{
+    /// Set the `event` once commands recorded before reach and complete `stages`.
+    ///
+    /// # Safety
+    ///
+    /// Event must be waited only by commands submitted to the same queue.
+    ///
+    /// See: https://www.khronos.org/registry/vulkan/specs/1.1-extensions/man/html/vkCmdSetEvent.html
+    pub unsafe fn set_event(
+        &mut self,
+        event: &Event<B>,
+        stages: rendy_core::hal::pso::PipelineStage,
+    ) {
+        self.capability.assert();
+        rendy_core::hal::command::CommandBuffer::set_event(self.inner.raw, event.raw(), stages)
+    }
+
+    /// Reset the `event` once commands recorded before reach and complete `stages`.
+    ///
+    /// # Safety
+    ///
+    /// Event must not be waited by commands executed concurrently.
+    ///
+    /// See: https://www.khronos.org/registry/vulkan/specs/1.1-extensions/man/html/vkCmdResetEvent.html
+    pub unsafe fn reset_event(
+        &mut self,
+        event: &Event<B>,
+        stages: rendy_core::hal::pso::PipelineStage,
+    ) {
+        self.capability.assert();
+        rendy_core::hal::command::CommandBuffer::reset_event(self.inner.raw, event.raw(), stages)
+    }
+
+    /// Wait for all `events` to be set before commands recorded after
+    /// reach `stages.end`, then insert memory `barriers`.
+    /// `stages.start` must include stages at which events were set.
+    ///
+    /// Unlike `pipeline_barrier` only commands that set the events are waited for,
+    /// commands recorded between setting and waiting can overlap with commands after the wait.
+    ///
+    /// # Safety
+    ///
+    /// Events must be set by commands submitted earlier to the same queue or by the host.
+    ///
+    /// See: https://www.khronos.org/registry/vulkan/specs/1.1-extensions/man/html/vkCmdWaitEvents.html
+    pub unsafe fn wait_events<'b>(
+        &mut self,
+        events: &[&Event<B>],
+        stages: std::ops::Range<rendy_core::hal::pso::PipelineStage>,
+        barriers: impl IntoIterator<Item = rendy_core::hal::memory::Barrier<'b, B>>,
+    ) {
+        self.capability.assert();
+        rendy_core::hal::command::CommandBuffer::wait_events(
+            self.inner.raw,
+            events.iter().map(|event| event.raw()),
+            stages,
+            barriers,
+        )
+    }
+}
+
 impl<'a, B, C, L> Encoder<'a, B, C, L>
 where
     B: rendy_core::hal::Backend,
@@ -800,6 +1197,54 @@ where
         self.level
     }
 
+    /// Reset `queries` from the `pool` before they can be used.
+    ///
+    /// # Safety
+    ///
+    /// Queries must not be active.
+    ///
+    /// See: https://www.khronos.org/registry/vulkan/specs/1.1-extensions/man/html/vkCmdResetQueryPool.html
+    pub unsafe fn reset_query_pool(&mut self, pool: &QueryPool<B>, queries: std::ops::Range<u32>) {
+        assert!(queries.end <= pool.count(), "Query range is out of bounds");
+        rendy_core::hal::command::CommandBuffer::reset_query_pool(
+            self.inner.raw,
+            pool.raw(),
+            queries,
+        )
+    }
+
+    /// Copy results of `queries` from the `pool` into the `buffer` as 64-bit values.
+    /// Layout matches [`QueryPool::get_results`].
+    ///
+    /// [`QueryPool::get_results`]: ../struct.QueryPool.html#method.get_results
+    ///
+    /// # Safety
+    ///
+    /// `buffer` must be large enough and created with `TRANSFER_DST` usage.
+    ///
+    /// See: https://www.khronos.org/registry/vulkan/specs/1.1-extensions/man/html/vkCmdCopyQueryPoolResults.html
+    pub unsafe fn copy_query_pool_results(
+        &mut self,
+        pool: &QueryPool<B>,
+        queries: std::ops::Range<u32>,
+        buffer: &B::Buffer,
+        offset: u64,
+        flags: rendy_core::hal::query::ResultFlags,
+    ) where
+        C: Supports<Transfer>,
+    {
+        self.capability.assert();
+        rendy_core::hal::command::CommandBuffer::copy_query_pool_results(
+            self.inner.raw,
+            pool.raw(),
+            queries,
+            buffer,
+            offset,
+            result_stride(pool.ty(), flags) as u64 * 8,
+            flags | rendy_core::hal::query::ResultFlags::BITS_64,
+        )
+    }
+
     /// Copy buffer regions.
     /// `src` and `dst` can be the same buffer or alias in memory.
     /// But regions must not overlap.
@@ -907,6 +1352,65 @@ where
         )
     }
 
+    /// Clear color image subresource ranges outside of render pass.
+    /// Queue must support graphics or compute operations.
+    ///
+    /// # Safety
+    ///
+    /// Image must be in `layout` which is either `TransferDstOptimal` or `General`.
+    /// Transition into that layout must be recorded by caller.
+    ///
+    /// See: https://www.khronos.org/registry/vulkan/specs/1.1-extensions/man/html/vkCmdClearColorImage.html
+    pub unsafe fn clear_color_image(
+        &mut self,
+        image: &B::Image,
+        layout: rendy_core::hal::image::Layout,
+        color: rendy_core::hal::command::ClearColor,
+        ranges: impl IntoIterator<Item = rendy_core::hal::image::SubresourceRange>,
+    ) where
+        C: Supports<Execute>,
+    {
+        self.capability.assert();
+        assert_clear_layout(layout);
+
+        rendy_core::hal::command::CommandBuffer::clear_image(
+            self.inner.raw,
+            image,
+            layout,
+            rendy_core::hal::command::ClearValue { color },
+            ranges,
+        )
+    }
+
+    /// Clear depth-stencil image subresource ranges outside of render pass.
+    ///
+    /// # Safety
+    ///
+    /// Image must be in `layout` which is either `TransferDstOptimal` or `General`.
+    /// Transition into that layout must be recorded by caller.
+    ///
+    /// See: https://www.khronos.org/registry/vulkan/specs/1.1-extensions/man/html/vkCmdClearDepthStencilImage.html
+    pub unsafe fn clear_depth_stencil_image(
+        &mut self,
+        image: &B::Image,
+        layout: rendy_core::hal::image::Layout,
+        depth_stencil: rendy_core::hal::command::ClearDepthStencil,
+        ranges: impl IntoIterator<Item = rendy_core::hal::image::SubresourceRange>,
+    ) where
+        C: Supports<Graphics>,
+    {
+        self.capability.assert();
+        assert_clear_layout(layout);
+
+        rendy_core::hal::command::CommandBuffer::clear_image(
+            self.inner.raw,
+            image,
+            layout,
+            rendy_core::hal::command::ClearValue { depth_stencil },
+            ranges,
+        )
+    }
+
     /// Blit image regions, potentially using specified filter when resize is necessary.
     ///
     /// # Safety
@@ -972,6 +1476,15 @@ where
     }
 }
 
+fn assert_clear_layout(layout: rendy_core::hal::image::Layout) {
+    assert!(
+        layout == rendy_core::hal::image::Layout::TransferDstOptimal
+            || layout == rendy_core::hal::image::Layout::General,
+        "Image must be in `TransferDstOptimal` or `General` layout to be cleared, not {:?}",
+        layout
+    );
+}
+
 impl<B, C, U, L, R> CommandBuffer<B, C, RecordingState<U>, L, R>
 where
     B: rendy_core::hal::Backend,
@@ -1007,3 +1520,70 @@ where
         }
     }
 }
+
+#[test]
+fn test_validate_push_constants() {
+    use rendy_core::hal::pso::ShaderStageFlags;
+
+    let ranges = [
+        (ShaderStageFlags::VERTEX, 0..16),
+        (
+            ShaderStageFlags::VERTEX | ShaderStageFlags::FRAGMENT,
+            16..20,
+        ),
+    ];
+
+    assert_eq!(
+        validate_push_constants(&ranges, ShaderStageFlags::VERTEX, 4, &[7]),
+        Ok(())
+    );
+    assert_eq!(
+        validate_push_constants(&ranges, ShaderStageFlags::VERTEX, 2, &[7]),
+        Err(PushConstantsError::Misaligned(2))
+    );
+    assert_eq!(
+        validate_push_constants(&ranges, ShaderStageFlags::FRAGMENT, 0, &[7]),
+        Err(PushConstantsError::OutOfRange {
+            stage: ShaderStageFlags::FRAGMENT,
+            range: 0..4,
+        })
+    );
+    assert_eq!(
+        validate_push_constants(&ranges, ShaderStageFlags::VERTEX, 16, &[7]),
+        Err(PushConstantsError::StagesMismatch {
+            stages: ShaderStageFlags::VERTEX,
+            expected: ShaderStageFlags::VERTEX | ShaderStageFlags::FRAGMENT,
+        })
+    );
+    assert_eq!(
+        validate_push_constants(
+            &ranges,
+            ShaderStageFlags::VERTEX | ShaderStageFlags::FRAGMENT,
+            16,
+            &[7]
+        ),
+        Ok(())
+    );
+}
+
+#[test]
+fn test_check_draw_indirect_count() {
+    use rendy_core::hal::Features;
+
+    assert_eq!(
+        check_draw_indirect_count(Features::empty()),
+        Err(DrawIndirectCountUnsupported)
+    );
+    assert_eq!(
+        check_draw_indirect_count(Features::DRAW_INDIRECT_COUNT),
+        Ok(())
+    );
+}
+
+#[test]
+#[should_panic]
+#[cfg(debug_assertions)]
+fn test_draw_indirect_count_misaligned() {
+    let size = std::mem::size_of::<DrawIndexedCommand>();
+    debug_assert_draw_indirect_count(0, 2, size as u32, size);
+}
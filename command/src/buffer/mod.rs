@@ -11,6 +11,7 @@ use {
     crate::{
         capability::{Capability, Supports},
         family::FamilyId,
+        fence::Fence,
     },
     rendy_core::hal::Backend,
 };
@@ -292,7 +293,7 @@ where
     /// all primary command buffers where [`Submit`] instance (created from this `CommandBuffer`)
     /// was submitted must be complete.
     ///
-    /// [`Submit`]: struct.Submit
+    /// [`Submit`]: struct.Submit.html
     /// [waiting]: ..rendy_core::hal/device/trait.Device.html#method.wait_for_fences
     /// [`Fence`]: ..rendy_core::hal/trait.Backend.html#associatedtype.Fence
     /// [submitted]: ..rendy_core::hal/queue/struct.CommandQueue.html#method.submit
@@ -307,11 +308,46 @@ where
     S: Resettable,
 {
     /// Reset command buffer.
+    /// Other command buffers allocated from the same pool are not affected.
+    ///
+    /// Buffer that is pending execution can't be reset this way,
+    /// see [`reset_if_complete`](#method.reset_if_complete).
+    ///
+    /// Raw command buffer is reset implicitly when recording begins,
+    /// which is permitted for buffers allocated from pool created with individual reset flag.
     pub fn reset(self) -> CommandBuffer<B, C, InitialState, L, IndividualReset> {
         unsafe { self.change_state(|_| InitialState) }
     }
 }
 
+impl<B, C, N, L> CommandBuffer<B, C, PendingState<N>, L, IndividualReset>
+where
+    B: Backend,
+    N: Resettable,
+{
+    /// Reset command buffer pending execution if `fence` is signaled.
+    /// Returns command buffer back if `fence` is not signaled yet.
+    ///
+    /// # Safety
+    ///
+    /// None of [`Submit`] instances created from this `CommandBuffer` are alive.
+    ///
+    /// `fence` must be submitted within same `Submission` as this `CommandBuffer` or later
+    /// to the same queue.
+    ///
+    /// [`Submit`]: struct.Submit.html
+    pub unsafe fn reset_if_complete(
+        self,
+        fence: &Fence<B>,
+    ) -> Result<CommandBuffer<B, C, InitialState, L, IndividualReset>, Self> {
+        if fence.is_signaled() {
+            Ok(self.mark_complete().reset())
+        } else {
+            Err(self)
+        }
+    }
+}
+
 impl<B, C, S, L> CommandBuffer<B, C, S, L>
 where
     B: Backend,
@@ -353,3 +389,46 @@ where
         }
     }
 }
+
+#[test]
+fn test_reset_individual() {
+    use rendy_core::{empty, Device, DeviceId, InstanceId};
+
+    let family = FamilyId {
+        device: DeviceId::new(InstanceId::new()),
+        index: 0,
+    };
+
+    let buffer = unsafe {
+        CommandBuffer::<empty::Backend, _, _, _, _>::from_raw(
+            empty::CommandBuffer,
+            crate::capability::Graphics,
+            InitialState,
+            PrimaryLevel,
+            IndividualReset,
+            family,
+        )
+    };
+
+    let (_submit, buffer) = buffer
+        .begin(MultiShot(NoSimultaneousUse), ())
+        .finish()
+        .submit();
+
+    let device = Device::from_raw(empty::Device, family.device);
+    let unsignaled = Fence::new(&device, false).unwrap();
+    let signaled = Fence::new(&device, true).unwrap();
+
+    // Buffer stays pending until fence is signaled.
+    let buffer = match unsafe { buffer.reset_if_complete(&unsignaled) } {
+        Ok(_) => panic!("Buffer must not be reset before fence is signaled"),
+        Err(buffer) => buffer,
+    };
+    let buffer = match unsafe { buffer.reset_if_complete(&signaled) } {
+        Ok(buffer) => buffer,
+        Err(_) => panic!("Buffer must be reset once fence is signaled"),
+    };
+
+    let buffer = buffer.begin(OneShot, ()).finish().reset();
+    buffer.into_raw();
+}
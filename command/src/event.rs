@@ -0,0 +1,133 @@
+use {
+    crate::core::{device_owned, Device, DeviceId},
+    rendy_core::hal::{
+        device::{Device as _, OomOrDeviceLost, OutOfMemory},
+        Backend,
+    },
+};
+
+/// Error creating an event.
+#[derive(Clone, Debug, PartialEq)]
+pub enum EventCreationError {
+    /// Backend doesn't implement events.
+    Unsupported(rendy_core::Backend),
+
+    /// Ran out of memory.
+    OutOfMemory(OutOfMemory),
+}
+
+impl std::fmt::Display for EventCreationError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EventCreationError::Unsupported(backend) => {
+                write!(fmt, "Events are not supported by {} backend", backend)
+            }
+            EventCreationError::OutOfMemory(err) => {
+                write!(fmt, "Failed to create event: {:?}", err)
+            }
+        }
+    }
+}
+
+impl std::error::Error for EventCreationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EventCreationError::Unsupported(_) => None,
+            EventCreationError::OutOfMemory(err) => Some(err),
+        }
+    }
+}
+
+/// Check if backend implements events.
+/// DirectX 12 and OpenGL backends of gfx-hal 0.5 don't.
+pub fn supports_events(backend: rendy_core::Backend) -> bool {
+    match backend {
+        rendy_core::Backend::Vulkan | rendy_core::Backend::Metal => true,
+        rendy_core::Backend::Dx12 | rendy_core::Backend::Gl | rendy_core::Backend::Empty => false,
+    }
+}
+
+/// Event wrapper.
+///
+/// Events split pipeline barrier in two halves.
+/// Commands set the event with `Encoder::set_event` once prior work is done
+/// and `Encoder::wait_events` blocks later commands until it is set,
+/// so unrelated commands recorded in between can execute without waiting.
+///
+/// Unlike semaphores events can only synchronize commands submitted to the same queue.
+/// Host can set and reset events as well and check their status.
+#[derive(Debug)]
+pub struct Event<B: Backend> {
+    device: DeviceId,
+    raw: B::Event,
+}
+
+device_owned!(Event<B>);
+
+impl<B> Event<B>
+where
+    B: Backend,
+{
+    /// Create new event in unset state.
+    ///
+    /// Returns `EventCreationError::Unsupported` if backend doesn't implement events.
+    pub fn new(device: &Device<B>) -> Result<Self, EventCreationError> {
+        let backend = rendy_core::Backend::which::<B>();
+        if !supports_events(backend) {
+            return Err(EventCreationError::Unsupported(backend));
+        }
+
+        let raw = device
+            .create_event()
+            .map_err(EventCreationError::OutOfMemory)?;
+        Ok(Event {
+            device: device.id(),
+            raw,
+        })
+    }
+
+    /// Destroy event.
+    ///
+    /// # Safety
+    ///
+    /// Event must not be used by pending command buffers.
+    pub unsafe fn dispose(self, device: &Device<B>) {
+        self.assert_device_owner(device);
+        device.destroy_event(self.raw);
+    }
+
+    /// Get raw event.
+    pub fn raw(&self) -> &B::Event {
+        &self.raw
+    }
+
+    /// Check if event is set.
+    pub fn is_set(&self, device: &Device<B>) -> Result<bool, OomOrDeviceLost> {
+        self.assert_device_owner(device);
+        unsafe { device.get_event_status(&self.raw) }
+    }
+
+    /// Set event from the host.
+    pub fn set(&self, device: &Device<B>) -> Result<(), OutOfMemory> {
+        self.assert_device_owner(device);
+        unsafe { device.set_event(&self.raw) }
+    }
+
+    /// Reset event from the host.
+    ///
+    /// # Safety
+    ///
+    /// Event must not be waited by pending command buffers.
+    pub unsafe fn reset(&self, device: &Device<B>) -> Result<(), OutOfMemory> {
+        self.assert_device_owner(device);
+        device.reset_event(&self.raw)
+    }
+}
+
+#[test]
+fn test_supports_events() {
+    assert!(supports_events(rendy_core::Backend::Vulkan));
+    assert!(supports_events(rendy_core::Backend::Metal));
+    assert!(!supports_events(rendy_core::Backend::Dx12));
+    assert!(!supports_events(rendy_core::Backend::Gl));
+}
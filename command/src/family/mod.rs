@@ -100,12 +100,25 @@ where
         self.id
     }
 
+    /// Number of queues created for the family.
+    pub fn queue_count(&self) -> usize {
+        self.queues.len()
+    }
+
     /// Get queue by index
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not less than `queue_count()`.
     pub fn queue(&self, index: usize) -> &Queue<B> {
         &self.queues[index]
     }
 
     /// Get queue by index
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not less than `queue_count()`.
     pub fn queue_mut(&mut self, index: usize) -> &mut Queue<B> {
         &mut self.queues[index]
     }
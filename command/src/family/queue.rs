@@ -148,6 +148,59 @@ where
         }
     }
 
+    /// Submit raw command buffers, e.g. recorded by external code, to the queue.
+    /// Waits and signals are performed in the same way as in [`submit`].
+    /// Fence must be unsignaled and is marked as submitted.
+    ///
+    /// Semaphores are only borrowed for the duration of the call and remain owned by caller.
+    /// Wait semaphores must have signal operation submitted before
+    /// and each signal is consumed by the wait.
+    /// Signal semaphores must be unsignaled and have no pending waits.
+    /// Caller must not destroy semaphores until operations on them are complete,
+    /// e.g. until the `fence` is signaled.
+    ///
+    /// # Safety
+    ///
+    /// Command buffers must be in executable state and be allocated from the pool
+    /// of this queue's family. They must not be reset or destroyed until execution
+    /// is complete.
+    ///
+    /// [`submit`]: #method.submit
+    pub unsafe fn submit_raw<'a>(
+        &mut self,
+        submission: Submission<
+            B,
+            impl IntoIterator<
+                Item = (
+                    &'a (impl std::borrow::Borrow<B::Semaphore> + 'a),
+                    rendy_core::hal::pso::PipelineStage,
+                ),
+            >,
+            impl IntoIterator<Item = &'a B::CommandBuffer>,
+            impl IntoIterator<Item = &'a (impl std::borrow::Borrow<B::Semaphore> + 'a)>,
+        >,
+        fence: Option<&mut Fence<B>>,
+    ) {
+        assert!(fence.as_ref().is_none_or(|f| f.is_unsignaled()));
+
+        self.raw.submit(
+            rendy_core::hal::queue::Submission {
+                command_buffers: submission.submits,
+                wait_semaphores: submission.waits.into_iter().map(|w| (w.0.borrow(), w.1)),
+                signal_semaphores: submission.signals.into_iter().map(|s| s.borrow()),
+            },
+            fence.as_ref().map(|f| f.raw()),
+        );
+
+        if let Some(fence) = fence {
+            fence.mark_submitted(FenceEpoch {
+                queue: self.id,
+                epoch: self.next_epoch,
+            });
+            self.next_epoch += 1;
+        }
+    }
+
     /// Wait for queue to finish all pending commands.
     pub fn wait_idle(&self) -> Result<(), rendy_core::hal::device::OutOfMemory> {
         self.raw.wait_idle()
@@ -58,6 +58,23 @@ where
             marker: self.marker,
         }
     }
+
+    /// Add raw command buffers recorded outside of rendy to the submission.
+    /// Such submission can only be submitted with [`Queue::submit_raw`].
+    ///
+    /// [`Queue::submit_raw`]: struct.Queue.html#method.submit_raw
+    pub fn raw_submits<'a, C>(self, command_buffers: C) -> Submission<B, W, C, S>
+    where
+        C: IntoIterator<Item = &'a B::CommandBuffer>,
+        B::CommandBuffer: 'a,
+    {
+        Submission {
+            waits: self.waits,
+            submits: command_buffers,
+            signals: self.signals,
+            marker: self.marker,
+        }
+    }
 }
 
 impl<B, C, S> Submission<B, NoWaits<B>, C, S>
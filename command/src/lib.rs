@@ -81,8 +81,10 @@ use rendy_core as core;
 
 mod buffer;
 mod capability;
+mod event;
 mod family;
 mod fence;
 mod pool;
+mod query;
 
-pub use crate::{buffer::*, capability::*, family::*, fence::*, pool::*};
+pub use crate::{buffer::*, capability::*, event::*, family::*, fence::*, pool::*, query::*};
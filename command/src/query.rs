@@ -0,0 +1,216 @@
+use {
+    crate::core::{device_owned, Device, DeviceId},
+    rendy_core::hal::{
+        device::{Device as _, OomOrDeviceLost},
+        query::{ControlFlags, CreationError, Id, PipelineStatistic, Query, ResultFlags, Type},
+        Backend, Features,
+    },
+    std::{mem::size_of, ops::Range},
+};
+
+/// Type of queries in the query pool.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum QueryType {
+    /// Count samples that pass depth and stencil tests.
+    /// Non-precise queries only guarantee that result is non-zero
+    /// if any sample passed, which is enough for visibility tests.
+    Occlusion {
+        /// Request exact sample count.
+        /// Requires `PRECISE_OCCLUSION_QUERY` feature.
+        precise: bool,
+    },
+
+    /// Count invocations of pipeline stages.
+    /// Requires `PIPELINE_STATISTICS_QUERY` feature.
+    PipelineStatistics(PipelineStatistic),
+
+    /// Timestamps written with `write_timestamp`.
+    ///
+    /// gfx-hal 0.5 reports neither timestamp period (nanoseconds per tick)
+    /// nor number of valid timestamp bits of queue families.
+    /// Timestamps can only be compared with each other
+    /// and must not be written on queues that don't support them.
+    Timestamp,
+}
+
+impl QueryType {
+    /// Get raw query type.
+    pub fn raw(&self) -> Type {
+        match *self {
+            QueryType::Occlusion { .. } => Type::Occlusion,
+            QueryType::PipelineStatistics(statistics) => Type::PipelineStatistics(statistics),
+            QueryType::Timestamp => Type::Timestamp,
+        }
+    }
+
+    /// Features required to create query pool of this type.
+    pub fn required_features(&self) -> Features {
+        match *self {
+            QueryType::Occlusion { precise: true } => Features::PRECISE_OCCLUSION_QUERY,
+            QueryType::PipelineStatistics(_) => Features::PIPELINE_STATISTICS_QUERY,
+            QueryType::Occlusion { precise: false } | QueryType::Timestamp => Features::empty(),
+        }
+    }
+
+    /// Flags to begin query of this type with.
+    pub fn control_flags(&self) -> ControlFlags {
+        match *self {
+            QueryType::Occlusion { precise: true } => ControlFlags::PRECISE,
+            _ => ControlFlags::empty(),
+        }
+    }
+
+    /// Number of values each query produces.
+    /// Pipeline statistics query produces one value per enabled statistic
+    /// in order of flag bits.
+    pub fn values_per_query(&self) -> u32 {
+        match *self {
+            QueryType::PipelineStatistics(statistics) => statistics.bits().count_ones(),
+            QueryType::Occlusion { .. } | QueryType::Timestamp => 1,
+        }
+    }
+}
+
+/// Query pool wrapper.
+#[derive(Debug)]
+pub struct QueryPool<B: Backend> {
+    device: DeviceId,
+    raw: B::QueryPool,
+    ty: QueryType,
+    count: Id,
+}
+
+device_owned!(QueryPool<B>);
+
+impl<B> QueryPool<B>
+where
+    B: Backend,
+{
+    /// Create query pool with `count` queries of specified type.
+    ///
+    /// Returns `CreationError::Unsupported` if device lacks
+    /// `features` required by the query type.
+    pub fn create(
+        device: &Device<B>,
+        features: Features,
+        ty: QueryType,
+        count: Id,
+    ) -> Result<Self, CreationError> {
+        if !features.contains(ty.required_features()) {
+            return Err(CreationError::Unsupported(ty.raw()));
+        }
+        let raw = unsafe { device.create_query_pool(ty.raw(), count) }?;
+        Ok(QueryPool {
+            device: device.id(),
+            raw,
+            ty,
+            count,
+        })
+    }
+
+    /// Destroy query pool.
+    ///
+    /// # Safety
+    ///
+    /// Query pool must not be used by pending command buffers.
+    pub unsafe fn dispose(self, device: &Device<B>) {
+        self.assert_device_owner(device);
+        device.destroy_query_pool(self.raw);
+    }
+
+    /// Get raw query pool.
+    pub fn raw(&self) -> &B::QueryPool {
+        &self.raw
+    }
+
+    /// Get type of queries in the pool.
+    pub fn ty(&self) -> QueryType {
+        self.ty
+    }
+
+    /// Get number of queries in the pool.
+    pub fn count(&self) -> Id {
+        self.count
+    }
+
+    /// Get raw query with specified index.
+    pub fn query(&self, id: Id) -> Query<'_, B> {
+        assert!(id < self.count, "Query index is out of bounds");
+        Query {
+            pool: &self.raw,
+            id,
+        }
+    }
+
+    /// Read results of `queries` as 64-bit values.
+    ///
+    /// Each query produces [`QueryType::values_per_query`] values
+    /// followed by availability value if `WITH_AVAILABILITY` flag is set.
+    ///
+    /// Returns `None` if results are not ready yet
+    /// and neither `PARTIAL` nor `WITH_AVAILABILITY` flags are set.
+    ///
+    /// [`QueryType::values_per_query`]: enum.QueryType.html#method.values_per_query
+    pub fn get_results(
+        &self,
+        device: &Device<B>,
+        queries: Range<Id>,
+        flags: ResultFlags,
+    ) -> Result<Option<Vec<u64>>, OomOrDeviceLost> {
+        self.assert_device_owner(device);
+        assert!(
+            queries.start <= queries.end && queries.end <= self.count,
+            "Query range is out of bounds"
+        );
+
+        let stride = result_stride(self.ty, flags);
+        let mut values = vec![0u64; (stride * (queries.end - queries.start)) as usize];
+        let ready = unsafe {
+            let data = std::slice::from_raw_parts_mut(
+                values.as_mut_ptr() as *mut u8,
+                values.len() * size_of::<u64>(),
+            );
+            device.get_query_pool_results(
+                &self.raw,
+                queries,
+                data,
+                stride as u64 * size_of::<u64>() as u64,
+                flags | ResultFlags::BITS_64,
+            )
+        }?;
+
+        if ready || flags.intersects(ResultFlags::PARTIAL | ResultFlags::WITH_AVAILABILITY) {
+            Ok(Some(values))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// Number of 64-bit values per query in results.
+pub(crate) fn result_stride(ty: QueryType, flags: ResultFlags) -> u32 {
+    ty.values_per_query() + flags.contains(ResultFlags::WITH_AVAILABILITY) as u32
+}
+
+#[test]
+fn test_result_stride() {
+    let statistics = QueryType::PipelineStatistics(
+        PipelineStatistic::INPUT_ASSEMBLY_PRIMITIVES
+            | PipelineStatistic::FRAGMENT_SHADER_INVOCATIONS,
+    );
+    assert_eq!(statistics.values_per_query(), 2);
+    assert_eq!(result_stride(statistics, ResultFlags::WAIT), 2);
+    assert_eq!(result_stride(statistics, ResultFlags::WITH_AVAILABILITY), 3);
+
+    let occlusion = QueryType::Occlusion { precise: true };
+    assert_eq!(result_stride(occlusion, ResultFlags::empty()), 1);
+    assert_eq!(occlusion.control_flags(), ControlFlags::PRECISE);
+    assert_eq!(
+        occlusion.required_features(),
+        Features::PRECISE_OCCLUSION_QUERY
+    );
+    assert_eq!(
+        QueryType::Occlusion { precise: false }.control_flags(),
+        ControlFlags::empty()
+    );
+}
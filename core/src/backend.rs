@@ -236,18 +236,32 @@ impl std::error::Error for ParseBackendError {}
 impl std::str::FromStr for Backend {
     type Err = ParseBackendError;
 
+    /// Parse backend name ignoring case.
+    /// Accepts `vulkan`, `dx12`, `metal`, `gl` and `empty`.
     fn from_str(string: &str) -> Result<Self, ParseBackendError> {
-        match string {
-            "Dx12" | "dx12" | "DirectX 12" => Ok(Backend::Dx12),
-            "Empty" | "empty" => Ok(Backend::Empty),
-            "Gl" | "gl" => Ok(Backend::Gl),
-            "Metal" | "metal" => Ok(Backend::Metal),
-            "Vulkan" | "vulkan" => Ok(Backend::Vulkan),
+        match string.trim().to_ascii_lowercase().as_str() {
+            "dx12" | "directx 12" => Ok(Backend::Dx12),
+            "empty" => Ok(Backend::Empty),
+            "gl" => Ok(Backend::Gl),
+            "metal" => Ok(Backend::Metal),
+            "vulkan" => Ok(Backend::Vulkan),
             _ => Err(ParseBackendError(string.to_string())),
         }
     }
 }
 
+impl Backend {
+    /// Parse comma-separated list of backend names, e.g. `"vulkan, gl"`.
+    /// Result can be used as priority list to pick enabled backend.
+    pub fn parse_list(string: &str) -> Result<Vec<Self>, ParseBackendError> {
+        string
+            .split(',')
+            .filter(|name| !name.trim().is_empty())
+            .map(str::parse)
+            .collect()
+    }
+}
+
 /// Error signaling that particular backend is not enabled.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct NotEnabled(pub Backend);
@@ -296,6 +310,73 @@ impl std::convert::TryFrom<Backend> for EnabledBackend {
     }
 }
 
+/// Error parsing name of enabled backend.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseEnabledBackendError {
+    /// Name doesn't match any backend.
+    Unknown(ParseBackendError),
+
+    /// Backend is known but not enabled.
+    NotEnabled(NotEnabled),
+}
+
+impl std::fmt::Display for ParseEnabledBackendError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseEnabledBackendError::Unknown(err) => write!(fmt, "{}", err),
+            ParseEnabledBackendError::NotEnabled(err) => write!(fmt, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for ParseEnabledBackendError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseEnabledBackendError::Unknown(err) => Some(err),
+            ParseEnabledBackendError::NotEnabled(err) => Some(err),
+        }
+    }
+}
+
+impl std::str::FromStr for EnabledBackend {
+    type Err = ParseEnabledBackendError;
+
+    fn from_str(string: &str) -> Result<Self, ParseEnabledBackendError> {
+        let backend: Backend = string.parse().map_err(ParseEnabledBackendError::Unknown)?;
+        std::convert::TryFrom::try_from(backend).map_err(ParseEnabledBackendError::NotEnabled)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Backend {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Backend {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let string = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        string.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for EnabledBackend {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for EnabledBackend {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let string = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        string.parse().map_err(serde::de::Error::custom)
+    }
+}
+
 #[doc(hidden)]
 pub trait BackendSwitch {
     type Dx12;
@@ -309,11 +390,14 @@ pub trait BackendSwitch {
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Never {}
 
-/// Allows create new enumerations with variants for each active backend.
-/// `rendy_backend!` can be used to match over it.
+/// Define enumeration with variant for each backend.
+/// `Empty` variant is defined only if empty backend is enabled
+/// so that production builds don't have to match over it.
+#[doc(hidden)]
 #[macro_export]
-macro_rules! backend_enum {
-    ($(#[$meta:meta])* pub enum $name:ident($data:ident $(<$($p:ty),*>)?);) => {
+#[cfg(feature = "empty")]
+macro_rules! rendy_backend_enum_definition {
+    ($(#[$meta:meta])* $name:ident) => {
         $(#[$meta])*
         pub enum $name {
             Dx12(<Self as $crate::BackendSwitch>::Dx12),
@@ -322,6 +406,36 @@ macro_rules! backend_enum {
             Metal(<Self as $crate::BackendSwitch>::Metal),
             Vulkan(<Self as $crate::BackendSwitch>::Vulkan),
         }
+    };
+}
+
+/// Define enumeration with variant for each backend.
+/// `Empty` variant is defined only if empty backend is enabled
+/// so that production builds don't have to match over it.
+#[doc(hidden)]
+#[macro_export]
+#[cfg(not(feature = "empty"))]
+macro_rules! rendy_backend_enum_definition {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        pub enum $name {
+            Dx12(<Self as $crate::BackendSwitch>::Dx12),
+            Gl(<Self as $crate::BackendSwitch>::Gl),
+            Metal(<Self as $crate::BackendSwitch>::Metal),
+            Vulkan(<Self as $crate::BackendSwitch>::Vulkan),
+        }
+    };
+}
+
+/// Allows create new enumerations with variants for each active backend.
+/// `rendy_backend!` can be used to match over it.
+///
+/// `Empty` variant exists only when `empty` feature is enabled.
+/// Other disabled backends keep their variants with uninhabited payload.
+#[macro_export]
+macro_rules! backend_enum {
+    ($(#[$meta:meta])* pub enum $name:ident($data:ident $(<$($p:ty),*>)?);) => {
+        $crate::rendy_backend_enum_definition! { $(#[$meta])* $name }
 
         impl $name {
             $crate::rendy_with_dx12_backend! {
@@ -452,3 +566,24 @@ fn test_enabled_backends() {
         assert_eq!(EnabledBackend::which::<crate::vulkan::Backend>(), std::convert::TryFrom::try_from(Backend::Vulkan).expect("Must be enabled"));
     }
 }
+
+#[test]
+fn test_parse_backend() {
+    assert_eq!("Vulkan".parse(), Ok(Backend::Vulkan));
+    assert_eq!("DX12".parse(), Ok(Backend::Dx12));
+    assert_eq!(" gl ".parse(), Ok(Backend::Gl));
+    assert!("glide".parse::<Backend>().is_err());
+    assert_eq!(
+        Backend::parse_list("metal, Vulkan,gl"),
+        Ok(vec![Backend::Metal, Backend::Vulkan, Backend::Gl])
+    );
+    for &backend in &[
+        Backend::Dx12,
+        Backend::Empty,
+        Backend::Gl,
+        Backend::Metal,
+        Backend::Vulkan,
+    ] {
+        assert_eq!(backend.to_string().parse(), Ok(backend));
+    }
+}
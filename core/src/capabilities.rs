@@ -0,0 +1,90 @@
+use crate::{
+    hal::{adapter::Adapter, queue::QueueFamily, Features, Limits},
+    Backend,
+};
+
+/// Summary of device capabilities commonly checked by portable code.
+///
+/// Computed from physical device features, limits and queue families.
+/// Some fields are approximate on the GL backend
+/// as it reports capabilities of the context rather than the hardware.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Backend of the device.
+    pub backend: Backend,
+
+    /// Maximum width and height of 2D image.
+    pub max_texture_size: u32,
+
+    /// Maximum number of simultaneously bound descriptor sets.
+    /// GL emulates descriptor sets so this value is approximate there.
+    pub max_bound_descriptor_sets: u16,
+
+    /// At least one queue family supports compute
+    /// and compute workgroups can be dispatched.
+    /// On GL this depends on context version reported by the driver.
+    pub compute: bool,
+
+    /// Geometry shaders are supported.
+    pub geometry_shader: bool,
+
+    /// Tessellation shaders are supported.
+    pub tessellation_shader: bool,
+
+    /// More than one draw can be issued by single indirect draw command.
+    /// GL may report support while emulating it with a loop of draws.
+    pub multi_draw_indirect: bool,
+
+    /// Draw count for indirect draws can be sourced from a buffer.
+    pub draw_indirect_count: bool,
+
+    /// Descriptor arrays can be indexed with non-uniform values and left unsized.
+    pub descriptor_indexing: bool,
+
+    /// Timeline semaphores are supported.
+    /// Always `false` as gfx-hal doesn't expose them yet.
+    pub timeline_semaphores: bool,
+}
+
+impl Capabilities {
+    /// Collect capabilities of the device with enabled `features`
+    /// created from the `adapter`.
+    pub fn new<B: crate::hal::Backend>(adapter: &Adapter<B>, features: Features) -> Self {
+        use crate::hal::adapter::PhysicalDevice as _;
+
+        Self::from_parts(
+            Backend::which::<B>(),
+            features,
+            &adapter.physical_device.limits(),
+            adapter
+                .queue_families
+                .iter()
+                .any(|family| family.queue_type().supports_compute()),
+        )
+    }
+
+    /// Collect capabilities from device features and limits.
+    /// `compute_queue` signals that device has queue family with compute support.
+    pub fn from_parts(
+        backend: Backend,
+        features: Features,
+        limits: &Limits,
+        compute_queue: bool,
+    ) -> Self {
+        Capabilities {
+            backend,
+            max_texture_size: limits.max_image_2d_size,
+            max_bound_descriptor_sets: limits.max_bound_descriptor_sets,
+            compute: compute_queue && limits.max_compute_work_group_count[0] > 0,
+            geometry_shader: features.contains(Features::GEOMETRY_SHADER),
+            tessellation_shader: features.contains(Features::TESSELLATION_SHADER),
+            multi_draw_indirect: features.contains(Features::MULTI_DRAW_INDIRECT)
+                && limits.max_draw_indirect_count > 1,
+            draw_indirect_count: features.contains(Features::DRAW_INDIRECT_COUNT),
+            descriptor_indexing: features.contains(
+                Features::SAMPLED_TEXTURE_DESCRIPTOR_INDEXING | Features::UNSIZED_DESCRIPTOR_ARRAY,
+            ),
+            timeline_semaphores: false,
+        }
+    }
+}
@@ -0,0 +1,73 @@
+//! Contains functions for building extents, rects and viewports
+//! without manual casts between `u32` and `i16`.
+
+use {
+    crate::hal::{
+        pso::{Rect, Viewport},
+        window::Extent2D,
+    },
+    std::{convert::TryFrom, ops::Range},
+};
+
+/// Create extent from window size in physical pixels.
+/// Zero dimensions (e.g. of minimized window) are clamped to 1
+/// as zero-sized images and swapchains are not allowed.
+pub fn window_extent(width: u32, height: u32) -> Extent2D {
+    Extent2D {
+        width: width.max(1),
+        height: height.max(1),
+    }
+}
+
+/// Convert dimension to `i16` used by `Rect`.
+/// Values above `i16::MAX` are clamped.
+pub fn clamp_to_i16(value: u32) -> i16 {
+    i16::try_from(value).unwrap_or(i16::MAX)
+}
+
+/// Create rect at origin that covers whole `extent`.
+/// Dimensions above `i16::MAX` are clamped.
+pub fn full_rect(extent: Extent2D) -> Rect {
+    Rect {
+        x: 0,
+        y: 0,
+        w: clamp_to_i16(extent.width),
+        h: clamp_to_i16(extent.height),
+    }
+}
+
+/// Create viewport that covers whole `extent` with specified depth range.
+/// Dimensions above `i16::MAX` are clamped.
+pub fn full_viewport(extent: Extent2D, depth: Range<f32>) -> Viewport {
+    Viewport {
+        rect: full_rect(extent),
+        depth,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_full_rect() {
+        assert_eq!(
+            full_rect(window_extent(0, 480)),
+            Rect {
+                x: 0,
+                y: 0,
+                w: 1,
+                h: 480
+            }
+        );
+        assert_eq!(
+            full_rect(window_extent(40000, 70000)),
+            Rect {
+                x: 0,
+                y: 0,
+                w: i16::MAX,
+                h: i16::MAX
+            }
+        );
+    }
+}
@@ -0,0 +1,30 @@
+use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
+
+/// Window handle constructed from raw parts.
+///
+/// Allows surface creation for windows of libraries that don't implement
+/// `HasRawWindowHandle`, e.g. Android `ANativeWindow` or SDL window.
+#[derive(Clone, Copy, Debug)]
+pub struct RawHandle(RawWindowHandle);
+
+impl RawHandle {
+    /// Wrap raw window handle.
+    ///
+    /// # Safety
+    ///
+    /// `handle` must refer to a valid window that outlives all surfaces created for it.
+    pub unsafe fn new(handle: RawWindowHandle) -> Self {
+        RawHandle(handle)
+    }
+
+    /// Get raw window handle.
+    pub fn handle(&self) -> RawWindowHandle {
+        self.0
+    }
+}
+
+unsafe impl HasRawWindowHandle for RawHandle {
+    fn raw_window_handle(&self) -> RawWindowHandle {
+        self.0
+    }
+}
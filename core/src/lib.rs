@@ -11,7 +11,9 @@
     unused_qualifications
 )]
 
-pub use crate::{backend::*, casts::*, slow::*, wrap::*};
+pub use crate::{
+    backend::*, capabilities::*, casts::*, extent::*, handle::*, slow::*, stage::*, wrap::*,
+};
 
 #[doc(inline)]
 pub use gfx_hal as hal;
@@ -60,7 +62,11 @@ mod backend;
 
 #[macro_use]
 mod features;
+mod capabilities;
 mod casts;
+mod extent;
+mod handle;
 mod slow;
+mod stage;
 pub mod types;
 mod wrap;
@@ -0,0 +1,82 @@
+//! Contains functions mapping shader stages to pipeline stages
+//! in which work of the shaders happens.
+
+use crate::hal::pso::{PipelineStage, ShaderStageFlags, Stage};
+
+const STAGES: [Stage; 6] = [
+    Stage::Vertex,
+    Stage::Hull,
+    Stage::Domain,
+    Stage::Geometry,
+    Stage::Fragment,
+    Stage::Compute,
+];
+
+/// Get pipeline stage in which shader of the `stage` is executed.
+pub fn shader_pipeline_stage(stage: Stage) -> PipelineStage {
+    match stage {
+        Stage::Vertex => PipelineStage::VERTEX_SHADER,
+        Stage::Hull => PipelineStage::HULL_SHADER,
+        Stage::Domain => PipelineStage::DOMAIN_SHADER,
+        Stage::Geometry => PipelineStage::GEOMETRY_SHADER,
+        Stage::Fragment => PipelineStage::FRAGMENT_SHADER,
+        Stage::Compute => PipelineStage::COMPUTE_SHADER,
+    }
+}
+
+/// Get pipeline stages in which shaders of the `stages` are executed.
+/// Useful to derive stage mask of barriers for resources
+/// accessed from descriptors with given stage flags.
+/// Bits that don't correspond to any shader stage are ignored.
+pub fn shader_pipeline_stages(stages: ShaderStageFlags) -> PipelineStage {
+    STAGES
+        .iter()
+        .filter(|&&stage| stages.contains(stage.into()))
+        .fold(PipelineStage::empty(), |acc, &stage| {
+            acc | shader_pipeline_stage(stage)
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_shader_pipeline_stages() {
+        let table = [
+            (Stage::Vertex, PipelineStage::VERTEX_SHADER),
+            (Stage::Hull, PipelineStage::HULL_SHADER),
+            (Stage::Domain, PipelineStage::DOMAIN_SHADER),
+            (Stage::Geometry, PipelineStage::GEOMETRY_SHADER),
+            (Stage::Fragment, PipelineStage::FRAGMENT_SHADER),
+            (Stage::Compute, PipelineStage::COMPUTE_SHADER),
+        ];
+        for &(stage, pipeline_stage) in &table {
+            assert_eq!(shader_pipeline_stage(stage), pipeline_stage);
+            assert_eq!(shader_pipeline_stages(stage.into()), pipeline_stage);
+        }
+
+        assert_eq!(
+            shader_pipeline_stages(ShaderStageFlags::VERTEX | ShaderStageFlags::FRAGMENT),
+            PipelineStage::VERTEX_SHADER | PipelineStage::FRAGMENT_SHADER
+        );
+        assert_eq!(
+            shader_pipeline_stages(ShaderStageFlags::GRAPHICS),
+            PipelineStage::VERTEX_SHADER
+                | PipelineStage::HULL_SHADER
+                | PipelineStage::DOMAIN_SHADER
+                | PipelineStage::GEOMETRY_SHADER
+                | PipelineStage::FRAGMENT_SHADER
+        );
+        assert_eq!(
+            shader_pipeline_stages(ShaderStageFlags::ALL),
+            PipelineStage::VERTEX_SHADER
+                | PipelineStage::HULL_SHADER
+                | PipelineStage::DOMAIN_SHADER
+                | PipelineStage::GEOMETRY_SHADER
+                | PipelineStage::FRAGMENT_SHADER
+                | PipelineStage::COMPUTE_SHADER
+        );
+        assert!(shader_pipeline_stages(ShaderStageFlags::empty()).is_empty());
+    }
+}
@@ -138,6 +138,205 @@ impl AsAttribute for TexCoord {
     const FORMAT: Format = Format::Rg32Sfloat;
 }
 
+/// Type for position attribute of vertex with half-float components.
+/// Fourth component is always 1 as 3-component half-float formats
+/// are rarely supported for vertex buffers.
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HalfPosition(pub [u16; 4]);
+impl<T> From<T> for HalfPosition
+where
+    T: Into<[f32; 3]>,
+{
+    fn from(from: T) -> Self {
+        let [x, y, z] = from.into();
+        HalfPosition([f32_to_f16(x), f32_to_f16(y), f32_to_f16(z), f32_to_f16(1.0)])
+    }
+}
+
+impl HalfPosition {
+    /// Get position with full precision components.
+    pub fn unpack(&self) -> [f32; 3] {
+        let [x, y, z, _] = self.0;
+        [f16_to_f32(x), f16_to_f32(y), f16_to_f32(z)]
+    }
+}
+
+impl AsAttribute for HalfPosition {
+    const NAME: &'static str = "position";
+    const FORMAT: Format = Format::Rgba16Sfloat;
+}
+
+/// Type for texture coord attribute of vertex with half-float components.
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HalfTexCoord(pub [u16; 2]);
+impl<T> From<T> for HalfTexCoord
+where
+    T: Into<[f32; 2]>,
+{
+    fn from(from: T) -> Self {
+        let [u, v] = from.into();
+        HalfTexCoord([f32_to_f16(u), f32_to_f16(v)])
+    }
+}
+
+impl HalfTexCoord {
+    /// Get texture coord with full precision components.
+    pub fn unpack(&self) -> [f32; 2] {
+        let [u, v] = self.0;
+        [f16_to_f32(u), f16_to_f32(v)]
+    }
+}
+
+impl AsAttribute for HalfTexCoord {
+    const NAME: &'static str = "tex_coord";
+    const FORMAT: Format = Format::Rg16Sfloat;
+}
+
+/// Type for normal attribute of vertex packed into 10-bit signed normalized components.
+/// Shader reads it as regular `vec3` normal.
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PackedNormal(pub u32);
+impl<T> From<T> for PackedNormal
+where
+    T: Into<[f32; 3]>,
+{
+    fn from(from: T) -> Self {
+        let [x, y, z] = from.into();
+        PackedNormal(pack_snorm(x, 10) | pack_snorm(y, 10) << 10 | pack_snorm(z, 10) << 20)
+    }
+}
+
+impl PackedNormal {
+    /// Get normal with full precision components.
+    pub fn unpack(&self) -> [f32; 3] {
+        [
+            unpack_snorm(self.0, 10),
+            unpack_snorm(self.0 >> 10, 10),
+            unpack_snorm(self.0 >> 20, 10),
+        ]
+    }
+}
+
+impl AsAttribute for PackedNormal {
+    const NAME: &'static str = "normal";
+    const FORMAT: Format = Format::A2b10g10r10Snorm;
+}
+
+/// Type for normal attribute of vertex in octahedral encoding
+/// with 16-bit signed normalized components.
+/// Shader must decode it, see [`unpack`] for reference.
+///
+/// [`unpack`]: #method.unpack
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OctNormal(pub [u16; 2]);
+impl<T> From<T> for OctNormal
+where
+    T: Into<[f32; 3]>,
+{
+    fn from(from: T) -> Self {
+        let [x, y, z] = from.into();
+        let norm = x.abs() + y.abs() + z.abs();
+        let (mut u, mut v) = (x / norm, y / norm);
+        if z < 0.0 {
+            let (fu, fv) = (u, v);
+            u = (1.0 - fv.abs()) * fu.signum();
+            v = (1.0 - fu.abs()) * fv.signum();
+        }
+        OctNormal([pack_snorm(u, 16) as u16, pack_snorm(v, 16) as u16])
+    }
+}
+
+impl OctNormal {
+    /// Decode normal of unit length.
+    pub fn unpack(&self) -> [f32; 3] {
+        let mut x = unpack_snorm(self.0[0] as u32, 16);
+        let mut y = unpack_snorm(self.0[1] as u32, 16);
+        let z = 1.0 - x.abs() - y.abs();
+        let t = (-z).max(0.0);
+        x -= t * x.signum();
+        y -= t * y.signum();
+        let length = (x * x + y * y + z * z).sqrt();
+        [x / length, y / length, z / length]
+    }
+}
+
+impl AsAttribute for OctNormal {
+    const NAME: &'static str = "normal";
+    const FORMAT: Format = Format::Rg16Snorm;
+}
+
+/// Convert `value` to half-float bits rounding to nearest.
+fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+
+    if exponent == 0xff {
+        // Infinity or NaN.
+        return sign | 0x7c00 | if mantissa != 0 { 0x200 } else { 0 };
+    }
+
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1f {
+        sign | 0x7c00
+    } else if exponent <= 0 {
+        if exponent < -10 {
+            return sign;
+        }
+        // Subnormal half-float.
+        let mantissa = mantissa | 0x80_0000;
+        let shift = (14 - exponent) as u32;
+        let round = (mantissa >> (shift - 1)) & 1;
+        sign | ((mantissa >> shift) + round) as u16
+    } else {
+        // Carry from rounding correctly overflows into exponent.
+        let round = (mantissa >> 12) & 1;
+        sign | (((exponent as u32) << 10 | mantissa >> 13) + round) as u16
+    }
+}
+
+/// Convert half-float bits to `f32`.
+fn f16_to_f32(half: u16) -> f32 {
+    let sign = ((half & 0x8000) as u32) << 16;
+    let exponent = ((half >> 10) & 0x1f) as u32;
+    let mantissa = (half & 0x3ff) as u32;
+    match exponent {
+        0 => {
+            let value = mantissa as f32 * (1.0 / (1 << 24) as f32);
+            if sign != 0 {
+                -value
+            } else {
+                value
+            }
+        }
+        0x1f => f32::from_bits(sign | 0x7f80_0000 | mantissa << 13),
+        _ => f32::from_bits(sign | (exponent + 127 - 15) << 23 | mantissa << 13),
+    }
+}
+
+/// Pack `value` clamped to `-1.0 ..= 1.0` into signed normalized integer of `bits` width.
+fn pack_snorm(value: f32, bits: u32) -> u32 {
+    let max = ((1 << (bits - 1)) - 1) as f32;
+    let value = (value.clamp(-1.0, 1.0) * max).round() as i32;
+    value as u32 & ((1 << bits) - 1)
+}
+
+/// Unpack signed normalized integer of `bits` width from low bits of `packed`.
+fn unpack_snorm(packed: u32, bits: u32) -> f32 {
+    let max = ((1 << (bits - 1)) - 1) as f32;
+    let value = ((packed << (32 - bits)) as i32) >> (32 - bits);
+    (value as f32 / max).max(-1.0)
+}
+
 /// Vertex format contains information to initialize graphics pipeline
 /// Attributes must be sorted by offset.
 #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -593,6 +792,31 @@ impl AsVertex for PosNormTangTex {
     }
 }
 
+/// Vertex format with half-float position and texture coordinates
+/// and packed normal attributes.
+/// Takes half the memory of `PosNormTex`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PackedPosNormTex {
+    /// Position of the vertex in 3D space.
+    pub position: HalfPosition,
+    /// Normal vector of the vertex.
+    pub normal: PackedNormal,
+    /// UV texture coordinates used by the vertex.
+    pub tex_coord: HalfTexCoord,
+}
+
+impl AsVertex for PackedPosNormTex {
+    fn vertex() -> VertexFormat {
+        VertexFormat::new((
+            HalfPosition::vertex(),
+            PackedNormal::vertex(),
+            HalfTexCoord::vertex(),
+        ))
+    }
+}
+
 /// Full vertex transformation attribute.
 /// Typically provided on per-instance basis.
 /// It takes 4 attribute locations.
@@ -651,3 +875,54 @@ macro_rules! impl_as_attributes {
 }
 
 impl_as_attributes!(A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_close(actual: &[f32], expected: &[f32], tolerance: f32) {
+        for (a, e) in actual.iter().zip(expected) {
+            assert!(
+                (a - e).abs() <= tolerance,
+                "{:?} differs from {:?}",
+                actual,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_half_float() {
+        for &value in &[0.0, 1.0, -2.5, 0.1, 65504.0, 6.0e-5, -3.0e-7] {
+            let unpacked = f16_to_f32(f32_to_f16(value));
+            assert!((unpacked - value).abs() <= value.abs() / 1024.0 + 6.0e-8);
+        }
+        assert_eq!(f32_to_f16(1.0), 0x3c00);
+        assert_eq!(f32_to_f16(-2.0), 0xc000);
+        assert_eq!(f32_to_f16(1.0e6), 0x7c00);
+        assert!(f16_to_f32(f32_to_f16(f32::NAN)).is_nan());
+
+        let position = HalfPosition::from([1.5, -0.25, 100.0]);
+        assert_eq!(position.unpack(), [1.5, -0.25, 100.0]);
+        assert_eq!(size_of::<PackedPosNormTex>(), 16);
+        assert_eq!(PackedPosNormTex::vertex().stride, 16);
+    }
+
+    #[test]
+    fn test_packed_normal() {
+        let s = 1.0 / 3f32.sqrt();
+        let normals = [
+            [0.0, 0.0, 1.0],
+            [0.0, 0.0, -1.0],
+            [1.0, 0.0, 0.0],
+            [0.0, -1.0, 0.0],
+            [s, -s, s],
+            [-s, s, -s],
+            [0.6, 0.0, -0.8],
+        ];
+        for normal in &normals {
+            assert_close(&PackedNormal::from(*normal).unpack(), normal, 1.0 / 511.0);
+            assert_close(&OctNormal::from(*normal).unpack(), normal, 1.0e-4);
+        }
+    }
+}
@@ -70,7 +70,9 @@ impl<B: Backend> Barriers<B> {
         }
     }
 
-    /// Add an image to the barriers
+    /// Add an image to the barriers.
+    /// Only subresources in `image_range` are transitioned,
+    /// the rest of the image is left in its current layout.
     pub fn add_image(
         &mut self,
         image: Handle<Image<B>>,
@@ -113,6 +115,39 @@ impl<B: Backend> Barriers<B> {
         }
     }
 
+    /// Add subresources of an image to the barriers.
+    ///
+    /// Unlike `add_image`, last layout is taken from `layouts` per mip level and array layer,
+    /// so subresources in different layouts get separate transitions.
+    /// `layouts` is updated to `next_layout` for subresources in `image_range`.
+    pub fn add_image_subresources(
+        &mut self,
+        image: Handle<Image<B>>,
+        layouts: &mut SubresourceLayouts,
+        image_range: image::SubresourceRange,
+        last_stage: pso::PipelineStage,
+        last_access: image::Access,
+        target_layout: image::Layout,
+        next_stage: pso::PipelineStage,
+        next_access: image::Access,
+        next_layout: image::Layout,
+    ) {
+        for (range, last_layout) in layouts.split(&image_range) {
+            self.add_image(
+                image.clone(),
+                range,
+                last_stage,
+                last_access,
+                last_layout,
+                target_layout,
+                next_stage,
+                next_access,
+                next_layout,
+            );
+        }
+        layouts.set(&image_range, next_layout);
+    }
+
     /// Add a buffer to the barriers
     pub fn add_buffer(
         &mut self,
@@ -187,3 +222,107 @@ impl<B: Backend> Barriers<B> {
         self.after_image_transitions.clear();
     }
 }
+
+/// Layouts of individual mip levels and array layers of an image.
+///
+/// Allows transitioning part of the image, e.g. single mip level during mip generation,
+/// while keeping track of the layouts the rest of the image is in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SubresourceLayouts {
+    layers: image::Layer,
+    layouts: Vec<image::Layout>,
+}
+
+impl SubresourceLayouts {
+    /// Create tracker for image with `levels` and `layers` in `layout`.
+    pub fn new(levels: image::Level, layers: image::Layer, layout: image::Layout) -> Self {
+        SubresourceLayouts {
+            layers,
+            layouts: vec![layout; levels as usize * layers as usize],
+        }
+    }
+
+    fn index(&self, level: image::Level, layer: image::Layer) -> usize {
+        assert!(layer < self.layers, "Layer {} is out of bounds", layer);
+        let index = level as usize * self.layers as usize + layer as usize;
+        assert!(
+            index < self.layouts.len(),
+            "Level {} is out of bounds",
+            level
+        );
+        index
+    }
+
+    /// Current layout of the subresource.
+    pub fn layout(&self, level: image::Level, layer: image::Layer) -> image::Layout {
+        self.layouts[self.index(level, layer)]
+    }
+
+    /// Split `range` into ranges of subresources that share the same layout.
+    /// Each level yields ranges of consecutive layers.
+    pub fn split(
+        &self,
+        range: &image::SubresourceRange,
+    ) -> Vec<(image::SubresourceRange, image::Layout)> {
+        let mut result = Vec::new();
+        for level in range.levels.clone() {
+            let mut start = range.layers.start;
+            while start < range.layers.end {
+                let layout = self.layout(level, start);
+                let mut end = start + 1;
+                while end < range.layers.end && self.layout(level, end) == layout {
+                    end += 1;
+                }
+
+                result.push((
+                    image::SubresourceRange {
+                        aspects: range.aspects,
+                        levels: level..level + 1,
+                        layers: start..end,
+                    },
+                    layout,
+                ));
+                start = end;
+            }
+        }
+        result
+    }
+
+    /// Set layout of subresources in `range`.
+    pub fn set(&mut self, range: &image::SubresourceRange, layout: image::Layout) {
+        for level in range.levels.clone() {
+            for layer in range.layers.clone() {
+                let index = self.index(level, layer);
+                self.layouts[index] = layout;
+            }
+        }
+    }
+}
+
+#[test]
+fn test_transition_single_level() {
+    let mut layouts = SubresourceLayouts::new(4, 2, image::Layout::ShaderReadOnlyOptimal);
+    let range = image::SubresourceRange {
+        aspects: rendy_core::hal::format::Aspects::COLOR,
+        levels: 2..3,
+        layers: 0..2,
+    };
+
+    assert_eq!(
+        layouts.split(&range),
+        vec![(range.clone(), image::Layout::ShaderReadOnlyOptimal)]
+    );
+    layouts.set(&range, image::Layout::TransferDstOptimal);
+
+    assert_eq!(layouts.layout(1, 0), image::Layout::ShaderReadOnlyOptimal);
+    assert_eq!(layouts.layout(2, 1), image::Layout::TransferDstOptimal);
+    assert_eq!(layouts.layout(3, 0), image::Layout::ShaderReadOnlyOptimal);
+
+    let whole = image::SubresourceRange {
+        levels: 0..4,
+        ..range.clone()
+    };
+    let split = layouts.split(&whole);
+    assert_eq!(split.len(), 4);
+    assert_eq!(split[2], (range, image::Layout::TransferDstOptimal));
+}
@@ -7,8 +7,8 @@ use {
             Submission, Supports,
         },
         core::Device,
-        resource::{Handle, Image},
-        upload::ImageState,
+        resource::{Buffer, Escape, Handle, Image},
+        upload::{ImageState, ImageStateOrLayout, ImageTransition},
     },
     rendy_core::hal::device::{Device as _, OutOfMemory},
     smallvec::SmallVec,
@@ -219,6 +219,102 @@ where
         Ok(())
     }
 
+    /// Upload first mip level of the `image` from `staging` and fill the rest of mip levels from it.
+    ///
+    /// Copy and blits are recorded into the same command buffer as other blits on the queue,
+    /// so a batch of images gets uploaded and mipmapped with single submission on next flush.
+    /// All layers of the first level are copied from `staging`
+    /// with `data_width` and `data_height` texels between rows and layers.
+    ///
+    /// # Safety
+    ///
+    /// `device` must be the same that was used to create this `Blitter`.
+    /// `image` and `staging` must belong to the `device`.
+    /// `image` must not be used by device, its previous content is discarded.
+    /// `next.queue` must belong to a family that supports graphics.
+    pub unsafe fn upload_image_with_mips(
+        &self,
+        device: &Device<B>,
+        image: Handle<Image<B>>,
+        data_width: u32,
+        data_height: u32,
+        staging: Escape<Buffer<B>>,
+        filter: rendy_core::hal::image::Filter,
+        next: ImageState,
+    ) -> Result<(), OutOfMemory> {
+        use rendy_core::hal::{
+            image::{Access, Layout, Offset, SubresourceLayers, SubresourceRange},
+            pso::PipelineStage,
+        };
+
+        let mut family_ops = self.family_ops[next.queue.family.index]
+            .as_ref()
+            .unwrap()
+            .lock();
+
+        let next_ops = family_ops.next_ops(device, next.queue.index)?;
+        let mut encoder = next_ops.command_buffer.encoder();
+
+        let aspects = image.format().surface_desc().aspects;
+        let range = SubresourceRange {
+            aspects,
+            levels: 0..image.levels(),
+            layers: 0..image.layers(),
+        };
+
+        let copied = ImageState {
+            queue: next.queue,
+            stage: PipelineStage::TRANSFER,
+            access: Access::TRANSFER_WRITE,
+            layout: Layout::TransferDstOptimal,
+        };
+
+        let transition = ImageTransition::new(ImageStateOrLayout::undefined(), copied);
+        encoder.pipeline_barrier(
+            transition.stages.clone(),
+            rendy_core::hal::memory::Dependencies::empty(),
+            Some(transition.barrier(image.raw(), range.clone())),
+        );
+
+        encoder.copy_buffer_to_image(
+            staging.raw(),
+            image.raw(),
+            Layout::TransferDstOptimal,
+            Some(rendy_core::hal::command::BufferImageCopy {
+                buffer_offset: 0,
+                buffer_width: data_width,
+                buffer_height: data_height,
+                image_layers: SubresourceLayers {
+                    aspects,
+                    level: 0,
+                    layers: 0..image.layers(),
+                },
+                image_offset: Offset::ZERO,
+                image_extent: image.kind().extent(),
+            }),
+        );
+
+        if image.levels() > 1 {
+            fill_mips(
+                &mut encoder,
+                &image,
+                filter,
+                std::iter::repeat(copied),
+                std::iter::repeat(next),
+            );
+        } else {
+            let transition = ImageTransition::new(copied, next);
+            encoder.pipeline_barrier(
+                transition.stages.clone(),
+                rendy_core::hal::memory::Dependencies::empty(),
+                Some(transition.barrier(image.raw(), range)),
+            );
+        }
+
+        next_ops.staging_buffers.push(staging);
+        Ok(())
+    }
+
     /// Blit provided regions of `src_image` to `dst_image`.
     ///
     /// # Safety
@@ -267,6 +363,29 @@ where
         }
     }
 
+    /// Wait for all flushed blits to complete.
+    ///
+    /// # Safety
+    ///
+    /// `device` must be the same that was used to create this `Blitter`.
+    ///
+    pub(crate) unsafe fn wait(
+        &mut self,
+        device: &Device<B>,
+    ) -> Result<(), rendy_core::hal::device::OomOrDeviceLost> {
+        let fences: Vec<_> = self
+            .family_ops
+            .iter_mut()
+            .flatten()
+            .flat_map(|ops| ops.get_mut().pending.iter().map(|pending| &pending.fence))
+            .collect();
+
+        if !fences.is_empty() {
+            device.wait_for_fences(fences, rendy_core::hal::device::WaitFor::All, !0)?;
+        }
+        Ok(())
+    }
+
     /// Flush new updates.
     ///
     /// # Safety
@@ -296,6 +415,38 @@ where
     }
 }
 
+/// Fill all mip levels from the first level of provided image,
+/// recording blits into `encoder`.
+///
+/// Unlike [`Blitter::fill_mips`] this doesn't own a submission,
+/// so blits can be batched with other commands.
+///
+/// [`Blitter::fill_mips`]: struct.Blitter.html#method.fill_mips
+///
+/// # Safety
+///
+/// `image` must have been created from the same `Device` as `encoder`.
+/// `last` state must be valid for corresponding image layer at the time of command execution.
+/// `last` and `next` should contain at least `image.levels()` elements.
+/// `image.levels()` must be greater than 1
+pub unsafe fn fill_mips<B, C, L>(
+    encoder: &mut Encoder<'_, B, C, L>,
+    image: &Handle<Image<B>>,
+    filter: rendy_core::hal::image::Filter,
+    last: impl IntoIterator<Item = ImageState>,
+    next: impl IntoIterator<Item = ImageState>,
+) where
+    B: rendy_core::hal::Backend,
+    C: Supports<Graphics>,
+    L: Level,
+{
+    let (_, blits) = BlitRegion::mip_blits_for_image(image, last, next);
+    for blit in blits {
+        log::trace!("Blit: {:#?}", blit);
+        blit_image(encoder, image, image, filter, Some(blit));
+    }
+}
+
 /// Blits one or more regions from src_image into dst_image using
 /// specified Filter
 ///
@@ -390,6 +541,8 @@ pub(crate) struct FamilyGraphicsOps<B: rendy_core::hal::Backend> {
 struct GraphicsOps<B: rendy_core::hal::Backend, S> {
     command_buffer: CommandBuffer<B, Graphics, S, PrimaryLevel, IndividualReset>,
     fence: B::Fence,
+    /// Staging buffers used by recorded commands.
+    staging_buffers: Vec<Escape<Buffer<B>>>,
 }
 
 impl<B> FamilyGraphicsOps<B>
@@ -414,6 +567,7 @@ where
             self.pending.push_back(GraphicsOps {
                 command_buffer,
                 fence: next.fence,
+                staging_buffers: next.staging_buffers,
             });
         }
     }
@@ -437,6 +591,7 @@ where
                         Ok(GraphicsOps {
                             command_buffer: pool.allocate_buffers(1).remove(0),
                             fence: device.create_fence(false)?,
+                            staging_buffers: Vec::new(),
                         })
                     },
                     Ok,
@@ -446,6 +601,7 @@ where
                 *slot = Some(GraphicsOps {
                     command_buffer: initial.command_buffer.begin(OneShot, ()),
                     fence: initial.fence,
+                    staging_buffers: initial.staging_buffers,
                 });
 
                 Ok(slot.as_mut().unwrap())
@@ -473,9 +629,12 @@ where
                     device
                         .reset_fence(&pending.fence)
                         .expect("Can always reset signalled fence");
+                    let mut staging_buffers = pending.staging_buffers;
+                    staging_buffers.clear();
                     self.initial.push(GraphicsOps {
                         command_buffer: pending.command_buffer.mark_complete().reset(),
                         fence: pending.fence,
+                        staging_buffers,
                     })
                 }
             }
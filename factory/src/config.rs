@@ -14,6 +14,7 @@ use crate::{
 /// [`BasicHeapsConfigure`] can be used as sane default.
 /// `queues` - [`QueuesConfigure`] implementation to configure device queues creation.
 /// [`OneGraphicsQueue`] can be used if only one graphics queue will satisfy requirements.
+/// `app` - [`AppInfo`] reported to the driver when instance is created.
 ///
 /// [`DeviceConfigure`]: trait.DevicesConfigure.html
 /// [`BasicDevicesConfigure`]: struct.BasicDevicesConfigure.html
@@ -21,6 +22,7 @@ use crate::{
 /// [`BasicHeapsConfigure`]: struct.BasicHeapsConfigure.html
 /// [`QueuesConfigure`]: trait.QueuesConfigure.html
 /// [`OneGraphicsQueue`]: struct.OneGraphicsQueue.html
+/// [`AppInfo`]: struct.AppInfo.html
 #[derive(Clone, Debug, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Config<D = BasicDevicesConfigure, H = BasicHeapsConfigure, Q = OneGraphicsQueue> {
@@ -32,12 +34,55 @@ pub struct Config<D = BasicDevicesConfigure, H = BasicHeapsConfigure, Q = OneGra
 
     /// Config for queue families.
     pub queues: Q,
+
+    /// Application name and version.
+    /// Configs saved without it get `AppInfo::default()`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub app: AppInfo,
+}
+
+/// Application info passed to instance creation.
+///
+/// Drivers may apply application-specific profiles keyed by the name,
+/// so this affects behavior and not only how application is shown in GPU tools.
+/// gfx-hal 0.5 doesn't accept engine name and version,
+/// backends report their own instead.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AppInfo {
+    /// Application name.
+    pub name: String,
+
+    /// Application version.
+    pub version: u32,
+}
+
+impl AppInfo {
+    /// Create application info with specified name and version.
+    pub fn new(name: impl Into<String>, version: u32) -> Self {
+        AppInfo {
+            name: name.into(),
+            version,
+        }
+    }
+}
+
+impl Default for AppInfo {
+    fn default() -> Self {
+        AppInfo::new("Rendy", 1)
+    }
 }
 
 /// Queues configuration.
 ///
 /// Method [`configure`] receives collection of queue families and
-/// returns an iterator over family ids and number of queues.
+/// returns an iterator over family ids and priorities of queues to create.
+/// One queue is created for each priority.
+///
+/// If more queues are requested than family supports
+/// the number of queues is clamped to `QueueFamily::max_queues` with a warning.
+/// Families with no queues to create are skipped.
+/// Created queues are available via `Family::queue_mut` with indices up to `Family::queue_count`.
 ///
 /// [`configure`]: trait.QueuesConfigure.html#tymethod.configure
 pub unsafe trait QueuesConfigure {
@@ -60,9 +105,11 @@ pub unsafe trait QueuesConfigure {
 /// TODO: Try to pick family that is capable of presenting
 /// This is possible in platform-dependent way for some platforms.
 ///
+/// To pick multiple queues from the graphics family [`GraphicsQueues`] can be used.
 /// To pick multiple families with require number of queues
 /// a custom [`QueuesConfigure`] implementation can be used instead.
 ///
+/// [`GraphicsQueues`]: struct.GraphicsQueues.html
 /// [`QueuesConfigure`]: trait.QueuesConfigure.html
 #[derive(Clone, Copy, Debug, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -91,6 +138,44 @@ unsafe impl QueuesConfigure for OneGraphicsQueue {
     }
 }
 
+/// QueuePicker that picks first graphics queue family
+/// and requests specified number of queues with equal priorities from it.
+///
+/// Multiple queues allow recording and submitting work in parallel.
+/// Number of queues is clamped to the number family supports.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GraphicsQueues(pub usize);
+
+impl Default for GraphicsQueues {
+    fn default() -> Self {
+        GraphicsQueues(1)
+    }
+}
+
+unsafe impl QueuesConfigure for GraphicsQueues {
+    type Priorities = Vec<f32>;
+    type Families = Option<(FamilyId, Vec<f32>)>;
+    fn configure(
+        &self,
+        device: DeviceId,
+        families: &[impl rendy_core::hal::queue::QueueFamily],
+    ) -> Option<(FamilyId, Vec<f32>)> {
+        families
+            .iter()
+            .find(|f| f.queue_type().supports_graphics() && f.max_queues() > 0)
+            .map(|f| {
+                (
+                    FamilyId {
+                        device,
+                        index: f.id().0,
+                    },
+                    vec![1.0; self.0],
+                )
+            })
+    }
+}
+
 /// Saved config for queues.
 /// This config can be loaded from config files
 /// in any format supported by serde ecosystem.
@@ -2,30 +2,43 @@ use {
     crate::{
         blitter::Blitter,
         command::{
-            families_from_device, CommandPool, Families, Family, FamilyId, Fence, QueueType, Reset,
+            families_from_device, CommandPool, Encoder, Event, EventCreationError, Families,
+            Family, FamilyId, Fence, PrimaryLevel, QueryPool, QueryType, Queue, QueueId, QueueType,
+            Reset, Submittable,
         },
         config::{Config, DevicesConfigure, HeapsConfigure, QueuesConfigure},
-        core::{rendy_with_slow_safety_checks, Device, DeviceId, Instance, InstanceId},
+        core::{
+            rendy_with_slow_safety_checks, Capabilities, Device, DeviceId, Instance, InstanceId,
+        },
         descriptor::DescriptorAllocator,
-        memory::{self, Heaps, MemoryUsage, TotalMemoryUtilization, Write},
+        memory::{
+            self, HeapBudget, Heaps, HeapsError, MemoryBlock, MemoryUsage, TotalMemoryUtilization,
+            Write,
+        },
+        oneshot::OneshotPools,
         resource::*,
-        upload::{BufferState, ImageState, ImageStateOrLayout, Uploader},
-        wsi::{Surface, SwapchainError, Target},
+        timeline::{wait_fences, Timeline},
+        upload::{
+            pack_layer_mip_data, BufferState, ImageState, ImageStateOrLayout, ImageTransition,
+            LayerMipData, Staging, Uploader,
+        },
+        wsi::{clamp_image_count, RecreateError, Surface, SwapchainError, SwapchainMode, Target},
     },
     rendy_core::{
         hal::{
             adapter::{Adapter, Gpu, PhysicalDevice},
-            buffer,
+            buffer::{self, ViewCreationError},
             device::{
                 AllocationError, CreationError, Device as _, MapError, OomOrDeviceLost,
                 OutOfMemory, WaitFor,
             },
             format, image,
             pso::DescriptorSetLayoutBinding,
+            queue::QueueFamily as _,
             window::{Extent2D, InitError, Surface as GfxSurface},
             Backend, Features, Instance as _, Limits,
         },
-        HasRawWindowHandle,
+        HasRawWindowHandle, RawWindowHandle,
     },
     smallvec::SmallVec,
     std::{borrow::BorrowMut, cmp::max, mem::ManuallyDrop},
@@ -37,6 +50,7 @@ struct ResourceHub<B: Backend> {
     buffers: ResourceTracker<Buffer<B>>,
     images: ResourceTracker<Image<B>>,
     views: ResourceTracker<ImageView<B>>,
+    buffer_views: ResourceTracker<BufferView<B>>,
     layouts: ResourceTracker<DescriptorSetLayout<B>>,
     sets: ResourceTracker<DescriptorSet<B>>,
     samplers: ResourceTracker<Sampler<B>>,
@@ -52,6 +66,7 @@ where
             buffers: ResourceTracker::default(),
             images: ResourceTracker::default(),
             views: ResourceTracker::default(),
+            buffer_views: ResourceTracker::default(),
             layouts: ResourceTracker::default(),
             sets: ResourceTracker::default(),
             samplers: ResourceTracker::default(),
@@ -75,6 +90,8 @@ where
         self.sets
             .cleanup(|s| s.dispose(allocator), &next, &complete);
         self.views.cleanup(|v| v.dispose(device), &next, &complete);
+        self.buffer_views
+            .cleanup(|v| v.dispose(device), &next, &complete);
         self.layouts
             .cleanup(|l| l.dispose(device), &next, &complete);
         self.buffers
@@ -94,6 +111,7 @@ where
         drop(self.samplers_cache);
         self.sets.dispose(|s| s.dispose(allocator));
         self.views.dispose(|v| v.dispose(device));
+        self.buffer_views.dispose(|v| v.dispose(device));
         self.layouts.dispose(|l| l.dispose(device));
         self.buffers.dispose(|b| b.dispose(device, heaps));
         self.images.dispose(|i| i.dispose(device, heaps));
@@ -110,6 +128,14 @@ pub enum UploadError {
     Map(MapError),
     /// Failed to upload the data.
     Upload(OutOfMemory),
+    /// Resource was used by another queue than the one upload is submitted to.
+    /// Transferring resources across queues is not supported.
+    QueueMismatch {
+        /// Queue that used the resource last.
+        last: QueueId,
+        /// Queue that the upload is submitted to.
+        next: QueueId,
+    },
 }
 
 impl std::fmt::Display for UploadError {
@@ -118,6 +144,11 @@ impl std::fmt::Display for UploadError {
             UploadError::Create(err) => write!(fmt, "Upload failed: {:?}", err),
             UploadError::Map(err) => write!(fmt, "Upload failed: {:?}", err),
             UploadError::Upload(err) => write!(fmt, "Upload failed: {:?}", err),
+            UploadError::QueueMismatch { last, next } => write!(
+                fmt,
+                "Upload failed: resource is used by queue {:?}, but upload is submitted to {:?}",
+                last, next
+            ),
         }
     }
 }
@@ -128,6 +159,106 @@ impl std::error::Error for UploadError {
             UploadError::Create(err) => Some(err),
             UploadError::Map(err) => Some(err),
             UploadError::Upload(err) => Some(err),
+            UploadError::QueueMismatch { .. } => None,
+        }
+    }
+}
+
+/// Failure creating an image with initial data.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ImageDataError {
+    /// Failed to create the image.
+    Create(ImageCreationError),
+    /// Subresource is out of image bounds or its data is specified more than once.
+    BadSubresource {
+        /// Array layer of the subresource.
+        layer: image::Layer,
+        /// Mip level of the subresource.
+        level: image::Level,
+    },
+    /// Size of the data doesn't match size of the subresource.
+    BadSize {
+        /// Array layer of the subresource.
+        layer: image::Layer,
+        /// Mip level of the subresource.
+        level: image::Level,
+        /// Size of the subresource in bytes.
+        expected: u64,
+        /// Size of provided data in bytes.
+        actual: u64,
+    },
+    /// Failed to upload the data.
+    Upload(UploadError),
+}
+
+impl std::fmt::Display for ImageDataError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImageDataError::Create(err) => write!(
+                fmt,
+                "Failed to create image with data because of failure to create the image: {:?}",
+                err
+            ),
+            ImageDataError::BadSubresource { layer, level } => write!(
+                fmt,
+                "Failed to create image with data because layer {} of level {} is out of bounds or specified twice",
+                layer, level
+            ),
+            ImageDataError::BadSize {
+                layer,
+                level,
+                expected,
+                actual,
+            } => write!(
+                fmt,
+                "Failed to create image with data because layer {} of level {} requires {} bytes but {} provided",
+                layer, level, expected, actual
+            ),
+            ImageDataError::Upload(err) => write!(
+                fmt,
+                "Failed to create image with data because of upload failure: {:?}",
+                err
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ImageDataError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ImageDataError::Create(err) => Some(err),
+            ImageDataError::BadSubresource { .. } => None,
+            ImageDataError::BadSize { .. } => None,
+            ImageDataError::Upload(err) => Some(err),
+        }
+    }
+}
+
+/// Failure saving pipeline cache.
+#[derive(Debug)]
+pub enum PipelineCacheError {
+    /// Failed to retrieve data from the cache.
+    Data(OutOfMemory),
+    /// Failed to write the data.
+    Io(std::io::Error),
+}
+
+impl std::fmt::Display for PipelineCacheError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PipelineCacheError::Data(err) => {
+                write!(fmt, "Failed to save pipeline cache: {:?}", err)
+            }
+            PipelineCacheError::Io(err) => write!(fmt, "Failed to save pipeline cache: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for PipelineCacheError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PipelineCacheError::Data(err) => Some(err),
+            PipelineCacheError::Io(err) => Some(err),
         }
     }
 }
@@ -167,8 +298,14 @@ pub struct Factory<B: Backend> {
     epochs: Vec<parking_lot::RwLock<Vec<u64>>>,
     uploader: Uploader<B>,
     blitter: Blitter<B>,
+    oneshot: OneshotPools<B>,
+    timeline: parking_lot::Mutex<Timeline<B>>,
     families_indices: Vec<usize>,
-    device: Device<B>,
+    features: Features,
+    capabilities: Capabilities,
+    pipeline_cache: Option<B::PipelineCache>,
+    device: ManuallyDrop<Device<B>>,
+    device_released: bool,
     adapter: Adapter<B>,
     instance: InstanceOrId<B>,
 }
@@ -184,7 +321,31 @@ where
     B: Backend,
 {
     fn drop(&mut self) {
+        if self.device_released {
+            log::trace!("Factory dropped after device was released");
+            return;
+        }
+
         log::debug!("Dropping factory");
+        unsafe {
+            self.dispose_resources();
+            ManuallyDrop::drop(&mut self.device);
+        }
+        log::trace!("Device destroyed");
+        log::trace!("Factory dropped");
+    }
+}
+
+impl<B> Factory<B>
+where
+    B: Backend,
+{
+    /// Destroy all resources created by the factory, but not the device.
+    ///
+    /// # Safety
+    ///
+    /// Must be called only once, after which only the device can be used.
+    unsafe fn dispose_resources(&mut self) {
         self.wait_idle().unwrap();
 
         unsafe {
@@ -193,6 +354,10 @@ where
             log::trace!("Uploader disposed");
             self.blitter.dispose(&self.device);
             log::trace!("Blitter disposed");
+            self.oneshot.dispose(&self.device);
+            log::trace!("Oneshot pools disposed");
+            self.timeline.get_mut().dispose(&self.device);
+            log::trace!("Timeline disposed");
             std::ptr::read(&mut *self.resources).dispose(
                 &self.device,
                 self.heaps.get_mut(),
@@ -216,7 +381,28 @@ where
             log::trace!("Descriptor allocator disposed");
         }
 
-        log::trace!("Factory dropped");
+        if let Some(cache) = self.pipeline_cache.take() {
+            unsafe {
+                self.device.destroy_pipeline_cache(cache);
+            }
+            log::trace!("Pipeline cache destroyed");
+        }
+    }
+
+    /// Destroy all resources created by the factory and hand the device back to the caller,
+    /// e.g. host application that passed it to [`init_from_raw_parts`].
+    ///
+    /// Queues of the device are owned by `Families` created with the factory
+    /// and are not affected.
+    ///
+    /// [`init_from_raw_parts`]: fn.init_from_raw_parts.html
+    pub fn into_raw_device(mut self) -> B::Device {
+        unsafe {
+            self.dispose_resources();
+            self.device_released = true;
+            // Device won't be touched by `Drop` after it is marked as released.
+            std::ptr::read(&*self.device).into_raw()
+        }
     }
 }
 
@@ -280,6 +466,51 @@ where
         Ok(self.resources.buffers.escape(buffer))
     }
 
+    /// Creates a buffer without memory bound to it.
+    ///
+    /// Memory for the buffer can be allocated with [`allocate_memory`]
+    /// according to buffer's requirements and bound with [`bind_buffer_memory`].
+    ///
+    /// [`allocate_memory`]: #method.allocate_memory
+    /// [`bind_buffer_memory`]: #method.bind_buffer_memory
+    pub fn create_buffer_unbound(
+        &self,
+        info: BufferInfo,
+    ) -> Result<UnboundBuffer<B>, BufferCreationError> {
+        profile_scope!("create_buffer_unbound");
+
+        unsafe { UnboundBuffer::create(&self.device, info) }
+    }
+
+    /// Bind memory `block` at `offset` to the `buffer`.
+    ///
+    /// # Safety
+    ///
+    /// `block` must be allocated with [`allocate_memory`] of this factory.
+    /// Memory bound to the buffer must not be used by other resources
+    /// unless they alias it intentionally.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `block` at `offset` doesn't satisfy
+    /// buffer's memory requirements.
+    ///
+    /// [`allocate_memory`]: #method.allocate_memory
+    pub unsafe fn bind_buffer_memory(
+        &self,
+        buffer: UnboundBuffer<B>,
+        block: MemoryBlock<B>,
+        offset: u64,
+    ) -> Result<Escape<Buffer<B>>, BufferCreationError> {
+        let buffer = Buffer::bind(&self.device, buffer, block, offset)?;
+        Ok(self.resources.buffers.escape(buffer))
+    }
+
+    /// Destroy buffer that was never bound to memory.
+    pub fn destroy_unbound_buffer(&self, buffer: UnboundBuffer<B>) {
+        unsafe { buffer.dispose(&self.device) }
+    }
+
     /// Creates an image with the specified properties.
     ///
     /// This function returns relevant value, that is, the value cannot be dropped.
@@ -324,6 +555,149 @@ where
         Ok(self.resources.images.escape(image))
     }
 
+    /// Creates an image without memory bound to it.
+    ///
+    /// Memory for the image can be allocated with [`allocate_memory`]
+    /// according to image's requirements and bound with [`bind_image_memory`].
+    ///
+    /// [`allocate_memory`]: #method.allocate_memory
+    /// [`bind_image_memory`]: #method.bind_image_memory
+    pub fn create_image_unbound(
+        &self,
+        info: ImageInfo,
+    ) -> Result<UnboundImage<B>, ImageCreationError> {
+        profile_scope!("create_image_unbound");
+
+        unsafe { UnboundImage::create(&self.device, info) }
+    }
+
+    /// Bind memory `block` at `offset` to the `image`.
+    ///
+    /// # Safety
+    ///
+    /// `block` must be allocated with [`allocate_memory`] of this factory.
+    /// Memory bound to the image must not be used by other resources
+    /// unless they alias it intentionally.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `block` at `offset` doesn't satisfy
+    /// image's memory requirements.
+    ///
+    /// [`allocate_memory`]: #method.allocate_memory
+    pub unsafe fn bind_image_memory(
+        &self,
+        image: UnboundImage<B>,
+        block: MemoryBlock<B>,
+        offset: u64,
+    ) -> Result<Escape<Image<B>>, ImageCreationError> {
+        let image = Image::bind(&self.device, image, block, offset)?;
+        Ok(self.resources.images.escape(image))
+    }
+
+    /// Destroy image that was never bound to memory.
+    pub fn destroy_unbound_image(&self, image: UnboundImage<B>) {
+        unsafe { image.dispose(&self.device) }
+    }
+
+    /// Allocate memory block satisfying `requirements` for intended `memory_usage`.
+    /// Block can be bound to buffers and images created unbound.
+    pub fn allocate_memory(
+        &self,
+        requirements: &rendy_core::hal::memory::Requirements,
+        memory_usage: impl MemoryUsage,
+    ) -> Result<MemoryBlock<B>, HeapsError> {
+        profile_scope!("allocate_memory");
+
+        self.heaps.lock().allocate(
+            &self.device,
+            requirements.type_mask as u32,
+            memory_usage,
+            requirements.size,
+            requirements.alignment,
+        )
+    }
+
+    /// Free memory block that was not bound to any resource.
+    ///
+    /// # Safety
+    ///
+    /// `block` must be allocated with [`allocate_memory`] of this factory.
+    ///
+    /// [`allocate_memory`]: #method.allocate_memory
+    pub unsafe fn free_memory(&self, block: MemoryBlock<B>) {
+        self.heaps.lock().free(&self.device, block);
+    }
+
+    /// Creates an image and uploads initial data for its subresources.
+    ///
+    /// Data of all subresources is copied from single staging buffer
+    /// upon next [`flush_uploads`] or [`maintain`] call,
+    /// after which image is in the `next` state.
+    /// Subresources without data are left undefined.
+    /// `TRANSFER_DST` usage is added to `info`.
+    ///
+    /// [`flush_uploads`]: #method.flush_uploads
+    /// [`maintain`]: #method.maintain
+    pub fn create_image_with_data(
+        &self,
+        mut info: ImageInfo,
+        memory_usage: impl MemoryUsage,
+        next: ImageState,
+        data: &[LayerMipData<'_>],
+    ) -> Result<Handle<Image<B>>, ImageDataError> {
+        info.usage |= image::Usage::TRANSFER_DST;
+        let (content, regions) = pack_layer_mip_data(info.kind, info.levels, info.format, data)?;
+
+        let image: Handle<Image<B>> = self
+            .create_image(info, memory_usage)
+            .map_err(ImageDataError::Create)?
+            .into();
+
+        if regions.is_empty() {
+            unsafe {
+                self.transition_image(
+                    image.clone(),
+                    image::SubresourceRange {
+                        aspects: info.format.surface_desc().aspects,
+                        levels: 0..info.levels,
+                        layers: 0..info.kind.num_layers(),
+                    },
+                    image::Layout::Undefined,
+                    next,
+                );
+            }
+            return Ok(image);
+        }
+
+        // Image was just created by this factory and is not used by device.
+        unsafe {
+            let staging = self
+                .staging_buffer(&content)
+                .map_err(ImageDataError::Upload)?;
+            self.uploader
+                .upload_image_regions(&self.device, image.clone(), regions, staging, next)
+                .map_err(|err| ImageDataError::Upload(UploadError::Upload(err)))?;
+        }
+
+        Ok(image)
+    }
+
+    /// Check whether sparse (partially resident) images with specified properties
+    /// can be created and bound tile by tile.
+    ///
+    /// Fails with `MissingFeatures` if sparse binding and residency features
+    /// for the image kind are not enabled on the device.
+    /// Otherwise fails with `Unsupported`, as gfx-hal 0.5 has no sparse binding
+    /// submission path yet.
+    pub fn check_sparse_image_support(&self, info: ImageInfo) -> Result<(), SparseImageError> {
+        let required = sparse_image_features(info.kind);
+        if !self.features.contains(required) {
+            return Err(SparseImageError::MissingFeatures(required - self.features));
+        }
+        Err(SparseImageError::Unsupported)
+    }
+
     /// Fetch image format details for a particular `ImageInfo`.
     pub fn image_format_properties(&self, info: ImageInfo) -> Option<FormatProperties> {
         self.physical().image_format_properties(
@@ -339,6 +713,49 @@ where
         )
     }
 
+    /// Fetch features supported for the `format` with linear and optimal tiling
+    /// and for buffers.
+    pub fn format_properties(&self, format: format::Format) -> format::Properties {
+        self.physical().format_properties(Some(format))
+    }
+
+    /// Check if images of the `format` with optimal tiling support all `features`.
+    pub fn supports_image_features(
+        &self,
+        format: format::Format,
+        features: format::ImageFeature,
+    ) -> bool {
+        self.format_properties(format)
+            .optimal_tiling
+            .contains(features)
+    }
+
+    /// Check if images of the `format` with optimal tiling can be sampled with linear filter.
+    pub fn supports_linear_filter(&self, format: format::Format) -> bool {
+        self.supports_image_features(
+            format,
+            format::ImageFeature::SAMPLED | format::ImageFeature::SAMPLED_LINEAR,
+        )
+    }
+
+    /// Check if images of the `format` with optimal tiling can be used as color attachment.
+    pub fn supports_color_attachment(&self, format: format::Format) -> bool {
+        self.supports_image_features(format, format::ImageFeature::COLOR_ATTACHMENT)
+    }
+
+    /// Check if images of the `format` with optimal tiling can be source and destination of blits.
+    pub fn supports_blit(&self, format: format::Format) -> bool {
+        self.supports_image_features(
+            format,
+            format::ImageFeature::BLIT_SRC | format::ImageFeature::BLIT_DST,
+        )
+    }
+
+    /// Check if images of the `format` with optimal tiling can be used as storage images.
+    pub fn supports_storage(&self, format: format::Format) -> bool {
+        self.supports_image_features(format, format::ImageFeature::STORAGE)
+    }
+
     /// Create an image view with the specified properties
     ///
     /// This function returns relevant value, that is, the value cannot be dropped.
@@ -381,6 +798,58 @@ where
         Ok(self.resources.views.escape(view))
     }
 
+    /// Create a buffer view with the specified properties
+    /// to bind the buffer as uniform or storage texel buffer.
+    ///
+    /// This function returns relevant value, that is, the value cannot be dropped.
+    /// However buffer view can be destroyed using [`destroy_relevant_buffer_view`] function.
+    ///
+    /// Fails with `UnsupportedFormat` if the format doesn't support
+    /// texel buffer usage the buffer was created with.
+    ///
+    /// [`destroy_relevant_buffer_view`]: #method.destroy_relevant_buffer_view
+    pub fn create_relevant_buffer_view(
+        &self,
+        buffer: Handle<Buffer<B>>,
+        info: BufferViewInfo,
+    ) -> Result<BufferView<B>, BufferViewCreationError> {
+        let required = texel_buffer_features(buffer.info().usage);
+        let supported = self.format_properties(info.format).buffer_features;
+        if !supported.contains(required) {
+            return Err(crate::resource::CreationError::Create(
+                ViewCreationError::UnsupportedFormat(Some(info.format)),
+            ));
+        }
+
+        BufferView::create(&self.device, info, buffer)
+    }
+
+    /// Destroy buffer view.
+    /// If buffer view was created using [`create_buffer_view`] it must be unescaped first.
+    ///
+    /// # Safety
+    ///
+    /// Buffer view must not be used by any pending commands or referenced anywhere.
+    ///
+    /// [`create_buffer_view`]: #method.create_buffer_view
+    pub unsafe fn destroy_relevant_buffer_view(&self, view: BufferView<B>) {
+        view.dispose(&self.device);
+    }
+
+    /// Create a buffer view with the specified properties
+    ///
+    /// This function (unlike [`create_relevant_buffer_view`]) returns value that can be dropped.
+    ///
+    /// [`create_relevant_buffer_view`]: #method.create_relevant_buffer_view
+    pub fn create_buffer_view(
+        &self,
+        buffer: Handle<Buffer<B>>,
+        info: BufferViewInfo,
+    ) -> Result<Escape<BufferView<B>>, BufferViewCreationError> {
+        let view = self.create_relevant_buffer_view(buffer, info)?;
+        Ok(self.resources.buffer_views.escape(view))
+    }
+
     /// Create an sampler with the specified properties
     ///
     /// This function returns relevant value, that is, the value cannot be dropped.
@@ -512,23 +981,36 @@ where
     {
         assert!(buffer.info().usage.contains(buffer::Usage::TRANSFER_DST));
 
+        let staging = self.staging_buffer(content)?;
+
+        self.uploader
+            .upload_buffer(&self.device, buffer, offset, staging, last, next)
+            .map_err(UploadError::Upload)
+    }
+
+    /// Get staging buffer filled with `content`.
+    /// Staging buffers are taken from the pool and returned there once upload is complete.
+    unsafe fn staging_buffer<T>(&self, content: &[T]) -> Result<Staging<B>, UploadError>
+    where
+        T: 'static + Copy,
+    {
         let content_size = content.len() as u64 * std::mem::size_of::<T>() as u64;
         let mut staging = self
-            .create_buffer(
-                BufferInfo {
-                    size: content_size,
-                    usage: buffer::Usage::TRANSFER_SRC,
-                },
-                memory::Upload,
-            )
+            .uploader
+            .staging_buffer(content_size, |size| {
+                self.create_buffer(
+                    BufferInfo {
+                        size,
+                        usage: buffer::Usage::TRANSFER_SRC,
+                    },
+                    memory::Upload,
+                )
+            })
             .map_err(UploadError::Create)?;
 
-        self.upload_visible_buffer(&mut staging, 0, content)
+        self.upload_visible_buffer(staging.buffer_mut(), 0, content)
             .map_err(UploadError::Map)?;
-
-        self.uploader
-            .upload_buffer(&self.device, buffer, offset, staging, last, next)
-            .map_err(UploadError::Upload)
+        Ok(staging)
     }
 
     /// Update buffer content with provided staging buffer.
@@ -555,8 +1037,47 @@ where
     ) -> Result<(), OutOfMemory> {
         assert!(buffer.info().usage.contains(buffer::Usage::TRANSFER_DST));
         assert!(staging.info().usage.contains(buffer::Usage::TRANSFER_SRC));
-        self.uploader
-            .upload_buffer(&self.device, buffer, offset, staging, last, next)
+        self.uploader.upload_buffer(
+            &self.device,
+            buffer,
+            offset,
+            Staging::new(staging),
+            last,
+            next,
+        )
+    }
+
+    /// Record transition of part of image from one state to another into `encoder`.
+    ///
+    /// Unlike [`transition_image`] the barrier is recorded immediately,
+    /// so this can be used with command buffers managed outside of the render graph.
+    /// Stage and access masks of the barrier are taken from `last` and `next`.
+    /// See [`ImageTransition`] for details.
+    ///
+    /// [`transition_image`]: #method.transition_image
+    /// [`ImageTransition`]: struct.ImageTransition.html
+    ///
+    /// # Safety
+    ///
+    /// Image must be created by this `Factory`.
+    /// If image is used by device then `last` state must match the last usage state of the image
+    /// before commands recorded by `encoder` are executed.
+    /// Stages from both states must be supported by the queue `encoder` records commands for.
+    pub unsafe fn record_image_transition<C, L>(
+        &self,
+        encoder: &mut Encoder<'_, B, C, L>,
+        image: &Image<B>,
+        image_range: SubresourceRange,
+        last: impl Into<ImageStateOrLayout>,
+        next: ImageState,
+    ) {
+        image.assert_device_owner(&self.device);
+        let transition = ImageTransition::new(last, next);
+        encoder.pipeline_barrier(
+            transition.stages.clone(),
+            rendy_core::hal::memory::Dependencies::empty(),
+            Some(transition.barrier(image.raw(), image_range)),
+        );
     }
 
     /// Update image layers content with provided data.
@@ -628,8 +1149,10 @@ where
 
         let content_size = content.len() as u64 * std::mem::size_of::<T>() as u64;
         let format_desc = image.format().surface_desc();
-        let texels_count = (image_extent.width / format_desc.dim.0 as u32) as u64
-            * (image_extent.height / format_desc.dim.1 as u32) as u64
+        let (block_width, block_height) = (format_desc.dim.0 as u32, format_desc.dim.1 as u32);
+        // Extent of compressed image may end in the middle of a block.
+        let texels_count = image_extent.width.div_ceil(block_width) as u64
+            * image_extent.height.div_ceil(block_height) as u64
             * image_extent.depth as u64
             * (image_layers.layers.end - image_layers.layers.start) as u64;
         let total_bytes = (format_desc.bits as u64 / 8) * texels_count;
@@ -638,6 +1161,76 @@ where
             "Size of must match size of the image region"
         );
 
+        let staging = self.staging_buffer(content)?;
+
+        self.uploader
+            .upload_image(
+                &self.device,
+                image,
+                data_width,
+                data_height,
+                image_layers,
+                image_offset,
+                image_extent,
+                staging,
+                last.into(),
+                next,
+            )
+            .map_err(UploadError::Upload)
+    }
+
+    /// Update all layers of the first mip level of the image with provided data
+    /// and fill the rest of mip levels from it.
+    ///
+    /// Unlike [`upload_image`] followed by [`Blitter::fill_mips`],
+    /// copy and blits of all images uploaded this way are recorded into single command buffer
+    /// and submitted together upon next [`flush_blits`] or [`maintain`] call.
+    /// Previous content of the image is discarded.
+    ///
+    /// [`upload_image`]: #method.upload_image
+    /// [`Blitter::fill_mips`]: struct.Blitter.html#method.fill_mips
+    /// [`flush_blits`]: #method.flush_blits
+    /// [`maintain`]: #method.maintain
+    ///
+    /// # Safety
+    ///
+    /// Image must be created by this `Factory` and must not be used by device.
+    /// `next.queue` must belong to a family that supports graphics.
+    /// In order to guarantee that updated content will be made visible to next device operation
+    /// that reads content of the image the `next` must match image usage state in that operation.
+    pub unsafe fn upload_image_with_mips<T>(
+        &self,
+        image: Handle<Image<B>>,
+        data_width: u32,
+        data_height: u32,
+        content: &[T],
+        filter: image::Filter,
+        next: ImageState,
+    ) -> Result<(), UploadError>
+    where
+        T: 'static + Copy,
+    {
+        assert!(image
+            .info()
+            .usage
+            .contains(image::Usage::TRANSFER_DST | image::Usage::TRANSFER_SRC));
+
+        let content_size = content.len() as u64 * std::mem::size_of::<T>() as u64;
+        let format_desc = image.format().surface_desc();
+        let (block_width, block_height) = (format_desc.dim.0 as u32, format_desc.dim.1 as u32);
+        let extent = image.kind().extent();
+        // Extent of compressed image may end in the middle of a block.
+        let texels_count = extent.width.div_ceil(block_width) as u64
+            * extent.height.div_ceil(block_height) as u64
+            * extent.depth as u64
+            * image.layers() as u64;
+        assert_eq!(
+            (format_desc.bits as u64 / 8) * texels_count,
+            content_size,
+            "Size of must match size of the first mip level"
+        );
+
+        // Staging buffer is freed by blitter, so it is not taken from the pool.
         let mut staging = self
             .create_buffer(
                 BufferInfo {
@@ -651,17 +1244,14 @@ where
         self.upload_visible_buffer(&mut staging, 0, content)
             .map_err(UploadError::Map)?;
 
-        self.uploader
-            .upload_image(
+        self.blitter
+            .upload_image_with_mips(
                 &self.device,
                 image,
                 data_width,
                 data_height,
-                image_layers,
-                image_offset,
-                image_extent,
                 staging,
-                last.into(),
+                filter,
                 next,
             )
             .map_err(UploadError::Upload)
@@ -686,6 +1276,27 @@ where
         )
     }
 
+    /// Create rendering surface from raw window handle.
+    ///
+    /// Fails with `InitError::UnsupportedWindowHandle`
+    /// if backend can't create surface for this kind of handle.
+    ///
+    /// # Safety
+    ///
+    /// `handle` must refer to a valid window that outlives created surface.
+    pub unsafe fn create_surface_from_raw_handle(
+        &mut self,
+        handle: RawWindowHandle,
+    ) -> Result<Surface<B>, InitError> {
+        profile_scope!("create_surface_from_raw_handle");
+        Surface::from_raw_handle(
+            self.instance
+                .as_instance()
+                .expect("Cannot create surface without instance"),
+            handle,
+        )
+    }
+
     /// Create rendering surface from window.
     ///
     /// # Safety
@@ -706,6 +1317,8 @@ where
 
     /// Get formats supported by the Surface
     ///
+    /// Formats are cached by the surface until `Surface::invalidate_caps` is called.
+    ///
     /// # Panics
     ///
     /// Panics if `surface` was not created by this `Factory`
@@ -725,6 +1338,8 @@ where
 
     /// Get compatibility of Surface
     ///
+    /// Capabilities are cached by the surface until `Surface::invalidate_caps` is called.
+    ///
     /// # Panics
     ///
     /// Panics if `surface` was not created by this `Factory`
@@ -742,6 +1357,19 @@ where
         unsafe { surface.capabilities(&self.adapter.physical_device) }
     }
 
+    /// Get number of swapchain images to request for the surface in `mode`.
+    ///
+    /// Count is clamped to range supported by the surface.
+    /// If surface requires more images than `mode` asks for, the minimum is used
+    /// and a warning is logged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `surface` was not created by this `Factory`
+    pub fn swapchain_image_count(&self, surface: &Surface<B>, mode: SwapchainMode) -> u32 {
+        clamp_image_count(&self.get_surface_capabilities(surface), mode.image_count())
+    }
+
     /// Get surface format.
     ///
     /// # Panics
@@ -770,6 +1398,30 @@ where
             .supports_queue_family(&self.adapter.queue_families[family.index])
     }
 
+    /// Find queue family that can present to the `surface`.
+    /// Families that also support graphics are preferred,
+    /// as presenting from them doesn't require ownership transfer of rendered images.
+    /// Otherwise any family that supports presentation is returned, e.g. compute-only one.
+    /// Returns `None` if no family can present to the `surface`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `surface` was not created by this `Factory`
+    pub fn present_family(&self, families: &Families<B>, surface: &Surface<B>) -> Option<FamilyId> {
+        let family = families
+            .find(|family| {
+                family.capability().supports_graphics()
+                    && self.surface_support(family.id(), surface)
+            })
+            .or_else(|| families.find(|family| self.surface_support(family.id(), surface)));
+
+        if let Some(family) = family {
+            debug_assert!(self.surface_support(family, surface));
+            log::debug!("Present family for {:?}: {:?}", surface, family);
+        }
+        family
+    }
+
     /// Destroy surface returning underlying window back to the caller.
     ///
     /// # Panics
@@ -820,6 +1472,35 @@ where
         }
     }
 
+    /// Recreate lost surface of the target for the window and swapchain for it.
+    /// Should be called when presentation reports `RecreateError::SurfaceLost`.
+    ///
+    /// # Safety
+    ///
+    /// Target images must not be used by pending commands or referenced anywhere.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `target` was not created by this `Factory`.
+    pub unsafe fn recreate_target_surface(
+        &self,
+        target: &mut Target<B>,
+        handle: &impl HasRawWindowHandle,
+        extent: Extent2D,
+    ) -> Result<(), RecreateError> {
+        profile_scope!("recreate_target_surface");
+
+        target.recreate_surface(
+            self.instance
+                .as_instance()
+                .expect("Cannot recreate surface without instance"),
+            &self.adapter.physical_device,
+            &self.device,
+            handle,
+            extent,
+        )
+    }
+
     /// Destroy target returning underlying surface back to the caller.
     ///
     /// # Safety
@@ -834,11 +1515,25 @@ where
         &self.device
     }
 
+    pub(crate) fn uploader(&self) -> &Uploader<B> {
+        &self.uploader
+    }
+
     /// Get raw physical device.
     pub fn physical(&self) -> &B::PhysicalDevice {
         &self.adapter.physical_device
     }
 
+    /// Get features enabled on the device.
+    pub fn features(&self) -> Features {
+        self.features
+    }
+
+    /// Get summary of device capabilities.
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+
     /// Create new semaphore.
     pub fn create_semaphore(&self) -> Result<B::Semaphore, OutOfMemory> {
         profile_scope!("create_semaphore");
@@ -997,6 +1692,144 @@ where
         unsafe { self.device.destroy_fence(fence.into_inner()) }
     }
 
+    /// Create new event in unset state.
+    /// Fails with `Unsupported` error if backend doesn't implement events.
+    pub fn create_event(&self) -> Result<Event<B>, EventCreationError> {
+        Event::new(&self.device)
+    }
+
+    /// Destroy event.
+    ///
+    /// # Safety
+    ///
+    /// Event must be created by this `Factory`
+    /// and must not be used by pending command buffers.
+    pub unsafe fn destroy_event(&self, event: Event<B>) {
+        event.dispose(&self.device)
+    }
+
+    /// Create query pool with `count` queries of specified type.
+    /// Fails with `Unsupported` error if features required by the type are not enabled.
+    pub fn create_query_pool(
+        &self,
+        ty: QueryType,
+        count: u32,
+    ) -> Result<QueryPool<B>, rendy_core::hal::query::CreationError> {
+        QueryPool::create(&self.device, self.features, ty, count)
+    }
+
+    /// Destroy query pool.
+    ///
+    /// # Safety
+    ///
+    /// Query pool must be created by this `Factory`
+    /// and must not be used by pending command buffers.
+    pub unsafe fn destroy_query_pool(&self, pool: QueryPool<B>) {
+        pool.dispose(&self.device)
+    }
+
+    /// Read results of `queries` from the `pool`.
+    /// See [`QueryPool::get_results`] for layout of the results.
+    ///
+    /// [`QueryPool::get_results`]: ../rendy_command/struct.QueryPool.html#method.get_results
+    pub fn get_query_results(
+        &self,
+        pool: &QueryPool<B>,
+        queries: std::ops::Range<u32>,
+        flags: rendy_core::hal::query::ResultFlags,
+    ) -> Result<Option<Vec<u64>>, OomOrDeviceLost> {
+        pool.get_results(&self.device, queries, flags)
+    }
+
+    /// Record commands into a transient command buffer,
+    /// submit it to the first queue of the `family` and wait until it completes.
+    ///
+    /// Command pool, command buffer and fence are kept by the factory
+    /// and reused by subsequent calls for the same family.
+    /// Calls for the same family are serialized.
+    ///
+    /// Useful for one-time setup work such as initial uploads or layout transitions
+    /// where blocking is acceptable.
+    ///
+    /// # Safety
+    ///
+    /// Resources referenced by recorded commands must be created by this `Factory`
+    /// and must be in states expected by the commands.
+    pub unsafe fn execute_oneshot<R>(
+        &self,
+        families: &mut Families<B>,
+        family: FamilyId,
+        record: impl FnOnce(&mut Encoder<'_, B, QueueType, PrimaryLevel>) -> R,
+    ) -> Result<R, OomOrDeviceLost> {
+        profile_scope!("execute_oneshot");
+
+        self.oneshot
+            .execute(&self.device, families.family_mut(family), record, |fence| {
+                self.wait_for_fence(fence, !0)
+            })
+    }
+
+    /// Submit `submits` to the `queue` after values in `wait_values` are reached
+    /// and return value of the factory's timeline that is reached when they complete.
+    ///
+    /// Values increase monotonically with each submission, regardless of the queue.
+    /// Reaching a value implies all preceding values are reached as well,
+    /// so callers can wait on it with `wait_timeline` at any granularity
+    /// instead of managing fences per frame.
+    ///
+    /// Timeline semaphores are not available in gfx-hal yet
+    /// (see `Capabilities::timeline_semaphores`), so each value is backed by a fence
+    /// and `wait_values` are waited on the host before submitting.
+    ///
+    /// # Safety
+    ///
+    /// `queue` must belong to the device of this `Factory`.
+    /// `submits` must satisfy requirements of `Queue::submit`.
+    pub unsafe fn submit_timeline(
+        &self,
+        queue: &mut Queue<B>,
+        submits: impl IntoIterator<Item = impl Submittable<B>>,
+        wait_values: impl IntoIterator<Item = u64>,
+    ) -> Result<u64, OomOrDeviceLost> {
+        profile_scope!("submit_timeline");
+
+        if let Some(wait_value) = wait_values.into_iter().max() {
+            self.wait_timeline(wait_value, !0)?;
+        }
+        Ok(self.timeline.lock().submit(&self.device, queue, submits)?)
+    }
+
+    /// Wait until `value` of the timeline and all values before it are reached.
+    /// Returns `false` on timeout.
+    ///
+    /// Timeline isn't locked while waiting and `timeout_ns` applies to the whole wait.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `value` wasn't returned by `submit_timeline` yet.
+    pub fn wait_timeline(&self, value: u64, timeout_ns: u64) -> Result<bool, OomOrDeviceLost> {
+        profile_scope!("wait_timeline");
+
+        let fences = self.timeline.lock().fences_to_wait(value);
+        if !unsafe { wait_fences(&self.device, &fences, timeout_ns) }? {
+            return Ok(false);
+        }
+        drop(fences);
+        self.timeline.lock().mark_reached(value);
+        Ok(true)
+    }
+
+    /// Get greatest timeline value that is reached along with all values before it.
+    /// Doesn't block.
+    pub fn timeline_value(&self) -> Result<u64, OomOrDeviceLost> {
+        self.timeline.lock().poll(&self.device)
+    }
+
+    /// Get last timeline value returned by `submit_timeline`.
+    pub fn last_timeline_value(&self) -> u64 {
+        self.timeline.lock().last()
+    }
+
     /// Create new command pool for specified family.
     pub fn create_command_pool<R>(
         &self,
@@ -1022,6 +1855,93 @@ where
         pool.dispose(&self.device);
     }
 
+    /// Create pipeline cache owned by the factory,
+    /// optionally initialized with data retrieved on previous run.
+    ///
+    /// Cache data is specific to the driver and device.
+    /// Data that physical device reports as incompatible is discarded
+    /// and an empty cache is created instead.
+    /// Replaces previously created cache.
+    pub fn init_pipeline_cache(&mut self, data: Option<&[u8]>) -> Result<(), OutOfMemory> {
+        profile_scope!("init_pipeline_cache");
+
+        let data = data.filter(|data| {
+            let valid = self.adapter.physical_device.is_valid_cache(data);
+            if !valid {
+                log::warn!(
+                    "Pipeline cache data ({} bytes) is incompatible with the device and discarded",
+                    data.len()
+                );
+            }
+            valid
+        });
+
+        let cache = unsafe { self.device.create_pipeline_cache(data) }?;
+        if let Some(old) = self.pipeline_cache.replace(cache) {
+            unsafe { self.device.destroy_pipeline_cache(old) };
+        }
+        Ok(())
+    }
+
+    /// Create pipeline cache owned by the factory from the file written by `save_pipeline_cache`.
+    /// Empty cache is created if file can't be read.
+    pub fn load_pipeline_cache(
+        &mut self,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<(), OutOfMemory> {
+        let path = path.as_ref();
+        match std::fs::read(path) {
+            Ok(data) => {
+                log::debug!(
+                    "Loaded {} bytes of pipeline cache from {}",
+                    data.len(),
+                    path.display()
+                );
+                self.init_pipeline_cache(Some(&data))
+            }
+            Err(err) => {
+                log::debug!(
+                    "Failed to read pipeline cache from {}: {}",
+                    path.display(),
+                    err
+                );
+                self.init_pipeline_cache(None)
+            }
+        }
+    }
+
+    /// Get pipeline cache owned by the factory.
+    /// Should be passed to pipeline creation functions.
+    pub fn pipeline_cache(&self) -> Option<&B::PipelineCache> {
+        self.pipeline_cache.as_ref()
+    }
+
+    /// Retrieve data from pipeline cache owned by the factory.
+    /// Returns `None` if cache wasn't created.
+    pub fn pipeline_cache_data(&self) -> Result<Option<Vec<u8>>, OutOfMemory> {
+        match &self.pipeline_cache {
+            Some(cache) => unsafe { self.device.get_pipeline_cache_data(cache) }.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Write data from pipeline cache owned by the factory to the file.
+    /// Does nothing if cache wasn't created.
+    pub fn save_pipeline_cache(
+        &self,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<(), PipelineCacheError> {
+        profile_scope!("save_pipeline_cache");
+
+        if let Some(data) = self
+            .pipeline_cache_data()
+            .map_err(PipelineCacheError::Data)?
+        {
+            std::fs::write(path, data).map_err(PipelineCacheError::Io)?;
+        }
+        Ok(())
+    }
+
     fn next_epochs(&mut self, families: &Families<B>) -> Epochs {
         Epochs {
             values: families
@@ -1073,6 +1993,26 @@ where
         unsafe { self.blitter.flush(families) }
     }
 
+    /// Flush uploads and blits and wait for them to complete.
+    ///
+    /// Blocks the calling thread on fence wait until all uploaded resources
+    /// (including mip levels generated with the blitter) are ready to use.
+    /// Staging buffers of completed uploads are released.
+    /// Does nothing if there are no pending uploads.
+    pub fn wait_for_uploads(&mut self, families: &mut Families<B>) -> Result<(), OomOrDeviceLost> {
+        profile_scope!("wait_for_uploads");
+
+        self.flush_uploads(families);
+        self.flush_blits(families);
+        unsafe {
+            self.uploader.wait(&self.device)?;
+            self.blitter.wait(&self.device)?;
+            self.uploader.cleanup(&self.device);
+            self.blitter.cleanup(&self.device);
+        }
+        Ok(())
+    }
+
     /// Flush uploads and cleanup unused resources.
     pub fn maintain(&mut self, families: &mut Families<B>) {
         self.flush_uploads(families);
@@ -1150,11 +2090,60 @@ where
             .collect())
     }
 
+    /// Create descriptor sets with different layouts.
+    /// Sets are returned in order of `layouts`.
+    ///
+    /// Runs of the same layout are allocated with single allocator call,
+    /// so layouts that repeat should be grouped together.
+    pub fn create_descriptor_sets_with_layouts<T>(
+        &self,
+        layouts: impl IntoIterator<Item = Handle<DescriptorSetLayout<B>>>,
+    ) -> Result<T, OutOfMemory>
+    where
+        T: std::iter::FromIterator<Escape<DescriptorSet<B>>>,
+    {
+        profile_scope!("create_descriptor_sets_with_layouts");
+
+        let mut result = SmallVec::<[_; 32]>::new();
+        let mut allocator = self.descriptor_allocator.lock();
+        let mut layouts = layouts.into_iter().peekable();
+        while let Some(layout) = layouts.next() {
+            let mut count = 1;
+            while layouts
+                .next_if(|next| std::ptr::eq::<DescriptorSetLayout<B>>(&**next, &*layout))
+                .is_some()
+            {
+                count += 1;
+            }
+
+            let mut sets = SmallVec::<[_; 32]>::new();
+            // Sets allocated before an error are escaped and freed with `result`.
+            unsafe {
+                DescriptorSet::create_many(&self.device, &mut allocator, layout, count, &mut sets)
+            }?;
+            result.extend(sets.into_iter().map(|set| self.resources.sets.escape(set)));
+        }
+
+        Ok(result.into_iter().collect())
+    }
+
+    /// Create writer that batches descriptor set updates.
+    /// Pending writes are flushed with `DescriptorWriter::commit`.
+    pub fn descriptor_writer<'a>(&self) -> crate::DescriptorWriter<'a, B> {
+        crate::DescriptorWriter::new()
+    }
+
     /// Query memory utilization.
     pub fn memory_utilization(&self) -> TotalMemoryUtilization {
         self.heaps.lock().utilization()
     }
 
+    /// Query per-heap memory budget reported by the driver.
+    /// Returns `None` if unavailable, see `Heaps::device_budget`.
+    pub fn memory_budget(&self) -> Option<Vec<HeapBudget>> {
+        self.heaps.lock().device_budget()
+    }
+
     /// Get Factory's instance id.
     pub fn instance_id(&self) -> InstanceId {
         self.device.id().instance
@@ -1237,6 +2226,7 @@ where
 
     let device_id = DeviceId::new(instance.id());
 
+    let features = adapter.physical_device.features() - Features::NDC_Y_UP;
     let (device, families) = {
         let families = config
             .queues
@@ -1245,11 +2235,22 @@ where
             .collect::<SmallVec<[_; 16]>>();
         let (create_queues, get_queues): (SmallVec<[_; 32]>, SmallVec<[_; 32]>) = families
             .iter()
-            .map(|(index, priorities)| {
-                (
-                    (&adapter.queue_families[index.index], priorities.as_ref()),
-                    (*index, priorities.as_ref().len()),
-                )
+            .filter_map(|(index, priorities)| {
+                let family = &adapter.queue_families[index.index];
+                let mut priorities = priorities.as_ref();
+                if priorities.len() > family.max_queues() {
+                    log::warn!(
+                        "{} queues requested from family {} which supports only {}",
+                        priorities.len(),
+                        index.index,
+                        family.max_queues(),
+                    );
+                    priorities = &priorities[..family.max_queues()];
+                }
+                if priorities.is_empty() {
+                    return None;
+                }
+                Some(((family, priorities), (*index, priorities.len())))
             })
             .unzip();
 
@@ -1258,12 +2259,7 @@ where
         let Gpu {
             device,
             mut queue_groups,
-        } = unsafe {
-            adapter.physical_device.open(
-                &create_queues,
-                adapter.physical_device.features() - Features::NDC_Y_UP,
-            )
-        }?;
+        } = unsafe { adapter.physical_device.open(&create_queues, features) }?;
 
         let families = unsafe {
             families_from_device(
@@ -1278,9 +2274,91 @@ where
 
     let device = Device::from_raw(device, device_id);
 
-    let (types, heaps) = config
-        .heaps
-        .configure(&adapter.physical_device.memory_properties());
+    let factory = assemble(
+        adapter,
+        device,
+        &families,
+        features,
+        &config.heaps,
+        instance.id(),
+    )?;
+
+    Ok((factory, families))
+}
+
+/// Initialize `Factory` and Queue `Families` from the device
+/// opened by the host application.
+///
+/// This is meant for embedding rendy into an application that
+/// manages the instance and opens the device itself.
+/// The device is moved into the `Factory` and destroyed when the `Factory` is dropped.
+/// Use [`Factory::into_raw_device`] to destroy resources created by the `Factory`
+/// and get the device back instead.
+/// The instance is borrowed and must outlive the `Factory`.
+///
+/// `queues` lists queue families and number of queues
+/// that were requested when `gpu` was opened.
+///
+/// [`Factory::into_raw_device`]: struct.Factory.html#method.into_raw_device
+///
+/// # Safety
+///
+/// `gpu` must be opened from `adapter` that was enumerated from `instance`,
+/// with `features` enabled and queues specified by `queues`.
+pub unsafe fn init_from_raw_parts<B>(
+    instance: &Instance<B>,
+    adapter: Adapter<B>,
+    gpu: Gpu<B>,
+    features: Features,
+    queues: impl IntoIterator<Item = (rendy_core::hal::queue::QueueFamilyId, usize)>,
+    heaps: &impl HeapsConfigure,
+) -> Result<(Factory<B>, Families<B>), CreationError>
+where
+    B: Backend,
+{
+    let device_id = DeviceId::new(instance.id());
+    let Gpu {
+        device,
+        mut queue_groups,
+    } = gpu;
+
+    let families = families_from_device(
+        device_id,
+        &mut queue_groups,
+        queues.into_iter().map(|(family, count)| {
+            (
+                FamilyId {
+                    device: device_id,
+                    index: family.0,
+                },
+                count,
+            )
+        }),
+        &adapter.queue_families,
+    );
+
+    let device = Device::from_raw(device, device_id);
+
+    let factory = assemble(adapter, device, &families, features, heaps, instance.id())?;
+
+    Ok((factory, families))
+}
+
+fn assemble<B>(
+    adapter: Adapter<B>,
+    device: Device<B>,
+    families: &Families<B>,
+    features: Features,
+    heaps: &impl HeapsConfigure,
+    instance: InstanceId,
+) -> Result<Factory<B>, CreationError>
+where
+    B: Backend,
+{
+    let capabilities = Capabilities::new(&adapter, features);
+    log::debug!("Device capabilities: {:#?}", capabilities);
+
+    let (types, heaps) = heaps.configure(&adapter.physical_device.memory_properties());
     let heaps = heaps.into_iter().collect::<SmallVec<[_; 16]>>();
     let types = types.into_iter().collect::<SmallVec<[_; 32]>>();
 
@@ -1306,16 +2384,29 @@ where
         ),
         heaps: ManuallyDrop::new(parking_lot::Mutex::new(heaps)),
         resources: ManuallyDrop::new(ResourceHub::default()),
-        uploader: unsafe { Uploader::new(&device, &families) }
+        uploader: unsafe { Uploader::new(&device, families) }
             .map_err(rendy_core::hal::device::CreationError::OutOfMemory)?,
-        blitter: unsafe { Blitter::new(&device, &families) }
+        blitter: unsafe { Blitter::new(&device, families) }
             .map_err(rendy_core::hal::device::CreationError::OutOfMemory)?,
+        oneshot: OneshotPools::new(
+            families
+                .as_slice()
+                .iter()
+                .map(|family| family.id().index + 1)
+                .max()
+                .unwrap_or(0),
+        ),
+        timeline: parking_lot::Mutex::new(Timeline::new()),
         families_indices: families.indices().into(),
+        features,
+        capabilities,
+        pipeline_cache: None,
         epochs,
-        device,
+        device: ManuallyDrop::new(device),
+        device_released: false,
         adapter,
-        instance: InstanceOrId::Id(instance.id()),
+        instance: InstanceOrId::Id(instance),
     };
 
-    Ok((factory, families))
+    Ok(factory)
 }
@@ -21,6 +21,10 @@ mod barriers;
 mod blitter;
 mod config;
 mod factory;
+mod oneshot;
+mod resize;
+mod timeline;
 mod upload;
+mod writer;
 
-pub use crate::{barriers::*, blitter::*, config::*, factory::*, upload::*};
+pub use crate::{barriers::*, blitter::*, config::*, factory::*, resize::*, upload::*, writer::*};
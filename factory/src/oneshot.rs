@@ -0,0 +1,110 @@
+use {
+    crate::{
+        command::{
+            CommandBuffer, CommandPool, Encoder, Family, Fence, IndividualReset, InitialState,
+            OneShot, PrimaryLevel, QueueType, Submission,
+        },
+        core::Device,
+    },
+    rendy_core::hal::device::{Device as _, OomOrDeviceLost},
+    std::iter::once,
+};
+
+/// Transient command pools used by `Factory::execute_oneshot`.
+/// Pools are created lazily on first use and reused afterwards.
+#[derive(Debug)]
+pub(crate) struct OneshotPools<B: rendy_core::hal::Backend> {
+    family_pools: Vec<parking_lot::Mutex<Option<OneshotPool<B>>>>,
+}
+
+#[derive(Debug)]
+struct OneshotPool<B: rendy_core::hal::Backend> {
+    pool: CommandPool<B, QueueType, IndividualReset>,
+    buffer: Option<CommandBuffer<B, QueueType, InitialState, PrimaryLevel, IndividualReset>>,
+    fence: Option<Fence<B>>,
+}
+
+impl<B> OneshotPools<B>
+where
+    B: rendy_core::hal::Backend,
+{
+    pub(crate) fn new(families_count: usize) -> Self {
+        OneshotPools {
+            family_pools: (0..families_count)
+                .map(|_| parking_lot::Mutex::new(None))
+                .collect(),
+        }
+    }
+
+    /// Record commands with `record` into a primary command buffer,
+    /// submit it to the first queue of the `family` and wait for completion with `wait`.
+    ///
+    /// # Safety
+    ///
+    /// `device` must be the same that was used to create `family`.
+    /// `wait` must wait until fence is signaled.
+    pub(crate) unsafe fn execute<R>(
+        &self,
+        device: &Device<B>,
+        family: &mut Family<B>,
+        record: impl FnOnce(&mut Encoder<'_, B, QueueType, PrimaryLevel>) -> R,
+        wait: impl FnOnce(&mut Fence<B>) -> Result<bool, OomOrDeviceLost>,
+    ) -> Result<R, OomOrDeviceLost> {
+        let mut lock = self.family_pools[family.id().index].lock();
+        let oneshot = match &mut *lock {
+            Some(oneshot) => oneshot,
+            slot @ None => slot.get_or_insert(OneshotPool {
+                pool: family.create_pool(device)?,
+                buffer: None,
+                fence: None,
+            }),
+        };
+
+        let mut fence = match oneshot.fence.take() {
+            Some(fence) => fence,
+            None => Fence::new(device, false)?,
+        };
+
+        let buffer = match oneshot.buffer.take() {
+            Some(buffer) => buffer,
+            None => oneshot.pool.allocate_buffers(1).remove(0),
+        };
+
+        let mut buffer = buffer.begin(OneShot, ());
+        let result = record(&mut buffer.encoder());
+        let (submit, buffer) = buffer.finish().submit_once();
+
+        family.queue_mut(0).submit(
+            Some(Submission::new().submits(once(submit))),
+            Some(&mut fence),
+        );
+
+        // Fence and command buffer are leaked if waiting fails
+        // as it is not known whether they are still in use.
+        wait(&mut fence)?;
+        fence.reset(device)?;
+
+        oneshot.buffer = Some(buffer.mark_complete().reset());
+        oneshot.fence = Some(fence);
+
+        Ok(result)
+    }
+
+    /// # Safety
+    ///
+    /// `device` must be the same that was used to create pools.
+    /// `device` must be idle.
+    pub(crate) unsafe fn dispose(&mut self, device: &Device<B>) {
+        self.family_pools.drain(..).for_each(|oneshot| {
+            if let Some(mut oneshot) = oneshot.into_inner() {
+                if let Some(buffer) = oneshot.buffer {
+                    oneshot.pool.free_buffers(once(buffer));
+                }
+                if let Some(fence) = oneshot.fence {
+                    device.destroy_fence(fence.into_inner());
+                }
+                oneshot.pool.dispose(device);
+            }
+        });
+    }
+}
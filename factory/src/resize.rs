@@ -0,0 +1,89 @@
+use {
+    crate::{
+        factory::{Factory, UploadError},
+        memory::{Block as _, Explicit},
+        resource::{Buffer, BufferInfo, Escape},
+        upload::BufferState,
+    },
+    rendy_core::hal::{buffer::Usage, Backend},
+};
+
+/// Reallocation of buffers created by `Factory`.
+///
+/// Buffers can't allocate memory or record commands on their own,
+/// so this is implemented here rather than by `Buffer` itself.
+pub trait BufferResize<B: Backend> {
+    /// Reallocate buffer with `new_size` keeping its usage and memory properties.
+    ///
+    /// If `preserve` is `true` content is copied by the device, truncated if buffer shrinks.
+    /// Copy operation will actually be submitted to the `next.queue`
+    /// upon next [`flush_uploads`] or [`maintain`] call to the `Factory`.
+    /// Otherwise content is discarded and `last` and `next` are ignored.
+    ///
+    /// Previous buffer is dropped and destroyed once device is done with it,
+    /// so commands recorded with it before remain valid.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `preserve` is `true` and buffer wasn't created
+    /// with `TRANSFER_SRC` and `TRANSFER_DST` usage.
+    ///
+    /// # Safety
+    ///
+    /// If buffer is used by device then `last` state must match the last usage state of the buffer
+    /// before resizing happen.
+    /// In order to guarantee that copied content will be made visible to next device operation
+    /// that reads content of the new buffer the `next` must match buffer usage state in that operation.
+    ///
+    /// [`flush_uploads`]: struct.Factory.html#method.flush_uploads
+    /// [`maintain`]: struct.Factory.html#method.maintain
+    unsafe fn resize(
+        &mut self,
+        factory: &Factory<B>,
+        new_size: u64,
+        preserve: bool,
+        last: Option<BufferState>,
+        next: BufferState,
+    ) -> Result<(), UploadError>;
+}
+
+impl<B> BufferResize<B> for Escape<Buffer<B>>
+where
+    B: Backend,
+{
+    unsafe fn resize(
+        &mut self,
+        factory: &Factory<B>,
+        new_size: u64,
+        preserve: bool,
+        last: Option<BufferState>,
+        next: BufferState,
+    ) -> Result<(), UploadError> {
+        assert!(
+            !preserve
+                || self
+                    .info()
+                    .usage
+                    .contains(Usage::TRANSFER_SRC | Usage::TRANSFER_DST),
+            "Buffer must have TRANSFER_SRC and TRANSFER_DST usage to preserve content"
+        );
+
+        let info = BufferInfo {
+            size: new_size,
+            ..*self.info()
+        };
+        let properties = self.block().properties();
+        let resized = factory
+            .create_buffer(info, Explicit::new(properties))
+            .map_err(UploadError::Create)?;
+
+        let size = new_size.min(self.size());
+        let old = std::mem::replace(self, resized);
+        if preserve {
+            factory
+                .uploader()
+                .copy_buffer(factory.device(), old, self, size, last, next)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,175 @@
+use {
+    crate::{
+        command::{Queue, Submission, Submittable},
+        core::Device,
+    },
+    rendy_core::hal::device::{Device as _, OomOrDeviceLost, WaitFor},
+    std::{collections::VecDeque, sync::Arc},
+};
+
+/// Monotonic counter of submissions used by `Factory::submit_timeline`.
+/// Each submission signals the next value.
+///
+/// gfx-hal doesn't expose timeline semaphores yet,
+/// so values are emulated with a fence per submission.
+///
+/// Fences are shared with waiters so that `Factory` can wait on them
+/// without holding the lock of the timeline.
+#[derive(Debug)]
+pub(crate) struct Timeline<B: rendy_core::hal::Backend> {
+    /// Last value assigned to a submission.
+    last: u64,
+    /// All values up to this one are reached.
+    reached: u64,
+    /// Fences of submitted values that are not known to be reached, in increasing order.
+    pending: VecDeque<(u64, Arc<B::Fence>)>,
+    /// Signaled fences that may still be waited upon by other threads.
+    retired: Vec<Arc<B::Fence>>,
+    /// Unsignaled fences to reuse.
+    free: Vec<B::Fence>,
+}
+
+impl<B> Timeline<B>
+where
+    B: rendy_core::hal::Backend,
+{
+    pub(crate) fn new() -> Self {
+        Timeline {
+            last: 0,
+            reached: 0,
+            pending: VecDeque::new(),
+            retired: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+
+    /// Last value assigned to a submission.
+    pub(crate) fn last(&self) -> u64 {
+        self.last
+    }
+
+    /// Submit `submits` to the `queue` and return value signaled on completion.
+    ///
+    /// # Safety
+    ///
+    /// `device` must be the same that was used to create `queue`.
+    /// `submits` must satisfy requirements of `Queue::submit`.
+    pub(crate) unsafe fn submit(
+        &mut self,
+        device: &Device<B>,
+        queue: &mut Queue<B>,
+        submits: impl IntoIterator<Item = impl Submittable<B>>,
+    ) -> Result<u64, rendy_core::hal::device::OutOfMemory> {
+        self.recycle(device)?;
+        let fence = match self.free.pop() {
+            Some(fence) => fence,
+            None => device.create_fence(false)?,
+        };
+
+        queue.submit_raw_fence(Some(Submission::new().submits(submits)), Some(&fence));
+        self.last += 1;
+        self.pending.push_back((self.last, Arc::new(fence)));
+        Ok(self.last)
+    }
+
+    /// Check which values are reached without blocking.
+    /// Returns greatest value such that it and all values before it are reached.
+    pub(crate) fn poll(&mut self, device: &Device<B>) -> Result<u64, OomOrDeviceLost> {
+        while let Some((value, fence)) = self.pending.front() {
+            if !unsafe { device.get_fence_status(fence) }? {
+                break;
+            }
+            let value = *value;
+            self.mark_reached(value);
+        }
+        self.recycle(device)?;
+        Ok(self.reached)
+    }
+
+    /// Collect fences that must be signaled for `value` to be reached.
+    /// Returned fences can be waited upon after the timeline is unlocked,
+    /// followed by `mark_reached`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `value` wasn't assigned to a submission yet.
+    pub(crate) fn fences_to_wait(&self, value: u64) -> Vec<Arc<B::Fence>> {
+        assert!(
+            value <= self.last,
+            "Value {} is not submitted yet, last submitted is {}",
+            value,
+            self.last
+        );
+
+        self.pending
+            .iter()
+            .take_while(|(pending, _)| *pending <= value)
+            .map(|(_, fence)| fence.clone())
+            .collect()
+    }
+
+    /// Mark `value` and all values before it as reached.
+    /// Fences of the values must be signaled.
+    pub(crate) fn mark_reached(&mut self, value: u64) {
+        while let Some((pending, _)) = self.pending.front() {
+            if *pending > value {
+                break;
+            }
+            let (_, fence) = self.pending.pop_front().unwrap();
+            self.retired.push(fence);
+        }
+        self.reached = self.reached.max(value);
+    }
+
+    /// Reset retired fences that are not waited upon anymore and make them available for reuse.
+    fn recycle(&mut self, device: &Device<B>) -> Result<(), rendy_core::hal::device::OutOfMemory> {
+        let mut index = 0;
+        while index < self.retired.len() {
+            if Arc::strong_count(&self.retired[index]) > 1 {
+                index += 1;
+                continue;
+            }
+            let fence = Arc::try_unwrap(self.retired.swap_remove(index))
+                .expect("Fence is not shared with anyone");
+            unsafe { device.reset_fence(&fence) }?;
+            self.free.push(fence);
+        }
+        Ok(())
+    }
+
+    /// # Safety
+    ///
+    /// `device` must be the same that was used to create fences.
+    /// `device` must be idle and no thread may wait for the timeline.
+    pub(crate) unsafe fn dispose(&mut self, device: &Device<B>) {
+        let pending = self.pending.drain(..).map(|(_, fence)| fence);
+        for fence in pending.chain(self.retired.drain(..)) {
+            let fence = Arc::try_unwrap(fence).expect("Fence is still waited upon");
+            device.destroy_fence(fence);
+        }
+        for fence in self.free.drain(..) {
+            device.destroy_fence(fence);
+        }
+    }
+}
+
+/// Wait for all `fences` against single deadline.
+/// Returns `false` on timeout.
+///
+/// # Safety
+///
+/// `device` must be the same that was used to create fences.
+pub(crate) unsafe fn wait_fences<B: rendy_core::hal::Backend>(
+    device: &Device<B>,
+    fences: &[Arc<B::Fence>],
+    timeout_ns: u64,
+) -> Result<bool, OomOrDeviceLost> {
+    if fences.is_empty() {
+        return Ok(true);
+    }
+    device.wait_for_fences(
+        fences.iter().map(|fence| &**fence),
+        WaitFor::All,
+        timeout_ns,
+    )
+}
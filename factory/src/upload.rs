@@ -6,6 +6,7 @@ use {
             PendingOnceState, PrimaryLevel, QueueId, RecordingState, Submission, Transfer,
         },
         core::Device,
+        factory::{ImageDataError, UploadError},
         resource::{Buffer, Escape, Handle, Image},
     },
     rendy_core::hal::device::{Device as _, OutOfMemory},
@@ -35,6 +36,92 @@ impl BufferState {
         }
     }
 
+    /// Create buffer state with stages and access inferred from `usage`.
+    /// Mapping for each usage flag is following:
+    ///
+    /// * `VERTEX` - `VERTEX_INPUT` / `VERTEX_BUFFER_READ`.
+    /// * `INDEX` - `VERTEX_INPUT` / `INDEX_BUFFER_READ`.
+    /// * `INDIRECT` - `DRAW_INDIRECT` / `INDIRECT_COMMAND_READ`.
+    /// * `UNIFORM` - shader stages / `UNIFORM_READ`.
+    /// * `UNIFORM_TEXEL` - shader stages / `SHADER_READ`.
+    /// * `STORAGE`, `STORAGE_TEXEL` - shader stages / `SHADER_READ | SHADER_WRITE`.
+    /// * `TRANSFER_SRC` - `TRANSFER` / `TRANSFER_READ`.
+    /// * `TRANSFER_DST` - `TRANSFER` / `TRANSFER_WRITE`.
+    ///
+    /// Shader stages are `VERTEX_SHADER | FRAGMENT_SHADER | COMPUTE_SHADER`,
+    /// which is only valid for graphics queue.
+    /// Flags of all usages are combined.
+    /// Empty `usage` gives the same state as `new`.
+    /// Use `new` with `with_stage` and `with_access` for anything else.
+    pub fn for_usage(queue: QueueId, usage: rendy_core::hal::buffer::Usage) -> Self {
+        use rendy_core::hal::{
+            buffer::{Access, Usage},
+            pso::PipelineStage,
+        };
+
+        let shader_stages = PipelineStage::VERTEX_SHADER
+            | PipelineStage::FRAGMENT_SHADER
+            | PipelineStage::COMPUTE_SHADER;
+
+        let mapping = [
+            (
+                Usage::VERTEX,
+                PipelineStage::VERTEX_INPUT,
+                Access::VERTEX_BUFFER_READ,
+            ),
+            (
+                Usage::INDEX,
+                PipelineStage::VERTEX_INPUT,
+                Access::INDEX_BUFFER_READ,
+            ),
+            (
+                Usage::INDIRECT,
+                PipelineStage::DRAW_INDIRECT,
+                Access::INDIRECT_COMMAND_READ,
+            ),
+            (Usage::UNIFORM, shader_stages, Access::UNIFORM_READ),
+            (Usage::UNIFORM_TEXEL, shader_stages, Access::SHADER_READ),
+            (
+                Usage::STORAGE,
+                shader_stages,
+                Access::SHADER_READ | Access::SHADER_WRITE,
+            ),
+            (
+                Usage::STORAGE_TEXEL,
+                shader_stages,
+                Access::SHADER_READ | Access::SHADER_WRITE,
+            ),
+            (
+                Usage::TRANSFER_SRC,
+                PipelineStage::TRANSFER,
+                Access::TRANSFER_READ,
+            ),
+            (
+                Usage::TRANSFER_DST,
+                PipelineStage::TRANSFER,
+                Access::TRANSFER_WRITE,
+            ),
+        ];
+
+        let (stage, access) = mapping
+            .iter()
+            .filter(|&&(flag, _, _)| usage.contains(flag))
+            .fold(
+                (PipelineStage::empty(), Access::empty()),
+                |(stage, access), &(_, s, a)| (stage | s, access | a),
+            );
+
+        if stage.is_empty() {
+            BufferState::new(queue)
+        } else {
+            BufferState {
+                queue,
+                stage,
+                access,
+            }
+        }
+    }
+
     /// Set specific stage.
     pub fn with_stage(mut self, stage: rendy_core::hal::pso::PipelineStage) -> Self {
         self.stage = stage;
@@ -75,6 +162,61 @@ impl ImageState {
         }
     }
 
+    /// Create image state with stages and access inferred from `layout`.
+    /// Mapping for each layout is following:
+    ///
+    /// * `ShaderReadOnlyOptimal` - `FRAGMENT_SHADER` / `SHADER_READ`.
+    /// * `ColorAttachmentOptimal` - `COLOR_ATTACHMENT_OUTPUT` /
+    ///   `COLOR_ATTACHMENT_READ | COLOR_ATTACHMENT_WRITE`.
+    /// * `DepthStencilAttachmentOptimal` - `EARLY_FRAGMENT_TESTS | LATE_FRAGMENT_TESTS` /
+    ///   `DEPTH_STENCIL_ATTACHMENT_READ | DEPTH_STENCIL_ATTACHMENT_WRITE`.
+    /// * `DepthStencilReadOnlyOptimal` - `EARLY_FRAGMENT_TESTS | LATE_FRAGMENT_TESTS | FRAGMENT_SHADER` /
+    ///   `DEPTH_STENCIL_ATTACHMENT_READ | SHADER_READ`.
+    /// * `TransferSrcOptimal` - `TRANSFER` / `TRANSFER_READ`.
+    /// * `TransferDstOptimal` - `TRANSFER` / `TRANSFER_WRITE`.
+    /// * `Present` - `BOTTOM_OF_PIPE` / no access.
+    /// * `General`, `Undefined` and `Preinitialized` give the same state as `new`.
+    ///
+    /// Use `new` with `with_stage` and `with_access` for anything else,
+    /// e.g. for images sampled in vertex or compute shaders.
+    pub fn for_layout(queue: QueueId, layout: rendy_core::hal::image::Layout) -> Self {
+        use rendy_core::hal::{
+            image::{Access, Layout},
+            pso::PipelineStage,
+        };
+
+        let (stage, access) = match layout {
+            Layout::ShaderReadOnlyOptimal => (PipelineStage::FRAGMENT_SHADER, Access::SHADER_READ),
+            Layout::ColorAttachmentOptimal => (
+                PipelineStage::COLOR_ATTACHMENT_OUTPUT,
+                Access::COLOR_ATTACHMENT_READ | Access::COLOR_ATTACHMENT_WRITE,
+            ),
+            Layout::DepthStencilAttachmentOptimal => (
+                PipelineStage::EARLY_FRAGMENT_TESTS | PipelineStage::LATE_FRAGMENT_TESTS,
+                Access::DEPTH_STENCIL_ATTACHMENT_READ | Access::DEPTH_STENCIL_ATTACHMENT_WRITE,
+            ),
+            Layout::DepthStencilReadOnlyOptimal => (
+                PipelineStage::EARLY_FRAGMENT_TESTS
+                    | PipelineStage::LATE_FRAGMENT_TESTS
+                    | PipelineStage::FRAGMENT_SHADER,
+                Access::DEPTH_STENCIL_ATTACHMENT_READ | Access::SHADER_READ,
+            ),
+            Layout::TransferSrcOptimal => (PipelineStage::TRANSFER, Access::TRANSFER_READ),
+            Layout::TransferDstOptimal => (PipelineStage::TRANSFER, Access::TRANSFER_WRITE),
+            Layout::Present => (PipelineStage::BOTTOM_OF_PIPE, Access::empty()),
+            Layout::General | Layout::Undefined | Layout::Preinitialized => {
+                return ImageState::new(queue, layout)
+            }
+        };
+
+        ImageState {
+            queue,
+            stage,
+            access,
+            layout,
+        }
+    }
+
     /// Set specific stage.
     pub fn with_stage(mut self, stage: rendy_core::hal::pso::PipelineStage) -> Self {
         self.stage = stage;
@@ -108,6 +250,74 @@ impl ImageStateOrLayout {
     }
 }
 
+/// Transition of image from one state to another.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImageTransition {
+    /// Stages to wait for and stages that wait for the transition.
+    pub stages: std::ops::Range<rendy_core::hal::pso::PipelineStage>,
+
+    /// Access to make available and access to make visible.
+    pub access: std::ops::Range<rendy_core::hal::image::Access>,
+
+    /// Layouts before and after transition.
+    pub layouts: std::ops::Range<rendy_core::hal::image::Layout>,
+}
+
+impl ImageTransition {
+    /// Compute transition from `last` state or layout to `next` state.
+    /// If `last` is just a layout then image is assumed to be unused by device,
+    /// so transition waits for nothing.
+    /// Content of the image in `Undefined` layout is discarded,
+    /// so there is no access to make available.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `last` and `next` states belong to different queue families,
+    /// as ownership transfer is not supported.
+    pub fn new(last: impl Into<ImageStateOrLayout>, next: ImageState) -> Self {
+        use rendy_core::hal::{image::Access, pso::PipelineStage};
+
+        let (last_stage, last_access, last_layout) = match last.into() {
+            ImageStateOrLayout::State(last) => {
+                assert_eq!(
+                    last.queue.family, next.queue.family,
+                    "Can't transfer ownership of the image between queue families"
+                );
+                (last.stage, last.access, last.layout)
+            }
+            ImageStateOrLayout::Layout(last_layout) => {
+                (PipelineStage::TOP_OF_PIPE, Access::empty(), last_layout)
+            }
+        };
+
+        let last_access = if last_layout == rendy_core::hal::image::Layout::Undefined {
+            Access::empty()
+        } else {
+            last_access
+        };
+
+        ImageTransition {
+            stages: last_stage..next.stage,
+            access: last_access..next.access,
+            layouts: last_layout..next.layout,
+        }
+    }
+
+    /// Make barrier for the `range` of `image` that performs this transition.
+    pub fn barrier<'a, B: rendy_core::hal::Backend>(
+        &self,
+        image: &'a B::Image,
+        range: rendy_core::hal::image::SubresourceRange,
+    ) -> rendy_core::hal::memory::Barrier<'a, B> {
+        rendy_core::hal::memory::Barrier::Image {
+            states: (self.access.start, self.layouts.start)..(self.access.end, self.layouts.end),
+            target: image,
+            families: None,
+            range,
+        }
+    }
+}
+
 impl From<ImageState> for ImageStateOrLayout {
     fn from(state: ImageState) -> Self {
         ImageStateOrLayout::State(state)
@@ -120,9 +330,188 @@ impl From<rendy_core::hal::image::Layout> for ImageStateOrLayout {
     }
 }
 
+/// Initial data of one layer of one mip level of the image.
+#[derive(Clone, Copy, Debug)]
+pub struct LayerMipData<'a> {
+    /// Array layer of the image.
+    pub layer: rendy_core::hal::image::Layer,
+
+    /// Mip level of the image.
+    pub level: rendy_core::hal::image::Level,
+
+    /// Tightly packed texels of the whole subresource.
+    pub data: &'a [u8],
+}
+
+/// Pack data of image subresources into single staging content.
+/// Returns packed content and copy regions, one per subresource.
+/// Each subresource is placed at offset aligned for both texel size and copy requirements.
+pub(crate) fn pack_layer_mip_data(
+    kind: rendy_core::hal::image::Kind,
+    levels: rendy_core::hal::image::Level,
+    format: rendy_core::hal::format::Format,
+    data: &[LayerMipData<'_>],
+) -> Result<(Vec<u8>, Vec<rendy_core::hal::command::BufferImageCopy>), ImageDataError> {
+    let format_desc = format.surface_desc();
+    let texel_size = format_desc.bits as u64 / 8;
+
+    // Copy offset must be multiple of both texel size and 4.
+    let mut align = 4;
+    while align % texel_size != 0 {
+        align += 4;
+    }
+
+    let mut content = Vec::new();
+    let mut regions = Vec::with_capacity(data.len());
+    let mut filled = std::collections::HashSet::new();
+
+    for subresource in data {
+        let (layer, level) = (subresource.layer, subresource.level);
+        if layer >= kind.num_layers() || level >= levels || !filled.insert((layer, level)) {
+            return Err(ImageDataError::BadSubresource { layer, level });
+        }
+
+        let extent = kind.level_extent(level);
+        let expected = (extent.width as u64).div_ceil(format_desc.dim.0 as u64)
+            * (extent.height as u64).div_ceil(format_desc.dim.1 as u64)
+            * extent.depth as u64
+            * texel_size;
+        if expected != subresource.data.len() as u64 {
+            return Err(ImageDataError::BadSize {
+                layer,
+                level,
+                expected,
+                actual: subresource.data.len() as u64,
+            });
+        }
+
+        let offset = (content.len() as u64).div_ceil(align) * align;
+        content.resize(offset as usize, 0);
+        content.extend_from_slice(subresource.data);
+
+        regions.push(rendy_core::hal::command::BufferImageCopy {
+            buffer_offset: offset,
+            buffer_width: 0,
+            buffer_height: 0,
+            image_layers: rendy_core::hal::image::SubresourceLayers {
+                aspects: format_desc.aspects,
+                level,
+                layers: layer..layer + 1,
+            },
+            image_offset: rendy_core::hal::image::Offset::ZERO,
+            image_extent: extent,
+        });
+    }
+
+    Ok((content, regions))
+}
+
+/// Smallest size of pooled staging buffer.
+const STAGING_MIN_SIZE: u64 = 256;
+
+/// Largest size of pooled staging buffer.
+/// Bigger uploads get dedicated staging buffer that is freed after upload.
+const STAGING_MAX_SIZE: u64 = 1 << 24;
+
+/// Maximum number of free staging buffers kept per size class.
+const STAGING_MAX_FREE: usize = 8;
+
+/// Size class index and size of staging buffer that fits `size` bytes.
+/// Returns `None` if buffer of this size shouldn't be pooled.
+fn staging_size_class(size: u64) -> Option<(usize, u64)> {
+    if size > STAGING_MAX_SIZE {
+        None
+    } else {
+        let size = size.next_power_of_two().max(STAGING_MIN_SIZE);
+        Some(((size / STAGING_MIN_SIZE).trailing_zeros() as usize, size))
+    }
+}
+
+/// Free staging buffers bucketed by power-of-two size classes.
+#[derive(Debug)]
+pub(crate) struct StagingPool<T> {
+    free: Vec<Vec<T>>,
+    created: usize,
+}
+
+impl<T> StagingPool<T> {
+    pub(crate) fn new() -> Self {
+        StagingPool {
+            free: Vec::new(),
+            created: 0,
+        }
+    }
+
+    /// Take buffer that can fit `size` bytes from the pool
+    /// or create new one with `create` that receives size to allocate.
+    /// Returns buffer and flag telling if it should be returned to the pool after use.
+    pub(crate) fn get<E>(
+        &mut self,
+        size: u64,
+        create: impl FnOnce(u64) -> Result<T, E>,
+    ) -> Result<(T, bool), E> {
+        match staging_size_class(size) {
+            Some((class, size)) => {
+                if let Some(buffer) = self.free.get_mut(class).and_then(Vec::pop) {
+                    return Ok((buffer, true));
+                }
+                self.created += 1;
+                log::trace!(
+                    "Create staging buffer of {} bytes, {} created in total",
+                    size,
+                    self.created
+                );
+                Ok((create(size)?, true))
+            }
+            None => Ok((create(size)?, false)),
+        }
+    }
+
+    /// Return buffer of `size` bytes taken from the pool.
+    /// Buffer is dropped if there are enough free buffers of its size class.
+    pub(crate) fn put(&mut self, size: u64, buffer: T) {
+        let (class, _) = staging_size_class(size).expect("Buffer is not from the pool");
+        while self.free.len() <= class {
+            self.free.push(Vec::new());
+        }
+        if self.free[class].len() < STAGING_MAX_FREE {
+            self.free[class].push(buffer);
+        }
+    }
+}
+
+/// Host-visible buffer with data to copy to device-local resource.
+#[derive(Debug)]
+pub(crate) struct Staging<B: rendy_core::hal::Backend> {
+    buffer: Escape<Buffer<B>>,
+    /// Size of the data in the buffer.
+    size: u64,
+    /// Buffer should be returned to the `StagingPool` once upload is complete.
+    pooled: bool,
+}
+
+impl<B> Staging<B>
+where
+    B: rendy_core::hal::Backend,
+{
+    /// Wrap staging buffer provided by user.
+    pub(crate) fn new(buffer: Escape<Buffer<B>>) -> Self {
+        Staging {
+            size: buffer.size(),
+            buffer,
+            pooled: false,
+        }
+    }
+
+    pub(crate) fn buffer_mut(&mut self) -> &mut Escape<Buffer<B>> {
+        &mut self.buffer
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct Uploader<B: rendy_core::hal::Backend> {
     family_uploads: Vec<Option<parking_lot::Mutex<FamilyUploads<B>>>>,
+    staging: parking_lot::Mutex<StagingPool<Escape<Buffer<B>>>>,
 }
 
 impl<B> Uploader<B>
@@ -152,13 +541,32 @@ where
                 command_buffers: Vec::new(),
                 barriers: Barriers::new(
                     rendy_core::hal::pso::PipelineStage::TRANSFER,
-                    rendy_core::hal::buffer::Access::TRANSFER_WRITE,
+                    rendy_core::hal::buffer::Access::TRANSFER_READ
+                        | rendy_core::hal::buffer::Access::TRANSFER_WRITE,
                     rendy_core::hal::image::Access::TRANSFER_WRITE,
                 ),
             }));
         }
 
-        Ok(Uploader { family_uploads })
+        Ok(Uploader {
+            family_uploads,
+            staging: parking_lot::Mutex::new(StagingPool::new()),
+        })
+    }
+
+    /// Get staging buffer that can fit `size` bytes of data.
+    /// Buffer is taken from the pool if possible, otherwise it is created with `create`.
+    pub(crate) fn staging_buffer<E>(
+        &self,
+        size: u64,
+        create: impl FnOnce(u64) -> Result<Escape<Buffer<B>>, E>,
+    ) -> Result<Staging<B>, E> {
+        let (buffer, pooled) = self.staging.lock().get(size, create)?;
+        Ok(Staging {
+            buffer,
+            size,
+            pooled,
+        })
     }
 
     /// # Safety
@@ -171,7 +579,7 @@ where
         device: &Device<B>,
         buffer: &Buffer<B>,
         offset: u64,
-        staging: Escape<Buffer<B>>,
+        staging: Staging<B>,
         last: Option<BufferState>,
         next: BufferState,
     ) -> Result<(), OutOfMemory> {
@@ -196,12 +604,12 @@ where
         let next_upload = family_uploads.next_upload(device, next.queue.index)?;
         let mut encoder = next_upload.command_buffer.encoder();
         encoder.copy_buffer(
-            staging.raw(),
+            staging.buffer.raw(),
             buffer.raw(),
             Some(rendy_core::hal::command::BufferCopy {
                 src: 0,
                 dst: offset,
-                size: staging.size(),
+                size: staging.size,
             }),
         );
 
@@ -210,6 +618,65 @@ where
         Ok(())
     }
 
+    /// Copy first `size` bytes of `src` buffer into `dst` buffer.
+    /// `src` is kept alive until the copy is complete.
+    ///
+    /// # Safety
+    ///
+    /// `device` must be the same that was used to create this `Uploader`.
+    /// `src` and `dst` must belong to the `device`.
+    ///
+    pub(crate) unsafe fn copy_buffer(
+        &self,
+        device: &Device<B>,
+        src: Escape<Buffer<B>>,
+        dst: &Buffer<B>,
+        size: u64,
+        last: Option<BufferState>,
+        next: BufferState,
+    ) -> Result<(), UploadError> {
+        if let Some(last) = last {
+            if last.queue != next.queue {
+                return Err(UploadError::QueueMismatch {
+                    last: last.queue,
+                    next: next.queue,
+                });
+            }
+        }
+
+        let mut family_uploads = self.family_uploads[next.queue.family.index]
+            .as_ref()
+            .unwrap()
+            .lock();
+
+        // Writes to `src` must be visible to the copy.
+        family_uploads.barriers.add_buffer(
+            last.map_or(rendy_core::hal::pso::PipelineStage::empty(), |l| l.stage),
+            last.map_or(rendy_core::hal::buffer::Access::empty(), |l| l.access),
+            next.stage,
+            next.access,
+        );
+
+        let next_upload = family_uploads
+            .next_upload(device, next.queue.index)
+            .map_err(UploadError::Upload)?;
+        let mut encoder = next_upload.command_buffer.encoder();
+        encoder.copy_buffer(
+            src.raw(),
+            dst.raw(),
+            Some(rendy_core::hal::command::BufferCopy {
+                src: 0,
+                dst: 0,
+                size,
+            }),
+        );
+
+        // Source buffer is released with staging buffers once upload is complete.
+        next_upload.staging_buffers.push(Staging::new(src));
+
+        Ok(())
+    }
+
     /// # Safety
     ///
     /// `image` must belong to the `device` that was used to create this Uploader.
@@ -221,37 +688,24 @@ where
         last: ImageStateOrLayout,
         next: ImageState,
     ) {
-        use rendy_core::hal::image::{Access, Layout};
-
         let mut family_uploads = self.family_uploads[next.queue.family.index]
             .as_ref()
             .unwrap()
             .lock();
 
-        let (last_stage, mut last_access, last_layout) = match last {
-            ImageStateOrLayout::State(last) => {
-                if last.queue != next.queue {
-                    unimplemented!("Can't sync resources across queues");
-                }
-                (last.stage, last.access, last.layout)
+        if let ImageStateOrLayout::State(last) = last {
+            if last.queue != next.queue {
+                unimplemented!("Can't sync resources across queues");
             }
-            ImageStateOrLayout::Layout(last_layout) => (
-                rendy_core::hal::pso::PipelineStage::TOP_OF_PIPE,
-                Access::empty(),
-                last_layout,
-            ),
-        };
-
-        if last_layout == Layout::Undefined || last_layout == next.layout {
-            last_access = Access::empty();
         }
 
+        let transition = ImageTransition::new(last, next);
         family_uploads.barriers.add_image(
             image,
             image_range,
-            last_stage,
-            last_access,
-            last_layout,
+            transition.stages.start,
+            transition.access.start,
+            transition.layouts.start,
             next.layout,
             next.stage,
             next.access,
@@ -273,7 +727,7 @@ where
         image_layers: rendy_core::hal::image::SubresourceLayers,
         image_offset: rendy_core::hal::image::Offset,
         image_extent: rendy_core::hal::image::Extent,
-        staging: Escape<Buffer<B>>,
+        staging: Staging<B>,
         last: ImageStateOrLayout,
         next: ImageState,
     ) -> Result<(), OutOfMemory> {
@@ -351,7 +805,7 @@ where
         let next_upload = family_uploads.next_upload(device, next.queue.index)?;
         let mut encoder = next_upload.command_buffer.encoder();
         encoder.copy_buffer_to_image(
-            staging.raw(),
+            staging.buffer.raw(),
             image.raw(),
             target_layout,
             Some(rendy_core::hal::command::BufferImageCopy {
@@ -368,6 +822,57 @@ where
         Ok(())
     }
 
+    /// Discard content of the whole image and copy `regions` from `staging` into it.
+    ///
+    /// # Safety
+    ///
+    /// `device` must be the same that was used to create this `Uploader`.
+    /// `image` must not be used by device.
+    pub(crate) unsafe fn upload_image_regions(
+        &self,
+        device: &Device<B>,
+        image: Handle<Image<B>>,
+        regions: Vec<rendy_core::hal::command::BufferImageCopy>,
+        staging: Staging<B>,
+        next: ImageState,
+    ) -> Result<(), OutOfMemory> {
+        use rendy_core::hal::image::{Access, Layout};
+
+        let mut family_uploads = self.family_uploads[next.queue.family.index]
+            .as_ref()
+            .unwrap()
+            .lock();
+
+        let target_layout = if next.layout == Layout::General {
+            Layout::General
+        } else {
+            Layout::TransferDstOptimal
+        };
+
+        family_uploads.barriers.add_image(
+            image.clone(),
+            rendy_core::hal::image::SubresourceRange {
+                aspects: image.format().surface_desc().aspects,
+                levels: 0..image.levels(),
+                layers: 0..image.layers(),
+            },
+            rendy_core::hal::pso::PipelineStage::TOP_OF_PIPE,
+            Access::empty(),
+            Layout::Undefined,
+            target_layout,
+            next.stage,
+            next.access,
+            next.layout,
+        );
+
+        let next_upload = family_uploads.next_upload(device, next.queue.index)?;
+        let mut encoder = next_upload.command_buffer.encoder();
+        encoder.copy_buffer_to_image(staging.buffer.raw(), image.raw(), target_layout, regions);
+
+        next_upload.staging_buffers.push(staging);
+        Ok(())
+    }
+
     /// Cleanup pending updates.
     ///
     /// # Safety
@@ -375,13 +880,43 @@ where
     /// `device` must be the same that was used to create this `Uploader`.
     ///
     pub(crate) unsafe fn cleanup(&mut self, device: &Device<B>) {
+        let staging = self.staging.get_mut();
         for uploader in self.family_uploads.iter_mut() {
             if let Some(uploader) = uploader {
-                uploader.get_mut().cleanup(device);
+                uploader.get_mut().cleanup(device, staging);
             }
         }
     }
 
+    /// Wait for all flushed updates to complete.
+    ///
+    /// # Safety
+    ///
+    /// `device` must be the same that was used to create this `Uploader`.
+    ///
+    pub(crate) unsafe fn wait(
+        &mut self,
+        device: &Device<B>,
+    ) -> Result<(), rendy_core::hal::device::OomOrDeviceLost> {
+        let fences: Vec<_> = self
+            .family_uploads
+            .iter_mut()
+            .flatten()
+            .flat_map(|uploads| {
+                uploads
+                    .get_mut()
+                    .pending
+                    .iter()
+                    .map(|pending| &pending.fence)
+            })
+            .collect();
+
+        if !fences.is_empty() {
+            device.wait_for_fences(fences, rendy_core::hal::device::WaitFor::All, !0)?;
+        }
+        Ok(())
+    }
+
     /// Flush new updates.
     ///
     /// # Safety
@@ -426,7 +961,7 @@ pub(crate) struct FamilyUploads<B: rendy_core::hal::Backend> {
 pub(crate) struct PendingUploads<B: rendy_core::hal::Backend> {
     barrier_buffer: CommandBuffer<B, Transfer, PendingOnceState, PrimaryLevel, IndividualReset>,
     command_buffer: CommandBuffer<B, Transfer, PendingOnceState, PrimaryLevel, IndividualReset>,
-    staging_buffers: Vec<Escape<Buffer<B>>>,
+    staging_buffers: Vec<Staging<B>>,
     fence: B::Fence,
 }
 
@@ -436,7 +971,7 @@ struct NextUploads<B: rendy_core::hal::Backend> {
         CommandBuffer<B, Transfer, RecordingState<OneShot>, PrimaryLevel, IndividualReset>,
     command_buffer:
         CommandBuffer<B, Transfer, RecordingState<OneShot>, PrimaryLevel, IndividualReset>,
-    staging_buffers: Vec<Escape<Buffer<B>>>,
+    staging_buffers: Vec<Staging<B>>,
     fence: B::Fence,
 }
 
@@ -514,7 +1049,7 @@ where
     ///
     /// `device` must be the same that was used with other methods of this instance.
     ///
-    unsafe fn cleanup(&mut self, device: &Device<B>) {
+    unsafe fn cleanup(&mut self, device: &Device<B>, staging: &mut StagingPool<Escape<Buffer<B>>>) {
         while let Some(pending) = self.pending.pop_front() {
             match device.get_fence_status(&pending.fence) {
                 Ok(false) => {
@@ -529,6 +1064,11 @@ where
                         .reset_fence(&pending.fence)
                         .expect("Can always reset signalled fence");
                     self.fences.push(pending.fence);
+                    for buffer in pending.staging_buffers {
+                        if buffer.pooled {
+                            staging.put(buffer.buffer.size(), buffer.buffer);
+                        }
+                    }
                     self.command_buffers.push([
                         pending.command_buffer.mark_complete().reset(),
                         pending.barrier_buffer.mark_complete().reset(),
@@ -566,3 +1106,133 @@ where
         self.pool.dispose(device);
     }
 }
+
+#[test]
+fn test_image_transitions() {
+    use {
+        crate::{
+            command::FamilyId,
+            core::{DeviceId, InstanceId},
+        },
+        rendy_core::hal::{
+            image::{Access, Layout},
+            pso::PipelineStage,
+        },
+    };
+
+    let queue = QueueId {
+        index: 0,
+        family: FamilyId {
+            index: 0,
+            device: DeviceId::new(InstanceId::new()),
+        },
+    };
+    let transfer = ImageState::for_layout(queue, Layout::TransferDstOptimal);
+    let sampled = ImageState::for_layout(queue, Layout::ShaderReadOnlyOptimal);
+    let color = ImageState::for_layout(queue, Layout::ColorAttachmentOptimal);
+
+    assert_eq!(
+        ImageTransition::new(Layout::Undefined, transfer),
+        ImageTransition {
+            stages: PipelineStage::TOP_OF_PIPE..PipelineStage::TRANSFER,
+            access: Access::empty()..Access::TRANSFER_WRITE,
+            layouts: Layout::Undefined..Layout::TransferDstOptimal,
+        }
+    );
+    assert_eq!(
+        ImageTransition::new(transfer, sampled),
+        ImageTransition {
+            stages: PipelineStage::TRANSFER..PipelineStage::FRAGMENT_SHADER,
+            access: Access::TRANSFER_WRITE..Access::SHADER_READ,
+            layouts: Layout::TransferDstOptimal..Layout::ShaderReadOnlyOptimal,
+        }
+    );
+    assert_eq!(
+        ImageTransition::new(sampled, color),
+        ImageTransition {
+            stages: PipelineStage::FRAGMENT_SHADER..PipelineStage::COLOR_ATTACHMENT_OUTPUT,
+            access: Access::SHADER_READ
+                ..Access::COLOR_ATTACHMENT_READ | Access::COLOR_ATTACHMENT_WRITE,
+            layouts: Layout::ShaderReadOnlyOptimal..Layout::ColorAttachmentOptimal,
+        }
+    );
+}
+
+#[test]
+fn test_staging_pool_reuse() {
+    let mut pool = StagingPool::new();
+    let mut created = 0;
+    for i in 0..100u64 {
+        let (buffer, pooled) = pool
+            .get(64 + i, |size| -> Result<u64, ()> {
+                created += 1;
+                Ok(size)
+            })
+            .unwrap();
+        assert!(pooled);
+        assert!(buffer >= 64 + i);
+        // Upload is complete.
+        pool.put(buffer, buffer);
+    }
+    assert_eq!(created, 1);
+    assert_eq!(pool.created, 1);
+
+    let (_, pooled) = pool
+        .get(STAGING_MAX_SIZE + 1, |size| -> Result<u64, ()> { Ok(size) })
+        .unwrap();
+    assert!(!pooled);
+}
+
+#[test]
+fn test_pack_layer_mip_data() {
+    use rendy_core::hal::{format::Format, image::Kind};
+
+    let kind = Kind::D2(2, 2, 2, 1);
+    let level0: Vec<u8> = (0..16).collect();
+    let level1 = [0xAA, 0xBB, 0xCC, 0xDD];
+    let data = [
+        LayerMipData {
+            layer: 0,
+            level: 0,
+            data: &level0,
+        },
+        LayerMipData {
+            layer: 1,
+            level: 0,
+            data: &level0,
+        },
+        LayerMipData {
+            layer: 1,
+            level: 1,
+            data: &level1,
+        },
+    ];
+
+    let (content, regions) = pack_layer_mip_data(kind, 2, Format::Rgba8Unorm, &data).unwrap();
+    assert_eq!(regions.len(), 3);
+    assert_eq!(regions[2].image_layers.level, 1);
+    assert_eq!(regions[2].image_layers.layers, 1..2);
+    assert_eq!(regions[2].image_extent.width, 1);
+
+    // Texel of the second layer's second mip.
+    let offset = regions[2].buffer_offset as usize;
+    assert_eq!(&content[offset..offset + 4], &level1);
+
+    assert_eq!(
+        pack_layer_mip_data(kind, 2, Format::Rgba8Unorm, &data[..1]).map(|_| ()),
+        Ok(())
+    );
+    assert_eq!(
+        pack_layer_mip_data(kind, 2, Format::Rgba8Unorm, &[data[0], data[0]]).map(|_| ()),
+        Err(ImageDataError::BadSubresource { layer: 0, level: 0 })
+    );
+    assert_eq!(
+        pack_layer_mip_data(kind, 2, Format::Rgb8Unorm, &data[..1]).map(|_| ()),
+        Err(ImageDataError::BadSize {
+            layer: 0,
+            level: 0,
+            expected: 12,
+            actual: 16,
+        })
+    );
+}
@@ -0,0 +1,372 @@
+use {
+    crate::{resource::DescriptorSet, Factory},
+    rendy_core::hal::{
+        device::Device as _,
+        pso::{
+            BufferDescriptorFormat, Descriptor, DescriptorArrayIndex, DescriptorBinding,
+            DescriptorSetCopy, DescriptorSetLayoutBinding, DescriptorSetWrite, DescriptorType,
+            ImageDescriptorType,
+        },
+        Backend,
+    },
+    std::ops::Range,
+};
+
+/// Error validating descriptor write against layout of the set.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DescriptorWriteError {
+    /// Layout has no binding with specified index.
+    NoBinding(DescriptorBinding),
+
+    /// Written range exceeds number of descriptors in the binding.
+    OutOfBounds {
+        /// Binding index.
+        binding: DescriptorBinding,
+        /// Number of descriptors in the binding.
+        count: DescriptorArrayIndex,
+        /// End of the written range.
+        end: DescriptorArrayIndex,
+    },
+
+    /// Descriptor doesn't match type of the binding.
+    TypeMismatch {
+        /// Binding index.
+        binding: DescriptorBinding,
+        /// Index of descriptor in the binding array.
+        index: DescriptorArrayIndex,
+        /// Type of the binding.
+        ty: DescriptorType,
+    },
+}
+
+impl std::fmt::Display for DescriptorWriteError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DescriptorWriteError::NoBinding(binding) => {
+                write!(fmt, "Descriptor set layout has no binding {}", binding)
+            }
+            DescriptorWriteError::OutOfBounds {
+                binding,
+                count,
+                end,
+            } => write!(
+                fmt,
+                "Write to descriptors up to {} exceeds {} descriptors of binding {}",
+                end, count, binding
+            ),
+            DescriptorWriteError::TypeMismatch { binding, index, ty } => write!(
+                fmt,
+                "Descriptor {} of binding {} doesn't match binding type {:?}",
+                index, binding, ty
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DescriptorWriteError {}
+
+/// Kind of descriptor, used to check it against binding type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DescriptorKind {
+    Sampler,
+    Image,
+    CombinedImageSampler,
+    Buffer,
+    TexelBuffer,
+}
+
+impl DescriptorKind {
+    fn of<B: Backend>(descriptor: &Descriptor<'_, B>) -> Self {
+        match descriptor {
+            Descriptor::Sampler(_) => DescriptorKind::Sampler,
+            Descriptor::Image(..) => DescriptorKind::Image,
+            Descriptor::CombinedImageSampler(..) => DescriptorKind::CombinedImageSampler,
+            Descriptor::Buffer(..) => DescriptorKind::Buffer,
+            Descriptor::TexelBuffer(_) => DescriptorKind::TexelBuffer,
+        }
+    }
+
+    fn fits(&self, binding: &DescriptorSetLayoutBinding) -> bool {
+        match (self, binding.ty) {
+            (DescriptorKind::Sampler, DescriptorType::Sampler) => true,
+            (
+                DescriptorKind::Image,
+                DescriptorType::Image {
+                    ty: ImageDescriptorType::Sampled { with_sampler },
+                },
+            ) => !with_sampler || binding.immutable_samplers,
+            (
+                DescriptorKind::Image,
+                DescriptorType::Image {
+                    ty: ImageDescriptorType::Storage { .. },
+                },
+            ) => true,
+            (DescriptorKind::Image, DescriptorType::InputAttachment) => true,
+            (
+                DescriptorKind::CombinedImageSampler,
+                DescriptorType::Image {
+                    ty: ImageDescriptorType::Sampled { with_sampler: true },
+                },
+            ) => true,
+            (
+                DescriptorKind::Buffer,
+                DescriptorType::Buffer {
+                    format: BufferDescriptorFormat::Structured { .. },
+                    ..
+                },
+            ) => true,
+            (
+                DescriptorKind::TexelBuffer,
+                DescriptorType::Buffer {
+                    format: BufferDescriptorFormat::Texel,
+                    ..
+                },
+            ) => true,
+            _ => false,
+        }
+    }
+}
+
+fn check_write(
+    bindings: &[DescriptorSetLayoutBinding],
+    binding: DescriptorBinding,
+    array_offset: DescriptorArrayIndex,
+    kinds: impl ExactSizeIterator<Item = DescriptorKind>,
+) -> Result<(), DescriptorWriteError> {
+    let layout_binding = bindings
+        .iter()
+        .find(|b| b.binding == binding)
+        .ok_or(DescriptorWriteError::NoBinding(binding))?;
+
+    let end = array_offset + kinds.len();
+    if end > layout_binding.count {
+        return Err(DescriptorWriteError::OutOfBounds {
+            binding,
+            count: layout_binding.count,
+            end,
+        });
+    }
+
+    for (index, kind) in (array_offset..).zip(kinds) {
+        if !kind.fits(layout_binding) {
+            return Err(DescriptorWriteError::TypeMismatch {
+                binding,
+                index,
+                ty: layout_binding.ty,
+            });
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug)]
+struct PendingWrite<'a, B: Backend> {
+    set: &'a B::DescriptorSet,
+    binding: DescriptorBinding,
+    array_offset: DescriptorArrayIndex,
+    descriptors: Range<usize>,
+}
+
+/// Accumulates descriptor writes and copies across multiple sets
+/// to submit them with a single `write_descriptor_sets` call.
+///
+/// For simple cases `write_descriptor_sets` of the device
+/// can still be called directly.
+#[derive(Debug)]
+pub struct DescriptorWriter<'a, B: Backend> {
+    descriptors: Vec<Descriptor<'a, B>>,
+    writes: Vec<PendingWrite<'a, B>>,
+    copies: Vec<DescriptorSetCopy<'a, B>>,
+}
+
+impl<'a, B> Default for DescriptorWriter<'a, B>
+where
+    B: Backend,
+{
+    fn default() -> Self {
+        DescriptorWriter {
+            descriptors: Vec::new(),
+            writes: Vec::new(),
+            copies: Vec::new(),
+        }
+    }
+}
+
+impl<'a, B> DescriptorWriter<'a, B>
+where
+    B: Backend,
+{
+    /// Create empty writer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add write of `descriptors` into `binding` of the `set` starting at `array_offset`.
+    /// Descriptors are checked against layout the set was allocated with.
+    /// Unlike raw writes, descriptors are not allowed to spill into the next binding.
+    pub fn write(
+        &mut self,
+        set: &'a DescriptorSet<B>,
+        binding: DescriptorBinding,
+        array_offset: DescriptorArrayIndex,
+        descriptors: impl IntoIterator<Item = Descriptor<'a, B>>,
+    ) -> Result<&mut Self, DescriptorWriteError> {
+        let start = self.descriptors.len();
+        self.descriptors.extend(descriptors);
+
+        if let Err(err) = check_write(
+            &set.layout().info().bindings,
+            binding,
+            array_offset,
+            self.descriptors[start..].iter().map(DescriptorKind::of),
+        ) {
+            self.descriptors.truncate(start);
+            return Err(err);
+        }
+
+        self.writes.push(PendingWrite {
+            set: set.raw(),
+            binding,
+            array_offset,
+            descriptors: start..self.descriptors.len(),
+        });
+        Ok(self)
+    }
+
+    /// Add write to raw descriptor set without validation.
+    pub fn write_raw(
+        &mut self,
+        set: &'a B::DescriptorSet,
+        binding: DescriptorBinding,
+        array_offset: DescriptorArrayIndex,
+        descriptors: impl IntoIterator<Item = Descriptor<'a, B>>,
+    ) -> &mut Self {
+        let start = self.descriptors.len();
+        self.descriptors.extend(descriptors);
+        self.writes.push(PendingWrite {
+            set,
+            binding,
+            array_offset,
+            descriptors: start..self.descriptors.len(),
+        });
+        self
+    }
+
+    /// Add copy of descriptors between sets.
+    /// Copies are performed after all writes.
+    pub fn copy(&mut self, copy: DescriptorSetCopy<'a, B>) -> &mut Self {
+        self.copies.push(copy);
+        self
+    }
+
+    /// Check if there are no pending writes and copies.
+    pub fn is_empty(&self) -> bool {
+        self.writes.is_empty() && self.copies.is_empty()
+    }
+
+    /// Perform all pending writes with single `write_descriptor_sets` call
+    /// followed by single `copy_descriptor_sets` call.
+    ///
+    /// # Safety
+    ///
+    /// Descriptor sets must be created by the `factory`
+    /// and must not be used by pending command buffers.
+    pub unsafe fn commit(self, factory: &Factory<B>) {
+        let descriptors = &self.descriptors;
+        if !self.writes.is_empty() {
+            factory
+                .device()
+                .write_descriptor_sets(self.writes.iter().map(|write| DescriptorSetWrite {
+                    set: write.set,
+                    binding: write.binding,
+                    array_offset: write.array_offset,
+                    descriptors: &descriptors[write.descriptors.clone()],
+                }));
+        }
+        if !self.copies.is_empty() {
+            factory.device().copy_descriptor_sets(&self.copies);
+        }
+    }
+}
+
+#[test]
+fn test_check_write() {
+    use rendy_core::hal::pso::{BufferDescriptorType, ShaderStageFlags};
+
+    let binding = |binding, ty, count| DescriptorSetLayoutBinding {
+        binding,
+        ty,
+        count,
+        stage_flags: ShaderStageFlags::FRAGMENT,
+        immutable_samplers: false,
+    };
+    let bindings = [
+        binding(
+            0,
+            DescriptorType::Buffer {
+                ty: BufferDescriptorType::Uniform,
+                format: BufferDescriptorFormat::Structured {
+                    dynamic_offset: false,
+                },
+            },
+            1,
+        ),
+        binding(
+            1,
+            DescriptorType::Image {
+                ty: ImageDescriptorType::Sampled {
+                    with_sampler: false,
+                },
+            },
+            4,
+        ),
+        binding(2, DescriptorType::Sampler, 1),
+    ];
+
+    fn kinds(kinds: &[DescriptorKind]) -> impl ExactSizeIterator<Item = DescriptorKind> + '_ {
+        kinds.iter().copied()
+    }
+
+    assert_eq!(
+        check_write(&bindings, 0, 0, kinds(&[DescriptorKind::Buffer])),
+        Ok(())
+    );
+    assert_eq!(
+        check_write(
+            &bindings,
+            1,
+            2,
+            kinds(&[DescriptorKind::Image, DescriptorKind::Image])
+        ),
+        Ok(())
+    );
+    assert_eq!(
+        check_write(&bindings, 2, 0, kinds(&[DescriptorKind::Sampler])),
+        Ok(())
+    );
+    assert_eq!(
+        check_write(&bindings, 3, 0, kinds(&[DescriptorKind::Sampler])),
+        Err(DescriptorWriteError::NoBinding(3))
+    );
+    assert_eq!(
+        check_write(
+            &bindings,
+            1,
+            3,
+            kinds(&[DescriptorKind::Image, DescriptorKind::Image])
+        ),
+        Err(DescriptorWriteError::OutOfBounds {
+            binding: 1,
+            count: 4,
+            end: 5
+        })
+    );
+    assert_eq!(
+        check_write(&bindings, 2, 0, kinds(&[DescriptorKind::Image])),
+        Err(DescriptorWriteError::TypeMismatch {
+            binding: 2,
+            index: 0,
+            ty: DescriptorType::Sampler
+        })
+    );
+}
@@ -15,5 +15,6 @@ use rendy_factory as factory;
 
 pub mod cirque;
 mod frame;
+mod pool;
 
-pub use crate::frame::*;
+pub use crate::{frame::*, pool::*};
@@ -0,0 +1,207 @@
+//! Command pools reclaimed when frames complete.
+
+use {
+    crate::{
+        command::{
+            Capability, CommandBuffer, CommandPool, Family, InitialState, Level, PendingState,
+            PrimaryLevel, QueueType, Resettable, Supports,
+        },
+        factory::Factory,
+        frame::{CompleteFrame, Frame, Frames},
+    },
+    rendy_core::hal::device::OutOfMemory,
+};
+
+#[derive(Debug)]
+struct FramePool<B: rendy_core::hal::Backend, C, L> {
+    pool: CommandPool<B, C>,
+    free: Vec<CommandBuffer<B, C, InitialState, L>>,
+    /// Buffers submitted as part of the frame.
+    /// Their state is a lie until the pool is reset.
+    submitted: Vec<CommandBuffer<B, C, InitialState, L>>,
+}
+
+/// Command pools tagged with frames their command buffers were submitted in.
+///
+/// Each frame gets its own pool. Once the frame is complete the pool is reset as a whole
+/// and its command buffers are reused for later frames of the same family.
+/// Pool of a frame that is not complete yet is never reset.
+#[derive(Debug)]
+pub struct FrameCommandPools<B: rendy_core::hal::Backend, C = QueueType, L = PrimaryLevel> {
+    free: Vec<FramePool<B, C, L>>,
+    pending: std::collections::VecDeque<(u64, FramePool<B, C, L>)>,
+}
+
+impl<B, C, L> Default for FrameCommandPools<B, C, L>
+where
+    B: rendy_core::hal::Backend,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<B, C, L> FrameCommandPools<B, C, L>
+where
+    B: rendy_core::hal::Backend,
+{
+    /// Create empty collection of pools.
+    pub fn new() -> Self {
+        FrameCommandPools {
+            free: Vec::new(),
+            pending: std::collections::VecDeque::new(),
+        }
+    }
+
+    /// Get command buffer to record commands of the `frame`.
+    /// Command buffer is allocated from the pool of the `frame`
+    /// which is created for `family` if necessary.
+    ///
+    /// Command buffer must be returned with [`submitted`] once submitted.
+    /// Pool is reset when the `frame` completes, so command buffer that wasn't returned
+    /// must not be used after that.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `family` doesn't support capability `C`.
+    /// Panics if `frame` is older than the last frame buffer was requested for.
+    /// Panics if buffers for the `frame` were already requested from another family.
+    ///
+    /// [`submitted`]: #method.submitted
+    pub fn buffer(
+        &mut self,
+        frame: Frame,
+        factory: &Factory<B>,
+        family: &Family<B>,
+    ) -> Result<CommandBuffer<B, C, InitialState, L>, OutOfMemory>
+    where
+        C: Capability,
+        L: Level,
+        QueueType: Supports<C>,
+    {
+        let pool = self.frame_pool(frame, factory, family)?;
+        Ok(match pool.free.pop() {
+            Some(buffer) => buffer,
+            None => pool.pool.allocate_buffers(1).remove(0),
+        })
+    }
+
+    /// Return command buffer that was submitted as part of the `frame`.
+    /// It will be reused after the `frame` is complete.
+    ///
+    /// # Safety
+    ///
+    /// `buffer` must be acquired with [`buffer`] for the same `frame`.
+    /// All submissions of the `buffer` must be made before any fence of the `frame`
+    /// is submitted to the same queue.
+    /// None of [`Submit`] instances created from the `buffer` may be used after that.
+    ///
+    /// [`buffer`]: #method.buffer
+    /// [`Submit`]: ../command/struct.Submit.html
+    pub unsafe fn submitted<N>(
+        &mut self,
+        frame: Frame,
+        buffer: CommandBuffer<B, C, PendingState<N>, L>,
+    ) where
+        N: Resettable,
+    {
+        let pool = match self
+            .pending
+            .iter_mut()
+            .find(|(index, _)| *index == frame.index())
+        {
+            Some((_, pool)) => pool,
+            None => panic!("No command buffers were acquired for the frame"),
+        };
+        // Buffer stays pending until the pool is reset after the frame completes.
+        pool.submitted.push(buffer.mark_complete().mark_reset());
+    }
+
+    /// Reset pools of all frames up to and including `complete`.
+    pub fn reclaim(&mut self, complete: &CompleteFrame) {
+        self.reclaim_until(complete.index() + 1);
+    }
+
+    /// Reset pools of all frames that `frames` reports as complete.
+    pub fn reclaim_frames(&mut self, frames: &Frames<B>) {
+        self.reclaim_until(frames.complete_upper_bound());
+    }
+
+    /// Dispose of all pools and command buffers.
+    ///
+    /// # Safety
+    ///
+    /// All frames command buffers were submitted in must be complete.
+    pub unsafe fn dispose(mut self, factory: &Factory<B>)
+    where
+        L: Level,
+    {
+        let pending = self.pending.drain(..).map(|(_, pool)| pool);
+        for mut pool in self.free.drain(..).chain(pending) {
+            pool.pool
+                .free_buffers(pool.free.drain(..).chain(pool.submitted.drain(..)));
+            pool.pool.dispose(factory.device());
+        }
+    }
+
+    fn reclaim_until(&mut self, complete_upper_bound: u64) {
+        while let Some((index, _)) = self.pending.front() {
+            if *index >= complete_upper_bound {
+                break;
+            }
+            let (_, mut pool) = self.pending.pop_front().unwrap();
+            unsafe {
+                // Frame is complete so none of the buffers are pending.
+                pool.pool.reset();
+            }
+            pool.free.append(&mut pool.submitted);
+            self.free.push(pool);
+        }
+    }
+
+    fn frame_pool(
+        &mut self,
+        frame: Frame,
+        factory: &Factory<B>,
+        family: &Family<B>,
+    ) -> Result<&mut FramePool<B, C, L>, OutOfMemory>
+    where
+        C: Capability,
+        QueueType: Supports<C>,
+    {
+        match self.pending.back() {
+            Some((index, pool)) if *index == frame.index() => {
+                assert_eq!(
+                    pool.pool.family_id(),
+                    family.id(),
+                    "Command buffers for frame {} were requested from another family",
+                    index
+                );
+            }
+            Some((index, _)) if *index > frame.index() => panic!(
+                "Frame {} is older than frame {} command buffers were already requested for",
+                frame.index(),
+                index
+            ),
+            _ => {
+                let free = self
+                    .free
+                    .iter()
+                    .position(|pool| pool.pool.family_id() == family.id());
+                let pool = match free {
+                    Some(free) => self.free.swap_remove(free),
+                    None => FramePool {
+                        pool: factory
+                            .create_command_pool(family)?
+                            .with_capability::<C>()
+                            .expect("Family must support required capability"),
+                        free: Vec::new(),
+                        submitted: Vec::new(),
+                    },
+                };
+                self.pending.push_back((frame.index(), pool));
+            }
+        }
+        Ok(&mut self.pending.back_mut().unwrap().1)
+    }
+}
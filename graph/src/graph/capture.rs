@@ -0,0 +1,293 @@
+use {
+    super::Graph,
+    crate::{
+        chain,
+        command::FamilyId,
+        factory::Factory,
+        memory::Download,
+        resource::{BufferCreationError, BufferInfo},
+        ImageId,
+    },
+    rendy_core::hal::{
+        device::{MapError, OomOrDeviceLost},
+        format::{Aspects, Format},
+        image::{Access, Extent, Layout},
+        pso::PipelineStage,
+        queue::QueueFamilyId,
+        Backend,
+    },
+    thread_profiler::profile_scope,
+};
+
+/// State in which image is left by the last node that accesses it.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct CaptureState {
+    family: QueueFamilyId,
+    access: Access,
+    layout: Layout,
+    stages: PipelineStage,
+}
+
+/// Collect final states of all images used by the graph.
+pub(super) fn capture_states(chains: &chain::Chains) -> Vec<Option<CaptureState>> {
+    let count = chains.images.keys().map(|id| id.0 + 1).max().unwrap_or(0);
+    let mut states = vec![None; count];
+    for (id, chain) in &chains.images {
+        if let Some(link) = chain.links().last() {
+            let state = link.state();
+            states[id.0] = Some(CaptureState {
+                family: link.family(),
+                access: state.access,
+                layout: state.layout,
+                stages: state.stages,
+            });
+        }
+    }
+    states
+}
+
+/// Aspect that is read back for the format and size of one texel of that aspect in bytes.
+/// Depth aspect is read for depth and depth-stencil formats.
+fn capture_aspect(format: Format) -> (Aspects, u32) {
+    let desc = format.surface_desc();
+    if desc.aspects.contains(Aspects::DEPTH) {
+        let size = match format {
+            Format::D16Unorm | Format::D16UnormS8Uint => 2,
+            _ => 4,
+        };
+        (Aspects::DEPTH, size)
+    } else if desc.aspects.contains(Aspects::STENCIL) {
+        (Aspects::STENCIL, 1)
+    } else {
+        (Aspects::COLOR, desc.bits as u32 / 8)
+    }
+}
+
+/// Content of the graph image read back with [`Graph::debug_capture`].
+///
+/// [`Graph::debug_capture`]: struct.Graph.html#method.debug_capture
+#[derive(Clone, Debug)]
+pub struct CapturedImage {
+    /// Tightly packed texels of the first mip level of all layers.
+    pub data: Vec<u8>,
+
+    /// Format of the image.
+    /// For depth-stencil formats `data` contains only the depth aspect.
+    pub format: Format,
+
+    /// Aspect that was read.
+    pub aspect: Aspects,
+
+    /// Extent of the first mip level.
+    pub extent: Extent,
+
+    /// Number of layers in `data`.
+    pub layers: u16,
+}
+
+/// Error reading back graph image.
+#[derive(Debug)]
+pub enum DebugCaptureError {
+    /// Failed to create staging buffer.
+    Buffer(BufferCreationError),
+
+    /// Failed to map staging buffer.
+    Map(MapError),
+
+    /// Failed to execute copy commands.
+    Execute(OomOrDeviceLost),
+}
+
+impl std::fmt::Display for DebugCaptureError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DebugCaptureError::Buffer(err) => write!(
+                fmt,
+                "Failed to capture image because of failure to create staging buffer: {:?}",
+                err
+            ),
+            DebugCaptureError::Map(err) => write!(
+                fmt,
+                "Failed to capture image because of failure to map staging buffer: {:?}",
+                err
+            ),
+            DebugCaptureError::Execute(err) => write!(
+                fmt,
+                "Failed to capture image because of failure to execute copy: {:?}",
+                err
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DebugCaptureError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DebugCaptureError::Buffer(err) => Some(err),
+            DebugCaptureError::Map(err) => Some(err),
+            DebugCaptureError::Execute(err) => Some(err),
+        }
+    }
+}
+
+impl<B, T> Graph<B, T>
+where
+    B: Backend,
+    T: ?Sized,
+{
+    /// Wait for all submitted frames to complete and read content of the image
+    /// as it was left by the last node that accesses it.
+    ///
+    /// Only the first mip level is read.
+    /// For depth-stencil formats depth aspect is read.
+    ///
+    /// Image is copied to staging buffer on the queue family of the last node
+    /// and transitioned back to its state afterwards, so the graph can continue rendering.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if graph was built without
+    /// [`GraphBuilder::with_debug_capture`] or no node accesses the image.
+    ///
+    /// [`GraphBuilder::with_debug_capture`]: struct.GraphBuilder.html#method.with_debug_capture
+    pub fn debug_capture(
+        &mut self,
+        factory: &Factory<B>,
+        families: &mut crate::command::Families<B>,
+        id: ImageId,
+    ) -> Result<CapturedImage, DebugCaptureError> {
+        profile_scope!("debug_capture");
+
+        self.assert_device_owner(factory.device());
+
+        let state = self
+            .captures
+            .as_ref()
+            .expect("Debug capture must be enabled with `GraphBuilder::with_debug_capture`")
+            .get(id.0)
+            .copied()
+            .flatten()
+            .expect("Image must be accessed by at least one node");
+
+        self.wait_last_frame(factory);
+
+        let image = self.ctx.get_image(id).unwrap();
+        let format = image.format();
+        let (aspect, texel_size) = capture_aspect(format);
+        let extent = image.kind().extent();
+        let layers = image.layers();
+        let size = texel_size as u64
+            * extent.width as u64
+            * extent.height as u64
+            * extent.depth as u64
+            * layers as u64;
+
+        let mut staging = factory
+            .create_buffer(
+                BufferInfo {
+                    size,
+                    usage: rendy_core::hal::buffer::Usage::TRANSFER_DST,
+                },
+                Download,
+            )
+            .map_err(DebugCaptureError::Buffer)?;
+
+        // Layout of depth-stencil image must be transitioned for both aspects.
+        let range = rendy_core::hal::image::SubresourceRange {
+            aspects: format.surface_desc().aspects,
+            levels: 0..1,
+            layers: 0..layers,
+        };
+
+        unsafe {
+            factory
+                .execute_oneshot(
+                    families,
+                    FamilyId {
+                        device: self.device,
+                        index: state.family.0,
+                    },
+                    |encoder| {
+                        encoder.pipeline_barrier(
+                            state.stages..PipelineStage::TRANSFER,
+                            rendy_core::hal::memory::Dependencies::empty(),
+                            Some(rendy_core::hal::memory::Barrier::Image {
+                                states: (state.access, state.layout)
+                                    ..(Access::TRANSFER_READ, Layout::TransferSrcOptimal),
+                                target: image.raw(),
+                                families: None,
+                                range: range.clone(),
+                            }),
+                        );
+                        encoder.copy_image_to_buffer(
+                            image.raw(),
+                            Layout::TransferSrcOptimal,
+                            staging.raw(),
+                            Some(rendy_core::hal::command::BufferImageCopy {
+                                buffer_offset: 0,
+                                buffer_width: 0,
+                                buffer_height: 0,
+                                image_layers: rendy_core::hal::image::SubresourceLayers {
+                                    aspects: aspect,
+                                    level: 0,
+                                    layers: 0..layers,
+                                },
+                                image_offset: rendy_core::hal::image::Offset::ZERO,
+                                image_extent: extent,
+                            }),
+                        );
+                        encoder.pipeline_barrier(
+                            PipelineStage::TRANSFER..state.stages | PipelineStage::HOST,
+                            rendy_core::hal::memory::Dependencies::empty(),
+                            vec![
+                                rendy_core::hal::memory::Barrier::Image {
+                                    states: (Access::TRANSFER_READ, Layout::TransferSrcOptimal)
+                                        ..(state.access, state.layout),
+                                    target: image.raw(),
+                                    families: None,
+                                    range: range.clone(),
+                                },
+                                rendy_core::hal::memory::Barrier::Buffer {
+                                    states: rendy_core::hal::buffer::Access::TRANSFER_WRITE
+                                        ..rendy_core::hal::buffer::Access::HOST_READ,
+                                    target: staging.raw(),
+                                    families: None,
+                                    range: rendy_core::hal::buffer::SubRange::WHOLE,
+                                },
+                            ],
+                        );
+                    },
+                )
+                .map_err(DebugCaptureError::Execute)?;
+        }
+
+        let data = {
+            let mut mapping = staging
+                .map(factory.device(), 0..size)
+                .map_err(DebugCaptureError::Map)?;
+            unsafe {
+                mapping
+                    .read::<u8>(factory.device(), 0..size)
+                    .map_err(DebugCaptureError::Map)?
+                    .to_vec()
+            }
+        };
+
+        Ok(CapturedImage {
+            data,
+            format,
+            aspect,
+            extent,
+            layers,
+        })
+    }
+}
+
+#[test]
+fn test_capture_aspect() {
+    assert_eq!(capture_aspect(Format::Rgba8Unorm), (Aspects::COLOR, 4));
+    assert_eq!(capture_aspect(Format::Rgba32Sfloat), (Aspects::COLOR, 16));
+    assert_eq!(capture_aspect(Format::D32Sfloat), (Aspects::DEPTH, 4));
+    assert_eq!(capture_aspect(Format::D24UnormS8Uint), (Aspects::DEPTH, 4));
+    assert_eq!(capture_aspect(Format::D16Unorm), (Aspects::DEPTH, 2));
+}
@@ -0,0 +1,169 @@
+//! GraphViz description of the scheduled graph.
+//! Produced only in debug builds for documentation and debugging.
+
+use {
+    crate::chain::{self, AccessFlags as _},
+    std::{collections::HashMap, fmt::Write as _},
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Resource {
+    Buffer(usize),
+    Image(usize),
+}
+
+/// Describe nodes, grouped into clusters per queue,
+/// and resources passed between nodes in submission order.
+///
+/// Resources are edges from the node that last wrote the resource
+/// to the next nodes that access it.
+/// Explicit dependencies without shared resources are dashed edges.
+pub(super) fn to_dot<S>(
+    names: &[&'static str],
+    nodes: &[chain::Node],
+    schedule: &chain::Schedule<S>,
+) -> String {
+    let mut out = String::new();
+    writeln!(out, "digraph rendy {{").unwrap();
+    writeln!(out, "    node [shape=box];").unwrap();
+
+    // Position of the node in submission order.
+    let mut order = vec![0; nodes.len()];
+    for (index, submission) in schedule.ordered().enumerate() {
+        order[submission.node()] = index;
+    }
+
+    for family in schedule.iter() {
+        for queue in family.iter() {
+            let qid = queue.id();
+            writeln!(
+                out,
+                "    subgraph cluster_{}_{} {{",
+                qid.family().0,
+                qid.index()
+            )
+            .unwrap();
+            writeln!(
+                out,
+                "        label=\"Family {} queue {}\";",
+                qid.family().0,
+                qid.index()
+            )
+            .unwrap();
+            for submission in queue.iter() {
+                let node = submission.node();
+                writeln!(
+                    out,
+                    "        n{} [label=\"#{} {}\"];",
+                    node, order[node], names[node]
+                )
+                .unwrap();
+            }
+            writeln!(out, "    }}").unwrap();
+        }
+    }
+
+    let mut last_write: HashMap<Resource, usize> = HashMap::new();
+    for submission in schedule.ordered() {
+        let node = &nodes[submission.node()];
+
+        let mut accesses: Vec<_> = node
+            .buffers
+            .iter()
+            .map(|(id, state)| {
+                (
+                    Resource::Buffer(id.0),
+                    format!("buffer {}\\n{:?}", id.0, state.access),
+                    state.access.exclusive(),
+                )
+            })
+            .chain(node.images.iter().map(|(id, state)| {
+                (
+                    Resource::Image(id.0),
+                    format!("image {}\\n{:?}\\n{:?}", id.0, state.layout, state.access),
+                    state.access.exclusive(),
+                )
+            }))
+            .collect();
+        accesses.sort_by(|a, b| a.1.cmp(&b.1));
+
+        let mut linked = Vec::new();
+        for (resource, label, write) in accesses {
+            if let Some(&from) = last_write.get(&resource) {
+                writeln!(
+                    out,
+                    "    n{} -> n{} [label=\"{}\"{}];",
+                    from,
+                    node.id,
+                    label,
+                    if write { ", color=red" } else { "" }
+                )
+                .unwrap();
+                linked.push(from);
+            }
+            if write {
+                last_write.insert(resource, node.id);
+            }
+        }
+
+        for &dependency in &node.dependencies {
+            if !linked.contains(&dependency) {
+                writeln!(out, "    n{} -> n{} [style=dashed];", dependency, node.id).unwrap();
+            }
+        }
+    }
+
+    writeln!(out, "}}").unwrap();
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        rendy_core::hal::{
+            buffer::{Access, Usage},
+            pso::PipelineStage,
+            queue::QueueFamilyId,
+        },
+    };
+
+    fn compute_node(id: usize, access: Access, dependencies: Vec<usize>) -> chain::Node {
+        chain::Node {
+            id,
+            family: QueueFamilyId(0),
+            dependencies,
+            buffers: Some((
+                chain::Id(0),
+                chain::BufferState {
+                    access,
+                    stages: PipelineStage::COMPUTE_SHADER,
+                    layout: (),
+                    usage: Usage::STORAGE,
+                },
+            ))
+            .into_iter()
+            .collect(),
+            images: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let nodes = vec![
+            compute_node(0, Access::SHADER_WRITE, vec![]),
+            compute_node(1, Access::SHADER_READ, vec![0]),
+        ];
+        let chains = chain::collect(nodes.clone(), |_| 1);
+        let mut schedule = chain::sync(&chains, || ((), ()));
+        schedule.build_order();
+
+        let dot = to_dot(&["Write", "Read"], &nodes, &schedule);
+        assert!(dot.starts_with("digraph rendy {"));
+        assert!(dot.contains("subgraph cluster_0_0 {"));
+        assert!(dot.contains("n0 [label=\"#0 Write\"];"));
+        assert!(dot.contains("n1 [label=\"#1 Read\"];"));
+        assert!(dot.contains("n0 -> n1 [label=\"buffer 0\\nSHADER_READ\"];"));
+        assert!(!dot.contains("style=dashed"));
+    }
+}
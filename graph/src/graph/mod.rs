@@ -1,3 +1,13 @@
+mod capture;
+mod dot;
+mod transient;
+mod validate;
+
+pub use self::{
+    capture::{CapturedImage, DebugCaptureError},
+    validate::ResourceId,
+};
+
 use {
     crate::{
         chain,
@@ -5,17 +15,21 @@ use {
         core::{device_owned, DeviceId},
         factory::Factory,
         frame::{Fences, Frame, Frames},
-        memory::Data,
+        memory::{Block as _, Data, Download, Dynamic},
         node::{
             BufferBarrier, DynNode, ImageBarrier, NodeBuffer, NodeBuildError, NodeBuilder,
             NodeImage,
         },
         resource::{
-            Buffer, BufferCreationError, BufferInfo, Handle, Image, ImageCreationError, ImageInfo,
+            Buffer, BufferCreationError, BufferInfo, CreationError, Handle, Image,
+            ImageCreationError, ImageInfo,
         },
-        BufferId, ImageId, NodeId,
+        BufferId, ImageId, NodeId, PerFrameBufferId, PerFrameImageId,
     },
-    rendy_core::hal::{queue::QueueFamilyId, Backend},
+    rendy_core::hal::{
+        adapter::PhysicalDevice as _, device::Device as _, queue::QueueFamilyId, Backend,
+    },
+    std::ptr::NonNull,
     thread_profiler::profile_scope,
 };
 
@@ -23,9 +37,39 @@ use {
 struct GraphNode<B: Backend, T: ?Sized> {
     node: Box<dyn DynNode<B, T>>,
     queue: (usize, usize),
+    images: Vec<ImageId>,
+}
+
+/// Result of matching surface-sized images against current surface extent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SurfaceExtentStatus {
+    /// Images match surface extent or no node reports the extent.
+    Unchanged,
+
+    /// Images were recreated with new surface extent.
+    Resized,
+
+    /// Surface has zero size. Frames are skipped until it is restored.
+    Minimized,
+
+    /// Some nodes that use surface-sized images can't be resized.
+    /// Frames are skipped and graph must be rebuilt.
+    RebuildRequired,
+
+    /// Surface was lost, e.g. native window was destroyed.
+    /// Frames are skipped and graph must be rebuilt
+    /// with new surface created for the window.
+    SurfaceLost,
 }
 
 /// Graph that renders whole frame.
+///
+/// `T` is the type of auxiliary data shared by all nodes.
+/// Every node receives the same `&T` when built, run and disposed,
+/// so it can read frame-global state without storing references to it.
+/// Auxiliary data can only be modified between calls to [`run`].
+///
+/// [`run`]: #method.run
 #[derive(Debug)]
 pub struct Graph<B: Backend, T: ?Sized> {
     device: DeviceId,
@@ -36,6 +80,10 @@ pub struct Graph<B: Backend, T: ?Sized> {
     fences: Vec<Fences<B>>,
     inflight: u32,
     ctx: GraphContext<B>,
+    captures: Option<Vec<Option<capture::CaptureState>>>,
+    surface_sized: Vec<ImageId>,
+    surface_extent_status: SurfaceExtentStatus,
+    dot: Option<String>,
 }
 
 device_owned!(Graph<B, T: ?Sized>);
@@ -49,8 +97,36 @@ pub enum GraphBuildError {
     Image(ImageCreationError),
     /// Failed to create a semaphore.
     Semaphore(rendy_core::hal::device::OutOfMemory),
+    /// No queue family is suitable for the node.
+    /// E.g. no family can present to the node's surface.
+    NoQueueFamily {
+        /// Id of the node.
+        node: NodeId,
+        /// Name of the node as reported by its builder.
+        name: &'static str,
+    },
+    /// Node declares usage of the resource that requires transition
+    /// which can't be inserted, or that its usage flags or layout don't permit.
+    /// Checked only in debug builds.
+    InvalidUsage {
+        /// Misused resource.
+        resource: ResourceId,
+        /// Node that used resource before `to_node`, if the misuse involves two nodes.
+        from_node: Option<NodeId>,
+        /// Node that misuses the resource.
+        to_node: NodeId,
+        /// Description of the misuse.
+        detail: String,
+    },
     /// Failed to build a node.
-    Node(NodeBuildError),
+    Node {
+        /// Id of the node.
+        node: NodeId,
+        /// Name of the node as reported by its builder.
+        name: &'static str,
+        /// Error returned by the node builder.
+        error: NodeBuildError,
+    },
 }
 
 impl std::fmt::Display for GraphBuildError {
@@ -71,10 +147,35 @@ impl std::fmt::Display for GraphBuildError {
                 "Failed to build graph because of failure to create a semaphore: {:?}",
                 err
             ),
-            GraphBuildError::Node(err) => write!(
+            GraphBuildError::NoQueueFamily { node, name } => write!(
                 fmt,
-                "Failed to build graph because of failure to build a node: {:?}",
-                err
+                "Failed to build graph because no queue family is suitable for node {:?} ({})",
+                node, name
+            ),
+            GraphBuildError::InvalidUsage {
+                resource,
+                from_node: Some(from_node),
+                to_node,
+                detail,
+            } => write!(
+                fmt,
+                "Failed to build graph because of invalid usage of {:?} by nodes {:?} and {:?}: {}",
+                resource, from_node, to_node, detail
+            ),
+            GraphBuildError::InvalidUsage {
+                resource,
+                from_node: None,
+                to_node,
+                detail,
+            } => write!(
+                fmt,
+                "Failed to build graph because of invalid usage of {:?} by node {:?}: {}",
+                resource, to_node, detail
+            ),
+            GraphBuildError::Node { node, name, error } => write!(
+                fmt,
+                "Failed to build graph because of failure to build node {:?} ({}): {}",
+                node, name, error
             ),
         }
     }
@@ -86,11 +187,67 @@ impl std::error::Error for GraphBuildError {
             GraphBuildError::Buffer(err) => Some(err),
             GraphBuildError::Image(err) => Some(err),
             GraphBuildError::Semaphore(err) => Some(err),
-            GraphBuildError::Node(err) => Some(err),
+            GraphBuildError::NoQueueFamily { .. } => None,
+            GraphBuildError::InvalidUsage { .. } => None,
+            GraphBuildError::Node { error, .. } => Some(error),
+        }
+    }
+}
+
+/// Error reading output buffer of the graph.
+#[derive(Clone, Debug, PartialEq)]
+pub enum OutputBufferError {
+    /// Buffer wasn't marked as output with `GraphBuilder::mark_output_buffer`
+    /// or no node accesses it.
+    NotOutput(BufferId),
+    /// Failed to invalidate mapped memory of the buffer.
+    OutOfMemory(rendy_core::hal::device::OutOfMemory),
+}
+
+impl From<rendy_core::hal::device::OutOfMemory> for OutputBufferError {
+    fn from(error: rendy_core::hal::device::OutOfMemory) -> Self {
+        OutputBufferError::OutOfMemory(error)
+    }
+}
+
+impl std::fmt::Display for OutputBufferError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputBufferError::NotOutput(id) => write!(
+                fmt,
+                "Buffer {:?} is not an output of the graph or no node accesses it",
+                id
+            ),
+            OutputBufferError::OutOfMemory(err) => write!(
+                fmt,
+                "Failed to read output buffer because of failure to invalidate memory: {:?}",
+                err
+            ),
         }
     }
 }
 
+impl std::error::Error for OutputBufferError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            OutputBufferError::NotOutput(_) => None,
+            OutputBufferError::OutOfMemory(err) => Some(err),
+        }
+    }
+}
+
+/// Pointer to persistently mapped memory of the output buffer.
+#[derive(Debug)]
+struct OutputMapping {
+    id: BufferId,
+    ptr: NonNull<u8>,
+    coherent: bool,
+}
+
+// Mapping is only read through `&mut Graph` after the device is done with the buffer.
+unsafe impl Send for OutputMapping {}
+unsafe impl Sync for OutputMapping {}
+
 /// Graphics context contains all transient resources managed by graph.
 #[derive(Debug)]
 pub struct GraphContext<B: Backend> {
@@ -101,6 +258,11 @@ pub struct GraphContext<B: Backend> {
             Option<rendy_core::hal::command::ClearValue>,
         )>,
     >,
+    outputs: Vec<OutputMapping>,
+    per_frame_buffers: Vec<Vec<Handle<Buffer<B>>>>,
+    per_frame_images: Vec<Vec<Handle<Image<B>>>>,
+    transient_buffers: std::sync::Mutex<transient::TransientPool<Handle<Buffer<B>>>>,
+    frame_index: usize,
     /// Number of potential frames in flight
     pub frames_in_flight: u32,
 }
@@ -111,11 +273,14 @@ impl<B: Backend> GraphContext<B> {
         chains: &chain::Chains,
         buffers: impl IntoIterator<Item = &'a BufferInfo>,
         images: impl IntoIterator<Item = &'a (ImageInfo, Option<rendy_core::hal::command::ClearValue>)>,
+        output_buffers: &[BufferId],
+        debug_capture: bool,
         frames_in_flight: u32,
     ) -> Result<Self, GraphBuildError> {
         profile_scope!("alloc");
 
         log::trace!("Allocate buffers");
+        let mut outputs = Vec::new();
         let buffers: Vec<Option<Handle<Buffer<B>>>> = buffers
             .into_iter()
             .enumerate()
@@ -124,15 +289,37 @@ impl<B: Backend> GraphContext<B> {
                     .buffers
                     .get(&chain::Id(index))
                     .map(|buffer| {
-                        factory
-                            .create_buffer(
-                                BufferInfo {
-                                    usage: buffer.usage(),
-                                    ..*info
-                                },
-                                Data,
-                            )
-                            .map(|buffer| Some(buffer.into()))
+                        let info = BufferInfo {
+                            usage: buffer.usage(),
+                            ..*info
+                        };
+                        if !output_buffers.contains(&BufferId(index)) {
+                            return factory
+                                .create_buffer(info, Data)
+                                .map(|buffer| Some(buffer.into()));
+                        }
+
+                        // Output buffers are kept mapped for the whole graph lifetime
+                        // so they can be read after the device is done with them.
+                        let mut buffer = factory.create_buffer(info, Download)?;
+                        let mapping =
+                            buffer.map(factory.device(), 0..info.size).map_err(|err| {
+                                CreationError::Allocate(match err {
+                                    rendy_core::hal::device::MapError::OutOfMemory(oom) => {
+                                        oom.into()
+                                    }
+                                    _ => rendy_core::hal::device::OutOfMemory::Host.into(),
+                                })
+                            })?;
+                        outputs.push(OutputMapping {
+                            id: BufferId(index),
+                            ptr: mapping.ptr(),
+                            coherent: buffer
+                                .block()
+                                .properties()
+                                .contains(rendy_core::hal::memory::Properties::COHERENT),
+                        });
+                        Ok(Some(buffer.into()))
                     })
                     .unwrap_or(Ok(None))
             })
@@ -148,14 +335,12 @@ impl<B: Backend> GraphContext<B> {
                     .images
                     .get(&chain::Id(index))
                     .map(|image| {
+                        let mut usage = image.usage();
+                        if debug_capture {
+                            usage |= rendy_core::hal::image::Usage::TRANSFER_SRC;
+                        }
                         factory
-                            .create_image(
-                                ImageInfo {
-                                    usage: image.usage(),
-                                    ..*info
-                                },
-                                Data,
-                            )
+                            .create_image(ImageInfo { usage, ..*info }, Data)
                             .map(|image| Some((image.into(), *clear)))
                     })
                     .unwrap_or(Ok(None))
@@ -166,10 +351,123 @@ impl<B: Backend> GraphContext<B> {
         Ok(Self {
             buffers,
             images,
+            outputs,
+            per_frame_buffers: Vec::new(),
+            per_frame_images: Vec::new(),
+            transient_buffers: std::sync::Mutex::new(transient::TransientPool::new(
+                frames_in_flight,
+            )),
+            frame_index: 0,
             frames_in_flight,
         })
     }
 
+    fn alloc_per_frame(
+        &mut self,
+        factory: &Factory<B>,
+        buffers: &[BufferInfo],
+        images: &[ImageInfo],
+    ) -> Result<(), GraphBuildError> {
+        profile_scope!("alloc_per_frame");
+
+        let frames = self.frames_in_flight as usize;
+        self.per_frame_buffers = buffers
+            .iter()
+            .map(|info| {
+                (0..frames)
+                    .map(|_| factory.create_buffer(*info, Dynamic).map(Into::into))
+                    .collect()
+            })
+            .collect::<Result<_, _>>()
+            .map_err(GraphBuildError::Buffer)?;
+
+        self.per_frame_images = images
+            .iter()
+            .map(|info| {
+                (0..frames)
+                    .map(|_| factory.create_image(*info, Data).map(Into::into))
+                    .collect()
+            })
+            .collect::<Result<_, _>>()
+            .map_err(GraphBuildError::Image)?;
+
+        Ok(())
+    }
+
+    /// Index of the frame in flight that is being prepared.
+    /// Cycles through `0..frames_in_flight`.
+    pub fn frame_index(&self) -> usize {
+        self.frame_index
+    }
+
+    /// Get instance of the per-frame buffer for the frame being prepared.
+    pub fn get_per_frame_buffer(&self, id: PerFrameBufferId) -> Option<&Handle<Buffer<B>>> {
+        self.get_per_frame_buffer_at(id, self.frame_index)
+    }
+
+    /// Get instance of the per-frame buffer for the frame in flight with `index`.
+    pub fn get_per_frame_buffer_at(
+        &self,
+        id: PerFrameBufferId,
+        index: usize,
+    ) -> Option<&Handle<Buffer<B>>> {
+        self.per_frame_buffers
+            .get(id.0)
+            .and_then(|buffers| buffers.get(index))
+    }
+
+    /// Get instance of the per-frame image for the frame being prepared.
+    pub fn get_per_frame_image(&self, id: PerFrameImageId) -> Option<&Handle<Image<B>>> {
+        self.get_per_frame_image_at(id, self.frame_index)
+    }
+
+    /// Get instance of the per-frame image for the frame in flight with `index`.
+    pub fn get_per_frame_image_at(
+        &self,
+        id: PerFrameImageId,
+        index: usize,
+    ) -> Option<&Handle<Image<B>>> {
+        self.per_frame_images
+            .get(id.0)
+            .and_then(|images| images.get(index))
+    }
+
+    /// Create scratch buffer that lives only for the frame being prepared.
+    ///
+    /// Buffer is allocated from device-local memory of per-frame scratch pool
+    /// and returned to the pool once the frame is complete.
+    /// Buffers returned to the pool are reused by later frames, so the buffer may be larger
+    /// than requested and its content is undefined.
+    ///
+    /// Unlike buffers created with [`GraphBuilder::create_buffer`] scratch buffers are
+    /// not synchronized by graph. Node must record all commands that use the buffer
+    /// itself, including barriers between them.
+    /// Returned handle must not be used after the frame is complete.
+    ///
+    /// [`GraphBuilder::create_buffer`]: struct.GraphBuilder.html#method.create_buffer
+    pub fn create_transient_buffer(
+        &self,
+        factory: &Factory<B>,
+        info: BufferInfo,
+    ) -> Result<Handle<Buffer<B>>, BufferCreationError> {
+        profile_scope!("create_transient_buffer");
+
+        self.transient_buffers
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .acquire(self.frame_index, info, |info| {
+                factory.create_buffer(info, Data).map(Into::into)
+            })
+    }
+
+    /// Return scratch buffers of the complete frame that used current frame slot.
+    fn reclaim_transient_buffers(&mut self) {
+        self.transient_buffers
+            .get_mut()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .reclaim(self.frame_index);
+    }
+
     /// Get reference to transient image by id.
     pub fn get_image(&self, id: ImageId) -> Option<&Handle<Image<B>>> {
         self.get_image_with_clear(id).map(|(i, _)| i)
@@ -193,6 +491,41 @@ impl<B: Backend> GraphContext<B> {
     pub fn get_buffer(&self, id: BufferId) -> Option<&Handle<Buffer<B>>> {
         self.buffers.get(id.0).and_then(|x| x.as_ref()).map(|x| &*x)
     }
+
+    /// Check if buffer was marked as graph output.
+    pub fn is_output_buffer(&self, id: BufferId) -> bool {
+        self.outputs.iter().any(|output| output.id == id)
+    }
+
+    /// Recreate image with new extent keeping the rest of its properties.
+    fn resize_image(
+        &mut self,
+        factory: &Factory<B>,
+        id: ImageId,
+        extent: rendy_core::hal::window::Extent2D,
+    ) -> Result<(), ImageCreationError> {
+        if let Some((image, _)) = self.images.get_mut(id.0).and_then(Option::as_mut) {
+            let info = ImageInfo {
+                kind: resize_kind(image.kind(), extent),
+                ..*image.info()
+            };
+            *image = factory.create_image(info, Data)?.into();
+        }
+        Ok(())
+    }
+}
+
+/// Replace width and height of the image kind.
+fn resize_kind(
+    kind: rendy_core::hal::image::Kind,
+    extent: rendy_core::hal::window::Extent2D,
+) -> rendy_core::hal::image::Kind {
+    use rendy_core::hal::image::Kind;
+    match kind {
+        Kind::D1(_, layers) => Kind::D1(extent.width, layers),
+        Kind::D2(_, _, layers, samples) => Kind::D2(extent.width, extent.height, layers, samples),
+        Kind::D3(_, _, depth) => Kind::D3(extent.width, extent.height, depth),
+    }
 }
 
 impl<B, T> Graph<B, T>
@@ -202,11 +535,22 @@ where
 {
     /// Perform graph execution.
     /// Run every node of the graph and submit resulting command buffers to the queues.
+    ///
+    /// `aux` is borrowed immutably for the whole execution and shared by all nodes.
     pub fn run(&mut self, factory: &mut Factory<B>, families: &mut Families<B>, aux: &T) {
         profile_scope!("run");
 
         self.assert_device_owner(factory.device());
 
+        match self.update_surface_extent(factory, aux) {
+            SurfaceExtentStatus::Minimized
+            | SurfaceExtentStatus::RebuildRequired
+            | SurfaceExtentStatus::SurfaceLost => return,
+            SurfaceExtentStatus::Unchanged | SurfaceExtentStatus::Resized => {}
+        }
+
+        self.ctx.frame_index = (self.frames.next().index() % self.inflight as u64) as usize;
+
         if self.frames.next().index() >= self.inflight as _ {
             let wait = Frame::with_index(self.frames.next().index() - self.inflight as u64);
             let self_fences = &mut self.fences;
@@ -215,6 +559,7 @@ where
                 self_fences.push(fences);
             });
         }
+        self.ctx.reclaim_transient_buffers();
 
         let mut fences = self.fences.pop().unwrap_or_else(Fences::<B>::default);
         let mut fences_used = 0;
@@ -225,7 +570,7 @@ where
             let sid = submission.id();
             let qid = sid.queue();
 
-            let GraphNode { node, queue } = self
+            let GraphNode { node, queue, .. } = self
                 .nodes
                 .get_mut(submission.node())
                 .expect("Submission references node with out of bound index");
@@ -288,6 +633,116 @@ where
         self.frames.advance(fences);
     }
 
+    /// Number of frames graph renders simultaneously.
+    pub fn frames_in_flight(&self) -> u32 {
+        self.inflight
+    }
+
+    /// GraphViz description of the graph.
+    /// Nodes are labeled with their names and position in submission order
+    /// and clustered per queue they are scheduled to.
+    /// Resources passed between nodes are edges annotated with access and layout.
+    ///
+    /// Available only in debug builds, `None` otherwise.
+    pub fn to_dot(&self) -> Option<&str> {
+        self.dot.as_deref()
+    }
+
+    /// Status of surface-sized images after the last frame.
+    /// If it is `RebuildRequired` or `SurfaceLost` graph must be disposed and built again.
+    pub fn surface_extent_status(&self) -> SurfaceExtentStatus {
+        self.surface_extent_status
+    }
+
+    /// Query surface extent from nodes and recreate surface-sized images
+    /// if it has changed since last frame.
+    fn update_surface_extent(&mut self, factory: &Factory<B>, aux: &T) -> SurfaceExtentStatus {
+        let status = self.resize_surface_images(factory, aux);
+        self.surface_extent_status = status;
+        status
+    }
+
+    fn resize_surface_images(&mut self, factory: &Factory<B>, aux: &T) -> SurfaceExtentStatus {
+        match self.surface_extent_status {
+            SurfaceExtentStatus::RebuildRequired | SurfaceExtentStatus::SurfaceLost => {
+                return self.surface_extent_status
+            }
+            _ => {}
+        }
+
+        if self.nodes.iter().any(|node| node.node.surface_lost()) {
+            log::warn!("Surface is lost. Graph must be rebuilt with new surface");
+            return SurfaceExtentStatus::SurfaceLost;
+        }
+
+        let extent = match self
+            .nodes
+            .iter()
+            .find_map(|node| node.node.surface_extent(factory))
+        {
+            Some(extent) => extent,
+            None => return SurfaceExtentStatus::Unchanged,
+        };
+
+        if extent.width == 0 || extent.height == 0 {
+            return SurfaceExtentStatus::Minimized;
+        }
+
+        let stale = self.surface_sized.iter().any(|&id| {
+            self.ctx.get_image(id).is_some_and(|image| {
+                let current = image.kind().extent();
+                current.width != extent.width || current.height != extent.height
+            })
+        });
+
+        if !stale {
+            return SurfaceExtentStatus::Unchanged;
+        }
+
+        let surface_sized = &self.surface_sized;
+        let affected = |node: &GraphNode<B, T>| {
+            node.images
+                .iter()
+                .any(|image| surface_sized.contains(image))
+        };
+
+        if let Some(node) = self
+            .nodes
+            .iter()
+            .find(|&node| affected(node) && !node.node.resizable())
+        {
+            log::warn!(
+                "Surface extent changed to {:?} but node {:?} can't be resized",
+                extent,
+                node.node
+            );
+            return SurfaceExtentStatus::RebuildRequired;
+        }
+
+        log::debug!("Resize surface-sized images to {:?}", extent);
+        if let Err(err) = factory.wait_idle() {
+            log::error!("Failed to wait for device before resizing: {:?}", err);
+            return SurfaceExtentStatus::RebuildRequired;
+        }
+
+        for &id in &self.surface_sized {
+            if let Err(err) = self.ctx.resize_image(factory, id, extent) {
+                log::error!("Failed to resize image {:?}: {:?}", id, err);
+                return SurfaceExtentStatus::RebuildRequired;
+            }
+        }
+
+        for node in self.nodes.iter_mut().filter(|node| affected(node)) {
+            // Device is idle.
+            if let Err(err) = unsafe { node.node.resize(&self.ctx, factory, aux) } {
+                log::error!("Failed to resize node {:?}: {:?}", node.node, err);
+                return SurfaceExtentStatus::RebuildRequired;
+            }
+        }
+
+        SurfaceExtentStatus::Resized
+    }
+
     /// Get queue that will exeute given node.
     pub fn node_queue(&self, node: NodeId) -> QueueId {
         let (f, i) = self.nodes[node.0].queue;
@@ -300,6 +755,62 @@ where
         }
     }
 
+    /// Wait for all submitted frames to complete and read content of the output buffer.
+    ///
+    /// Fails with `OutputBufferError::NotOutput` if buffer wasn't marked as output with
+    /// [`GraphBuilder::mark_output_buffer`] or no node accesses it.
+    ///
+    /// [`GraphBuilder::mark_output_buffer`]: struct.GraphBuilder.html#method.mark_output_buffer
+    pub fn download_output_buffer(
+        &mut self,
+        factory: &Factory<B>,
+        id: BufferId,
+    ) -> Result<Vec<u8>, OutputBufferError> {
+        profile_scope!("download_output_buffer");
+
+        self.assert_device_owner(factory.device());
+
+        if !self.ctx.is_output_buffer(id) {
+            return Err(OutputBufferError::NotOutput(id));
+        }
+
+        self.wait_last_frame(factory);
+
+        let output = self
+            .ctx
+            .outputs
+            .iter()
+            .find(|output| output.id == id)
+            .unwrap();
+        let buffer = self.ctx.get_buffer(id).unwrap();
+
+        let size = buffer.size();
+        unsafe {
+            if !output.coherent {
+                let atom = factory.physical().limits().non_coherent_atom_size as u64;
+                let range = buffer.block().range();
+                factory.device().invalidate_mapped_memory_ranges(Some((
+                    buffer.block().memory(),
+                    invalidate_segment(range, atom),
+                )))?;
+            }
+
+            Ok(std::slice::from_raw_parts(output.ptr.as_ptr(), size as usize).to_vec())
+        }
+    }
+
+    /// Wait for the last submitted frame to complete.
+    fn wait_last_frame(&mut self, factory: &Factory<B>) {
+        if let Some(last) = self.frames.next().index().checked_sub(1) {
+            let self_fences = &mut self.fences;
+            self.frames
+                .wait_complete(Frame::with_index(last), factory, |mut fences| {
+                    factory.reset_fences(&mut fences).unwrap();
+                    self_fences.push(fences);
+                });
+        }
+    }
+
     /// Dispose of the `Graph`.
     pub fn dispose(self, factory: &mut Factory<B>, data: &T) {
         profile_scope!("dispose");
@@ -330,7 +841,12 @@ pub struct GraphBuilder<B: Backend, T: ?Sized> {
     nodes: Vec<Box<dyn NodeBuilder<B, T>>>,
     buffers: Vec<BufferInfo>,
     images: Vec<(ImageInfo, Option<rendy_core::hal::command::ClearValue>)>,
+    output_buffers: Vec<BufferId>,
+    surface_sized: Vec<ImageId>,
+    per_frame_buffers: Vec<BufferInfo>,
+    per_frame_images: Vec<ImageInfo>,
     frames_in_flight: u32,
+    debug_capture: bool,
 }
 
 impl<B, T> Default for GraphBuilder<B, T>
@@ -343,7 +859,12 @@ where
             nodes: Vec::default(),
             buffers: Vec::default(),
             images: Vec::default(),
-            frames_in_flight: u32::default(),
+            output_buffers: Vec::default(),
+            surface_sized: Vec::default(),
+            per_frame_buffers: Vec::default(),
+            per_frame_images: Vec::default(),
+            frames_in_flight: 3,
+            debug_capture: bool::default(),
         }
     }
 }
@@ -358,7 +879,12 @@ where
             .field("nodes", &self.nodes)
             .field("buffers", &self.buffers)
             .field("images", &self.images)
+            .field("output_buffers", &self.output_buffers)
+            .field("surface_sized", &self.surface_sized)
+            .field("per_frame_buffers", &self.per_frame_buffers)
+            .field("per_frame_images", &self.per_frame_images)
             .field("frames_in_flight", &self.frames_in_flight)
+            .field("debug_capture", &self.debug_capture)
             .finish()
     }
 }
@@ -374,7 +900,12 @@ where
             nodes: Vec::new(),
             buffers: Vec::new(),
             images: Vec::new(),
+            output_buffers: Vec::new(),
+            surface_sized: Vec::new(),
+            per_frame_buffers: Vec::new(),
+            per_frame_images: Vec::new(),
             frames_in_flight: 3,
+            debug_capture: false,
         }
     }
 
@@ -389,6 +920,20 @@ where
         BufferId(self.buffers.len() - 1)
     }
 
+    /// Mark buffer as output of the graph.
+    /// Output buffer is allocated from host-visible memory and made
+    /// readable by the host after the last node that accesses it.
+    /// Use [`Graph::download_output_buffer`] to read it after the frame.
+    ///
+    /// [`Graph::download_output_buffer`]: struct.Graph.html#method.download_output_buffer
+    pub fn mark_output_buffer(&mut self, id: BufferId) -> &mut Self {
+        assert!(id.0 < self.buffers.len(), "Buffer must be created first");
+        if !self.output_buffers.contains(&id) {
+            self.output_buffers.push(id);
+        }
+        self
+    }
+
     /// Create new image owned by graph.
     pub fn create_image(
         &mut self,
@@ -413,6 +958,48 @@ where
         ImageId(self.images.len() - 1)
     }
 
+    /// Create buffer that has separate instance for each frame in flight.
+    /// Use [`GraphContext::get_per_frame_buffer`] to get instance for the frame being prepared.
+    ///
+    /// Buffer is allocated from host-visible memory, so nodes can write it each frame
+    /// without waiting for the device to finish with previous frames.
+    /// Writes don't persist across frames: every frame in flight sees its own instance.
+    /// Unlike buffers created with [`create_buffer`] it is not synchronized by graph.
+    ///
+    /// [`GraphContext::get_per_frame_buffer`]: struct.GraphContext.html#method.get_per_frame_buffer
+    /// [`create_buffer`]: #method.create_buffer
+    pub fn create_per_frame_buffer(&mut self, info: BufferInfo) -> PerFrameBufferId {
+        self.per_frame_buffers.push(info);
+        PerFrameBufferId(self.per_frame_buffers.len() - 1)
+    }
+
+    /// Create image that has separate instance for each frame in flight.
+    /// Use [`GraphContext::get_per_frame_image`] to get instance for the frame being prepared.
+    ///
+    /// Content doesn't persist across frames: every frame in flight sees its own instance.
+    /// Unlike images created with [`create_image`] it is not synchronized by graph,
+    /// nodes must transition it themselves.
+    ///
+    /// [`GraphContext::get_per_frame_image`]: struct.GraphContext.html#method.get_per_frame_image
+    /// [`create_image`]: #method.create_image
+    pub fn create_per_frame_image(&mut self, info: ImageInfo) -> PerFrameImageId {
+        self.per_frame_images.push(info);
+        PerFrameImageId(self.per_frame_images.len() - 1)
+    }
+
+    /// Mark image as sized to the surface.
+    /// When surface extent reported by present nodes changes,
+    /// [`Graph::run`] recreates the image with new width and height.
+    ///
+    /// [`Graph::run`]: struct.Graph.html#method.run
+    pub fn mark_surface_sized(&mut self, id: ImageId) -> &mut Self {
+        assert!(id.0 < self.images.len(), "Image must be created first");
+        if !self.surface_sized.contains(&id) {
+            self.surface_sized.push(id);
+        }
+        self
+    }
+
     /// Add node to the graph.
     pub fn add_node<N: NodeBuilder<B, T> + 'static>(&mut self, builder: N) -> NodeId {
         self.add_dyn_node(Box::new(builder))
@@ -424,12 +1011,38 @@ where
         NodeId(self.nodes.len() - 1)
     }
 
-    /// Choose number of frames in flight for the graph
+    /// Choose number of frames in flight for the graph. Defaults to 3.
+    ///
+    /// Graph waits for frame `N - frames_in_flight` to complete before running frame `N`,
+    /// and nodes size per-frame resources by `GraphContext::frames_in_flight`.
+    /// More frames in flight keep device busy while CPU prepares next frame,
+    /// fewer frames bound latency between input and presentation.
+    ///
+    /// This is independent from number of swapchain images requested by present nodes,
+    /// which acquire from however many images swapchain has.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frames_in_flight` is zero.
     pub fn with_frames_in_flight(mut self, frames_in_flight: u32) -> Self {
+        assert!(
+            frames_in_flight >= 1,
+            "Graph must allow at least one frame in flight"
+        );
         self.frames_in_flight = frames_in_flight;
         self
     }
 
+    /// Allow reading back graph images with [`Graph::debug_capture`].
+    /// All graph images are created with `TRANSFER_SRC` usage when enabled,
+    /// so it should be left disabled in production.
+    ///
+    /// [`Graph::debug_capture`]: struct.Graph.html#method.debug_capture
+    pub fn with_debug_capture(mut self, debug_capture: bool) -> Self {
+        self.debug_capture = debug_capture;
+        self
+    }
+
     /// Build `Graph`.
     ///
     /// # Parameters
@@ -449,6 +1062,13 @@ where
     ) -> Result<Graph<B, T>, GraphBuildError> {
         profile_scope!("build");
 
+        if cfg!(debug_assertions) {
+            profile_scope!("validate_usage");
+            for (i, builder) in self.nodes.iter().enumerate() {
+                validate::validate_usage(NodeId(i), &builder.buffers(), &builder.subpass_images())?;
+            }
+        }
+
         log::trace!("Schedule nodes execution");
         let chain_nodes: Vec<chain::Node> = {
             profile_scope!("schedule_nodes");
@@ -456,7 +1076,13 @@ where
                 .iter()
                 .enumerate()
                 .map(|(i, b)| make_chain_node(&**b, i, factory, families))
-                .collect()
+                .collect::<Result<_, _>>()?
+        };
+
+        let validation_nodes = if cfg!(debug_assertions) {
+            Some(chain_nodes.clone())
+        } else {
+            None
         };
 
         let chains = chain::collect(chain_nodes, |id| {
@@ -464,13 +1090,21 @@ where
         });
         log::trace!("Scheduled nodes execution {:#?}", chains);
 
+        if let Some(nodes) = &validation_nodes {
+            profile_scope!("validate_layouts");
+            validate::validate_layouts(nodes, &chains)?;
+        }
+
         let mut ctx = GraphContext::alloc(
             factory,
             &chains,
             &self.buffers,
             &self.images,
+            &self.output_buffers,
+            self.debug_capture,
             self.frames_in_flight,
         )?;
+        ctx.alloc_per_frame(factory, &self.per_frame_buffers, &self.per_frame_images)?;
 
         log::trace!("Synchronize");
 
@@ -482,6 +1116,11 @@ where
         schedule.build_order();
         log::trace!("Schedule: {:#?}", schedule);
 
+        let dot = validation_nodes.map(|nodes| {
+            let names: Vec<_> = self.nodes.iter().map(|builder| builder.name()).collect();
+            dot::to_dot(&names, &nodes, &schedule)
+        });
+
         log::trace!("Build nodes");
         let mut built_nodes: Vec<_> = (0..self.nodes.len()).map(|_| None).collect();
         let mut node_descs: Vec<_> = self.nodes.into_iter().map(Some).collect();
@@ -497,6 +1136,8 @@ where
                         log::trace!("For submission {:#?}", submission.id());
                         let builder = node_descs[submission.node()].take().unwrap();
                         log::trace!("Build node {:#?}", builder);
+                        let images = builder.images().into_iter().map(|(id, _)| id).collect();
+                        let name = builder.name();
                         let node = build_node(
                             &mut ctx,
                             builder,
@@ -507,9 +1148,14 @@ where
                             &chains,
                             &submission,
                         )
-                        .map_err(GraphBuildError::Node)?;
+                        .map_err(|error| GraphBuildError::Node {
+                            node: NodeId(submission.node()),
+                            name,
+                            error,
+                        })?;
                         log::debug!("Node built: {:#?}", node);
-                        built_nodes[submission.node()] = Some((node, submission.id().queue()));
+                        built_nodes[submission.node()] =
+                            Some((node, submission.id().queue(), images));
                     }
                 }
             }
@@ -527,9 +1173,10 @@ where
             nodes: built_nodes
                 .into_iter()
                 .map(Option::unwrap)
-                .map(|(node, qid)| GraphNode {
+                .map(|(node, qid, images)| GraphNode {
                     node,
                     queue: (qid.family().0, qid.index()),
+                    images,
                 })
                 .collect(),
             schedule,
@@ -537,6 +1184,14 @@ where
             inflight: self.frames_in_flight,
             frames: Frames::new(),
             fences: Vec::new(),
+            captures: if self.debug_capture {
+                Some(capture::capture_states(&chains))
+            } else {
+                None
+            },
+            surface_sized: self.surface_sized,
+            surface_extent_status: SurfaceExtentStatus::Unchanged,
+            dot,
         })
     }
 }
@@ -555,34 +1210,40 @@ fn build_node<'a, B: Backend, T: ?Sized>(
     buffer_ids.sort();
     buffer_ids.dedup();
 
-    let buffers: Vec<_> = buffer_ids
-        .into_iter()
-        .map(|id| {
-            let chain_id = chain::Id(id.0);
-            let sync = submission.sync();
-            let buffer = ctx
-                .get_buffer(id)
-                .expect("Buffer referenced from at least one node must be instantiated");
-            NodeBuffer {
-                id,
-                range: 0..buffer.size(),
-                acquire: sync.acquire.buffers.get(&chain_id).map(
+    let buffers: Vec<_> =
+        buffer_ids
+            .into_iter()
+            .map(|id| {
+                let chain_id = chain::Id(id.0);
+                let sync = submission.sync();
+                let buffer = ctx
+                    .get_buffer(id)
+                    .expect("Buffer referenced from at least one node must be instantiated");
+                let release = sync.release.buffers.get(&chain_id).map(
                     |chain::Barrier { states, families }| BufferBarrier {
                         states: states.start.0..states.end.0,
                         stages: states.start.2..states.end.2,
                         families: families.clone(),
                     },
-                ),
-                release: sync.release.buffers.get(&chain_id).map(
-                    |chain::Barrier { states, families }| BufferBarrier {
-                        states: states.start.0..states.end.0,
-                        stages: states.start.2..states.end.2,
-                        families: families.clone(),
+                );
+                NodeBuffer {
+                    id,
+                    range: 0..buffer.size(),
+                    acquire: sync.acquire.buffers.get(&chain_id).map(
+                        |chain::Barrier { states, families }| BufferBarrier {
+                            states: states.start.0..states.end.0,
+                            stages: states.start.2..states.end.2,
+                            families: families.clone(),
+                        },
+                    ),
+                    release: if ctx.is_output_buffer(id) {
+                        output_release(&chains.buffers[&chain_id], submission.id(), release)
+                    } else {
+                        release
                     },
-                ),
-            }
-        })
-        .collect();
+                }
+            })
+            .collect();
 
     let mut image_ids: Vec<_> = builder.images().into_iter().map(|(id, _)| id).collect();
     image_ids.sort();
@@ -635,21 +1296,77 @@ fn build_node<'a, B: Backend, T: ?Sized>(
     builder.build(ctx, factory, family, queue, aux, buffers, images)
 }
 
+/// Segment of non-coherent memory to invalidate before reading block in `range`.
+/// Bounds are rounded to multiple of `atom`. If the end is not a multiple already
+/// the block may end where memory object ends, so invalidation reaches the end of the mapping instead.
+fn invalidate_segment(range: std::ops::Range<u64>, atom: u64) -> rendy_core::hal::memory::Segment {
+    let start = range.start - range.start % atom;
+    rendy_core::hal::memory::Segment {
+        offset: start,
+        size: if range.end.div_ceil(atom) * atom == range.end {
+            Some(range.end - start)
+        } else {
+            None
+        },
+    }
+}
+
+/// Make writes to the output buffer visible to the host
+/// after the last submission that accesses it in the frame.
+fn output_release(
+    chain: &chain::Chain<chain::Buffer>,
+    sid: chain::SubmissionId,
+    release: Option<BufferBarrier>,
+) -> Option<BufferBarrier> {
+    use rendy_core::hal::{buffer::Access, pso::PipelineStage};
+
+    let last = chain.links().iter().next_back()?;
+    if !last
+        .queues()
+        .any(|(qid, queue)| qid == sid.queue() && queue.last == sid.index())
+    {
+        return release;
+    }
+
+    match release {
+        Some(mut barrier) => {
+            barrier.states.end |= Access::HOST_READ;
+            barrier.stages.end |= PipelineStage::HOST;
+            Some(barrier)
+        }
+        None => {
+            let state = last.submission_state(sid);
+            Some(BufferBarrier {
+                states: state.access..Access::HOST_READ,
+                stages: state.stages..PipelineStage::HOST,
+                families: None,
+            })
+        }
+    }
+}
+
 fn make_chain_node<B, T>(
     builder: &dyn NodeBuilder<B, T>,
     id: usize,
     factory: &mut Factory<B>,
     families: &Families<B>,
-) -> chain::Node
+) -> Result<chain::Node, GraphBuildError>
 where
     B: Backend,
     T: ?Sized,
 {
+    let family =
+        builder
+            .family(factory, families)
+            .ok_or_else(|| GraphBuildError::NoQueueFamily {
+                node: NodeId(id),
+                name: builder.name(),
+            })?;
     let buffers = builder.buffers();
     let images = builder.images();
-    chain::Node {
+    Ok(chain::Node {
         id,
-        family: QueueFamilyId(builder.family(factory, families).unwrap().index),
+        family: QueueFamilyId(family.index),
         dependencies: builder.dependencies().into_iter().map(|id| id.0).collect(),
         buffers: buffers
             .into_iter()
@@ -679,5 +1396,239 @@ where
                 )
             })
             .collect(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::node::ImageAccess,
+        rendy_core::hal::{
+            buffer::{Access, Usage},
+            pso::PipelineStage,
+        },
+    };
+
+    fn compute_node(id: usize, access: Access, dependencies: Vec<usize>) -> chain::Node {
+        chain::Node {
+            id,
+            family: QueueFamilyId(0),
+            dependencies,
+            buffers: Some((
+                chain::Id(0),
+                chain::BufferState {
+                    access,
+                    stages: PipelineStage::COMPUTE_SHADER,
+                    layout: (),
+                    usage: Usage::STORAGE,
+                },
+            ))
+            .into_iter()
+            .collect(),
+            images: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_output_buffer_host_read() {
+        let chains = chain::collect(
+            vec![
+                compute_node(0, Access::SHADER_WRITE, vec![]),
+                compute_node(1, Access::SHADER_READ | Access::SHADER_WRITE, vec![0]),
+            ],
+            |_| 1,
+        );
+        let schedule = chain::sync(&chains, || ((), ()));
+        let chain = &chains.buffers[&chain::Id(0)];
+
+        for submission in schedule
+            .iter()
+            .flat_map(|f| f.iter())
+            .flat_map(|q| q.iter())
+        {
+            let release = submission
+                .sync()
+                .release
+                .buffers
+                .get(&chain::Id(0))
+                .map(|barrier| BufferBarrier {
+                    states: barrier.states.start.0..barrier.states.end.0,
+                    stages: barrier.states.start.2..barrier.states.end.2,
+                    families: barrier.families.clone(),
+                });
+            let release = output_release(chain, submission.id(), release);
+
+            if submission.node() == 1 {
+                let release = release.expect("Last writer must release buffer to the host");
+                assert!(release.states.start.contains(Access::SHADER_WRITE));
+                assert!(release.states.end.contains(Access::HOST_READ));
+                assert!(release.stages.end.contains(PipelineStage::HOST));
+            } else {
+                if let Some(release) = release {
+                    assert!(!release.states.end.contains(Access::HOST_READ));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalidate_segment() {
+        let segment = invalidate_segment(80..192, 64);
+        assert_eq!(segment.offset, 64);
+        assert_eq!(segment.size, Some(128));
+
+        // End may be the end of memory object, which can't be rounded past.
+        let segment = invalidate_segment(64..100, 64);
+        assert_eq!(segment.offset, 64);
+        assert_eq!(segment.size, None);
+    }
+
+    fn depth_node(id: usize, access: ImageAccess, dependencies: Vec<usize>) -> chain::Node {
+        chain::Node {
+            id,
+            family: QueueFamilyId(0),
+            dependencies,
+            buffers: Default::default(),
+            images: Some((
+                chain::Id(0),
+                chain::ImageState {
+                    access: access.access,
+                    stages: access.stages,
+                    layout: access.layout,
+                    usage: access.usage,
+                },
+            ))
+            .into_iter()
+            .collect(),
+        }
+    }
+
+    #[test]
+    fn test_sample_depth_after_write() {
+        use rendy_core::hal::image;
+
+        let write = ImageAccess {
+            access: image::Access::DEPTH_STENCIL_ATTACHMENT_READ
+                | image::Access::DEPTH_STENCIL_ATTACHMENT_WRITE,
+            usage: image::Usage::DEPTH_STENCIL_ATTACHMENT,
+            layout: image::Layout::DepthStencilAttachmentOptimal,
+            stages: PipelineStage::EARLY_FRAGMENT_TESTS | PipelineStage::LATE_FRAGMENT_TESTS,
+        };
+        let sample = ImageAccess::sampled_depth(PipelineStage::FRAGMENT_SHADER);
+
+        for (i, &access) in [write, sample].iter().enumerate() {
+            validate::validate_usage(NodeId(i), &[], &[vec![(ImageId(0), access)]]).unwrap();
+        }
+        let nodes = vec![depth_node(0, write, vec![]), depth_node(1, sample, vec![0])];
+        let chains = chain::collect(nodes.clone(), |_| 1);
+        validate::validate_layouts(&nodes, &chains).unwrap();
+
+        let schedule = chain::sync(&chains, || ((), ()));
+        let transition = schedule
+            .iter()
+            .flat_map(|f| f.iter())
+            .flat_map(|q| q.iter())
+            .flat_map(|submission| {
+                let sync = submission.sync();
+                sync.acquire
+                    .images
+                    .get(&chain::Id(0))
+                    .into_iter()
+                    .chain(sync.release.images.get(&chain::Id(0)))
+                    .cloned()
+                    .collect::<Vec<_>>()
+            })
+            .find(|barrier| {
+                barrier.states.start.1 != barrier.states.end.1
+                    && barrier.states.end.1 == image::Layout::DepthStencilReadOnlyOptimal
+            })
+            .expect("Depth image must be transitioned for sampling");
+
+        assert_eq!(
+            transition.states.start.1,
+            image::Layout::DepthStencilAttachmentOptimal
+        );
+        assert_eq!(
+            transition.states.end.1,
+            image::Layout::DepthStencilReadOnlyOptimal
+        );
+        assert!(transition
+            .states
+            .start
+            .0
+            .contains(image::Access::DEPTH_STENCIL_ATTACHMENT_WRITE));
+        assert!(transition.states.end.0.contains(image::Access::SHADER_READ));
+
+        // Sampling depth image while writing it is a feedback loop.
+        let mut feedback = write;
+        feedback.access |= image::Access::SHADER_READ;
+        feedback.layout = image::Layout::General;
+        assert!(validate::validate_usage(NodeId(0), &[], &[vec![(ImageId(0), feedback)]]).is_err());
+    }
+
+    #[test]
+    fn test_validate_subpasses() {
+        use crate::node::render::subpass_attachment_images;
+
+        let albedo = ImageId(0);
+        let normal = ImageId(1);
+        let output = ImageId(2);
+
+        // Deferred pass writes G-buffer in the first subpass
+        // and reads it as input attachments in the second one.
+        let subpasses = vec![
+            subpass_attachment_images(None, vec![albedo, normal], None)
+                .into_iter()
+                .collect::<Vec<_>>(),
+            subpass_attachment_images(vec![albedo, normal], Some(output), None)
+                .into_iter()
+                .collect::<Vec<_>>(),
+        ];
+        validate::validate_usage(NodeId(0), &[], &subpasses).unwrap();
+    }
+
+    #[test]
+    fn test_validate_depth_input_subpasses() {
+        use crate::node::render::subpass_attachment_images;
+
+        let depth = ImageId(0);
+        let output = ImageId(1);
+
+        // Depth written by the first subpass can be read as input attachment by the next one.
+        let subpasses = vec![
+            subpass_attachment_images(None, None, Some(depth))
+                .into_iter()
+                .collect::<Vec<_>>(),
+            subpass_attachment_images(Some(depth), Some(output), None)
+                .into_iter()
+                .collect::<Vec<_>>(),
+        ];
+        validate::validate_usage(NodeId(0), &[], &subpasses).unwrap();
+
+        // But not by the subpass that writes it.
+        let feedback: Vec<_> = subpass_attachment_images(Some(depth), Some(output), Some(depth))
+            .into_iter()
+            .collect();
+        assert!(validate::validate_usage(NodeId(0), &[], &[feedback]).is_err());
+    }
+
+    #[test]
+    fn test_resize_kind() {
+        use rendy_core::hal::{image::Kind, window::Extent2D};
+
+        let extent = Extent2D {
+            width: 640,
+            height: 480,
+        };
+        assert_eq!(
+            resize_kind(Kind::D2(800, 600, 2, 4), extent),
+            Kind::D2(640, 480, 2, 4)
+        );
+        assert_eq!(resize_kind(Kind::D1(800, 3), extent), Kind::D1(640, 3));
+        assert_eq!(
+            resize_kind(Kind::D3(800, 600, 8), extent),
+            Kind::D3(640, 480, 8)
+        );
     }
 }
@@ -0,0 +1,98 @@
+use crate::resource::BufferInfo;
+
+/// Scratch resources handed out to nodes for a single frame.
+///
+/// Resources acquired for a frame in flight are returned to the free list
+/// when the same frame slot is reused, i.e. after the frame is complete.
+#[derive(Debug)]
+pub(super) struct TransientPool<R> {
+    used: Vec<Vec<(BufferInfo, R)>>,
+    free: Vec<(BufferInfo, R)>,
+}
+
+impl<R> TransientPool<R>
+where
+    R: Clone,
+{
+    pub(super) fn new(frames_in_flight: u32) -> Self {
+        TransientPool {
+            used: (0..frames_in_flight).map(|_| Vec::new()).collect(),
+            free: Vec::new(),
+        }
+    }
+
+    /// Get resource compatible with `info` for the frame in flight with `index`.
+    /// Reuses the smallest free resource that fits or creates new one.
+    pub(super) fn acquire<E>(
+        &mut self,
+        index: usize,
+        info: BufferInfo,
+        create: impl FnOnce(BufferInfo) -> Result<R, E>,
+    ) -> Result<R, E> {
+        let best = self
+            .free
+            .iter()
+            .enumerate()
+            .filter(|(_, (free, _))| free.size >= info.size && free.usage.contains(info.usage))
+            .min_by_key(|(_, (free, _))| free.size)
+            .map(|(i, _)| i);
+
+        let (info, resource) = match best {
+            Some(i) => self.free.swap_remove(i),
+            None => (info, create(info)?),
+        };
+        self.used[index].push((info, resource.clone()));
+        Ok(resource)
+    }
+
+    /// Return all resources acquired for the frame in flight with `index` to the free list.
+    ///
+    /// Frame that used the slot previously must be complete.
+    pub(super) fn reclaim(&mut self, index: usize) {
+        self.free.append(&mut self.used[index]);
+    }
+}
+
+#[test]
+fn test_transient_reclaim() {
+    use rendy_core::hal::buffer::Usage;
+
+    let mut pool = TransientPool::new(2);
+    let mut next = 0;
+    let mut create = |_| -> Result<u32, ()> {
+        next += 1;
+        Ok(next)
+    };
+    let info = |size, usage| BufferInfo { size, usage };
+
+    let a = pool.acquire(0, info(256, Usage::STORAGE), &mut create);
+    let b = pool.acquire(0, info(64, Usage::STORAGE), &mut create);
+    assert_eq!((a, b), (Ok(1), Ok(2)));
+
+    // Buffers of the frame in flight are not reused until the slot is reclaimed.
+    assert_eq!(
+        pool.acquire(1, info(64, Usage::STORAGE), &mut create),
+        Ok(3)
+    );
+
+    pool.reclaim(0);
+    // Smallest fitting buffer is picked.
+    assert_eq!(
+        pool.acquire(0, info(32, Usage::STORAGE), &mut create),
+        Ok(2)
+    );
+    assert_eq!(
+        pool.acquire(0, info(128, Usage::STORAGE), &mut create),
+        Ok(1)
+    );
+    // Usage must be supported by the reused buffer.
+    pool.reclaim(1);
+    assert_eq!(
+        pool.acquire(0, info(64, Usage::UNIFORM), &mut create),
+        Ok(4)
+    );
+    assert_eq!(
+        pool.acquire(0, info(64, Usage::STORAGE), &mut create),
+        Ok(3)
+    );
+}
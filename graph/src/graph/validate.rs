@@ -0,0 +1,242 @@
+//! Validation of resource usage declared by nodes.
+//! Catches mistakes that would otherwise surface only as
+//! driver validation errors without a hint of the node at fault.
+
+use {
+    super::GraphBuildError,
+    crate::{
+        chain,
+        node::{BufferAccess, ImageAccess},
+        BufferId, ImageId, NodeId,
+    },
+    rendy_core::hal::{buffer, image},
+};
+
+/// Id of the buffer or image in graph.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ResourceId {
+    /// Buffer resource.
+    Buffer(BufferId),
+    /// Image resource.
+    Image(ImageId),
+}
+
+/// Check that the node declares usage flags and layouts that permit its accesses.
+///
+/// Images are checked for each subpass separately,
+/// as subpasses of a render pass may use the same image
+/// in different layouts, e.g. written as attachment by one subpass
+/// and read as input attachment by the next.
+pub(super) fn validate_usage(
+    node: NodeId,
+    buffers: &[(BufferId, BufferAccess)],
+    subpasses: &[Vec<(ImageId, ImageAccess)>],
+) -> Result<(), GraphBuildError> {
+    for &(id, state) in buffers {
+        let missing = buffer_usage_for(state.access) - state.usage;
+        if !missing.is_empty() {
+            return Err(GraphBuildError::InvalidUsage {
+                resource: ResourceId::Buffer(id),
+                from_node: None,
+                to_node: node,
+                detail: format!(
+                    "Access {:?} requires usage {:?} which is not declared",
+                    state.access, missing
+                ),
+            });
+        }
+    }
+
+    for &(id, state) in subpasses.iter().flatten() {
+        let missing = image_usage_for(state.access) - state.usage;
+        if !missing.is_empty() {
+            return Err(GraphBuildError::InvalidUsage {
+                resource: ResourceId::Image(id),
+                from_node: None,
+                to_node: node,
+                detail: format!(
+                    "Access {:?} requires usage {:?} which is not declared",
+                    state.access, missing
+                ),
+            });
+        }
+
+        // Feedback loop within the subpass.
+        // Later subpasses may read depth written by earlier ones.
+        if state
+            .access
+            .contains(image::Access::DEPTH_STENCIL_ATTACHMENT_WRITE)
+            && state
+                .access
+                .intersects(image::Access::SHADER_READ | image::Access::INPUT_ATTACHMENT_READ)
+        {
+            return Err(GraphBuildError::InvalidUsage {
+                resource: ResourceId::Image(id),
+                from_node: None,
+                to_node: node,
+                detail: format!(
+                    "Image can't be read by shaders while written as depth attachment: {:?}",
+                    state.access
+                ),
+            });
+        }
+
+        if !layout_permits(state.layout, state.access) {
+            return Err(GraphBuildError::InvalidUsage {
+                resource: ResourceId::Image(id),
+                from_node: None,
+                to_node: node,
+                detail: format!(
+                    "Layout {:?} doesn't permit access {:?}",
+                    state.layout, state.access
+                ),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Check that nodes sharing a link of the image chain agree on the layout,
+/// as no transition is inserted between them.
+pub(super) fn validate_layouts(
+    nodes: &[chain::Node],
+    chains: &chain::Chains,
+) -> Result<(), GraphBuildError> {
+    // Node submitted by each submission.
+    let submitted: Vec<_> = chains
+        .schedule
+        .iter()
+        .flat_map(|family| family.iter())
+        .flat_map(|queue| queue.iter())
+        .map(|submission| (submission.id(), submission.node()))
+        .collect();
+
+    for (&id, image_chain) in &chains.images {
+        for link in image_chain.links() {
+            let mut first: Option<(usize, image::Layout)> = None;
+            for (qid, queue) in link.queues() {
+                for &(sid, node) in &submitted {
+                    if sid.queue() != qid || sid.index() < queue.first || sid.index() > queue.last {
+                        continue;
+                    }
+                    let state = match nodes[node].images.get(&id) {
+                        Some(state) => state,
+                        None => continue,
+                    };
+                    match first {
+                        None => first = Some((node, state.layout)),
+                        Some((first_node, layout)) if layout != state.layout => {
+                            return Err(GraphBuildError::InvalidUsage {
+                                resource: ResourceId::Image(ImageId(id.0)),
+                                from_node: Some(NodeId(first_node)),
+                                to_node: NodeId(node),
+                                detail: format!(
+                                    "Nodes read image in layouts {:?} and {:?} without transition between them",
+                                    layout, state.layout
+                                ),
+                            });
+                        }
+                        Some(_) => {}
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Buffer usage flags required for the access.
+fn buffer_usage_for(access: buffer::Access) -> buffer::Usage {
+    let mut usage = buffer::Usage::empty();
+    if access.contains(buffer::Access::INDIRECT_COMMAND_READ) {
+        usage |= buffer::Usage::INDIRECT;
+    }
+    if access.contains(buffer::Access::INDEX_BUFFER_READ) {
+        usage |= buffer::Usage::INDEX;
+    }
+    if access.contains(buffer::Access::VERTEX_BUFFER_READ) {
+        usage |= buffer::Usage::VERTEX;
+    }
+    if access.contains(buffer::Access::UNIFORM_READ) {
+        usage |= buffer::Usage::UNIFORM;
+    }
+    if access.contains(buffer::Access::TRANSFER_READ) {
+        usage |= buffer::Usage::TRANSFER_SRC;
+    }
+    if access.contains(buffer::Access::TRANSFER_WRITE) {
+        usage |= buffer::Usage::TRANSFER_DST;
+    }
+    usage
+}
+
+/// Image usage flags required for the access.
+fn image_usage_for(access: image::Access) -> image::Usage {
+    let mut usage = image::Usage::empty();
+    if access.contains(image::Access::INPUT_ATTACHMENT_READ) {
+        usage |= image::Usage::INPUT_ATTACHMENT;
+    }
+    if access.contains(image::Access::SHADER_WRITE) {
+        usage |= image::Usage::STORAGE;
+    }
+    if access
+        .intersects(image::Access::COLOR_ATTACHMENT_READ | image::Access::COLOR_ATTACHMENT_WRITE)
+    {
+        usage |= image::Usage::COLOR_ATTACHMENT;
+    }
+    if access.intersects(
+        image::Access::DEPTH_STENCIL_ATTACHMENT_READ
+            | image::Access::DEPTH_STENCIL_ATTACHMENT_WRITE,
+    ) {
+        usage |= image::Usage::DEPTH_STENCIL_ATTACHMENT;
+    }
+    if access.contains(image::Access::TRANSFER_READ) {
+        usage |= image::Usage::TRANSFER_SRC;
+    }
+    if access.contains(image::Access::TRANSFER_WRITE) {
+        usage |= image::Usage::TRANSFER_DST;
+    }
+    usage
+}
+
+/// Check if image in the `layout` can be accessed with `access`.
+fn layout_permits(layout: image::Layout, access: image::Access) -> bool {
+    use image::{Access, Layout};
+
+    let permitted = match layout {
+        Layout::General => return true,
+        Layout::ColorAttachmentOptimal => {
+            Access::COLOR_ATTACHMENT_READ | Access::COLOR_ATTACHMENT_WRITE
+        }
+        Layout::DepthStencilAttachmentOptimal => {
+            Access::DEPTH_STENCIL_ATTACHMENT_READ | Access::DEPTH_STENCIL_ATTACHMENT_WRITE
+        }
+        Layout::DepthStencilReadOnlyOptimal => {
+            Access::DEPTH_STENCIL_ATTACHMENT_READ
+                | Access::SHADER_READ
+                | Access::INPUT_ATTACHMENT_READ
+        }
+        Layout::ShaderReadOnlyOptimal => Access::SHADER_READ | Access::INPUT_ATTACHMENT_READ,
+        Layout::TransferSrcOptimal => Access::TRANSFER_READ,
+        Layout::TransferDstOptimal => Access::TRANSFER_WRITE,
+        Layout::Undefined | Layout::Preinitialized | Layout::Present => Access::empty(),
+    };
+    permitted.contains(access)
+}
+
+#[test]
+fn test_layout_permits() {
+    assert!(layout_permits(
+        image::Layout::ShaderReadOnlyOptimal,
+        image::Access::SHADER_READ
+    ));
+    assert!(!layout_permits(
+        image::Layout::ShaderReadOnlyOptimal,
+        image::Access::COLOR_ATTACHMENT_WRITE
+    ));
+    assert!(layout_permits(
+        image::Layout::General,
+        image::Access::SHADER_WRITE
+    ));
+}
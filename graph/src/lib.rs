@@ -27,6 +27,14 @@ pub struct BufferId(usize);
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct ImageId(usize);
 
+/// Id of the buffer in graph that has separate instance for each frame in flight.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PerFrameBufferId(usize);
+
+/// Id of the image in graph that has separate instance for each frame in flight.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PerFrameImageId(usize);
+
 /// Id of the node in graph.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct NodeId(usize);
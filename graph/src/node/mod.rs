@@ -2,10 +2,13 @@
 //!
 
 pub mod present;
+pub mod readback;
 pub mod render;
+pub mod utility;
 
 use {
     crate::{
+        chain::AccessFlags as _,
         command::{Capability, Families, Family, FamilyId, Fence, Queue, Submission, Submittable},
         factory::{Factory, UploadError},
         frame::Frames,
@@ -88,6 +91,23 @@ pub struct ImageAccess {
     pub stages: rendy_core::hal::pso::PipelineStage,
 }
 
+impl ImageAccess {
+    /// Access for sampling depth image written by previous nodes, e.g. for SSAO or soft particles.
+    /// Image is transitioned into `DepthStencilReadOnlyOptimal` layout.
+    ///
+    /// Image sampled by the node can't be written as depth attachment by the same node,
+    /// but it can be used as read-only depth attachment with the same layout.
+    /// Use `NodeImage::sampled_range` to create view for sampling.
+    pub fn sampled_depth(stages: rendy_core::hal::pso::PipelineStage) -> Self {
+        ImageAccess {
+            access: rendy_core::hal::image::Access::SHADER_READ,
+            usage: rendy_core::hal::image::Usage::SAMPLED,
+            layout: rendy_core::hal::image::Layout::DepthStencilReadOnlyOptimal,
+            stages,
+        }
+    }
+}
+
 /// Image pipeline barrier.
 /// Node implementation must insert it before first command that uses the image.
 /// Barrier must be inserted even if this node doesn't use the image.
@@ -129,6 +149,21 @@ pub struct NodeImage {
     pub release: Option<ImageBarrier>,
 }
 
+impl NodeImage {
+    /// Range of the image for the view that can be sampled.
+    /// Sampled view of depth-stencil image must have single aspect,
+    /// so only depth aspect is included for those.
+    pub fn sampled_range(&self) -> rendy_core::hal::image::SubresourceRange {
+        use rendy_core::hal::format::Aspects;
+
+        let mut range = self.range.clone();
+        if range.aspects.contains(Aspects::DEPTH) {
+            range.aspects = Aspects::DEPTH;
+        }
+        range
+    }
+}
+
 /// NodeSubmittable
 pub trait NodeSubmittable<'a, B: Backend> {
     /// Submittable type returned from `Node`.
@@ -141,6 +176,12 @@ pub trait NodeSubmittable<'a, B: Backend> {
 /// The node is building block of the framegraph.
 /// Node defines set of resources and operations to perform over them.
 /// Read-only data for operations comes from auxiliary data source `T`.
+/// It is shared by all nodes of the graph, nodes that write to it
+/// must use interior mutability.
+///
+/// Resources used every frame are created by node's builder and owned by the node
+/// (through `Escape` or `Handle`) until it is disposed.
+/// Graph provides resources declared by builder and transient per-frame buffers.
 ///
 /// # Parameters
 ///
@@ -238,6 +279,43 @@ pub trait DynNode<B: Backend, T: ?Sized>: std::fmt::Debug + Sync + Send {
     ///
     /// Must be called after waiting for device idle.
     unsafe fn dispose(self: Box<Self>, factory: &mut Factory<B>, aux: &T);
+
+    /// Current extent of the surface the node renders to, if any.
+    /// Graph queries it before each frame to resize surface-sized images.
+    fn surface_extent(&self, _factory: &Factory<B>) -> Option<rendy_core::hal::window::Extent2D> {
+        None
+    }
+
+    /// Check if surface the node presents to was lost.
+    /// Graph stops rendering frames when any node reports lost surface.
+    fn surface_lost(&self) -> bool {
+        false
+    }
+
+    /// Check if node can handle resize of images it uses with [`resize`].
+    ///
+    /// [`resize`]: #method.resize
+    fn resizable(&self) -> bool {
+        false
+    }
+
+    /// Recreate resources that depend on images that were resized.
+    /// Called only for nodes that report to be [`resizable`].
+    /// Default implementation returns an error.
+    ///
+    /// # Safety
+    ///
+    /// Must be called after waiting for device idle.
+    ///
+    /// [`resizable`]: #method.resizable
+    unsafe fn resize(
+        &mut self,
+        _ctx: &GraphContext<B>,
+        _factory: &Factory<B>,
+        _aux: &T,
+    ) -> Result<(), NodeBuildError> {
+        Err(NodeBuildError::custom("Node is not resizable"))
+    }
 }
 
 impl<B, T, N> DynNode<B, T> for (N,)
@@ -289,6 +367,15 @@ pub enum NodeBuildError {
     Swapchain(SwapchainError),
     /// Ran out of memory when creating something.
     OutOfMemory(rendy_core::hal::device::OutOfMemory),
+    /// Node-specific failure, e.g. missing resource or invalid arguments.
+    Custom(Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl NodeBuildError {
+    /// Create node-specific error from any error or message.
+    pub fn custom(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Self {
+        NodeBuildError::Custom(error.into())
+    }
 }
 
 impl std::fmt::Display for NodeBuildError {
@@ -324,6 +411,7 @@ impl std::fmt::Display for NodeBuildError {
                 "Failed to build node because device ran out of memory while attempting to build: {:?}",
                 err
             ),
+            NodeBuildError::Custom(err) => write!(fmt, "Failed to build node: {}", err),
         }
     }
 }
@@ -337,6 +425,7 @@ impl std::error::Error for NodeBuildError {
             NodeBuildError::Pipeline(err) => Some(err),
             NodeBuildError::Swapchain(err) => Some(err),
             NodeBuildError::OutOfMemory(err) => Some(err),
+            NodeBuildError::Custom(err) => Some(&**err),
         }
     }
 }
@@ -351,9 +440,25 @@ pub trait NodeBuilder<B: Backend, T: ?Sized>: std::fmt::Debug {
     /// Get images accessed by the node.
     fn images(&self) -> Vec<(ImageId, ImageAccess)>;
 
+    /// Get images accessed by each subpass of the node.
+    ///
+    /// Access returned by `images` combines all subpasses
+    /// and may pair layout used by one subpass with access made by another.
+    /// Graph validates declared usage against these instead.
+    /// Defaults to single subpass accessing all `images`.
+    fn subpass_images(&self) -> Vec<Vec<(ImageId, ImageAccess)>> {
+        vec![self.images()]
+    }
+
     /// Indices of nodes this one dependes on.
     fn dependencies(&self) -> Vec<NodeId>;
 
+    /// Name of the node used in error messages.
+    /// Defaults to type name of the builder.
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
     /// Build node.
     fn build<'a>(
         self: Box<Self>,
@@ -437,6 +542,13 @@ where
 
     /// Add dependency.
     /// Node will be placed after its dependencies.
+    ///
+    /// Use this to order nodes that have no resource in common
+    /// but still depend on each other, e.g. through side effects.
+    /// Dependency only orders execution, including across queues,
+    /// and no memory barriers are inserted for resources not declared by nodes.
+    /// Every dependency restricts the scheduler,
+    /// so overusing them defeats parallelism it could otherwise find.
     pub fn add_dependency(&mut self, dependency: NodeId) -> &mut Self {
         self.dependencies.push(dependency);
         self
@@ -460,6 +572,10 @@ where
         families.with_capability::<<N::Node as Node<B, T>>::Capability>()
     }
 
+    fn name(&self) -> &'static str {
+        std::any::type_name::<N>()
+    }
+
     fn buffers(&self) -> Vec<(BufferId, BufferAccess)> {
         let desc_buffers = self.desc.buffers();
         assert_eq!(self.buffers.len(), desc_buffers.len());
@@ -495,6 +611,8 @@ where
 }
 
 /// Convert graph barriers into gfx barriers.
+///
+/// Barriers are coalesced. See `gfx_barriers`.
 pub fn gfx_acquire_barriers<'a, 'b, B: Backend>(
     ctx: &'a GraphContext<B>,
     buffers: impl IntoIterator<Item = &'b NodeBuffer>,
@@ -503,52 +621,20 @@ pub fn gfx_acquire_barriers<'a, 'b, B: Backend>(
     std::ops::Range<rendy_core::hal::pso::PipelineStage>,
     Vec<rendy_core::hal::memory::Barrier<'a, B>>,
 ) {
-    let mut bstart = rendy_core::hal::pso::PipelineStage::empty();
-    let mut bend = rendy_core::hal::pso::PipelineStage::empty();
-
-    let mut istart = rendy_core::hal::pso::PipelineStage::empty();
-    let mut iend = rendy_core::hal::pso::PipelineStage::empty();
-
-    let barriers: Vec<rendy_core::hal::memory::Barrier<'_, B>> = buffers
-        .into_iter()
-        .filter_map(|buffer| {
-            buffer.acquire.as_ref().map(|acquire| {
-                bstart |= acquire.stages.start;
-                bend |= acquire.stages.end;
-
-                rendy_core::hal::memory::Barrier::Buffer {
-                    states: acquire.states.clone(),
-                    families: acquire.families.clone(),
-                    target: ctx
-                        .get_buffer(buffer.id)
-                        .expect("Buffer does not exist")
-                        .raw(),
-                    range: rendy_core::hal::buffer::SubRange {
-                        offset: buffer.range.start,
-                        size: Some(buffer.range.end - buffer.range.start),
-                    },
-                }
-            })
-        })
-        .chain(images.into_iter().filter_map(|image| {
-            image.acquire.as_ref().map(|acquire| {
-                istart |= acquire.stages.start;
-                iend |= acquire.stages.end;
-
-                rendy_core::hal::memory::Barrier::Image {
-                    states: acquire.states.clone(),
-                    families: acquire.families.clone(),
-                    target: ctx.get_image(image.id).expect("Image does not exist").raw(),
-                    range: image.range.clone(),
-                }
-            })
-        }))
-        .collect();
-
-    (bstart | istart..bend | iend, barriers)
+    gfx_barriers(
+        ctx,
+        buffers
+            .into_iter()
+            .filter_map(|buffer| buffer.acquire.as_ref().map(|acquire| (buffer, acquire))),
+        images
+            .into_iter()
+            .filter_map(|image| image.acquire.as_ref().map(|acquire| (image, acquire))),
+    )
 }
 
 /// Convert graph barriers into gfx barriers.
+///
+/// Barriers are coalesced. See `gfx_barriers`.
 pub fn gfx_release_barriers<'a, B: Backend>(
     ctx: &'a GraphContext<B>,
     buffers: impl IntoIterator<Item = &'a NodeBuffer>,
@@ -557,47 +643,165 @@ pub fn gfx_release_barriers<'a, B: Backend>(
     std::ops::Range<rendy_core::hal::pso::PipelineStage>,
     Vec<rendy_core::hal::memory::Barrier<'a, B>>,
 ) {
-    let mut bstart = rendy_core::hal::pso::PipelineStage::empty();
-    let mut bend = rendy_core::hal::pso::PipelineStage::empty();
-
-    let mut istart = rendy_core::hal::pso::PipelineStage::empty();
-    let mut iend = rendy_core::hal::pso::PipelineStage::empty();
-
-    let barriers: Vec<rendy_core::hal::memory::Barrier<'_, B>> = buffers
-        .into_iter()
-        .filter_map(|buffer| {
-            buffer.release.as_ref().map(|release| {
-                bstart |= release.stages.start;
-                bend |= release.stages.end;
-
-                rendy_core::hal::memory::Barrier::Buffer {
-                    states: release.states.clone(),
-                    families: release.families.clone(),
-                    target: ctx
-                        .get_buffer(buffer.id)
-                        .expect("Buffer does not exist")
-                        .raw(),
-                    range: rendy_core::hal::buffer::SubRange {
-                        offset: buffer.range.start,
-                        size: Some(buffer.range.end - buffer.range.start),
-                    },
-                }
-            })
-        })
-        .chain(images.into_iter().filter_map(|image| {
-            image.release.as_ref().map(|release| {
-                istart |= release.stages.start;
-                iend |= release.stages.end;
-
-                rendy_core::hal::memory::Barrier::Image {
-                    states: release.states.clone(),
-                    families: release.families.clone(),
-                    target: ctx.get_image(image.id).expect("Image does not exist").raw(),
-                    range: image.range.clone(),
-                }
-            })
-        }))
-        .collect();
-
-    (bstart | istart..bend | iend, barriers)
+    gfx_barriers(
+        ctx,
+        buffers
+            .into_iter()
+            .filter_map(|buffer| buffer.release.as_ref().map(|release| (buffer, release))),
+        images
+            .into_iter()
+            .filter_map(|image| image.release.as_ref().map(|release| (image, release))),
+    )
+}
+
+impl BufferBarrier {
+    /// Check if barrier has no effect and can be skipped.
+    /// This is the case for read-after-read without ownership transfer.
+    pub fn is_noop(&self) -> bool {
+        self.families.is_none() && !self.states.start.exclusive() && !self.states.end.exclusive()
+    }
+}
+
+impl ImageBarrier {
+    /// Check if barrier has no effect and can be skipped.
+    /// This is the case for read-after-read without layout transition and ownership transfer.
+    pub fn is_noop(&self) -> bool {
+        self.families.is_none()
+            && self.states.start.1 == self.states.end.1
+            && !self.states.start.0.exclusive()
+            && !self.states.end.0.exclusive()
+    }
+
+    /// Check if barrier only makes memory available and visible
+    /// and thus can be merged into global image memory barrier.
+    pub fn is_global(&self) -> bool {
+        self.families.is_none() && self.states.start.1 == self.states.end.1
+    }
+}
+
+/// Convert graph barriers into as few gfx barriers as possible.
+///
+/// No-op barriers are dropped.
+/// Buffer barriers without ownership transfer are merged into single global barrier,
+/// and so are image barriers without layout transition and ownership transfer.
+/// Stages of all barriers that are left are unioned.
+fn gfx_barriers<'a, 'b, B: Backend>(
+    ctx: &'a GraphContext<B>,
+    buffers: impl IntoIterator<Item = (&'b NodeBuffer, &'b BufferBarrier)>,
+    images: impl IntoIterator<Item = (&'b NodeImage, &'b ImageBarrier)>,
+) -> (
+    std::ops::Range<rendy_core::hal::pso::PipelineStage>,
+    Vec<rendy_core::hal::memory::Barrier<'a, B>>,
+) {
+    let mut start = rendy_core::hal::pso::PipelineStage::empty();
+    let mut end = rendy_core::hal::pso::PipelineStage::empty();
+
+    let mut all_buffers: Option<std::ops::Range<rendy_core::hal::buffer::Access>> = None;
+    let mut all_images: Option<std::ops::Range<rendy_core::hal::image::Access>> = None;
+
+    let mut total = 0;
+    let mut barriers = Vec::new();
+
+    for (buffer, barrier) in buffers {
+        total += 1;
+        if barrier.is_noop() {
+            continue;
+        }
+        start |= barrier.stages.start;
+        end |= barrier.stages.end;
+
+        if barrier.families.is_none() {
+            let access = all_buffers.get_or_insert(
+                rendy_core::hal::buffer::Access::empty()..rendy_core::hal::buffer::Access::empty(),
+            );
+            access.start |= barrier.states.start;
+            access.end |= barrier.states.end;
+        } else {
+            barriers.push(rendy_core::hal::memory::Barrier::Buffer {
+                states: barrier.states.clone(),
+                families: barrier.families.clone(),
+                target: ctx
+                    .get_buffer(buffer.id)
+                    .expect("Buffer does not exist")
+                    .raw(),
+                range: rendy_core::hal::buffer::SubRange {
+                    offset: buffer.range.start,
+                    size: Some(buffer.range.end - buffer.range.start),
+                },
+            });
+        }
+    }
+
+    for (image, barrier) in images {
+        total += 1;
+        if barrier.is_noop() {
+            continue;
+        }
+        start |= barrier.stages.start;
+        end |= barrier.stages.end;
+
+        if barrier.is_global() {
+            let access = all_images.get_or_insert(
+                rendy_core::hal::image::Access::empty()..rendy_core::hal::image::Access::empty(),
+            );
+            access.start |= barrier.states.start.0;
+            access.end |= barrier.states.end.0;
+        } else {
+            barriers.push(rendy_core::hal::memory::Barrier::Image {
+                states: barrier.states.clone(),
+                families: barrier.families.clone(),
+                target: ctx.get_image(image.id).expect("Image does not exist").raw(),
+                range: image.range.clone(),
+            });
+        }
+    }
+
+    barriers.extend(all_buffers.map(rendy_core::hal::memory::Barrier::AllBuffers));
+    barriers.extend(all_images.map(rendy_core::hal::memory::Barrier::AllImages));
+
+    log::trace!("Coalesced {} barriers into {}", total, barriers.len());
+
+    (start..end, barriers)
+}
+
+#[test]
+fn test_noop_barriers() {
+    use rendy_core::hal::{buffer, image, pso::PipelineStage};
+
+    let stages = PipelineStage::FRAGMENT_SHADER..PipelineStage::FRAGMENT_SHADER;
+
+    let read = BufferBarrier {
+        states: buffer::Access::UNIFORM_READ..buffer::Access::SHADER_READ,
+        stages: stages.clone(),
+        families: None,
+    };
+    assert!(read.is_noop());
+
+    let write = BufferBarrier {
+        states: buffer::Access::SHADER_WRITE..buffer::Access::SHADER_READ,
+        stages: stages.clone(),
+        families: None,
+    };
+    assert!(!write.is_noop());
+
+    let transition = ImageBarrier {
+        states: (image::Access::SHADER_READ, image::Layout::General)
+            ..(
+                image::Access::SHADER_READ,
+                image::Layout::ShaderReadOnlyOptimal,
+            ),
+        stages: stages.clone(),
+        families: None,
+    };
+    assert!(!transition.is_noop());
+    assert!(!transition.is_global());
+
+    let write = ImageBarrier {
+        states: (image::Access::SHADER_WRITE, image::Layout::General)
+            ..(image::Access::SHADER_READ, image::Layout::General),
+        stages,
+        families: None,
+    };
+    assert!(!write.is_noop());
+    assert!(write.is_global());
 }
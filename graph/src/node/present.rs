@@ -12,7 +12,7 @@ use crate::{
         gfx_acquire_barriers, gfx_release_barriers, BufferAccess, DynNode, ImageAccess, NodeBuffer,
         NodeBuildError, NodeBuilder, NodeImage,
     },
-    wsi::{Surface, Target},
+    wsi::{clamp_image_count, Surface, SwapchainMode, SwapchainStatus, Target},
     BufferId, ImageId, NodeId,
 };
 
@@ -50,6 +50,8 @@ pub struct PresentNode<B: rendy_core::hal::Backend> {
     pool: CommandPool<B, rendy_core::hal::queue::QueueType>,
     input_image: NodeImage,
     blit_filter: rendy_core::hal::image::Filter,
+    blit_supported: bool,
+    surface_lost: bool,
 }
 
 // Raw pointer destroys Send/Sync autoimpl, but it's always from the same graph.
@@ -61,19 +63,24 @@ where
     B: rendy_core::hal::Backend,
 {
     /// Node builder.
-    /// By default attempts to use 3 images in the swapchain with present mode priority:
+    /// By default attempts to use 3 images in the swapchain (`SwapchainMode::Throughput`)
+    /// with present mode priority:
     ///
     /// Mailbox > Fifo > Relaxed > Immediate.
     ///
     /// You can query the real image count and present mode which will be used with
     /// `PresentBuilder::image_count()` and `PresentBuilder::present_mode()`.
+    ///
+    /// Node runs on family picked by `Factory::present_family`.
+    /// If that family doesn't support graphics (e.g. compute-only workloads)
+    /// image is copied instead of blitted, so its format must match surface format.
+    /// Graph transitions image from layout it was written in (e.g. `General` for compute)
+    /// and swapchain image is transitioned to `Present` layout by the node.
     pub fn builder(factory: &Factory<B>, surface: Surface<B>, image: ImageId) -> PresentBuilder<B> {
         use rendy_core::hal::window::PresentMode;
 
         let caps = factory.get_surface_capabilities(&surface);
-        let image_count = 3
-            .min(*caps.image_count.end())
-            .max(*caps.image_count.start());
+        let image_count = clamp_image_count(&caps, SwapchainMode::default().image_count());
 
         let present_mode = match () {
             _ if caps.present_modes.contains(PresentMode::FIFO) => PresentMode::FIFO,
@@ -91,6 +98,7 @@ where
             present_mode,
             caps,
             blit_filter: rendy_core::hal::image::Filter::Nearest,
+            usage: rendy_core::hal::image::Usage::TRANSFER_DST,
         }
     }
 }
@@ -102,6 +110,7 @@ fn create_per_image_data<B: rendy_core::hal::Backend>(
     factory: &Factory<B>,
     target: &Target<B>,
     blit_filter: rendy_core::hal::image::Filter,
+    blit_supported: bool,
 ) -> Vec<ForImage<B>> {
     let input_image_res = ctx.get_image(input_image.id).expect("Image does not exist");
 
@@ -146,7 +155,7 @@ fn create_per_image_data<B: rendy_core::hal::Backend>(
             let extents_differ = target_image.kind().extent() != input_image_res.kind().extent();
             let formats_differ = target_image.format() != input_image_res.format();
 
-            if extents_differ || formats_differ
+            if (extents_differ || formats_differ) && blit_supported
             {
                 if formats_differ {
                     log::debug!("Present node is blitting because target format {:?} doesnt match image format {:?}", target_image.format(), input_image_res.format());
@@ -180,7 +189,11 @@ fn create_per_image_data<B: rendy_core::hal::Backend>(
                     );
                 }
             } else {
-                log::debug!("Present node is copying");
+                if extents_differ {
+                    log::debug!("Present node is copying intersection of target extent {:?} and image extent {:?} because queue doesn't support blitting", target_image.kind().extent(), input_image_res.kind().extent());
+                } else {
+                    log::debug!("Present node is copying");
+                }
                 unsafe {
                     encoder.copy_image(
                         input_image_res.raw(),
@@ -201,8 +214,8 @@ fn create_per_image_data<B: rendy_core::hal::Backend>(
                             },
                             dst_offset: rendy_core::hal::image::Offset::ZERO,
                             extent: rendy_core::hal::image::Extent {
-                                width: target_image.kind().extent().width,
-                                height: target_image.kind().extent().height,
+                                width: target_image.kind().extent().width.min(input_image_res.kind().extent().width),
+                                height: target_image.kind().extent().height.min(input_image_res.kind().extent().height),
                                 depth: 1,
                             },
                         }),
@@ -265,6 +278,7 @@ pub struct PresentBuilder<B: rendy_core::hal::Backend> {
     caps: rendy_core::hal::window::SurfaceCapabilities,
     dependencies: Vec<NodeId>,
     blit_filter: rendy_core::hal::image::Filter,
+    usage: rendy_core::hal::image::Usage,
 }
 
 impl<B> PresentBuilder<B>
@@ -290,14 +304,23 @@ where
     ///
     /// Check `PresentBuilder::image_count()` after calling this function but before
     /// building to see the final image count.
+    ///
+    /// Image count doesn't limit frames in flight which are set with
+    /// `GraphBuilder::with_frames_in_flight`.
+    ///
+    /// If surface requires more images than requested the minimum is used
+    /// and a warning is logged.
     pub fn with_image_count(mut self, image_count: u32) -> Self {
-        let image_count = image_count
-            .min(*self.caps.image_count.end())
-            .max(*self.caps.image_count.start());
-        self.image_count = image_count;
+        self.image_count = clamp_image_count(&self.caps, image_count);
         self
     }
 
+    /// Request number of images in the swapchain that suits latency or throughput `mode`.
+    /// See [`with_image_count`](#method.with_image_count) for details.
+    pub fn with_swapchain_mode(self, mode: SwapchainMode) -> Self {
+        self.with_image_count(mode.image_count())
+    }
+
     /// Set up filter used for resizing when backbuffer size does not match source image size.
     ///
     /// Default is `Nearest`.
@@ -306,6 +329,19 @@ where
         self
     }
 
+    /// Request additional usage flags for swapchain images,
+    /// e.g. `TRANSFER_SRC` to read back presented frames
+    /// or `STORAGE` to write them from compute shaders.
+    /// `TRANSFER_DST` required to copy image to the swapchain is always requested.
+    ///
+    /// Node build fails with `SwapchainError::BadUsage`
+    /// if surface doesn't support requested usage.
+    /// Check `caps().usage` for supported flags.
+    pub fn with_usage(mut self, usage: rendy_core::hal::image::Usage) -> Self {
+        self.usage |= usage;
+        self
+    }
+
     /// Request a priority of present modes when creating the swapchain for the
     /// PresentNode. Lower index means higher priority.
     ///
@@ -358,6 +394,16 @@ where
     pub fn present_mode(&self) -> rendy_core::hal::window::PresentMode {
         self.present_mode
     }
+
+    /// Get usage flags of swapchain images.
+    pub fn usage(&self) -> rendy_core::hal::image::Usage {
+        self.usage
+    }
+
+    /// Get capabilities of the surface.
+    pub fn caps(&self) -> &rendy_core::hal::window::SurfaceCapabilities {
+        &self.caps
+    }
 }
 
 impl<B, T> NodeBuilder<B, T> for PresentBuilder<B>
@@ -367,7 +413,8 @@ where
 {
     fn family(&self, factory: &mut Factory<B>, families: &Families<B>) -> Option<FamilyId> {
         // Find correct queue family.
-        families.find(|family| factory.surface_support(family.id(), &self.surface))
+        // Graph transfers ownership of the input image if it differs from the one image was rendered with.
+        factory.present_family(families, &self.surface)
     }
 
     fn buffers(&self) -> Vec<(BufferId, BufferAccess)> {
@@ -426,10 +473,35 @@ where
                 extent,
                 self.image_count,
                 self.present_mode,
-                rendy_core::hal::image::Usage::TRANSFER_DST,
+                self.usage,
             )
             .map_err(NodeBuildError::Swapchain)?;
 
+        // Presentation may happen from compute or transfer-only family
+        // which can only copy images. Copying requires matching formats.
+        let blit_supported = family.capability().supports_graphics();
+        let input_format = ctx
+            .get_image(input_image.id)
+            .expect("Context must contain node's image")
+            .format();
+        let target_format = target.backbuffer()[0].format();
+        if !blit_supported && input_format != target_format {
+            log::warn!(
+                "Family {:?} bound to the node can't blit image of format {:?} to surface format {:?}",
+                family.id(),
+                input_format,
+                target_format,
+            );
+            let surface = unsafe { factory.destroy_target(target) };
+            factory.destroy_surface(surface);
+            return Err(NodeBuildError::custom(format!(
+                "Image format {:?} doesn't match surface format {:?} and family {:?} doesn't support blitting",
+                input_format,
+                target_format,
+                family.id(),
+            )));
+        }
+
         let mut pool = factory
             .create_command_pool(family)
             .map_err(NodeBuildError::OutOfMemory)?;
@@ -441,6 +513,7 @@ where
             factory,
             &target,
             self.blit_filter,
+            blit_supported,
         );
 
         Ok(Box::new(PresentNode {
@@ -450,10 +523,35 @@ where
             per_image,
             input_image,
             blit_filter: self.blit_filter,
+            blit_supported,
+            surface_lost: false,
         }))
     }
 }
 
+impl<B> PresentNode<B>
+where
+    B: rendy_core::hal::Backend,
+{
+    fn recreate_per_image_data(&mut self, ctx: &GraphContext<B>, factory: &Factory<B>) {
+        for data in self.per_image.drain(..) {
+            unsafe {
+                data.dispose(factory, &mut self.pool);
+            }
+        }
+
+        self.per_image = create_per_image_data(
+            ctx,
+            &self.input_image,
+            &mut self.pool,
+            factory,
+            &self.target,
+            self.blit_filter,
+            self.blit_supported,
+        );
+    }
+}
+
 impl<B, T> DynNode<B, T> for PresentNode<B>
 where
     B: rendy_core::hal::Backend,
@@ -468,80 +566,99 @@ where
         _frames: &Frames<B>,
         waits: &[(&'a B::Semaphore, rendy_core::hal::pso::PipelineStage)],
         signals: &[&'a B::Semaphore],
-        mut fence: Option<&mut Fence<B>>,
+        fence: Option<&mut Fence<B>>,
     ) {
-        loop {
-            match self.target.next_image(&self.free_acquire) {
-                Ok(next) => {
-                    log::trace!("Present: {:#?}", next);
-                    let for_image = &mut self.per_image[next[0] as usize];
-                    core::mem::swap(&mut for_image.acquire, &mut self.free_acquire);
-
-                    queue.submit(
-                        Some(
-                            Submission::new()
-                                .submits(Some(&for_image.submit))
-                                .wait(waits.iter().cloned().chain(Some((
-                                    &for_image.acquire,
-                                    rendy_core::hal::pso::PipelineStage::TRANSFER,
-                                ))))
-                                .signal(signals.iter().cloned().chain(Some(&for_image.release))),
-                        ),
-                        fence.take(),
-                    );
+        let extent = ctx
+            .get_image(self.input_image.id)
+            .expect("Context must contain node's image")
+            .kind()
+            .extent()
+            .into();
 
-                    match next.present(queue.raw(), Some(&for_image.release)) {
-                        Ok(_) => break,
-                        Err(e) => {
-                            log::debug!(
-                                "Swapchain present error after next_image is acquired: {:?}",
-                                e
-                            );
-                            // recreate swapchain on next frame.
-                            break;
-                        }
-                    }
-                }
-                Err(rendy_core::hal::window::AcquireError::OutOfDate) => {
-                    // recreate swapchain and try again.
-                }
-                e => {
-                    e.unwrap();
-                    break;
-                }
+        let (index, status) = match self.target.acquire_or_recreate(
+            factory.physical(),
+            factory.device(),
+            extent,
+            &self.free_acquire,
+        ) {
+            Ok(acquired) => acquired,
+            Err(err) => {
+                log::debug!("Swapchain acquisition error: {}", err);
+                self.surface_lost |= err.is_surface_lost();
+                // Keep semaphores and fence in sync with the rest of the graph.
+                queue.submit(
+                    Some(
+                        Submission::new()
+                            .wait(waits.iter().cloned())
+                            .signal(signals.iter().cloned()),
+                    ),
+                    fence,
+                );
+                return;
             }
-            // Recreate swapchain when OutOfDate
-            // The code has to execute after match due to mutable aliasing issues.
-
-            // TODO: use retired swapchains once available in hal and remove that wait
-            factory.wait_idle().unwrap();
+        };
 
-            let extent = ctx
-                .get_image(self.input_image.id)
-                .expect("Context must contain node's image")
-                .kind()
-                .extent()
-                .into();
+        log::trace!("Present: {} {:?}", index, status);
+        if status == SwapchainStatus::Recreated {
+            self.recreate_per_image_data(ctx, factory);
+        }
 
-            self.target
-                .recreate(factory.physical(), factory.device(), extent)
-                .expect("Failed recreating swapchain");
+        let for_image = &mut self.per_image[index as usize];
+        core::mem::swap(&mut for_image.acquire, &mut self.free_acquire);
+
+        queue.submit(
+            Some(
+                Submission::new()
+                    .submits(Some(&for_image.submit))
+                    .wait(waits.iter().cloned().chain(Some((
+                        &for_image.acquire,
+                        rendy_core::hal::pso::PipelineStage::TRANSFER,
+                    ))))
+                    .signal(signals.iter().cloned().chain(Some(&for_image.release))),
+            ),
+            fence,
+        );
 
-            for data in self.per_image.drain(..) {
-                data.dispose(factory, &mut self.pool);
+        match self.target.present_or_recreate(
+            factory.physical(),
+            factory.device(),
+            extent,
+            queue.raw(),
+            index,
+            Some(&for_image.release),
+        ) {
+            Ok(SwapchainStatus::Recreated) => self.recreate_per_image_data(ctx, factory),
+            Ok(_) => {}
+            Err(err) => {
+                log::debug!("Swapchain presentation error: {}", err);
+                self.surface_lost |= err.is_surface_lost();
             }
-
-            self.per_image = create_per_image_data(
-                ctx,
-                &self.input_image,
-                &mut self.pool,
-                factory,
-                &self.target,
-                self.blit_filter,
-            );
         }
     }
 
+    fn surface_extent(&self, factory: &Factory<B>) -> Option<rendy_core::hal::window::Extent2D> {
+        unsafe { self.target.surface().extent(factory.physical()) }
+    }
+
+    fn surface_lost(&self) -> bool {
+        self.surface_lost
+    }
+
+    fn resizable(&self) -> bool {
+        true
+    }
+
+    unsafe fn resize(
+        &mut self,
+        ctx: &GraphContext<B>,
+        factory: &Factory<B>,
+        _aux: &T,
+    ) -> Result<(), NodeBuildError> {
+        // Swapchain follows extent of the input image on next acquisition.
+        self.recreate_per_image_data(ctx, factory);
+        Ok(())
+    }
+
     unsafe fn dispose(mut self: Box<Self>, factory: &mut Factory<B>, _aux: &T) {
         for data in self.per_image {
             data.dispose(factory, &mut self.pool);
@@ -0,0 +1,362 @@
+//! Defines node that reads buffer content back to the host.
+//!
+//! Content of the buffer is copied into host-visible memory every frame
+//! and handed to the callback once the frame is complete,
+//! so reading results of GPU work never blocks the render loop.
+
+use crate::{
+    command::{
+        CommandBuffer, CommandPool, ExecutableState, Families, Family, FamilyId, MultiShot,
+        PendingState, SimultaneousUse, Submit, Transfer,
+    },
+    factory::Factory,
+    frame::{Frame, Frames},
+    graph::GraphContext,
+    memory::Download,
+    node::{
+        gfx_acquire_barriers, gfx_release_barriers, BufferAccess, DynNode, ImageAccess, Node,
+        NodeBuffer, NodeBuildError, NodeBuilder, NodeImage, NodeSubmittable,
+    },
+    resource::{Buffer, BufferInfo, Escape},
+    BufferId, ImageId, NodeId,
+};
+
+/// Callback that receives content of the buffer read back by `ReadbackNode`.
+pub type ReadbackCallback = Box<dyn FnMut(&[u8]) + Send>;
+
+/// Host-visible copy of the buffer for one frame in flight.
+#[derive(Debug)]
+struct ReadbackSlot<B: rendy_core::hal::Backend> {
+    buffer: Escape<Buffer<B>>,
+    commands: CommandBuffer<B, Transfer, PendingState<ExecutableState<MultiShot<SimultaneousUse>>>>,
+    submit: Submit<B, SimultaneousUse>,
+    /// Frame that copies the buffer into this slot and wasn't handed to callback yet.
+    pending: Option<Frame>,
+}
+
+impl<B> ReadbackSlot<B>
+where
+    B: rendy_core::hal::Backend,
+{
+    /// Hand content of the slot to the callback.
+    fn deliver(&mut self, factory: &Factory<B>, callback: &mut ReadbackCallback) {
+        let size = self.buffer.size();
+        let mut mapping = match self.buffer.map(factory.device(), 0..size) {
+            Ok(mapping) => mapping,
+            Err(err) => {
+                log::error!("Failed to map readback buffer: {:?}", err);
+                return;
+            }
+        };
+
+        match unsafe { mapping.read::<u8>(factory.device(), 0..size) } {
+            Ok(data) => callback(data),
+            Err(err) => log::error!("Failed to read readback buffer: {:?}", err),
+        }
+    }
+}
+
+/// Node that reads content of the buffer back to the host.
+///
+/// Buffer is copied every frame into one of host-visible buffers, one per frame in flight.
+/// Copy made by frame `N` is handed to the callback by the first run after frame `N`
+/// is complete, at latest when frame `N + frames_in_flight` is being prepared.
+/// Callback is invoked in frame order.
+/// Copies made by frames that are still pending when graph is disposed are handed
+/// to the callback on dispose.
+pub struct ReadbackNode<B: rendy_core::hal::Backend> {
+    pool: CommandPool<B, Transfer>,
+    slots: Vec<ReadbackSlot<B>>,
+    /// Only accessed through `&mut self`, mutex makes node `Sync` without requiring it from callback.
+    callback: std::sync::Mutex<ReadbackCallback>,
+}
+
+impl<B> std::fmt::Debug for ReadbackNode<B>
+where
+    B: rendy_core::hal::Backend,
+{
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt.debug_struct("ReadbackNode")
+            .field("pool", &self.pool)
+            .field("slots", &self.slots)
+            .finish()
+    }
+}
+
+impl<B> ReadbackNode<B>
+where
+    B: rendy_core::hal::Backend,
+{
+    /// Node builder.
+    /// Node reads the `buffer` after nodes that access it before this one
+    /// and calls `callback` with its content once the frame is complete.
+    pub fn builder(
+        buffer: BufferId,
+        callback: impl FnMut(&[u8]) + Send + 'static,
+    ) -> ReadbackBuilder {
+        ReadbackBuilder {
+            buffer,
+            callback: Box::new(callback),
+            dependencies: Vec::new(),
+        }
+    }
+}
+
+impl<'a, B> NodeSubmittable<'a, B> for ReadbackNode<B>
+where
+    B: rendy_core::hal::Backend,
+{
+    type Submittable = &'a Submit<B, SimultaneousUse>;
+    type Submittables = &'a [Submit<B, SimultaneousUse>];
+}
+
+impl<B, T> Node<B, T> for ReadbackNode<B>
+where
+    B: rendy_core::hal::Backend,
+    T: ?Sized,
+{
+    type Capability = Transfer;
+
+    fn run<'a>(
+        &'a mut self,
+        ctx: &GraphContext<B>,
+        factory: &Factory<B>,
+        _aux: &T,
+        frames: &'a Frames<B>,
+    ) -> &'a [Submit<B, SimultaneousUse>] {
+        let due = take_due(
+            self.slots.iter_mut().map(|slot| &mut slot.pending),
+            |frame| frames.is_complete(frame),
+        );
+        let callback = self.callback.get_mut().unwrap();
+        for index in due {
+            self.slots[index].deliver(factory, callback);
+        }
+
+        let slot = &mut self.slots[ctx.frame_index()];
+
+        // Graph waits for the frame that used this slot before preparing the next one.
+        debug_assert!(slot.pending.is_none());
+
+        slot.pending = Some(frames.next());
+        std::slice::from_ref(&slot.submit)
+    }
+
+    unsafe fn dispose(mut self, factory: &mut Factory<B>, _aux: &T) {
+        // Graph waits for all frames before disposing nodes.
+        let pending = self.slots.iter_mut().map(|slot| &mut slot.pending);
+        let due = take_due(pending, |_| true);
+        let callback = self.callback.get_mut().unwrap();
+        for index in due {
+            self.slots[index].deliver(factory, callback);
+        }
+
+        self.pool.free_buffers(
+            self.slots
+                .into_iter()
+                .map(|slot| slot.commands.mark_complete()),
+        );
+        factory.destroy_command_pool(self.pool);
+    }
+}
+
+/// Readback node description.
+pub struct ReadbackBuilder {
+    buffer: BufferId,
+    callback: ReadbackCallback,
+    dependencies: Vec<NodeId>,
+}
+
+impl std::fmt::Debug for ReadbackBuilder {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt.debug_struct("ReadbackBuilder")
+            .field("buffer", &self.buffer)
+            .field("dependencies", &self.dependencies)
+            .finish()
+    }
+}
+
+impl ReadbackBuilder {
+    /// Add dependency.
+    /// Node will be placed after its dependencies.
+    pub fn add_dependency(&mut self, dependency: NodeId) -> &mut Self {
+        self.dependencies.push(dependency);
+        self
+    }
+
+    /// Add dependency.
+    /// Node will be placed after its dependencies.
+    pub fn with_dependency(mut self, dependency: NodeId) -> Self {
+        self.add_dependency(dependency);
+        self
+    }
+
+    /// Buffer the node reads back.
+    pub fn buffer(&self) -> BufferId {
+        self.buffer
+    }
+}
+
+impl<B, T> NodeBuilder<B, T> for ReadbackBuilder
+where
+    B: rendy_core::hal::Backend,
+    T: ?Sized,
+{
+    fn family(&self, _factory: &mut Factory<B>, families: &Families<B>) -> Option<FamilyId> {
+        families.with_capability::<Transfer>()
+    }
+
+    fn buffers(&self) -> Vec<(BufferId, BufferAccess)> {
+        vec![(self.buffer, transfer_src())]
+    }
+
+    fn images(&self) -> Vec<(ImageId, ImageAccess)> {
+        Vec::new()
+    }
+
+    fn dependencies(&self) -> Vec<NodeId> {
+        self.dependencies.clone()
+    }
+
+    fn build<'a>(
+        self: Box<Self>,
+        ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        family: &mut Family<B>,
+        _queue: usize,
+        _aux: &T,
+        buffers: Vec<NodeBuffer>,
+        images: Vec<NodeImage>,
+    ) -> Result<Box<dyn DynNode<B, T>>, NodeBuildError> {
+        assert_eq!(buffers.len(), 1);
+        assert!(images.is_empty());
+
+        let node_buffer = &buffers[0];
+        let src = ctx
+            .get_buffer(node_buffer.id)
+            .expect("Buffer does not exist");
+        let size = node_buffer.range.end - node_buffer.range.start;
+
+        let mut pool = factory
+            .create_command_pool(family)
+            .map_err(NodeBuildError::OutOfMemory)?
+            .with_capability::<Transfer>()
+            .expect("Graph builder must provide family with Transfer capability");
+
+        let mut slots = Vec::with_capacity(ctx.frames_in_flight as usize);
+        for initial in pool.allocate_buffers(ctx.frames_in_flight as usize) {
+            let buffer = factory
+                .create_buffer(
+                    BufferInfo {
+                        size,
+                        usage: rendy_core::hal::buffer::Usage::TRANSFER_DST,
+                    },
+                    Download,
+                )
+                .map_err(NodeBuildError::custom)?;
+
+            let mut recording = initial.begin(MultiShot(SimultaneousUse), ());
+            let mut encoder = recording.encoder();
+            unsafe {
+                let (stages, barriers) = gfx_acquire_barriers(ctx, &buffers, None);
+                if !barriers.is_empty() {
+                    encoder.pipeline_barrier(
+                        stages,
+                        rendy_core::hal::memory::Dependencies::empty(),
+                        barriers,
+                    );
+                }
+
+                encoder.copy_buffer(
+                    src.raw(),
+                    buffer.raw(),
+                    Some(rendy_core::hal::command::BufferCopy {
+                        src: node_buffer.range.start,
+                        dst: 0,
+                        size,
+                    }),
+                );
+
+                let (stages, mut barriers) = gfx_release_barriers(ctx, &buffers, None);
+                barriers.push(rendy_core::hal::memory::Barrier::Buffer {
+                    states: rendy_core::hal::buffer::Access::TRANSFER_WRITE
+                        ..rendy_core::hal::buffer::Access::HOST_READ,
+                    target: buffer.raw(),
+                    families: None,
+                    range: rendy_core::hal::buffer::SubRange::WHOLE,
+                });
+                encoder.pipeline_barrier(
+                    rendy_core::hal::pso::PipelineStage::TRANSFER | stages.start
+                        ..rendy_core::hal::pso::PipelineStage::HOST | stages.end,
+                    rendy_core::hal::memory::Dependencies::empty(),
+                    barriers,
+                );
+            }
+
+            let (submit, commands) = recording.finish().submit();
+            slots.push(ReadbackSlot {
+                buffer,
+                commands,
+                submit,
+                pending: None,
+            });
+        }
+
+        Ok(Box::new((ReadbackNode {
+            pool,
+            slots,
+            callback: std::sync::Mutex::new(self.callback),
+        },)))
+    }
+}
+
+/// Take frames for which `is_complete` returns `true` out of `pending`.
+/// Returns indices of those frames in `pending` in frame order.
+fn take_due<'a>(
+    pending: impl IntoIterator<Item = &'a mut Option<Frame>>,
+    is_complete: impl Fn(Frame) -> bool,
+) -> Vec<usize> {
+    let mut due: Vec<(Frame, usize)> = pending
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, pending)| match *pending {
+            Some(frame) if is_complete(frame) => {
+                *pending = None;
+                Some((frame, index))
+            }
+            _ => None,
+        })
+        .collect();
+    due.sort();
+    due.into_iter().map(|(_, index)| index).collect()
+}
+
+fn transfer_src() -> BufferAccess {
+    BufferAccess {
+        access: rendy_core::hal::buffer::Access::TRANSFER_READ,
+        usage: rendy_core::hal::buffer::Usage::TRANSFER_SRC,
+        stages: rendy_core::hal::pso::PipelineStage::TRANSFER,
+    }
+}
+
+#[test]
+fn test_take_due() {
+    let mut pending = vec![
+        Some(Frame::with_index(5)),
+        Some(Frame::with_index(3)),
+        None,
+        Some(Frame::with_index(4)),
+    ];
+
+    // Only complete frames are taken, oldest first.
+    let due = take_due(&mut pending, |frame| frame.index() <= 4);
+    assert_eq!(due, vec![1, 3]);
+    assert_eq!(pending, vec![Some(Frame::with_index(5)), None, None, None]);
+
+    // Taken frames are not delivered twice.
+    assert!(take_due(&mut pending, |frame| frame.index() <= 4).is_empty());
+
+    // On dispose everything still pending is delivered.
+    assert_eq!(take_due(&mut pending, |_| true), vec![0]);
+    assert!(pending.iter().all(Option::is_none));
+}
@@ -53,6 +53,9 @@ pub trait RenderGroupDesc<B: Backend, T: ?Sized>: std::fmt::Debug {
     }
 
     /// Build render group.
+    ///
+    /// `render_area` is the region of the framebuffer render pass renders to.
+    /// Viewport and scissor of pipelines should match it.
     fn build<'a>(
         self,
         ctx: &GraphContext<B>,
@@ -61,6 +64,7 @@ pub trait RenderGroupDesc<B: Backend, T: ?Sized>: std::fmt::Debug {
         aux: &T,
         framebuffer_width: u32,
         framebuffer_height: u32,
+        render_area: rendy_core::hal::pso::Rect,
         subpass: rendy_core::hal::pass::Subpass<'_, B>,
         buffers: Vec<NodeBuffer>,
         images: Vec<NodeImage>,
@@ -88,6 +92,26 @@ pub trait RenderGroup<B: Backend, T: ?Sized>: std::fmt::Debug + Send + Sync {
         aux: &T,
     );
 
+    /// Check if group can handle change of the render area with [`resize`].
+    ///
+    /// [`resize`]: #method.resize
+    fn resizable(&self) -> bool {
+        false
+    }
+
+    /// Update group for the new render area after attachments of the render pass were resized.
+    /// Called only for groups that report to be [`resizable`].
+    /// Default implementation returns an error.
+    ///
+    /// [`resizable`]: #method.resizable
+    fn resize(
+        &mut self,
+        _factory: &Factory<B>,
+        _render_area: rendy_core::hal::pso::Rect,
+    ) -> Result<(), rendy_core::hal::pso::CreationError> {
+        Err(rendy_core::hal::pso::CreationError::Other)
+    }
+
     /// Free all resources and destroy group instance.
     fn dispose(self: Box<Self>, factory: &mut Factory<B>, aux: &T);
 }
@@ -118,6 +142,7 @@ pub trait RenderGroupBuilder<B: Backend, T: ?Sized>: std::fmt::Debug {
     fn dependencies(&self) -> Vec<NodeId>;
 
     /// Build render group instance.
+    /// See [`RenderGroupDesc::build`](trait.RenderGroupDesc.html#tymethod.build) for details.
     fn build<'a>(
         self: Box<Self>,
         ctx: &GraphContext<B>,
@@ -126,6 +151,7 @@ pub trait RenderGroupBuilder<B: Backend, T: ?Sized>: std::fmt::Debug {
         aux: &T,
         framebuffer_width: u32,
         framebuffer_height: u32,
+        render_area: rendy_core::hal::pso::Rect,
         subpass: rendy_core::hal::pass::Subpass<'_, B>,
         buffers: Vec<NodeBuffer>,
         images: Vec<NodeImage>,
@@ -174,6 +200,7 @@ where
         aux: &T,
         framebuffer_width: u32,
         framebuffer_height: u32,
+        render_area: rendy_core::hal::pso::Rect,
         subpass: rendy_core::hal::pass::Subpass<'_, B>,
         buffers: Vec<NodeBuffer>,
         images: Vec<NodeImage>,
@@ -185,6 +212,7 @@ where
             aux,
             framebuffer_width,
             framebuffer_height,
+            render_area,
             subpass,
             buffers,
             images,
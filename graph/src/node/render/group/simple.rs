@@ -38,6 +38,9 @@ pub struct Pipeline {
 
     /// Primitive to use in the input assembler.
     pub input_assembler_desc: rendy_core::hal::pso::InputAssemblerDesc,
+
+    /// Multisampling for pipeline.
+    pub multisampling: Option<rendy_core::hal::pso::Multisampling>,
 }
 
 /// Descriptor for simple graphics pipeline implementation.
@@ -88,6 +91,12 @@ pub trait SimpleGraphicsPipelineDesc<B: Backend, T: ?Sized>: std::fmt::Debug {
         rendy_core::hal::pso::Rasterizer::FILL
     }
 
+    /// Multisampling desc. Defaults to single sample.
+    /// Sample count must match samples of the images the pass renders into.
+    fn multisampling(&self) -> Option<rendy_core::hal::pso::Multisampling> {
+        None
+    }
+
     /// Get vertex input.
     fn vertices(
         &self,
@@ -109,6 +118,7 @@ pub trait SimpleGraphicsPipelineDesc<B: Backend, T: ?Sized>: std::fmt::Debug {
     }
 
     /// Returns the InputAssemblerDesc. Defaults to a TriangleList with Restart disabled, can be overriden.
+    /// Pipelines that draw line or point meshes can return `Mesh::input_assembler`.
     fn input_assembler(&self) -> rendy_core::hal::pso::InputAssemblerDesc {
         rendy_core::hal::pso::InputAssemblerDesc {
             primitive: rendy_core::hal::pso::Primitive::TriangleList,
@@ -126,6 +136,7 @@ pub trait SimpleGraphicsPipelineDesc<B: Backend, T: ?Sized>: std::fmt::Debug {
             depth_stencil: self.depth_stencil().unwrap_or_default(),
             rasterizer: self.rasterizer(),
             input_assembler_desc: self.input_assembler(),
+            multisampling: self.multisampling(),
         }
     }
 
@@ -202,6 +213,7 @@ pub struct SimpleRenderGroup<B: Backend, P> {
     pipeline_layout: B::PipelineLayout,
     graphics_pipeline: B::GraphicsPipeline,
     pipeline: P,
+    render_area: rendy_core::hal::pso::Rect,
 }
 
 /// Descriptor for simple render group.
@@ -238,8 +250,9 @@ where
         factory: &mut Factory<B>,
         queue: QueueId,
         aux: &T,
-        framebuffer_width: u32,
-        framebuffer_height: u32,
+        _framebuffer_width: u32,
+        _framebuffer_height: u32,
+        render_area: rendy_core::hal::pso::Rect,
         subpass: rendy_core::hal::pass::Subpass<'_, B>,
         buffers: Vec<NodeBuffer>,
         images: Vec<NodeImage>,
@@ -285,13 +298,6 @@ where
             push_vertex_desc(elemets, stride, rate, &mut vertex_buffers, &mut attributes);
         }
 
-        let rect = rendy_core::hal::pso::Rect {
-            x: 0,
-            y: 0,
-            w: framebuffer_width as i16,
-            h: framebuffer_height as i16,
-        };
-
         let shaders = match shader_set.raw() {
             Err(e) => {
                 shader_set.dispose(factory);
@@ -314,13 +320,11 @@ where
                         targets: pipeline.colors.clone(),
                     },
                     depth_stencil: pipeline.depth_stencil,
-                    multisampling: None,
+                    multisampling: pipeline.multisampling,
+                    // Viewport and scissor are dynamic to follow render area on resize.
                     baked_states: rendy_core::hal::pso::BakedStates {
-                        viewport: Some(rendy_core::hal::pso::Viewport {
-                            rect,
-                            depth: 0.0..1.0,
-                        }),
-                        scissor: Some(rect),
+                        viewport: None,
+                        scissor: None,
                         blend_color: None,
                         depth_bounds: None,
                     },
@@ -329,7 +333,7 @@ where
                     flags: rendy_core::hal::pso::PipelineCreationFlags::empty(),
                     parent: rendy_core::hal::pso::BasePipeline::None,
                 }),
-                None,
+                factory.pipeline_cache(),
             )
         }
         .remove(0)
@@ -353,6 +357,7 @@ where
             pipeline_layout,
             graphics_pipeline,
             pipeline,
+            render_area,
         }))
     }
 }
@@ -383,10 +388,33 @@ where
         aux: &T,
     ) {
         encoder.bind_graphics_pipeline(&self.graphics_pipeline);
+        unsafe {
+            encoder.set_viewports(
+                0,
+                Some(&rendy_core::hal::pso::Viewport {
+                    rect: self.render_area,
+                    depth: 0.0..1.0,
+                }),
+            );
+            encoder.set_scissors(0, Some(&self.render_area));
+        }
         self.pipeline
             .draw(&self.pipeline_layout, encoder, index, aux);
     }
 
+    fn resizable(&self) -> bool {
+        true
+    }
+
+    fn resize(
+        &mut self,
+        _factory: &Factory<B>,
+        render_area: rendy_core::hal::pso::Rect,
+    ) -> Result<(), rendy_core::hal::pso::CreationError> {
+        self.render_area = render_area;
+        Ok(())
+    }
+
     fn dispose(self: Box<Self>, factory: &mut Factory<B>, aux: &T) {
         self.pipeline.dispose(factory, aux);
 
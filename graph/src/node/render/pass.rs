@@ -1,9 +1,11 @@
 use {
     crate::{
         command::{
-            CommandBuffer, CommandPool, ExecutableState, Families, Family, FamilyId, Fence,
-            Graphics, IndividualReset, MultiShot, NoSimultaneousUse, PendingState, Queue, QueueId,
-            SecondaryLevel, SimultaneousUse, Submission, Submit,
+            CommandBuffer, CommandPool, Encoder, ExecutableState, Families, Family, FamilyId,
+            Fence, Graphics, IndividualReset, MultiShot, NoSimultaneousUse, PendingState,
+            PrimaryLevel, Queue, QueueId, RenderPassContinue, RenderPassInlineEncoder,
+            RenderPassSecondaryEncoder, SecondaryLevel, SimultaneousUse, Submission, Submit,
+            Supports,
         },
         core::{
             hal::{device::Device as _, image::Layout, Backend},
@@ -20,7 +22,8 @@ use {
             render::group::{RenderGroup, RenderGroupBuilder},
             BufferAccess, DynNode, ImageAccess, NodeBuffer, NodeBuildError, NodeBuilder, NodeImage,
         },
-        wsi::{Surface, Target},
+        resource::Image,
+        wsi::{clamp_image_count, Surface, SwapchainMode, Target},
         BufferId, ImageId, NodeId,
     },
     either::Either,
@@ -32,13 +35,32 @@ struct RenderPassSurface;
 
 type Attachment = Either<ImageId, RenderPassSurface>;
 
+/// How render pass treats attachment content at the beginning of the pass.
+#[derive(Clone, Copy, Debug)]
+pub enum AttachmentLoad {
+    /// Clear attachment with the value.
+    Clear(rendy_core::hal::command::ClearValue),
+
+    /// Preserve attachment content written by previous nodes.
+    Load,
+
+    /// Previous content is not needed.
+    DontCare,
+}
+
 /// Build for rendering sub-pass.
 pub struct SubpassBuilder<B: Backend, T: ?Sized> {
     groups: Vec<Box<dyn RenderGroupBuilder<B, T>>>,
     inputs: Vec<Attachment>,
     colors: Vec<Attachment>,
     depth_stencil: Option<Attachment>,
+    ops: Vec<(
+        ImageId,
+        AttachmentLoad,
+        rendy_core::hal::pass::AttachmentStoreOp,
+    )>,
     dependencies: Vec<NodeId>,
+    secondary: bool,
 }
 
 impl<B, T> std::fmt::Debug for SubpassBuilder<B, T>
@@ -52,7 +74,9 @@ where
             .field("inputs", &self.inputs)
             .field("colors", &self.colors)
             .field("depth_stencil", &self.depth_stencil)
+            .field("ops", &self.ops)
             .field("dependencies", &self.dependencies)
+            .field("secondary", &self.secondary)
             .finish()
     }
 }
@@ -68,7 +92,9 @@ where
             inputs: Vec::default(),
             colors: Vec::default(),
             depth_stencil: None,
+            ops: Vec::default(),
             dependencies: Vec::default(),
+            secondary: false,
         }
     }
 }
@@ -114,24 +140,46 @@ where
     }
 
     /// Add input attachment to the subpass.
+    ///
+    /// Input attachment is read by fragment shader of this subpass
+    /// at the current fragment position only, with `subpassLoad`.
+    /// Shader must declare it as `subpassInput` with matching
+    /// `input_attachment_index` (index in order of added inputs)
+    /// and render group must bind it to descriptor of
+    /// `DescriptorType::InputAttachment` type.
+    ///
+    /// If image is written by earlier subpass of the same pass
+    /// a `BY_REGION` dependency between those subpasses is generated
+    /// and image is kept in `General` layout for the whole pass.
+    /// Input attachment must have same number of samples as
+    /// other attachments of the subpass.
     pub fn add_input(&mut self, input: ImageId) -> &mut Self {
         self.inputs.push(Either::Left(input));
         self
     }
 
     /// Add input attachment to the subpass.
+    /// See [`add_input`](#method.add_input) for details.
     pub fn with_input(mut self, input: ImageId) -> Self {
         self.add_input(input);
         self
     }
 
     /// Add color attachment to the subpass.
+    ///
+    /// Color attachments are bound to fragment shader outputs
+    /// at locations in the order they were added.
+    /// All color attachment images of the subpass must have same extent and number of samples.
     pub fn add_color(&mut self, color: ImageId) -> &mut Self {
         self.colors.push(Either::Left(color));
         self
     }
 
     /// Add color attachment to the subpass.
+    ///
+    /// Color attachments are bound to fragment shader outputs
+    /// at locations in the order they were added.
+    /// All color attachment images of the subpass must have same extent and number of samples.
     pub fn with_color(mut self, color: ImageId) -> Self {
         self.add_color(color);
         self
@@ -173,8 +221,38 @@ where
         self
     }
 
+    /// Set load and store operations for the attachment image.
+    ///
+    /// By default attachment is cleared with the value the image was created with
+    /// when render pass is its first user and loaded otherwise.
+    /// Content is always stored by default.
+    pub fn set_attachment_ops(
+        &mut self,
+        image: ImageId,
+        load: AttachmentLoad,
+        store: rendy_core::hal::pass::AttachmentStoreOp,
+    ) -> &mut Self {
+        self.ops.retain(|&(id, _, _)| id != image);
+        self.ops.push((image, load, store));
+        self
+    }
+
+    /// Set load and store operations for the attachment image.
+    pub fn with_attachment_ops(
+        mut self,
+        image: ImageId,
+        load: AttachmentLoad,
+        store: rendy_core::hal::pass::AttachmentStoreOp,
+    ) -> Self {
+        self.set_attachment_ops(image, load, store);
+        self
+    }
+
     /// Add dependency.
     /// `RenderPassNode` will be placed after its dependencies.
+    /// See [`DescBuilder::add_dependency`] for when to use it.
+    ///
+    /// [`DescBuilder::add_dependency`]: ../struct.DescBuilder.html#method.add_dependency
     pub fn add_dependency(&mut self, dependency: NodeId) -> &mut Self {
         self.dependencies.push(dependency);
         self
@@ -187,10 +265,44 @@ where
         self
     }
 
+    /// Record render groups of this subpass into secondary command buffers.
+    ///
+    /// Subpass is begun with `SubpassContents::SecondaryBuffers`
+    /// and executes secondary buffers that inherit render pass, subpass index and framebuffer.
+    /// Secondary buffers are allocated from a command pool owned by the subpass
+    /// and rerecorded together with primary buffer of the render pass.
+    /// By default render groups are recorded inline.
+    pub fn set_secondary_buffers(&mut self) -> &mut Self {
+        self.secondary = true;
+        self
+    }
+
+    /// Record render groups of this subpass into secondary command buffers.
+    /// See [`set_secondary_buffers`](#method.set_secondary_buffers) for details.
+    pub fn with_secondary_buffers(mut self) -> Self {
+        self.set_secondary_buffers();
+        self
+    }
+
     /// Make render pass from subpass.
     pub fn into_pass(self) -> RenderPassNodeBuilder<B, T> {
         RenderPassNodeBuilder::new().with_subpass(self)
     }
+
+    /// Accesses of images used as attachments by this subpass.
+    fn attachment_images(&self) -> HashMap<ImageId, ImageAccess> {
+        subpass_attachment_images(
+            self.inputs
+                .iter()
+                .filter_map(|e| e.as_ref().left())
+                .cloned(),
+            self.colors
+                .iter()
+                .filter_map(|e| e.as_ref().left())
+                .cloned(),
+            self.depth_stencil.and_then(Either::left),
+        )
+    }
 }
 
 /// Builder for render-pass node.
@@ -201,6 +313,9 @@ pub struct RenderPassNodeBuilder<B: Backend, T: ?Sized> {
         rendy_core::hal::window::Extent2D,
         Option<rendy_core::hal::command::ClearValue>,
     )>,
+    surface_usage: rendy_core::hal::image::Usage,
+    surface_image_count: u32,
+    render_area: Option<rendy_core::hal::pso::Rect>,
 }
 
 impl<B, T> std::fmt::Debug for RenderPassNodeBuilder<B, T>
@@ -212,6 +327,9 @@ where
         fmt.debug_struct("RenderPassNodeBuilder")
             .field("subpasses", &self.subpasses)
             .field("surface", &self.surface)
+            .field("surface_usage", &self.surface_usage)
+            .field("surface_image_count", &self.surface_image_count)
+            .field("render_area", &self.render_area)
             .finish()
     }
 }
@@ -225,6 +343,9 @@ where
         RenderPassNodeBuilder {
             subpasses: Vec::default(),
             surface: None,
+            surface_usage: rendy_core::hal::image::Usage::empty(),
+            surface_image_count: SwapchainMode::default().image_count(),
+            render_area: None,
         }
     }
 }
@@ -280,6 +401,80 @@ where
         self.add_surface(surface, suggested_extent, clear);
         self
     }
+
+    /// Request additional usage flags for surface images
+    /// on top of attachment usage derived from subpasses.
+    /// E.g. `TRANSFER_SRC` to read back presented frames.
+    ///
+    /// Node build fails with `SwapchainError::BadUsage`
+    /// if surface doesn't support requested usage.
+    pub fn add_surface_usage(&mut self, usage: rendy_core::hal::image::Usage) -> &mut Self {
+        self.surface_usage |= usage;
+        self
+    }
+
+    /// Request additional usage flags for surface images
+    /// on top of attachment usage derived from subpasses.
+    /// E.g. `TRANSFER_SRC` to read back presented frames.
+    ///
+    /// Node build fails with `SwapchainError::BadUsage`
+    /// if surface doesn't support requested usage.
+    pub fn with_surface_usage(mut self, usage: rendy_core::hal::image::Usage) -> Self {
+        self.add_surface_usage(usage);
+        self
+    }
+
+    /// Request a number of images in the swapchain created for the surface.
+    /// Count is clamped to range supported by the surface when node is built,
+    /// see `clamp_image_count`.
+    /// By default count of `SwapchainMode::Throughput` is requested.
+    pub fn set_surface_image_count(&mut self, image_count: u32) -> &mut Self {
+        self.surface_image_count = image_count;
+        self
+    }
+
+    /// Request a number of images in the swapchain created for the surface.
+    /// See [`set_surface_image_count`](#method.set_surface_image_count) for details.
+    pub fn with_surface_image_count(mut self, image_count: u32) -> Self {
+        self.set_surface_image_count(image_count);
+        self
+    }
+
+    /// Request number of images in the swapchain created for the surface
+    /// that suits latency or throughput `mode`.
+    pub fn set_swapchain_mode(&mut self, mode: SwapchainMode) -> &mut Self {
+        self.set_surface_image_count(mode.image_count())
+    }
+
+    /// Request number of images in the swapchain created for the surface
+    /// that suits latency or throughput `mode`.
+    pub fn with_swapchain_mode(mut self, mode: SwapchainMode) -> Self {
+        self.set_swapchain_mode(mode);
+        self
+    }
+
+    /// Restrict rendering to the region of attachments,
+    /// e.g. tile of a shadow atlas or part of split screen.
+    /// By default whole attachments are rendered.
+    ///
+    /// Render pass is begun with this render area
+    /// and viewport and scissor of render groups match it.
+    /// Content of attachments outside of the area is preserved if they are loaded,
+    /// otherwise it is undefined.
+    ///
+    /// Node build fails with `RenderAreaOutOfBounds`
+    /// if area doesn't fit into attachments.
+    pub fn set_render_area(&mut self, area: rendy_core::hal::pso::Rect) -> &mut Self {
+        self.render_area = Some(area);
+        self
+    }
+
+    /// Restrict rendering to the region of attachments.
+    /// See [`set_render_area`](#method.set_render_area) for details.
+    pub fn with_render_area(mut self, area: rendy_core::hal::pso::Rect) -> Self {
+        self.set_render_area(area);
+        self
+    }
 }
 
 impl<B, T> NodeBuilder<B, T> for RenderPassNodeBuilder<B, T>
@@ -287,8 +482,14 @@ where
     B: Backend,
     T: ?Sized + 'static,
 {
-    fn family(&self, _factory: &mut Factory<B>, families: &Families<B>) -> Option<FamilyId> {
-        families.with_capability::<Graphics>()
+    fn family(&self, factory: &mut Factory<B>, families: &Families<B>) -> Option<FamilyId> {
+        // Surface is presented from the same family, so it must support both graphics and present.
+        match &self.surface {
+            Some((surface, _, _)) => factory
+                .present_family(families, surface)
+                .filter(|&id| families.family(id).capability().supports_graphics()),
+            None => families.with_capability::<Graphics>(),
+        }
     }
 
     fn buffers(&self) -> Vec<(BufferId, BufferAccess)> {
@@ -320,41 +521,16 @@ where
             stages: rendy_core::hal::pso::PipelineStage::empty(),
             layout: Layout::Undefined,
         };
-        let mut attachments = HashMap::new();
+        let mut attachments: HashMap<_, ImageAccess> = HashMap::new();
         let mut images = HashMap::new();
 
         for subpass in &self.subpasses {
-            for &id in subpass.inputs.iter().filter_map(|e| e.as_ref().left()) {
-                let entry = attachments.entry(id).or_insert(ImageAccess {
-                    layout: Layout::ShaderReadOnlyOptimal,
-                    ..empty
-                });
-                entry.access |= rendy_core::hal::image::Access::INPUT_ATTACHMENT_READ;
-                entry.usage |= rendy_core::hal::image::Usage::INPUT_ATTACHMENT;
-                entry.stages |= rendy_core::hal::pso::PipelineStage::FRAGMENT_SHADER;
-            }
-
-            for &id in subpass.colors.iter().filter_map(|e| e.as_ref().left()) {
-                let entry = attachments.entry(id).or_insert(ImageAccess {
-                    layout: Layout::ColorAttachmentOptimal,
-                    ..empty
-                });
-                entry.access |= rendy_core::hal::image::Access::COLOR_ATTACHMENT_READ
-                    | rendy_core::hal::image::Access::COLOR_ATTACHMENT_WRITE;
-                entry.usage |= rendy_core::hal::image::Usage::COLOR_ATTACHMENT;
-                entry.stages |= rendy_core::hal::pso::PipelineStage::COLOR_ATTACHMENT_OUTPUT;
-            }
-
-            if let Some(id) = subpass.depth_stencil.and_then(Either::left) {
-                let entry = attachments.entry(id).or_insert(ImageAccess {
-                    layout: Layout::DepthStencilAttachmentOptimal,
-                    ..empty
-                });
-                entry.access |= rendy_core::hal::image::Access::DEPTH_STENCIL_ATTACHMENT_READ
-                    | rendy_core::hal::image::Access::DEPTH_STENCIL_ATTACHMENT_WRITE;
-                entry.usage |= rendy_core::hal::image::Usage::DEPTH_STENCIL_ATTACHMENT;
-                entry.stages |= rendy_core::hal::pso::PipelineStage::EARLY_FRAGMENT_TESTS
-                    | rendy_core::hal::pso::PipelineStage::LATE_FRAGMENT_TESTS;
+            // Attachment keeps layout of the first subpass that uses it.
+            for (id, access) in subpass.attachment_images() {
+                let entry = attachments.entry(id).or_insert(access);
+                entry.access |= access.access;
+                entry.usage |= access.usage;
+                entry.stages |= access.stages;
             }
 
             for group in &subpass.groups {
@@ -375,6 +551,30 @@ where
         attachments.into_iter().chain(images.into_iter()).collect()
     }
 
+    fn subpass_images(&self) -> Vec<Vec<(ImageId, ImageAccess)>> {
+        self.subpasses
+            .iter()
+            .map(|subpass| {
+                let mut images = subpass.attachment_images();
+                for group in &subpass.groups {
+                    for (id, access) in group.images() {
+                        let entry = images.entry(id).or_insert(ImageAccess {
+                            access: rendy_core::hal::image::Access::empty(),
+                            usage: rendy_core::hal::image::Usage::empty(),
+                            stages: rendy_core::hal::pso::PipelineStage::empty(),
+                            layout: Layout::Undefined,
+                        });
+                        entry.access |= access.access;
+                        entry.usage |= access.usage;
+                        entry.stages |= access.stages;
+                        entry.layout = common_layout(entry.layout, access.layout);
+                    }
+                }
+                images.into_iter().collect()
+            })
+            .collect()
+    }
+
     fn dependencies(&self) -> Vec<NodeId> {
         let mut dependencies: Vec<_> = self
             .subpasses
@@ -433,7 +633,8 @@ where
             })
             .collect();
 
-        let mut surface_usage = rendy_core::hal::image::Usage::empty();
+        let mut surface_usage = self.surface_usage;
+        let surface_image_count = self.surface_image_count;
         if surface_color_usage {
             surface_usage |= rendy_core::hal::image::Usage::COLOR_ATTACHMENT;
         }
@@ -444,7 +645,7 @@ where
         if surface.is_some() {
             log::debug!("Surface usage {:#?}", surface_usage);
         } else {
-            debug_assert_eq!(surface_usage, rendy_core::hal::image::Usage::empty());
+            debug_assert!(!surface_color_usage && !surface_depth_usage);
         }
 
         attachments.sort();
@@ -457,6 +658,95 @@ where
                 .expect("Attachment image wasn't provided")
         };
 
+        for (index, subpass) in self.subpasses.iter().enumerate() {
+            check_color_attachments(
+                index,
+                subpass.colors.iter().filter_map(|c| c.left()).map(|id| {
+                    let image = ctx.get_image(id).expect("Image does not exist");
+                    (image.kind().extent(), image.kind().num_samples())
+                }),
+            )
+            .map_err(NodeBuildError::custom)?;
+        }
+
+        // Load op, store op, clear value and initial layout for each attachment.
+        let subpasses = &self.subpasses;
+        let attachment_ops = |attachment: Attachment| match attachment {
+            Either::Left(image_id) => {
+                let node_image = find_attachment_node_image(image_id);
+                let explicit = subpasses
+                    .iter()
+                    .flat_map(|subpass| subpass.ops.iter())
+                    .rev()
+                    .find(|&&(id, _, _)| id == image_id)
+                    .map(|&(_, load, store)| (load, store));
+
+                let (load, store) = explicit.unwrap_or_else(|| {
+                    (
+                        node_image
+                            .clear
+                            .map_or(AttachmentLoad::Load, AttachmentLoad::Clear),
+                        rendy_core::hal::pass::AttachmentStoreOp::Store,
+                    )
+                });
+
+                // Content is discarded by the acquire barrier when the image
+                // is used for the first time in the frame.
+                let discarded = match &node_image.acquire {
+                    Some(acquire) => acquire.states.start.1 == Layout::Undefined,
+                    None => false,
+                };
+
+                let load = match load {
+                    AttachmentLoad::Load if discarded => {
+                        if explicit.is_some() {
+                            log::warn!(
+                                "Attachment {:?} is loaded by its first user. Content is undefined",
+                                image_id
+                            );
+                        }
+                        AttachmentLoad::DontCare
+                    }
+                    load => load,
+                };
+
+                match load {
+                    AttachmentLoad::Clear(value) => (
+                        rendy_core::hal::pass::AttachmentLoadOp::Clear,
+                        store,
+                        Some(value),
+                        Layout::Undefined,
+                    ),
+                    AttachmentLoad::Load => (
+                        rendy_core::hal::pass::AttachmentLoadOp::Load,
+                        store,
+                        None,
+                        node_image.layout,
+                    ),
+                    AttachmentLoad::DontCare => (
+                        rendy_core::hal::pass::AttachmentLoadOp::DontCare,
+                        store,
+                        None,
+                        Layout::Undefined,
+                    ),
+                }
+            }
+            Either::Right(RenderPassSurface) => match surface_clear {
+                Some(value) => (
+                    rendy_core::hal::pass::AttachmentLoadOp::Clear,
+                    rendy_core::hal::pass::AttachmentStoreOp::Store,
+                    Some(value),
+                    Layout::Undefined,
+                ),
+                None => (
+                    rendy_core::hal::pass::AttachmentLoadOp::Load,
+                    rendy_core::hal::pass::AttachmentStoreOp::Store,
+                    None,
+                    Layout::Present,
+                ),
+            },
+        };
+
         let mut framebuffer_width = u32::max_value();
         let mut framebuffer_height = u32::max_value();
         let mut framebuffer_layers = u16::max_value();
@@ -482,23 +772,8 @@ where
                             node_image.range.layers.end - node_image.range.layers.start,
                         );
                         Ok(vec![unsafe {
-                            factory
-                                .device()
-                                .create_image_view(
-                                    image.raw(),
-                                    rendy_core::hal::image::ViewKind::D2,
-                                    image.format(),
-                                    rendy_core::hal::format::Swizzle::NO,
-                                    rendy_core::hal::image::SubresourceRange {
-                                        // NOTE: Framebuffer must always be created with only one mip level. If image contains multiple levels,
-                                        // only the first one is bound as an attachment.
-                                        // TODO: Allow customizing this behaviour to choose which level to bind.
-                                        levels: 0 .. 1,
-                                        ..node_image.range.clone()
-                                    }
-                                )
-                                .map_err(NodeBuildError::View)?
-                            }])
+                            create_attachment_view(factory, image, node_image.range.clone())?
+                        }])
                     },
                     Either::Right(RenderPassSurface) => {
                         log::trace!("Surface attachment");
@@ -525,8 +800,7 @@ where
                             _ => panic!("No known present modes found"),
                         };
 
-                        let img_count_caps = caps.image_count;
-                        let image_count = 3.min(*img_count_caps.end()).max(*img_count_caps.start());
+                        let image_count = clamp_image_count(&caps, surface_image_count);
 
                         let target = factory
                             .create_target(
@@ -545,22 +819,7 @@ where
                             target.backbuffer()[0].layers(),
                         );
 
-                        let views = target.backbuffer().iter().map(|image| unsafe {
-                            factory
-                                .device()
-                                .create_image_view(
-                                    image.raw(),
-                                    rendy_core::hal::image::ViewKind::D2,
-                                    image.format(),
-                                    rendy_core::hal::format::Swizzle::NO,
-                                    rendy_core::hal::image::SubresourceRange {
-                                        aspects: image.format().surface_desc().aspects,
-                                        levels: 0 .. 1,
-                                        layers: 0 .. 1,
-                                    },
-                                )
-                                .map_err(NodeBuildError::View)
-                        }).collect::<Result<Vec<_>, NodeBuildError>>()?;
+                        let views = unsafe { create_surface_views(factory, &target)? };
 
                         node_target = Some(target);
                         Ok(views)
@@ -575,13 +834,12 @@ where
             let pass_attachments: Vec<_> = attachments
                 .iter()
                 .map(|&attachment| {
-                    let (format, clear, layout, samples) = match attachment {
+                    let (format, layout, samples) = match attachment {
                         Either::Left(image_id) => {
                             let node_image = find_attachment_node_image(image_id);
                             let image = ctx.get_image(image_id).expect("Image does not exist");
                             (
                                 image.format(),
-                                node_image.clear,
                                 node_image.layout,
                                 image.kind().num_samples(),
                             )
@@ -592,28 +850,18 @@ where
                                 .expect("Expect target created")
                                 .backbuffer()[0]
                                 .format(),
-                            surface_clear,
                             rendy_core::hal::image::Layout::Present,
                             1,
                         ),
                     };
 
+                    let (load, store, _, initial_layout) = attachment_ops(attachment);
+
                     rendy_core::hal::pass::Attachment {
                         format: Some(format),
-                        ops: rendy_core::hal::pass::AttachmentOps {
-                            load: if clear.is_some() {
-                                rendy_core::hal::pass::AttachmentLoadOp::Clear
-                            } else {
-                                rendy_core::hal::pass::AttachmentLoadOp::Load
-                            },
-                            store: rendy_core::hal::pass::AttachmentStoreOp::Store,
-                        },
+                        ops: rendy_core::hal::pass::AttachmentOps { load, store },
                         stencil_ops: rendy_core::hal::pass::AttachmentOps::DONT_CARE,
-                        layouts: if clear.is_some() {
-                            rendy_core::hal::image::Layout::Undefined..layout
-                        } else {
-                            layout..layout
-                        },
+                        layouts: initial_layout..layout,
                         samples,
                     }
                 })
@@ -649,23 +897,9 @@ where
                             )
                         })
                         .collect(),
-                    colors: subpass
-                        .colors
-                        .iter()
-                        .map(|&c| {
-                            (
-                                attachments.iter().position(|&a| a == c).unwrap(),
-                                match c {
-                                    Either::Left(image_id) => {
-                                        find_attachment_node_image(image_id).layout
-                                    }
-                                    Either::Right(RenderPassSurface) => {
-                                        rendy_core::hal::image::Layout::ColorAttachmentOptimal
-                                    }
-                                },
-                            )
-                        })
-                        .collect(),
+                    colors: color_refs(&subpass.colors, &attachments, |image_id| {
+                        find_attachment_node_image(image_id).layout
+                    }),
                     depth_stencil: subpass.depth_stencil.map(|ds| {
                         (
                             attachments.iter().position(|&a| a == ds).unwrap(),
@@ -695,17 +929,14 @@ where
                 })
                 .collect();
 
+            let dependencies = subpass_dependencies(&subpasses);
+
+            log::debug!("Subpass dependencies {:#?}", dependencies);
+
             let result = unsafe {
                 factory
                     .device()
-                    .create_render_pass(pass_attachments, subpasses, {
-                        assert_eq!(
-                            self.subpasses.len(),
-                            1,
-                            "TODO: Implement subpass dependencies to allow more than one subpass"
-                        );
-                        std::iter::empty::<rendy_core::hal::pass::SubpassDependency>()
-                    })
+                    .create_render_pass(pass_attachments, subpasses, dependencies)
             }
             .unwrap();
 
@@ -713,44 +944,45 @@ where
             result
         };
 
-        log::trace!(
-            "Create {} framebuffers",
-            views.len() - attachments.len() + 1
-        );
-
-        // Swapchain image views, if any, are last ones.
-        let mut framebuffers = (attachments.len() - 1..views.len())
-            .map(|i| unsafe {
-                log::trace!(
-                    "Create framebuffer for views {}..{} and {}",
-                    0,
-                    attachments.len() - 1,
-                    i,
-                );
-                factory
-                    .device()
-                    .create_framebuffer(
-                        &render_pass,
-                        views[..attachments.len() - 1].iter().chain(Some(&views[i])),
-                        rendy_core::hal::image::Extent {
-                            width: framebuffer_width,
-                            height: framebuffer_height,
-                            depth: framebuffer_layers as u32, // This is gfx-hal BUG as this parameter actually means framebuffer layers number,
-                        },
-                    )
-                    .map_err(NodeBuildError::OutOfMemory)
-            })
-            .collect::<Result<Vec<_>, _>>()?;
+        let mut framebuffers = unsafe {
+            create_framebuffers(
+                factory,
+                &render_pass,
+                &views,
+                attachments.len(),
+                rendy_core::hal::image::Extent {
+                    width: framebuffer_width,
+                    height: framebuffer_height,
+                    depth: framebuffer_layers as u32,
+                },
+            )?
+        };
 
         log::trace!("Collect clears for render pass");
 
+        // Clear values are indexed by attachment number,
+        // values for attachments that are not cleared are ignored.
         let clears: Vec<_> = attachments
             .iter()
-            .filter_map(|&a| match a {
-                Either::Left(image_id) => find_attachment_node_image(image_id).clear,
-                Either::Right(RenderPassSurface) => surface_clear,
+            .map(|&a| {
+                attachment_ops(a)
+                    .2
+                    .unwrap_or(rendy_core::hal::command::ClearValue {
+                        color: rendy_core::hal::command::ClearColor { float32: [0.0; 4] },
+                    })
             })
-            .map(Into::into)
+            .collect();
+
+        let framebuffer_extent = rendy_core::hal::window::Extent2D {
+            width: framebuffer_width,
+            height: framebuffer_height,
+        };
+        let render_area = pick_render_area(self.render_area, framebuffer_extent)
+            .map_err(NodeBuildError::custom)?;
+        let attachment_images = attachments
+            .iter()
+            .filter_map(|a| a.left())
+            .map(|id| (id, find_attachment_node_image(id).range.clone()))
             .collect();
 
         let mut command_pool = factory
@@ -821,6 +1053,19 @@ where
                 let subpass_colors = subpass.colors.len();
                 let subpass_depth = subpass.depth_stencil.is_some();
 
+                let secondary = if subpass.secondary {
+                    Some(SubpassSecondary {
+                        pool: factory
+                            .create_command_pool(family)
+                            .map_err(NodeBuildError::OutOfMemory)?
+                            .with_capability()
+                            .expect("Graph must specify family that supports `Graphics`"),
+                        commands: Vec::new(),
+                    })
+                } else {
+                    None
+                };
+
                 subpass
                     .groups
                     .into_iter()
@@ -861,6 +1106,7 @@ where
                             aux,
                             framebuffer_width,
                             framebuffer_height,
+                            render_area,
                             rendy_core::hal::pass::Subpass {
                                 index: index as u8,
                                 main_pass: &render_pass,
@@ -870,10 +1116,10 @@ where
                         )
                     })
                     .collect::<Result<Vec<_>, _>>()
-                    .map(|groups| SubpassNode { groups })
+                    .map(|groups| SubpassNode { groups, secondary })
+                    .map_err(NodeBuildError::Pipeline)
             })
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(NodeBuildError::Pipeline)?;
+            .collect::<Result<Vec<_>, _>>()?;
 
         let node: Box<dyn DynNode<B, T>> = match node_target {
             Some(target) => {
@@ -885,6 +1131,9 @@ where
                         framebuffer_width,
                         framebuffer_height,
                         _framebuffer_layers: framebuffer_layers,
+                        render_area,
+                        requested_area: self.render_area,
+                        attachment_images,
 
                         render_pass,
                         views,
@@ -910,6 +1159,7 @@ where
                         .collect(),
                     free_acquire: factory.create_semaphore().unwrap(),
                     target,
+                    surface_lost: false,
                 })
             }
             None => {
@@ -921,6 +1171,9 @@ where
                         framebuffer_width,
                         framebuffer_height,
                         _framebuffer_layers: framebuffer_layers,
+                        render_area,
+                        requested_area: self.render_area,
+                        attachment_images,
 
                         render_pass,
                         views,
@@ -950,6 +1203,8 @@ where
 struct SubpassNode<B: Backend, T: ?Sized> {
     /// RenderGroups of pipelines to exeucte withing subpass.
     groups: Vec<Box<dyn RenderGroup<B, T>>>,
+    /// Secondary buffers the groups are recorded into, if subpass isn't recorded inline.
+    secondary: Option<SubpassSecondary<B>>,
 }
 
 impl<B, T> std::fmt::Debug for SubpassNode<B, T>
@@ -960,10 +1215,189 @@ where
     fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         fmt.debug_struct("SubpassNode")
             .field("groups", &self.groups)
+            .field("secondary", &self.secondary)
             .finish()
     }
 }
 
+impl<B, T> SubpassNode<B, T>
+where
+    B: Backend,
+    T: ?Sized,
+{
+    /// Record render groups into secondary buffer for the primary buffer with `index`.
+    ///
+    /// # Safety
+    ///
+    /// Primary buffer with `index` must not be pending, so is the secondary buffer executed by it.
+    unsafe fn record_secondary(
+        &mut self,
+        index: usize,
+        subpass: rendy_core::hal::pass::Subpass<'_, B>,
+        framebuffer: &B::Framebuffer,
+        aux: &T,
+    ) -> Option<&Submit<B, SimultaneousUse, SecondaryLevel, RenderPassContinue>> {
+        let SubpassNode { groups, secondary } = self;
+        let SubpassSecondary { pool, commands } = secondary.as_mut()?;
+
+        if commands.len() <= index {
+            commands.resize_with(index + 1, || None);
+        }
+
+        let initial = match commands[index].take() {
+            Some(SubpassCommands { buffer, .. }) => buffer.mark_complete().reset(),
+            None => pool.allocate_buffers(1).pop().unwrap(),
+        };
+
+        let mut recording = initial.begin(MultiShot(SimultaneousUse), (subpass, framebuffer));
+        let mut encoder = recording.render_pass_encoder();
+        for group in groups.iter_mut() {
+            group.draw_inline(encoder.reborrow(), index, subpass, aux);
+        }
+
+        let (submit, buffer) = recording.finish().submit();
+        Some(
+            &commands[index]
+                .get_or_insert(SubpassCommands { submit, buffer })
+                .submit,
+        )
+    }
+}
+
+/// Command pool and secondary buffers of the subpass, one for each primary buffer.
+struct SubpassSecondary<B: Backend> {
+    pool: CommandPool<B, Graphics, IndividualReset>,
+    commands: Vec<Option<SubpassCommands<B>>>,
+}
+
+impl<B> std::fmt::Debug for SubpassSecondary<B>
+where
+    B: Backend,
+{
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt.debug_struct("SubpassSecondary")
+            .field("pool", &self.pool)
+            .field("commands", &self.commands)
+            .finish()
+    }
+}
+
+impl<B> SubpassSecondary<B>
+where
+    B: Backend,
+{
+    unsafe fn dispose(mut self, factory: &Factory<B>) {
+        for SubpassCommands { buffer, .. } in self.commands.drain(..).flatten() {
+            self.pool.free_buffers(Some(buffer.mark_complete()));
+        }
+        factory.destroy_command_pool(self.pool.with_queue_type());
+    }
+}
+
+type SubpassState = PendingState<ExecutableState<MultiShot<SimultaneousUse>, RenderPassContinue>>;
+
+struct SubpassCommands<B: Backend> {
+    submit: Submit<B, SimultaneousUse, SecondaryLevel, RenderPassContinue>,
+    buffer: CommandBuffer<B, Graphics, SubpassState, SecondaryLevel, IndividualReset>,
+}
+
+impl<B> std::fmt::Debug for SubpassCommands<B>
+where
+    B: Backend,
+{
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt.debug_struct("SubpassCommands")
+            .field("submit", &self.submit)
+            .field("buffer", &self.buffer)
+            .finish()
+    }
+}
+
+/// Encoder of the render pass in either subpass contents mode.
+enum SubpassEncoder<'a, B: Backend> {
+    Inline(RenderPassInlineEncoder<'a, B>),
+    Secondary(RenderPassSecondaryEncoder<'a, B>),
+}
+
+impl<'a, B> SubpassEncoder<'a, B>
+where
+    B: Backend,
+{
+    fn next_subpass(self, secondary: bool) -> Self {
+        match (self, secondary) {
+            (SubpassEncoder::Inline(encoder), false) => {
+                SubpassEncoder::Inline(encoder.next_subpass_inline())
+            }
+            (SubpassEncoder::Inline(encoder), true) => {
+                SubpassEncoder::Secondary(encoder.next_subpass_secondary())
+            }
+            (SubpassEncoder::Secondary(encoder), false) => {
+                SubpassEncoder::Inline(encoder.next_subpass_inline())
+            }
+            (SubpassEncoder::Secondary(encoder), true) => {
+                SubpassEncoder::Secondary(encoder.next_subpass_secondary())
+            }
+        }
+    }
+}
+
+/// Record all subpasses of the render pass into primary buffer with `index`.
+///
+/// # Safety
+///
+/// Primary buffer with `index` must not be pending.
+#[allow(clippy::too_many_arguments)]
+unsafe fn record_subpasses<B, C, T>(
+    encoder: &mut Encoder<'_, B, C, PrimaryLevel>,
+    subpasses: &mut [SubpassNode<B, T>],
+    index: usize,
+    render_pass: &B::RenderPass,
+    framebuffer: &B::Framebuffer,
+    area: rendy_core::hal::pso::Rect,
+    clears: &[rendy_core::hal::command::ClearValue],
+    aux: &T,
+) where
+    B: Backend,
+    C: Supports<Graphics>,
+    T: ?Sized,
+{
+    let mut pass_encoder = match subpasses.first() {
+        Some(first) if first.secondary.is_some() => SubpassEncoder::Secondary(
+            encoder.begin_render_pass_secondary(render_pass, framebuffer, area, clears),
+        ),
+        _ => SubpassEncoder::Inline(encoder.begin_render_pass_inline(
+            render_pass,
+            framebuffer,
+            area,
+            clears,
+        )),
+    };
+
+    for (subpass_index, subpass) in subpasses.iter_mut().enumerate() {
+        if subpass_index > 0 {
+            pass_encoder = pass_encoder.next_subpass(subpass.secondary.is_some());
+        }
+
+        let hal_subpass = rendy_core::hal::pass::Subpass {
+            index: subpass_index as u8,
+            main_pass: render_pass,
+        };
+
+        match &mut pass_encoder {
+            SubpassEncoder::Inline(inline) => subpass
+                .groups
+                .iter_mut()
+                .for_each(|group| group.draw_inline(inline.reborrow(), index, hal_subpass, aux)),
+            SubpassEncoder::Secondary(secondary) => {
+                let submit = subpass
+                    .record_secondary(index, hal_subpass, framebuffer, aux)
+                    .expect("Subpass is recorded into secondary buffers");
+                secondary.execute_commands(std::iter::once(submit));
+            }
+        }
+    }
+}
+
 struct BarriersCommands<B: Backend> {
     submit: Submit<B, SimultaneousUse, SecondaryLevel>,
     buffer: CommandBuffer<
@@ -993,8 +1427,14 @@ struct RenderPassNodeCommon<B: Backend, T: ?Sized> {
     framebuffer_width: u32,
     framebuffer_height: u32,
     _framebuffer_layers: u16,
+    render_area: rendy_core::hal::pso::Rect,
+    /// Render area set by the builder, whole framebuffer if `None`.
+    requested_area: Option<rendy_core::hal::pso::Rect>,
 
     render_pass: B::RenderPass,
+    /// Attachment images and their subresources, in order of their views.
+    attachment_images: Vec<(ImageId, rendy_core::hal::image::SubresourceRange)>,
+    /// Views of attachment images followed by views of swapchain images, if any.
     views: Vec<B::ImageView>,
     clears: Vec<rendy_core::hal::command::ClearValue>,
 
@@ -1018,6 +1458,7 @@ where
             .field("framebuffer_width", &self.framebuffer_width)
             .field("framebuffer_height", &self.framebuffer_height)
             .field("_framebuffer_layers", &self._framebuffer_layers)
+            .field("render_area", &self.render_area)
             .field("render_pass", &self.render_pass)
             .field("views", &self.views)
             .field("clears", &self.clears)
@@ -1041,18 +1482,12 @@ where
             for group in subpass.groups {
                 group.dispose(factory, aux)
             }
+            if let Some(secondary) = subpass.secondary {
+                secondary.dispose(factory);
+            }
         }
         let pool = &mut self.command_pool;
-        self.command_cirque.dispose(|buffer| {
-            buffer.either_with(
-                &mut *pool,
-                |pool, executable| pool.free_buffers(Some(executable)),
-                |pool, pending| {
-                    let executable = pending.mark_complete();
-                    pool.free_buffers(Some(executable))
-                },
-            );
-        });
+        free_cirque(self.command_cirque, pool);
         if let Some(BarriersCommands { submit, buffer }) = self.acquire.take() {
             drop(submit);
             let executable = buffer.mark_complete();
@@ -1070,6 +1505,205 @@ where
         }
         factory.device().destroy_render_pass(self.render_pass);
     }
+
+    /// Check if all render groups can follow change of the render area.
+    fn resizable(&self) -> bool {
+        self.subpasses
+            .iter()
+            .flat_map(|subpass| subpass.groups.iter())
+            .all(|group| group.resizable())
+    }
+
+    /// Recreate views of resized attachment images followed by views of `target` images
+    /// and update framebuffer extent and render area.
+    /// Returns extent for new framebuffers.
+    ///
+    /// Node is left unchanged on error.
+    ///
+    /// # Safety
+    ///
+    /// Must be called after waiting for device idle.
+    unsafe fn resize(
+        &mut self,
+        ctx: &GraphContext<B>,
+        factory: &Factory<B>,
+        target: Option<&Target<B>>,
+    ) -> Result<rendy_core::hal::image::Extent, NodeBuildError> {
+        let mut width = u32::MAX;
+        let mut height = u32::MAX;
+        let mut layers = u16::MAX;
+        let mut views = Vec::with_capacity(self.views.len());
+
+        let result = (|| {
+            for (id, range) in &self.attachment_images {
+                let image = ctx.get_image(*id).expect("Image does not exist");
+                let extent = image.kind().extent();
+                width = min(width, extent.width);
+                height = min(height, extent.height);
+                layers = min(layers, range.layers.end - range.layers.start);
+                views.push(create_attachment_view(factory, image, range.clone())?);
+            }
+
+            if let Some(target) = target {
+                width = min(width, target.extent().width);
+                height = min(height, target.extent().height);
+                layers = min(layers, target.backbuffer()[0].layers());
+                views.extend(create_surface_views(factory, target)?);
+            }
+
+            let extent = rendy_core::hal::window::Extent2D { width, height };
+            let render_area =
+                pick_render_area(self.requested_area, extent).map_err(NodeBuildError::custom)?;
+            for group in self
+                .subpasses
+                .iter_mut()
+                .flat_map(|subpass| subpass.groups.iter_mut())
+            {
+                group
+                    .resize(factory, render_area)
+                    .map_err(NodeBuildError::Pipeline)?;
+            }
+            Ok(render_area)
+        })();
+
+        let render_area = match result {
+            Ok(render_area) => render_area,
+            Err(err) => {
+                for view in views {
+                    factory.device().destroy_image_view(view);
+                }
+                return Err(err);
+            }
+        };
+
+        for view in std::mem::replace(&mut self.views, views) {
+            factory.device().destroy_image_view(view);
+        }
+        self.framebuffer_width = width;
+        self.framebuffer_height = height;
+        self._framebuffer_layers = layers;
+        self.render_area = render_area;
+
+        // Recorded commands refer to old framebuffers.
+        free_cirque(
+            std::mem::replace(&mut self.command_cirque, CommandCirque::new()),
+            &mut self.command_pool,
+        );
+
+        Ok(rendy_core::hal::image::Extent {
+            width,
+            height,
+            depth: layers as u32,
+        })
+    }
+}
+
+/// Free all command buffers of the cirque.
+///
+/// # Safety
+///
+/// Command buffers must not be in use.
+unsafe fn free_cirque<B: Backend>(
+    cirque: CommandCirque<B, Graphics>,
+    pool: &mut CommandPool<B, Graphics, IndividualReset>,
+) {
+    cirque.dispose(|buffer| {
+        buffer.either_with(
+            &mut *pool,
+            |pool, executable| pool.free_buffers(Some(executable)),
+            |pool, pending| {
+                let executable = pending.mark_complete();
+                pool.free_buffers(Some(executable))
+            },
+        );
+    });
+}
+
+/// Create view of the attachment image.
+unsafe fn create_attachment_view<B: Backend>(
+    factory: &Factory<B>,
+    image: &Image<B>,
+    range: rendy_core::hal::image::SubresourceRange,
+) -> Result<B::ImageView, NodeBuildError> {
+    factory
+        .device()
+        .create_image_view(
+            image.raw(),
+            rendy_core::hal::image::ViewKind::D2,
+            image.format(),
+            rendy_core::hal::format::Swizzle::NO,
+            rendy_core::hal::image::SubresourceRange {
+                // NOTE: Framebuffer must always be created with only one mip level. If image contains multiple levels,
+                // only the first one is bound as an attachment.
+                // TODO: Allow customizing this behaviour to choose which level to bind.
+                levels: 0..1,
+                ..range
+            },
+        )
+        .map_err(NodeBuildError::View)
+}
+
+/// Create views of all swapchain images of the target.
+unsafe fn create_surface_views<B: Backend>(
+    factory: &Factory<B>,
+    target: &Target<B>,
+) -> Result<Vec<B::ImageView>, NodeBuildError> {
+    let mut views = Vec::with_capacity(target.backbuffer().len());
+    for image in target.backbuffer() {
+        match factory.device().create_image_view(
+            image.raw(),
+            rendy_core::hal::image::ViewKind::D2,
+            image.format(),
+            rendy_core::hal::format::Swizzle::NO,
+            rendy_core::hal::image::SubresourceRange {
+                aspects: image.format().surface_desc().aspects,
+                levels: 0..1,
+                layers: 0..1,
+            },
+        ) {
+            Ok(view) => views.push(view),
+            Err(err) => {
+                for view in views {
+                    factory.device().destroy_image_view(view);
+                }
+                return Err(NodeBuildError::View(err));
+            }
+        }
+    }
+    Ok(views)
+}
+
+/// Create framebuffers for `views` of `attachment_count` attachments.
+/// Swapchain image views, if any, are last ones
+/// and framebuffer is created for each of them.
+unsafe fn create_framebuffers<B: Backend>(
+    factory: &Factory<B>,
+    render_pass: &B::RenderPass,
+    views: &[B::ImageView],
+    attachment_count: usize,
+    extent: rendy_core::hal::image::Extent,
+) -> Result<Vec<B::Framebuffer>, NodeBuildError> {
+    log::trace!("Create {} framebuffers", views.len() - attachment_count + 1);
+
+    (attachment_count - 1..views.len())
+        .map(|i| {
+            log::trace!(
+                "Create framebuffer for views {}..{} and {}",
+                0,
+                attachment_count - 1,
+                i,
+            );
+            // `extent.depth` is number of framebuffer layers.
+            factory
+                .device()
+                .create_framebuffer(
+                    render_pass,
+                    views[..attachment_count - 1].iter().chain(Some(&views[i])),
+                    extent,
+                )
+                .map_err(NodeBuildError::OutOfMemory)
+        })
+        .collect()
 }
 
 #[derive(Debug)]
@@ -1085,6 +1719,7 @@ struct RenderPassNodeWithSurface<B: Backend, T: ?Sized> {
     per_image: Vec<PerImage<B>>,
     free_acquire: B::Semaphore,
     target: Target<B>,
+    surface_lost: bool,
 }
 
 impl<B, T> std::fmt::Debug for RenderPassNodeWithSurface<B, T>
@@ -1098,6 +1733,7 @@ where
             .field("per_image", &self.per_image)
             .field("free_acquire", &self.free_acquire)
             .field("target", &self.target)
+            .field("surface_lost", &self.surface_lost)
             .finish()
     }
 }
@@ -1123,8 +1759,7 @@ where
                 RenderPassNodeCommon {
                     subpasses,
 
-                    framebuffer_width,
-                    framebuffer_height,
+                    render_area,
 
                     render_pass,
                     clears,
@@ -1139,6 +1774,7 @@ where
             target,
             free_acquire,
             per_image,
+            surface_lost,
         } = self;
 
         let next = match target.next_image(&free_acquire) {
@@ -1149,6 +1785,9 @@ where
             }
             Err(err) => {
                 log::debug!("Swapchain acquisition error: {:#?}", err);
+                if let rendy_core::hal::window::AcquireError::SurfaceLost(_) = err {
+                    *surface_lost = true;
+                }
                 None
             }
         };
@@ -1200,38 +1839,16 @@ where
                 if let Some(next) = &next {
                     let for_image = &mut per_image[next[0] as usize];
 
-                    let area = rendy_core::hal::pso::Rect {
-                        x: 0,
-                        y: 0,
-                        w: *framebuffer_width as _,
-                        h: *framebuffer_height as _,
-                    };
-
-                    let mut pass_encoder = encoder.begin_render_pass_inline(
+                    record_subpasses(
+                        &mut encoder,
+                        subpasses,
+                        index,
                         &render_pass,
                         &for_image.framebuffer,
-                        area,
+                        *render_area,
                         &clears,
+                        aux,
                     );
-
-                    subpasses
-                        .iter_mut()
-                        .enumerate()
-                        .for_each(|(subpass_index, subpass)| {
-                            subpass.groups.iter_mut().for_each(|group| {
-                                group.draw_inline(
-                                    pass_encoder.reborrow(),
-                                    index,
-                                    rendy_core::hal::pass::Subpass {
-                                        index: subpass_index as u8,
-                                        main_pass: &render_pass,
-                                    },
-                                    aux,
-                                )
-                            })
-                        });
-
-                    drop(pass_encoder);
                 }
 
                 if let Some(barriers) = &release {
@@ -1268,10 +1885,83 @@ where
             let for_image = &mut per_image[next[0] as usize];
             if let Err(err) = next.present(queue.raw(), Some(&for_image.release)) {
                 log::debug!("Swapchain presentation error: {:#?}", err);
+                if let rendy_core::hal::window::PresentError::SurfaceLost(_) = err {
+                    *surface_lost = true;
+                }
             }
         }
     }
 
+    fn surface_extent(&self, factory: &Factory<B>) -> Option<rendy_core::hal::window::Extent2D> {
+        unsafe { self.target.surface().extent(factory.physical()) }
+    }
+
+    fn surface_lost(&self) -> bool {
+        self.surface_lost
+    }
+
+    fn resizable(&self) -> bool {
+        self.common.resizable()
+    }
+
+    unsafe fn resize(
+        &mut self,
+        ctx: &GraphContext<B>,
+        factory: &Factory<B>,
+        _aux: &T,
+    ) -> Result<(), NodeBuildError> {
+        // Swapchain images follow surface that attachments were resized for.
+        let suggested = self
+            .target
+            .surface()
+            .extent(factory.physical())
+            .unwrap_or_else(|| self.target.extent());
+        self.target
+            .recreate(factory.physical(), factory.device(), suggested)
+            .map_err(NodeBuildError::Swapchain)?;
+
+        let extent = self.common.resize(ctx, factory, Some(&self.target))?;
+        let framebuffers = create_framebuffers(
+            factory,
+            &self.common.render_pass,
+            &self.common.views,
+            self.common.attachment_images.len() + 1,
+            extent,
+        )?;
+
+        // Number of swapchain images may change.
+        let mut old = std::mem::take(&mut self.per_image).into_iter();
+        for framebuffer in framebuffers {
+            let per_image = match old.next() {
+                Some(per_image) => {
+                    factory.device().destroy_framebuffer(per_image.framebuffer);
+                    PerImage {
+                        framebuffer,
+                        index: 0,
+                        ..per_image
+                    }
+                }
+                None => PerImage {
+                    framebuffer,
+                    acquire: factory
+                        .create_semaphore()
+                        .map_err(NodeBuildError::OutOfMemory)?,
+                    release: factory
+                        .create_semaphore()
+                        .map_err(NodeBuildError::OutOfMemory)?,
+                    index: 0,
+                },
+            };
+            self.per_image.push(per_image);
+        }
+        for per_image in old {
+            factory.device().destroy_framebuffer(per_image.framebuffer);
+            factory.destroy_semaphore(per_image.acquire);
+            factory.destroy_semaphore(per_image.release);
+        }
+        Ok(())
+    }
+
     unsafe fn dispose(self: Box<Self>, factory: &mut Factory<B>, aux: &T) {
         for per_image in self.per_image {
             factory.device().destroy_framebuffer(per_image.framebuffer);
@@ -1322,8 +2012,7 @@ where
                 RenderPassNodeCommon {
                     subpasses,
 
-                    framebuffer_width,
-                    framebuffer_height,
+                    render_area,
 
                     render_pass,
                     clears,
@@ -1377,34 +2066,16 @@ where
                     encoder.execute_commands(std::iter::once(&barriers.submit));
                 }
 
-                let area = rendy_core::hal::pso::Rect {
-                    x: 0,
-                    y: 0,
-                    w: *framebuffer_width as _,
-                    h: *framebuffer_height as _,
-                };
-
-                let mut pass_encoder =
-                    encoder.begin_render_pass_inline(&render_pass, framebuffer, area, &clears);
-
-                subpasses
-                    .iter_mut()
-                    .enumerate()
-                    .for_each(|(subpass_index, subpass)| {
-                        subpass.groups.iter_mut().for_each(|group| {
-                            group.draw_inline(
-                                pass_encoder.reborrow(),
-                                index,
-                                rendy_core::hal::pass::Subpass {
-                                    index: subpass_index as u8,
-                                    main_pass: &render_pass,
-                                },
-                                aux,
-                            )
-                        })
-                    });
-
-                drop(pass_encoder);
+                record_subpasses(
+                    &mut encoder,
+                    subpasses,
+                    index,
+                    &render_pass,
+                    framebuffer,
+                    *render_area,
+                    &clears,
+                    aux,
+                );
 
                 if let Some(barriers) = &release {
                     encoder.execute_commands(std::iter::once(&barriers.submit));
@@ -1424,12 +2095,249 @@ where
         );
     }
 
+    fn resizable(&self) -> bool {
+        self.common.resizable()
+    }
+
+    unsafe fn resize(
+        &mut self,
+        ctx: &GraphContext<B>,
+        factory: &Factory<B>,
+        _aux: &T,
+    ) -> Result<(), NodeBuildError> {
+        let extent = self.common.resize(ctx, factory, None)?;
+        let framebuffer = create_framebuffers(
+            factory,
+            &self.common.render_pass,
+            &self.common.views,
+            self.common.attachment_images.len(),
+            extent,
+        )?
+        .remove(0);
+        factory
+            .device()
+            .destroy_framebuffer(std::mem::replace(&mut self.framebuffer, framebuffer));
+        Ok(())
+    }
+
     unsafe fn dispose(self: Box<Self>, factory: &mut Factory<B>, aux: &T) {
         self.common.dispose(factory, aux);
         factory.device().destroy_framebuffer(self.framebuffer);
     }
 }
 
+/// Color attachments of the subpass don't match each other.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorAttachmentMismatch {
+    /// Color attachment images have different extents.
+    Extent {
+        /// Index of the subpass.
+        subpass: usize,
+        /// Extent of the first color attachment.
+        expected: rendy_core::hal::image::Extent,
+        /// Extent of the mismatched color attachment.
+        actual: rendy_core::hal::image::Extent,
+    },
+    /// Color attachment images have different number of samples.
+    Samples {
+        /// Index of the subpass.
+        subpass: usize,
+        /// Number of samples of the first color attachment.
+        expected: rendy_core::hal::image::NumSamples,
+        /// Number of samples of the mismatched color attachment.
+        actual: rendy_core::hal::image::NumSamples,
+    },
+}
+
+impl std::fmt::Display for ColorAttachmentMismatch {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColorAttachmentMismatch::Extent {
+                subpass,
+                expected,
+                actual,
+            } => write!(
+                fmt,
+                "Color attachments of subpass {} have different extents: {:?} and {:?}",
+                subpass, expected, actual
+            ),
+            ColorAttachmentMismatch::Samples {
+                subpass,
+                expected,
+                actual,
+            } => write!(
+                fmt,
+                "Color attachments of subpass {} have different number of samples: {} and {}",
+                subpass, expected, actual
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ColorAttachmentMismatch {}
+
+/// Render area of the render pass doesn't fit into its attachments.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RenderAreaOutOfBounds {
+    /// Requested render area.
+    pub area: rendy_core::hal::pso::Rect,
+    /// Extent of the framebuffer.
+    pub extent: rendy_core::hal::window::Extent2D,
+}
+
+impl std::fmt::Display for RenderAreaOutOfBounds {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            fmt,
+            "Render area {:?} doesn't fit into attachments of extent {:?}",
+            self.area, self.extent
+        )
+    }
+}
+
+impl std::error::Error for RenderAreaOutOfBounds {}
+
+/// Render area for framebuffer of `extent`.
+/// Returns `requested` area if it fits into the framebuffer or the whole framebuffer if `None`.
+fn pick_render_area(
+    requested: Option<rendy_core::hal::pso::Rect>,
+    extent: rendy_core::hal::window::Extent2D,
+) -> Result<rendy_core::hal::pso::Rect, RenderAreaOutOfBounds> {
+    match requested {
+        Some(area) => check_render_area(area, extent).map(|()| area),
+        None => Ok(rendy_core::full_rect(extent)),
+    }
+}
+
+/// Check that render area is not empty and fits into framebuffer of `extent`.
+fn check_render_area(
+    area: rendy_core::hal::pso::Rect,
+    extent: rendy_core::hal::window::Extent2D,
+) -> Result<(), RenderAreaOutOfBounds> {
+    let fits = area.x >= 0
+        && area.y >= 0
+        && area.w > 0
+        && area.h > 0
+        && (area.x as u32 + area.w as u32) <= extent.width
+        && (area.y as u32 + area.h as u32) <= extent.height;
+
+    if fits {
+        Ok(())
+    } else {
+        Err(RenderAreaOutOfBounds { area, extent })
+    }
+}
+
+/// Check that all color attachments of the subpass have same extent and number of samples.
+fn check_color_attachments(
+    subpass: usize,
+    colors: impl IntoIterator<
+        Item = (
+            rendy_core::hal::image::Extent,
+            rendy_core::hal::image::NumSamples,
+        ),
+    >,
+) -> Result<(), ColorAttachmentMismatch> {
+    let mut colors = colors.into_iter();
+    let (extent, samples) = match colors.next() {
+        Some(first) => first,
+        None => return Ok(()),
+    };
+
+    for (actual_extent, actual_samples) in colors {
+        if actual_extent != extent {
+            return Err(ColorAttachmentMismatch::Extent {
+                subpass,
+                expected: extent,
+                actual: actual_extent,
+            });
+        }
+        if actual_samples != samples {
+            return Err(ColorAttachmentMismatch::Samples {
+                subpass,
+                expected: samples,
+                actual: actual_samples,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// References to color attachments of the subpass.
+/// Reference at index `N` is bound to fragment shader output at location `N`.
+fn color_refs(
+    colors: &[Attachment],
+    attachments: &[Attachment],
+    image_layout: impl Fn(ImageId) -> Layout,
+) -> Vec<rendy_core::hal::pass::AttachmentRef> {
+    colors
+        .iter()
+        .map(|&c| {
+            (
+                attachments.iter().position(|&a| a == c).unwrap(),
+                match c {
+                    Either::Left(image_id) => image_layout(image_id),
+                    Either::Right(RenderPassSurface) => Layout::ColorAttachmentOptimal,
+                },
+            )
+        })
+        .collect()
+}
+
+/// Accesses of images used as input, color and depth-stencil attachments by one subpass.
+/// Image used as several attachments gets layout of the first kind in that order.
+pub(crate) fn subpass_attachment_images(
+    inputs: impl IntoIterator<Item = ImageId>,
+    colors: impl IntoIterator<Item = ImageId>,
+    depth_stencil: Option<ImageId>,
+) -> HashMap<ImageId, ImageAccess> {
+    use rendy_core::hal::{
+        image::{Access, Usage},
+        pso::PipelineStage,
+    };
+
+    let empty = ImageAccess {
+        access: Access::empty(),
+        usage: Usage::empty(),
+        stages: PipelineStage::empty(),
+        layout: Layout::Undefined,
+    };
+    let mut attachments = HashMap::new();
+
+    for id in inputs {
+        let entry = attachments.entry(id).or_insert(ImageAccess {
+            layout: Layout::ShaderReadOnlyOptimal,
+            ..empty
+        });
+        entry.access |= Access::INPUT_ATTACHMENT_READ;
+        entry.usage |= Usage::INPUT_ATTACHMENT;
+        entry.stages |= PipelineStage::FRAGMENT_SHADER;
+    }
+
+    for id in colors {
+        let entry = attachments.entry(id).or_insert(ImageAccess {
+            layout: Layout::ColorAttachmentOptimal,
+            ..empty
+        });
+        entry.access |= Access::COLOR_ATTACHMENT_READ | Access::COLOR_ATTACHMENT_WRITE;
+        entry.usage |= Usage::COLOR_ATTACHMENT;
+        entry.stages |= PipelineStage::COLOR_ATTACHMENT_OUTPUT;
+    }
+
+    if let Some(id) = depth_stencil {
+        let entry = attachments.entry(id).or_insert(ImageAccess {
+            layout: Layout::DepthStencilAttachmentOptimal,
+            ..empty
+        });
+        entry.access |=
+            Access::DEPTH_STENCIL_ATTACHMENT_READ | Access::DEPTH_STENCIL_ATTACHMENT_WRITE;
+        entry.usage |= Usage::DEPTH_STENCIL_ATTACHMENT;
+        entry.stages |= PipelineStage::EARLY_FRAGMENT_TESTS | PipelineStage::LATE_FRAGMENT_TESTS;
+    }
+
+    attachments
+}
+
 fn common_layout(acc: Layout, layout: Layout) -> Layout {
     match (acc, layout) {
         (Layout::Undefined, layout) => layout,
@@ -1443,3 +2351,263 @@ fn common_layout(acc: Layout, layout: Layout) -> Layout {
         (_, _) => Layout::General,
     }
 }
+
+/// Stages and accesses of attachment within subpass.
+/// Returns `None` if subpass doesn't use the attachment.
+fn subpass_attachment_access(
+    subpass: &rendy_core::hal::pass::SubpassDesc<'_>,
+    attachment: usize,
+) -> Option<(
+    rendy_core::hal::pso::PipelineStage,
+    rendy_core::hal::image::Access,
+)> {
+    use rendy_core::hal::{image::Access, pso::PipelineStage};
+
+    let mut result = None;
+    let mut add = |stages, access| {
+        let (acc_stages, acc_access) =
+            result.get_or_insert((PipelineStage::empty(), Access::empty()));
+        *acc_stages |= stages;
+        *acc_access |= access;
+    };
+
+    if subpass.inputs.iter().any(|&(a, _)| a == attachment) {
+        add(
+            PipelineStage::FRAGMENT_SHADER,
+            Access::INPUT_ATTACHMENT_READ,
+        );
+    }
+    if subpass.colors.iter().any(|&(a, _)| a == attachment) {
+        add(
+            PipelineStage::COLOR_ATTACHMENT_OUTPUT,
+            Access::COLOR_ATTACHMENT_READ | Access::COLOR_ATTACHMENT_WRITE,
+        );
+    }
+    if subpass.depth_stencil.map(|&(a, _)| a) == Some(attachment) {
+        add(
+            PipelineStage::EARLY_FRAGMENT_TESTS | PipelineStage::LATE_FRAGMENT_TESTS,
+            Access::DEPTH_STENCIL_ATTACHMENT_READ | Access::DEPTH_STENCIL_ATTACHMENT_WRITE,
+        );
+    }
+
+    result
+}
+
+/// Collect dependencies between subpasses that use the same attachment
+/// where at least one of them writes to it.
+/// Subpasses access attachments only at the current fragment position
+/// so all dependencies are `BY_REGION`.
+fn subpass_dependencies(
+    subpasses: &[rendy_core::hal::pass::SubpassDesc<'_>],
+) -> Vec<rendy_core::hal::pass::SubpassDependency> {
+    use rendy_core::hal::{image::Access, pass::SubpassDependency, pso::PipelineStage};
+
+    let writes = Access::COLOR_ATTACHMENT_WRITE | Access::DEPTH_STENCIL_ATTACHMENT_WRITE;
+    let attachment_count = subpasses
+        .iter()
+        .flat_map(|subpass| {
+            subpass
+                .inputs
+                .iter()
+                .chain(subpass.colors.iter())
+                .chain(subpass.depth_stencil)
+                .map(|&(a, _)| a + 1)
+        })
+        .max()
+        .unwrap_or(0);
+
+    let mut dependencies = Vec::new();
+    for dst in 1..subpasses.len() {
+        for src in 0..dst {
+            let mut stages = PipelineStage::empty()..PipelineStage::empty();
+            let mut accesses = Access::empty()..Access::empty();
+            for attachment in 0..attachment_count {
+                let src_access = subpass_attachment_access(&subpasses[src], attachment);
+                let dst_access = subpass_attachment_access(&subpasses[dst], attachment);
+                if let (Some((src_stages, src_access)), Some((dst_stages, dst_access))) =
+                    (src_access, dst_access)
+                {
+                    if (src_access | dst_access).intersects(writes) {
+                        stages.start |= src_stages;
+                        stages.end |= dst_stages;
+                        accesses.start |= src_access & writes;
+                        accesses.end |= dst_access;
+                    }
+                }
+            }
+
+            if !stages.start.is_empty() {
+                dependencies.push(SubpassDependency {
+                    passes: Some(src as u8)..Some(dst as u8),
+                    stages,
+                    accesses,
+                    flags: rendy_core::hal::memory::Dependencies::BY_REGION,
+                });
+            }
+        }
+    }
+    dependencies
+}
+
+#[test]
+fn test_input_attachment_dependency() {
+    use rendy_core::hal::{
+        image::Access,
+        memory::Dependencies,
+        pass::{AttachmentRef, SubpassDesc},
+        pso::PipelineStage,
+    };
+
+    let colors: [AttachmentRef; 1] = [(0, Layout::General)];
+    let inputs: [AttachmentRef; 1] = [(0, Layout::General)];
+    let output: [AttachmentRef; 1] = [(1, Layout::ColorAttachmentOptimal)];
+
+    let subpasses = [
+        SubpassDesc {
+            colors: &colors,
+            depth_stencil: None,
+            inputs: &[],
+            resolves: &[],
+            preserves: &[],
+        },
+        SubpassDesc {
+            colors: &output,
+            depth_stencil: None,
+            inputs: &inputs,
+            resolves: &[],
+            preserves: &[],
+        },
+    ];
+
+    let dependencies = subpass_dependencies(&subpasses);
+    assert_eq!(dependencies.len(), 1);
+    let dependency = &dependencies[0];
+    assert_eq!(dependency.passes, Some(0)..Some(1));
+    assert_eq!(
+        dependency.stages,
+        PipelineStage::COLOR_ATTACHMENT_OUTPUT..PipelineStage::FRAGMENT_SHADER
+    );
+    assert_eq!(
+        dependency.accesses,
+        Access::COLOR_ATTACHMENT_WRITE..Access::INPUT_ATTACHMENT_READ
+    );
+    assert_eq!(dependency.flags, Dependencies::BY_REGION);
+}
+
+#[test]
+fn test_multiple_render_targets() {
+    use rendy_core::hal::image::Extent;
+
+    let albedo = Either::Left(ImageId(3));
+    let normal = Either::Left(ImageId(1));
+    let material = Either::Left(ImageId(2));
+    let depth = Either::Left(ImageId(0));
+
+    let mut attachments = vec![material, depth, normal, albedo];
+    attachments.sort();
+
+    let colors = color_refs(&[albedo, normal, material], &attachments, |_| {
+        Layout::ColorAttachmentOptimal
+    });
+    assert_eq!(
+        colors,
+        vec![
+            (3, Layout::ColorAttachmentOptimal),
+            (1, Layout::ColorAttachmentOptimal),
+            (2, Layout::ColorAttachmentOptimal),
+        ]
+    );
+
+    let extent = Extent {
+        width: 800,
+        height: 600,
+        depth: 1,
+    };
+    assert_eq!(check_color_attachments(0, vec![(extent, 1); 3]), Ok(()));
+    assert_eq!(
+        check_color_attachments(0, vec![(extent, 1), (extent, 4)]),
+        Err(ColorAttachmentMismatch::Samples {
+            subpass: 0,
+            expected: 1,
+            actual: 4,
+        })
+    );
+    let small = Extent {
+        width: 400,
+        ..extent
+    };
+    assert_eq!(
+        check_color_attachments(1, vec![(extent, 1), (small, 1)]),
+        Err(ColorAttachmentMismatch::Extent {
+            subpass: 1,
+            expected: extent,
+            actual: small,
+        })
+    );
+}
+
+#[test]
+fn test_check_render_area() {
+    use rendy_core::hal::{pso::Rect, window::Extent2D};
+
+    let extent = Extent2D {
+        width: 800,
+        height: 600,
+    };
+    let quadrant = Rect {
+        x: 400,
+        y: 300,
+        w: 400,
+        h: 300,
+    };
+    assert_eq!(check_render_area(quadrant, extent), Ok(()));
+    assert_eq!(
+        check_render_area(rendy_core::full_rect(extent), extent),
+        Ok(())
+    );
+
+    for area in [
+        Rect { w: 401, ..quadrant },
+        Rect { h: 301, ..quadrant },
+        Rect { x: -1, ..quadrant },
+        Rect { w: 0, ..quadrant },
+    ] {
+        assert_eq!(
+            check_render_area(area, extent),
+            Err(RenderAreaOutOfBounds { area, extent })
+        );
+    }
+}
+
+#[test]
+fn test_pick_render_area_on_resize() {
+    use rendy_core::hal::{pso::Rect, window::Extent2D};
+
+    let extent = Extent2D {
+        width: 800,
+        height: 600,
+    };
+    let smaller = Extent2D {
+        width: 640,
+        height: 480,
+    };
+    assert_eq!(
+        pick_render_area(None, smaller),
+        Ok(rendy_core::full_rect(smaller))
+    );
+
+    let area = Rect {
+        x: 0,
+        y: 0,
+        w: 800,
+        h: 300,
+    };
+    assert_eq!(pick_render_area(Some(area), extent), Ok(area));
+    assert_eq!(
+        pick_render_area(Some(area), smaller),
+        Err(RenderAreaOutOfBounds {
+            area,
+            extent: smaller,
+        })
+    );
+}
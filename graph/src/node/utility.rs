@@ -0,0 +1,499 @@
+//! Defines utility nodes that clear and blit images.
+//!
+//! These nodes record their commands once when built
+//! and resubmit them every frame.
+
+use crate::{
+    command::{
+        CommandBuffer, CommandPool, Encoder, ExecutableState, Families, Family, FamilyId, Graphics,
+        MultiShot, PendingState, PrimaryLevel, SimultaneousUse, Submit,
+    },
+    factory::Factory,
+    frame::Frames,
+    graph::GraphContext,
+    node::{
+        gfx_acquire_barriers, gfx_release_barriers, BufferAccess, DynNode, ImageAccess, Node,
+        NodeBuffer, NodeBuildError, NodeBuilder, NodeImage, NodeSubmittable,
+    },
+    BufferId, ImageId, NodeId,
+};
+
+/// Commands recorded once at build time.
+#[derive(Debug)]
+struct Recorded<B: rendy_core::hal::Backend> {
+    pool: CommandPool<B, Graphics>,
+    buffer: CommandBuffer<B, Graphics, PendingState<ExecutableState<MultiShot<SimultaneousUse>>>>,
+    submit: Submit<B, SimultaneousUse>,
+}
+
+impl<B> Recorded<B>
+where
+    B: rendy_core::hal::Backend,
+{
+    /// Record commands surrounded by acquire and release barriers of the `images`.
+    fn record(
+        ctx: &GraphContext<B>,
+        factory: &Factory<B>,
+        family: &Family<B>,
+        images: &[NodeImage],
+        record: impl FnOnce(&mut Encoder<'_, B, Graphics, PrimaryLevel>),
+    ) -> Result<Self, NodeBuildError> {
+        let mut pool = factory
+            .create_command_pool(family)
+            .map_err(NodeBuildError::OutOfMemory)?
+            .with_capability::<Graphics>()
+            .expect("Graph builder must provide family with Graphics capability");
+
+        let initial = pool.allocate_buffers(1).remove(0);
+        let mut recording = initial.begin(MultiShot(SimultaneousUse), ());
+        let mut encoder = recording.encoder();
+
+        let (stages, barriers) = gfx_acquire_barriers(ctx, None, images);
+        if !barriers.is_empty() {
+            unsafe {
+                encoder.pipeline_barrier(
+                    stages,
+                    rendy_core::hal::memory::Dependencies::empty(),
+                    barriers,
+                );
+            }
+        }
+
+        record(&mut encoder);
+
+        let (stages, barriers) = gfx_release_barriers(ctx, None, images);
+        if !barriers.is_empty() {
+            unsafe {
+                encoder.pipeline_barrier(
+                    stages,
+                    rendy_core::hal::memory::Dependencies::empty(),
+                    barriers,
+                );
+            }
+        }
+
+        let (submit, buffer) = recording.finish().submit();
+        Ok(Recorded {
+            pool,
+            buffer,
+            submit,
+        })
+    }
+
+    unsafe fn dispose(self, factory: &mut Factory<B>) {
+        let Recorded {
+            mut pool, buffer, ..
+        } = self;
+        pool.free_buffers(Some(buffer.mark_complete()));
+        factory.destroy_command_pool(pool);
+    }
+}
+
+/// Node that clears an image to a value.
+///
+/// Clears whole image, all its layers and levels.
+/// Color images are cleared with the color value of `ClearValue`,
+/// depth-stencil images with the depth-stencil value.
+#[derive(Debug)]
+pub struct ClearNode<B: rendy_core::hal::Backend> {
+    recorded: Recorded<B>,
+}
+
+impl<B> ClearNode<B>
+where
+    B: rendy_core::hal::Backend,
+{
+    /// Node builder.
+    /// Node writes to the `image`, so nodes that access it after this one
+    /// see cleared content.
+    pub fn builder(image: ImageId, clear: rendy_core::hal::command::ClearValue) -> ClearBuilder {
+        ClearBuilder {
+            image,
+            clear,
+            dependencies: Vec::new(),
+        }
+    }
+}
+
+impl<'a, B> NodeSubmittable<'a, B> for ClearNode<B>
+where
+    B: rendy_core::hal::Backend,
+{
+    type Submittable = &'a Submit<B, SimultaneousUse>;
+    type Submittables = &'a [Submit<B, SimultaneousUse>];
+}
+
+impl<B, T> Node<B, T> for ClearNode<B>
+where
+    B: rendy_core::hal::Backend,
+    T: ?Sized,
+{
+    type Capability = Graphics;
+
+    fn run<'a>(
+        &'a mut self,
+        _ctx: &GraphContext<B>,
+        _factory: &Factory<B>,
+        _aux: &T,
+        _frames: &'a Frames<B>,
+    ) -> &'a [Submit<B, SimultaneousUse>] {
+        std::slice::from_ref(&self.recorded.submit)
+    }
+
+    unsafe fn dispose(self, factory: &mut Factory<B>, _aux: &T) {
+        self.recorded.dispose(factory);
+    }
+}
+
+/// Clear node description.
+#[derive(Debug)]
+pub struct ClearBuilder {
+    image: ImageId,
+    clear: rendy_core::hal::command::ClearValue,
+    dependencies: Vec<NodeId>,
+}
+
+impl ClearBuilder {
+    /// Add dependency.
+    /// Node will be placed after its dependencies.
+    pub fn add_dependency(&mut self, dependency: NodeId) -> &mut Self {
+        self.dependencies.push(dependency);
+        self
+    }
+
+    /// Add dependency.
+    /// Node will be placed after its dependencies.
+    pub fn with_dependency(mut self, dependency: NodeId) -> Self {
+        self.add_dependency(dependency);
+        self
+    }
+
+    /// Image the node clears.
+    pub fn image(&self) -> ImageId {
+        self.image
+    }
+}
+
+impl<B, T> NodeBuilder<B, T> for ClearBuilder
+where
+    B: rendy_core::hal::Backend,
+    T: ?Sized,
+{
+    fn family(&self, _factory: &mut Factory<B>, families: &Families<B>) -> Option<FamilyId> {
+        families.with_capability::<Graphics>()
+    }
+
+    fn buffers(&self) -> Vec<(BufferId, BufferAccess)> {
+        Vec::new()
+    }
+
+    fn images(&self) -> Vec<(ImageId, ImageAccess)> {
+        vec![(self.image, transfer_dst())]
+    }
+
+    fn dependencies(&self) -> Vec<NodeId> {
+        self.dependencies.clone()
+    }
+
+    fn build<'a>(
+        self: Box<Self>,
+        ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        family: &mut Family<B>,
+        _queue: usize,
+        _aux: &T,
+        buffers: Vec<NodeBuffer>,
+        images: Vec<NodeImage>,
+    ) -> Result<Box<dyn DynNode<B, T>>, NodeBuildError> {
+        assert!(buffers.is_empty());
+        assert_eq!(images.len(), 1);
+
+        let node_image = &images[0];
+        let image = ctx.get_image(node_image.id).expect("Image does not exist");
+        let clear = self.clear;
+
+        let recorded = Recorded::record(ctx, factory, family, &images, |encoder| unsafe {
+            if node_image
+                .range
+                .aspects
+                .contains(rendy_core::hal::format::Aspects::COLOR)
+            {
+                encoder.clear_color_image(
+                    image.raw(),
+                    node_image.layout,
+                    clear.color,
+                    Some(node_image.range.clone()),
+                );
+            } else {
+                encoder.clear_depth_stencil_image(
+                    image.raw(),
+                    node_image.layout,
+                    clear.depth_stencil,
+                    Some(node_image.range.clone()),
+                );
+            }
+        })?;
+
+        Ok(Box::new((ClearNode { recorded },)))
+    }
+}
+
+/// Node that blits one image to another, scaling it to the destination extent.
+///
+/// First level of the source image is blitted to the first level of the destination image.
+/// Number of blitted layers is the smaller of layer counts of the images.
+/// Images must be different and both support blitting with their formats,
+/// see `Factory::supports_blit`.
+#[derive(Debug)]
+pub struct BlitNode<B: rendy_core::hal::Backend> {
+    recorded: Recorded<B>,
+}
+
+impl<B> BlitNode<B>
+where
+    B: rendy_core::hal::Backend,
+{
+    /// Node builder.
+    /// Node reads `src` and writes to `dst`, so nodes that access `dst` after this one
+    /// see blitted content.
+    pub fn builder(
+        src: ImageId,
+        dst: ImageId,
+        filter: rendy_core::hal::image::Filter,
+    ) -> BlitBuilder {
+        BlitBuilder {
+            src,
+            dst,
+            filter,
+            dependencies: Vec::new(),
+        }
+    }
+}
+
+impl<'a, B> NodeSubmittable<'a, B> for BlitNode<B>
+where
+    B: rendy_core::hal::Backend,
+{
+    type Submittable = &'a Submit<B, SimultaneousUse>;
+    type Submittables = &'a [Submit<B, SimultaneousUse>];
+}
+
+impl<B, T> Node<B, T> for BlitNode<B>
+where
+    B: rendy_core::hal::Backend,
+    T: ?Sized,
+{
+    type Capability = Graphics;
+
+    fn run<'a>(
+        &'a mut self,
+        _ctx: &GraphContext<B>,
+        _factory: &Factory<B>,
+        _aux: &T,
+        _frames: &'a Frames<B>,
+    ) -> &'a [Submit<B, SimultaneousUse>] {
+        std::slice::from_ref(&self.recorded.submit)
+    }
+
+    unsafe fn dispose(self, factory: &mut Factory<B>, _aux: &T) {
+        self.recorded.dispose(factory);
+    }
+}
+
+/// Blit node description.
+#[derive(Debug)]
+pub struct BlitBuilder {
+    src: ImageId,
+    dst: ImageId,
+    filter: rendy_core::hal::image::Filter,
+    dependencies: Vec<NodeId>,
+}
+
+impl BlitBuilder {
+    /// Add dependency.
+    /// Node will be placed after its dependencies.
+    pub fn add_dependency(&mut self, dependency: NodeId) -> &mut Self {
+        self.dependencies.push(dependency);
+        self
+    }
+
+    /// Add dependency.
+    /// Node will be placed after its dependencies.
+    pub fn with_dependency(mut self, dependency: NodeId) -> Self {
+        self.add_dependency(dependency);
+        self
+    }
+
+    /// Image the node writes to.
+    pub fn dst(&self) -> ImageId {
+        self.dst
+    }
+}
+
+impl<B, T> NodeBuilder<B, T> for BlitBuilder
+where
+    B: rendy_core::hal::Backend,
+    T: ?Sized,
+{
+    fn family(&self, _factory: &mut Factory<B>, families: &Families<B>) -> Option<FamilyId> {
+        families.with_capability::<Graphics>()
+    }
+
+    fn buffers(&self) -> Vec<(BufferId, BufferAccess)> {
+        Vec::new()
+    }
+
+    fn images(&self) -> Vec<(ImageId, ImageAccess)> {
+        assert_ne!(self.src, self.dst, "Can't blit image to itself");
+        vec![
+            (
+                self.src,
+                ImageAccess {
+                    access: rendy_core::hal::image::Access::TRANSFER_READ,
+                    usage: rendy_core::hal::image::Usage::TRANSFER_SRC,
+                    layout: rendy_core::hal::image::Layout::TransferSrcOptimal,
+                    stages: rendy_core::hal::pso::PipelineStage::TRANSFER,
+                },
+            ),
+            (self.dst, transfer_dst()),
+        ]
+    }
+
+    fn dependencies(&self) -> Vec<NodeId> {
+        self.dependencies.clone()
+    }
+
+    fn build<'a>(
+        self: Box<Self>,
+        ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        family: &mut Family<B>,
+        _queue: usize,
+        _aux: &T,
+        buffers: Vec<NodeBuffer>,
+        images: Vec<NodeImage>,
+    ) -> Result<Box<dyn DynNode<B, T>>, NodeBuildError> {
+        assert!(buffers.is_empty());
+        assert_eq!(images.len(), 2);
+
+        let find = |id| {
+            let node_image = images
+                .iter()
+                .find(|image| image.id == id)
+                .expect("Image wasn't provided");
+            let image = ctx.get_image(id).expect("Image does not exist");
+            (node_image, image)
+        };
+        let (src_node_image, src) = find(self.src);
+        let (dst_node_image, dst) = find(self.dst);
+
+        let region = blit_region(
+            &src_node_image.range,
+            src.kind().extent(),
+            &dst_node_image.range,
+            dst.kind().extent(),
+        );
+        let filter = self.filter;
+
+        let recorded = Recorded::record(ctx, factory, family, &images, |encoder| unsafe {
+            encoder.blit_image(
+                src.raw(),
+                src_node_image.layout,
+                dst.raw(),
+                dst_node_image.layout,
+                filter,
+                Some(region),
+            );
+        })?;
+
+        Ok(Box::new((BlitNode { recorded },)))
+    }
+}
+
+fn transfer_dst() -> ImageAccess {
+    ImageAccess {
+        access: rendy_core::hal::image::Access::TRANSFER_WRITE,
+        usage: rendy_core::hal::image::Usage::TRANSFER_DST,
+        layout: rendy_core::hal::image::Layout::TransferDstOptimal,
+        stages: rendy_core::hal::pso::PipelineStage::TRANSFER,
+    }
+}
+
+/// Blit region from first level of `src` range to first level of `dst` range.
+fn blit_region(
+    src: &rendy_core::hal::image::SubresourceRange,
+    src_extent: rendy_core::hal::image::Extent,
+    dst: &rendy_core::hal::image::SubresourceRange,
+    dst_extent: rendy_core::hal::image::Extent,
+) -> rendy_core::hal::command::ImageBlit {
+    let layers = (src.layers.end - src.layers.start).min(dst.layers.end - dst.layers.start);
+
+    rendy_core::hal::command::ImageBlit {
+        src_subresource: rendy_core::hal::image::SubresourceLayers {
+            aspects: src.aspects,
+            level: src.levels.start,
+            layers: src.layers.start..src.layers.start + layers,
+        },
+        src_bounds: rendy_core::hal::image::Offset::ZERO
+            .into_bounds(&src_extent.at_level(src.levels.start)),
+        dst_subresource: rendy_core::hal::image::SubresourceLayers {
+            aspects: dst.aspects,
+            level: dst.levels.start,
+            layers: dst.layers.start..dst.layers.start + layers,
+        },
+        dst_bounds: rendy_core::hal::image::Offset::ZERO
+            .into_bounds(&dst_extent.at_level(dst.levels.start)),
+    }
+}
+
+#[test]
+fn test_blit_region() {
+    use rendy_core::hal::{
+        format::Aspects,
+        image::{Extent, Offset, SubresourceRange},
+    };
+
+    let src = SubresourceRange {
+        aspects: Aspects::COLOR,
+        levels: 0..4,
+        layers: 0..2,
+    };
+    let dst = SubresourceRange {
+        aspects: Aspects::COLOR,
+        levels: 0..1,
+        layers: 0..1,
+    };
+    let region = blit_region(
+        &src,
+        Extent {
+            width: 512,
+            height: 256,
+            depth: 1,
+        },
+        &dst,
+        Extent {
+            width: 128,
+            height: 64,
+            depth: 1,
+        },
+    );
+
+    assert_eq!(region.src_subresource.layers, 0..1);
+    assert_eq!(region.dst_subresource.layers, 0..1);
+    assert_eq!(
+        region.src_bounds,
+        Offset::ZERO..Offset {
+            x: 512,
+            y: 256,
+            z: 1
+        }
+    );
+    assert_eq!(
+        region.dst_bounds,
+        Offset::ZERO..Offset {
+            x: 128,
+            y: 64,
+            z: 1
+        }
+    );
+}
@@ -9,7 +9,12 @@ use {
     rendy_command::Families,
     rendy_core::{
         backend_enum,
-        hal::{device::CreationError, Backend, Instance as _, UnsupportedBackend},
+        hal::{
+            adapter::{Adapter, Gpu},
+            device::CreationError,
+            queue::QueueFamilyId,
+            Backend, Features, Instance as _, UnsupportedBackend,
+        },
         rendy_backend, rendy_with_dx12_backend, rendy_with_empty_backend, rendy_with_gl_backend,
         rendy_with_metal_backend, rendy_with_vulkan_backend, EnabledBackend, Instance,
     },
@@ -73,19 +78,78 @@ pub struct Rendy<B: Backend> {
 }
 
 impl<B: Backend> Rendy<B> {
+    /// Create instance, pick adapter and open device according to `config`.
+    ///
+    /// No surface is created, so this works for compute-only and offscreen use.
+    /// gfx-hal 0.5 doesn't let the caller choose instance extensions, backend picks them itself.
+    /// Vulkan backend asks for `VK_KHR_surface`, platform surface extensions
+    /// and `VK_EXT_debug_utils`, but enables only those the driver exposes,
+    /// so instance is created on headless drivers that lack surface support.
+    /// Creating a surface from such instance fails later.
     pub fn init(
         config: &Config<impl DevicesConfigure, impl HeapsConfigure, impl QueuesConfigure>,
     ) -> Result<Self, RendyInitError> {
-        let instance = B::Instance::create("Rendy", 1)?;
+        let instance = B::Instance::create(&config.app.name, config.app.version)?;
         let (factory, families) =
             rendy_factory::init_with_instance(Instance::new(instance), config)?;
         Ok(Rendy { factory, families })
     }
+
+    /// Asynchronous version of `init`, for entry points driven by futures executor,
+    /// e.g. `wasm_bindgen_futures` in browser.
+    ///
+    /// gfx-hal 0.5 creates instance and enumerates adapters synchronously on all targets,
+    /// so the future currently completes on first poll by running `init`.
+    /// Backends with asynchronous adapter acquisition can be awaited here without
+    /// changing callers.
+    pub async fn init_async(
+        config: &Config<impl DevicesConfigure, impl HeapsConfigure, impl QueuesConfigure>,
+    ) -> Result<Self, RendyInitError> {
+        Self::init(config)
+    }
+
+    /// Create rendy using the device opened by the host application.
+    /// Device is destroyed with the factory unless taken back with
+    /// `Factory::into_raw_device`, see `rendy_factory::init_from_raw_parts` for details.
+    ///
+    /// # Safety
+    ///
+    /// Same as for `rendy_factory::init_from_raw_parts`.
+    pub unsafe fn from_existing(
+        instance: &Instance<B>,
+        adapter: Adapter<B>,
+        gpu: Gpu<B>,
+        features: Features,
+        queues: impl IntoIterator<Item = (QueueFamilyId, usize)>,
+        heaps: &impl HeapsConfigure,
+    ) -> Result<Self, RendyInitError> {
+        let (factory, families) =
+            rendy_factory::init_from_raw_parts(instance, adapter, gpu, features, queues, heaps)?;
+        Ok(Rendy { factory, families })
+    }
 }
 
 /// Error type that may be returned by `AnyRendy::init_auto`
+#[non_exhaustive]
 pub struct RendyAutoInitError {
     pub errors: Vec<(EnabledBackend, RendyInitError)>,
+
+    /// Backends skipped because they were excluded by the caller.
+    pub excluded: Vec<rendy_core::Backend>,
+}
+
+impl RendyAutoInitError {
+    /// Create error from initialization errors of tried backends
+    /// and backends the caller excluded.
+    pub fn new(
+        errors: Vec<(EnabledBackend, RendyInitError)>,
+        exclude: &[rendy_core::Backend],
+    ) -> Self {
+        RendyAutoInitError {
+            errors,
+            excluded: excluded_backends(exclude),
+        }
+    }
 }
 
 impl std::fmt::Debug for RendyAutoInitError {
@@ -114,6 +178,12 @@ impl std::fmt::Display for RendyAutoInitError {
                     writeln!(fmt, "  {:#}: {:#}", backend, error)?;
                 }
             }
+            if !self.excluded.is_empty() {
+                writeln!(fmt, "Following backends are excluded:")?;
+                for &backend in &self.excluded {
+                    writeln!(fmt, "  {:#}", backend)?;
+                }
+            }
         } else if self.errors.is_empty() {
             write!(fmt, "No enabled backends among available: ")?;
             if let Some(&backend) = BASIC_PRIORITY.first() {
@@ -137,6 +207,15 @@ impl std::fmt::Display for RendyAutoInitError {
                 write!(fmt, " {}: {}", backend, error)?;
             }
         }
+        if !fmt.alternate() && !self.excluded.is_empty() {
+            write!(fmt, ". Following backends are excluded: ")?;
+            if let Some(&backend) = self.excluded.first() {
+                write!(fmt, "{}", backend)?;
+            }
+            for &backend in self.excluded.iter().skip(1) {
+                write!(fmt, ", {}", backend)?;
+            }
+        }
         Ok(())
     }
 }
@@ -146,20 +225,66 @@ backend_enum! { #[derive(Debug)] pub enum AnyRendy(Rendy); }
 impl AnyRendy {
     pub fn init_auto(
         config: &Config<impl DevicesConfigure, impl HeapsConfigure, impl QueuesConfigure>,
+    ) -> Result<Self, RendyAutoInitError> {
+        Self::init_auto_with_exclusions(config, &[])
+    }
+
+    /// Same as `init_auto` but never tries backends in `exclude`.
+    /// Useful to avoid backends known to be broken with detected driver.
+    ///
+    /// If all remaining backends fail or none are left
+    /// returned error lists excluded backends alongside initialization errors.
+    pub fn init_auto_with_exclusions(
+        config: &Config<impl DevicesConfigure, impl HeapsConfigure, impl QueuesConfigure>,
+        exclude: &[rendy_core::Backend],
     ) -> Result<Self, RendyAutoInitError> {
         let mut errors = Vec::with_capacity(5);
 
-        for backend in BASIC_PRIORITY
-            .iter()
-            .filter_map(|b| std::convert::TryInto::try_into(*b).ok())
-        {
+        for backend in auto_backends(exclude) {
             match Self::init(backend, config) {
                 Ok(rendy) => return Ok(rendy),
                 Err(err) => errors.push((backend, err)),
             }
         }
 
-        Err(RendyAutoInitError { errors })
+        Err(RendyAutoInitError::new(errors, exclude))
+    }
+
+    /// Asynchronous version of `init_auto`.
+    /// Tries backends in the same order and awaits initialization of each.
+    ///
+    /// On native targets it wraps synchronous initialization.
+    /// See `Rendy::init_async` for details.
+    pub async fn init_auto_async(
+        config: &Config<impl DevicesConfigure, impl HeapsConfigure, impl QueuesConfigure>,
+    ) -> Result<Self, RendyAutoInitError> {
+        Self::init_auto_async_with_exclusions(config, &[]).await
+    }
+
+    /// Asynchronous version of `init_auto_with_exclusions`.
+    pub async fn init_auto_async_with_exclusions(
+        config: &Config<impl DevicesConfigure, impl HeapsConfigure, impl QueuesConfigure>,
+        exclude: &[rendy_core::Backend],
+    ) -> Result<Self, RendyAutoInitError> {
+        let mut errors = Vec::with_capacity(5);
+
+        for backend in auto_backends(exclude) {
+            match Self::init_async(backend, config).await {
+                Ok(rendy) => return Ok(rendy),
+                Err(err) => errors.push((backend, err)),
+            }
+        }
+
+        Err(RendyAutoInitError::new(errors, exclude))
+    }
+
+    /// Asynchronous version of `init`.
+    /// Wraps synchronous initialization, see `Rendy::init_async` for details.
+    pub async fn init_async(
+        back: EnabledBackend,
+        config: &Config<impl DevicesConfigure, impl HeapsConfigure, impl QueuesConfigure>,
+    ) -> Result<Self, RendyInitError> {
+        Self::init(back, config)
     }
 
     #[rustfmt::skip]
@@ -227,6 +352,24 @@ pub const UNAVAILABLE: &[rendy_core::Backend] = &[
     rendy_core::Backend::Metal,
 ];
 
+/// Enabled backends from `BASIC_PRIORITY` that are not in `exclude`,
+/// in order automatic initialization tries them.
+fn auto_backends(exclude: &[rendy_core::Backend]) -> impl Iterator<Item = EnabledBackend> + '_ {
+    BASIC_PRIORITY
+        .iter()
+        .filter(move |b| !exclude.contains(b))
+        .filter_map(|b| std::convert::TryInto::try_into(*b).ok())
+}
+
+/// Backends from `BASIC_PRIORITY` that are in `exclude`.
+fn excluded_backends(exclude: &[rendy_core::Backend]) -> Vec<rendy_core::Backend> {
+    BASIC_PRIORITY
+        .iter()
+        .copied()
+        .filter(|b| exclude.contains(b))
+        .collect()
+}
+
 pub fn pick_backend(
     priority: impl IntoIterator<Item = rendy_core::Backend>,
 ) -> Option<EnabledBackend> {
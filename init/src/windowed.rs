@@ -1,5 +1,5 @@
 use {
-    super::{Rendy, RendyInitError, BASIC_PRIORITY, UNAVAILABLE},
+    super::{auto_backends, excluded_backends, Rendy, RendyInitError, BASIC_PRIORITY, UNAVAILABLE},
     rendy_command::Families,
     rendy_core::{
         backend_enum,
@@ -124,6 +124,10 @@ impl<B: Backend> WindowedRendy<B> {
 }
 
 impl<B: Backend> WindowedRendy<B> {
+    /// Create window, surface for it and rendy instance.
+    ///
+    /// Instance is created same way as with `Rendy::init` and
+    /// surface creation fails if surface extensions are not supported by the driver.
     #[rustfmt::skip]
     pub fn init<T: 'static>(
         config: &Config<impl DevicesConfigure, impl HeapsConfigure, impl QueuesConfigure>,
@@ -154,8 +158,26 @@ impl<B: Backend> WindowedRendy<B> {
 }
 
 /// Error type that may be returned by `AnyWindowedRendy::init_auto`
+#[non_exhaustive]
 pub struct WindowedRendyAutoInitError {
     pub errors: Vec<(EnabledBackend, WindowedRendyInitError)>,
+
+    /// Backends skipped because they were excluded by the caller.
+    pub excluded: Vec<rendy_core::Backend>,
+}
+
+impl WindowedRendyAutoInitError {
+    /// Create error from initialization errors of tried backends
+    /// and backends the caller excluded.
+    pub fn new(
+        errors: Vec<(EnabledBackend, WindowedRendyInitError)>,
+        exclude: &[rendy_core::Backend],
+    ) -> Self {
+        WindowedRendyAutoInitError {
+            errors,
+            excluded: excluded_backends(exclude),
+        }
+    }
 }
 
 impl std::fmt::Debug for WindowedRendyAutoInitError {
@@ -185,6 +207,12 @@ impl std::fmt::Display for WindowedRendyAutoInitError {
                     writeln!(fmt, "  {:#}: {:#}", backend, error)?;
                 }
             }
+            if !self.excluded.is_empty() {
+                writeln!(fmt, "Following backends are excluded:")?;
+                for &backend in &self.excluded {
+                    writeln!(fmt, "  {:#}", backend)?;
+                }
+            }
         } else {
             if self.errors.is_empty() {
                 write!(fmt, "No enabled backends among available:")?;
@@ -204,6 +232,12 @@ impl std::fmt::Display for WindowedRendyAutoInitError {
                     write!(fmt, "  {}: {}", backend, error)?;
                 }
             }
+            if !self.excluded.is_empty() {
+                write!(fmt, " Following backends are excluded:")?;
+                for &backend in &self.excluded {
+                    write!(fmt, "  {}", backend)?;
+                }
+            }
         }
         Ok(())
     }
@@ -216,20 +250,30 @@ impl AnyWindowedRendy {
         config: &Config<impl DevicesConfigure, impl HeapsConfigure, impl QueuesConfigure>,
         window_builder: WindowBuilder,
         event_loop: &EventLoop<T>,
+    ) -> Result<Self, WindowedRendyAutoInitError> {
+        Self::init_auto_with_exclusions(config, window_builder, event_loop, &[])
+    }
+
+    /// Same as `init_auto` but never tries backends in `exclude`.
+    ///
+    /// If all remaining backends fail or none are left
+    /// returned error lists excluded backends alongside initialization errors.
+    pub fn init_auto_with_exclusions<T>(
+        config: &Config<impl DevicesConfigure, impl HeapsConfigure, impl QueuesConfigure>,
+        window_builder: WindowBuilder,
+        event_loop: &EventLoop<T>,
+        exclude: &[rendy_core::Backend],
     ) -> Result<Self, WindowedRendyAutoInitError> {
         let mut errors = Vec::with_capacity(5);
 
-        for backend in BASIC_PRIORITY
-            .iter()
-            .filter_map(|b| std::convert::TryInto::try_into(*b).ok())
-        {
+        for backend in auto_backends(exclude) {
             match Self::init_ref_builder(backend, config, &window_builder, event_loop) {
                 Ok(rendy) => return Ok(rendy),
                 Err(err) => errors.push((backend, err)),
             }
         }
 
-        Err(WindowedRendyAutoInitError { errors })
+        Err(WindowedRendyAutoInitError::new(errors, exclude))
     }
 
     #[rustfmt::skip]
@@ -1,5 +1,10 @@
 mod heap;
 mod memory_type;
+#[cfg(feature = "mem-tracking")]
+mod tracking;
+
+#[cfg(feature = "mem-tracking")]
+pub use self::tracking::AllocationRecord;
 
 use {
     self::{heap::MemoryHeap, memory_type::MemoryType},
@@ -43,6 +48,21 @@ impl From<gfx_hal::device::OutOfMemory> for HeapsError {
     }
 }
 
+/// Collect memory types from `mask` that have properties required by `usage`
+/// along with their fitness.
+fn suitable_types(
+    properties: impl Iterator<Item = gfx_hal::memory::Properties>,
+    mask: u32,
+    usage: &impl MemoryUsage,
+) -> smallvec::SmallVec<[(usize, u32); 64]> {
+    properties
+        .enumerate()
+        .filter(|&(index, _)| (mask & (1u32 << index)) != 0)
+        .filter(|&(_, properties)| properties.contains(usage.properties_required()))
+        .map(|(index, properties)| (index, usage.memory_fitness(properties)))
+        .collect()
+}
+
 /// Config for `Heaps` allocator.
 #[derive(Clone, Copy, Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -59,6 +79,8 @@ pub struct HeapsConfig {
 pub struct Heaps<B: gfx_hal::Backend> {
     types: Vec<MemoryType<B>>,
     heaps: Vec<MemoryHeap>,
+    #[cfg(feature = "mem-tracking")]
+    tracker: tracking::Tracker,
 }
 
 impl<B> Heaps<B>
@@ -98,6 +120,8 @@ where
                 })
                 .collect(),
             heaps,
+            #[cfg(feature = "mem-tracking")]
+            tracker: tracking::Tracker::default(),
         }
     }
 
@@ -116,21 +140,9 @@ where
     ) -> Result<MemoryBlock<B>, HeapsError> {
         debug_assert!(fits_u32(self.types.len()));
 
-        let (memory_index, _, _) = {
-            let suitable_types = self
-                .types
-                .iter()
-                .enumerate()
-                .filter(|(index, _)| (mask & (1u32 << index)) != 0)
-                .filter_map(|(index, mt)| {
-                    if mt.properties().contains(usage.properties_required()) {
-                        let fitness = usage.memory_fitness(mt.properties());
-                        Some((index, mt, fitness))
-                    } else {
-                        None
-                    }
-                })
-                .collect::<smallvec::SmallVec<[_; 64]>>();
+        let (memory_index, _) = {
+            let suitable_types =
+                suitable_types(self.types.iter().map(|mt| mt.properties()), mask, &usage);
 
             if suitable_types.is_empty() {
                 return Err(HeapsError::NoSuitableMemory(
@@ -141,8 +153,10 @@ where
 
             suitable_types
                 .into_iter()
-                .filter(|(_, mt, _)| self.heaps[mt.heap_index()].available() > size + align)
-                .max_by_key(|&(_, _, fitness)| fitness)
+                .filter(|&(index, _)| {
+                    self.heaps[self.types[index].heap_index()].available() > size + align
+                })
+                .max_by_key(|&(_, fitness)| fitness)
                 .ok_or_else(|| {
                     log::error!("All suitable heaps are exhausted. {:#?}", self);
                     gfx_hal::device::OutOfMemory::Device
@@ -185,6 +199,8 @@ where
         memory_heap.allocated(allocated, block.size());
 
         Ok(MemoryBlock {
+            #[cfg(feature = "mem-tracking")]
+            tracking_id: self.tracker.track(block.size(), memory_index, block.kind()),
             block,
             memory_index,
         })
@@ -199,17 +215,38 @@ where
         debug_assert!(fits_usize(memory_index));
         let size = block.size();
 
+        #[cfg(feature = "mem-tracking")]
+        self.tracker.untrack(block.tracking_id);
+
         let memory_type = &mut self.types[memory_index as usize];
         let memory_heap = &mut self.heaps[memory_type.heap_index()];
         let freed = memory_type.free(device, block.block);
         memory_heap.freed(freed, size);
     }
 
+    /// Attach `label` to the allocation record of the `block`.
+    /// Labels are shown in leak reports.
+    /// Does nothing unless `mem-tracking` feature is enabled.
+    #[allow(unused_variables)]
+    pub fn set_label(&mut self, block: &MemoryBlock<B>, label: impl Into<String>) {
+        #[cfg(feature = "mem-tracking")]
+        self.tracker.set_label(block.tracking_id, label.into());
+    }
+
+    /// Get records of all allocations that are not yet freed.
+    #[cfg(feature = "mem-tracking")]
+    pub fn live_allocations(&self) -> Vec<AllocationRecord> {
+        self.tracker.live()
+    }
+
     /// Dispose of allocator.
     /// Cleanup allocators before dropping.
     /// Will panic if memory instances are left allocated.
-    pub fn dispose(self, device: &B::Device) {
-        for mt in self.types {
+    pub fn dispose(mut self, device: &B::Device) {
+        #[cfg(all(feature = "mem-tracking", debug_assertions))]
+        self.tracker.report_leaks();
+
+        for mt in self.types.drain(..) {
             mt.dispose(device)
         }
     }
@@ -221,6 +258,28 @@ where
             types: self.types.iter().map(MemoryType::utilization).collect(),
         }
     }
+
+    /// Get per-heap budget and usage reported by the driver
+    /// (`VK_EXT_memory_budget` on Vulkan).
+    ///
+    /// Returns `None` if budget can't be queried.
+    /// Callers should fall back to `utilization` in that case.
+    ///
+    /// gfx-hal 0.5 doesn't expose memory budget query,
+    /// so this function always returns `None` for now.
+    pub fn device_budget(&self) -> Option<Vec<HeapBudget>> {
+        None
+    }
+}
+
+#[cfg(all(feature = "mem-tracking", debug_assertions))]
+impl<B> Drop for Heaps<B>
+where
+    B: gfx_hal::Backend,
+{
+    fn drop(&mut self) {
+        self.tracker.report_leaks();
+    }
 }
 
 /// Memory block allocated from `Heaps`.
@@ -228,6 +287,8 @@ where
 pub struct MemoryBlock<B: gfx_hal::Backend> {
     block: BlockFlavor<B>,
     memory_index: u32,
+    #[cfg(feature = "mem-tracking")]
+    tracking_id: usize,
 }
 
 impl<B> MemoryBlock<B>
@@ -292,6 +353,15 @@ where
             // Chunk(block) => block.size(),
         }
     }
+
+    #[cfg(feature = "mem-tracking")]
+    fn kind(&self) -> Kind {
+        match self {
+            BlockFlavor::Dedicated(_) => Kind::Dedicated,
+            BlockFlavor::Linear(_) => Kind::Linear,
+            BlockFlavor::Dynamic(_) => Kind::Dynamic,
+        }
+    }
 }
 
 impl<B> Block<B> for MemoryBlock<B>
@@ -325,3 +395,29 @@ where
         any_block!(&mut self.block => block.unmap(device))
     }
 }
+
+#[test]
+fn test_explicit_properties() {
+    use crate::usage::Explicit;
+    use gfx_hal::memory::Properties;
+
+    let types = [
+        Properties::DEVICE_LOCAL,
+        Properties::CPU_VISIBLE | Properties::COHERENT,
+        Properties::DEVICE_LOCAL | Properties::CPU_VISIBLE,
+        Properties::DEVICE_LOCAL | Properties::CPU_VISIBLE | Properties::COHERENT,
+    ];
+    let usage = Explicit::new(Properties::DEVICE_LOCAL | Properties::CPU_VISIBLE)
+        .with_preferred(Properties::COHERENT);
+
+    let suitable = suitable_types(types.iter().copied(), !0, &usage);
+    let (index, _) = suitable
+        .into_iter()
+        .max_by_key(|&(_, fitness)| fitness)
+        .unwrap();
+    assert!(types[index].contains(Properties::DEVICE_LOCAL | Properties::CPU_VISIBLE));
+    assert_eq!(index, 3);
+
+    // Types not in the mask are never chosen.
+    assert!(suitable_types(types.iter().copied(), 0b0011, &usage).is_empty());
+}
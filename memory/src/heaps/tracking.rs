@@ -0,0 +1,83 @@
+use {
+    crate::allocator::Kind,
+    std::{backtrace::Backtrace, sync::Arc},
+};
+
+/// Record of live allocation made from `Heaps`.
+/// Collected only with `mem-tracking` feature enabled.
+#[derive(Clone, Debug)]
+pub struct AllocationRecord {
+    /// Size of the allocated block.
+    pub size: u64,
+
+    /// Memory type the block was allocated from.
+    pub memory_type: u32,
+
+    /// Allocator that served the allocation.
+    pub allocator: Kind,
+
+    /// Label attached with `Heaps::set_label`.
+    pub label: Option<String>,
+
+    /// Backtrace captured when allocation was made.
+    pub backtrace: Arc<Backtrace>,
+}
+
+impl std::fmt::Display for AllocationRecord {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            fmt,
+            "{} bytes from memory type {} ({:?} allocator)",
+            self.size, self.memory_type, self.allocator
+        )?;
+        if let Some(label) = &self.label {
+            write!(fmt, " labeled '{}'", label)?;
+        }
+        write!(fmt, " allocated at:\n{}", self.backtrace)
+    }
+}
+
+#[derive(Debug, Default)]
+pub(super) struct Tracker {
+    records: slab::Slab<AllocationRecord>,
+}
+
+impl Tracker {
+    pub(super) fn track(&mut self, size: u64, memory_type: u32, allocator: Kind) -> usize {
+        self.records.insert(AllocationRecord {
+            size,
+            memory_type,
+            allocator,
+            label: None,
+            backtrace: Arc::new(Backtrace::force_capture()),
+        })
+    }
+
+    pub(super) fn untrack(&mut self, id: usize) {
+        self.records.remove(id);
+    }
+
+    pub(super) fn set_label(&mut self, id: usize, label: String) {
+        self.records[id].label = Some(label);
+    }
+
+    pub(super) fn live(&self) -> Vec<AllocationRecord> {
+        self.records
+            .iter()
+            .map(|(_, record)| record.clone())
+            .collect()
+    }
+
+    /// Log all still-live allocations and forget them.
+    pub(super) fn report_leaks(&mut self) {
+        if !self.records.is_empty() {
+            log::error!(
+                "{} allocations are still live and probably leaked",
+                self.records.len()
+            );
+            for record in self.records.drain() {
+                log::error!("Leaked {}", record);
+            }
+        }
+    }
+}
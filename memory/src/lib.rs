@@ -29,3 +29,6 @@ pub use crate::{
     usage::*,
     utilization::*,
 };
+
+#[cfg(feature = "mem-tracking")]
+pub use crate::heaps::AllocationRecord;
@@ -155,6 +155,61 @@ impl MemoryUsage for Download {
     }
 }
 
+/// Memory usage with explicit set of required properties.
+/// Escape hatch for property combinations that well-known usages don't target,
+/// e.g. `DEVICE_LOCAL | CPU_VISIBLE` memory that some GPUs expose for fast uploads.
+///
+/// Only memory types with all `required` properties are suitable.
+/// Among them types with more of `preferred` properties are chosen.
+/// `LAZILY_ALLOCATED` memory is avoided unless required.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Explicit {
+    /// Properties memory type must have.
+    pub required: gfx_hal::memory::Properties,
+
+    /// Properties memory type should have if possible.
+    pub preferred: gfx_hal::memory::Properties,
+}
+
+impl Explicit {
+    /// Create usage that requires specified properties and prefers nothing else.
+    pub fn new(required: gfx_hal::memory::Properties) -> Self {
+        Explicit {
+            required,
+            preferred: gfx_hal::memory::Properties::empty(),
+        }
+    }
+
+    /// Prefer specified properties in addition to required ones.
+    pub fn with_preferred(mut self, preferred: gfx_hal::memory::Properties) -> Self {
+        self.preferred = preferred;
+        self
+    }
+}
+
+impl MemoryUsage for Explicit {
+    fn properties_required(&self) -> gfx_hal::memory::Properties {
+        self.required
+    }
+
+    #[inline]
+    fn memory_fitness(&self, properties: gfx_hal::memory::Properties) -> u32 {
+        assert!(properties.contains(self.required));
+
+        let lazy = gfx_hal::memory::Properties::LAZILY_ALLOCATED;
+        (properties & self.preferred).bits().count_ones() << 1
+            | (self.required.contains(lazy) || !properties.contains(lazy)) as u32
+    }
+
+    fn allocator_fitness(&self, kind: Kind) -> u32 {
+        match kind {
+            Kind::Dedicated => 1,
+            Kind::Dynamic => 2,
+            Kind::Linear => 0,
+        }
+    }
+}
+
 /// Well-known memory usage types.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum MemoryUsageValue {
@@ -22,6 +22,19 @@ pub struct MemoryHeapUtilization {
     pub size: u64,
 }
 
+/// Budget of one heap as reported by the driver.
+/// Unlike `MemoryHeapUtilization` it accounts for allocations
+/// made outside of `Heaps`, including other processes.
+#[derive(Clone, Copy, Debug)]
+pub struct HeapBudget {
+    /// Number of bytes the process can allocate from the heap
+    /// without degrading performance or failing allocation.
+    pub budget: u64,
+
+    /// Number of bytes currently allocated from the heap by the process.
+    pub usage: u64,
+}
+
 /// Memory utilization of one type.
 #[derive(Clone, Copy, Debug)]
 pub struct MemoryTypeUtilization {
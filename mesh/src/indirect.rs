@@ -0,0 +1,267 @@
+//!
+//! Build buffers of indirect draw commands.
+//!
+
+use crate::{
+    command::{DrawCommand, DrawIndexedCommand, QueueId, RenderPassEncoder},
+    core::cast_slice,
+    factory::{BufferState, Factory, UploadError},
+    memory::Data,
+    resource::{Buffer, BufferInfo, Escape},
+};
+use std::{marker::PhantomData, mem::size_of};
+
+/// Command that can be stored in indirect buffer.
+pub trait IndirectCommand: Copy + std::fmt::Debug + 'static {
+    /// Record indirect draw that reads `draw_count` commands
+    /// from `buffer` starting at `offset` with `stride` bytes between them.
+    ///
+    /// # Safety
+    ///
+    /// `buffer` must contain `draw_count` commands of this type
+    /// and must be created with `INDIRECT` usage.
+    unsafe fn draw_indirect<B: rendy_core::hal::Backend>(
+        encoder: &mut RenderPassEncoder<'_, B>,
+        buffer: &B::Buffer,
+        offset: u64,
+        draw_count: u32,
+        stride: u32,
+    );
+}
+
+impl IndirectCommand for DrawCommand {
+    unsafe fn draw_indirect<B: rendy_core::hal::Backend>(
+        encoder: &mut RenderPassEncoder<'_, B>,
+        buffer: &B::Buffer,
+        offset: u64,
+        draw_count: u32,
+        stride: u32,
+    ) {
+        encoder.draw_indirect(buffer, offset, draw_count, stride)
+    }
+}
+
+impl IndirectCommand for DrawIndexedCommand {
+    unsafe fn draw_indirect<B: rendy_core::hal::Backend>(
+        encoder: &mut RenderPassEncoder<'_, B>,
+        buffer: &B::Buffer,
+        offset: u64,
+        draw_count: u32,
+        stride: u32,
+    ) {
+        encoder.draw_indexed_indirect(buffer, offset, draw_count, stride)
+    }
+}
+
+/// Builder of the buffer with indirect draw commands.
+///
+/// Commands are tightly packed with stride equal to the size of command.
+/// If count is requested, number of commands is stored as `u32` right after them,
+/// so the same buffer can be used as count buffer for `draw_indirect_count`.
+#[derive(Clone, Debug)]
+pub struct IndirectBufferBuilder<C> {
+    commands: Vec<C>,
+    with_count: bool,
+}
+
+impl<C> Default for IndirectBufferBuilder<C> {
+    fn default() -> Self {
+        IndirectBufferBuilder {
+            commands: Vec::new(),
+            with_count: false,
+        }
+    }
+}
+
+impl<C> IndirectBufferBuilder<C>
+where
+    C: IndirectCommand,
+{
+    /// Create empty builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add draw command.
+    pub fn with_command(mut self, command: C) -> Self {
+        self.add_command(command);
+        self
+    }
+
+    /// Add draw command.
+    pub fn add_command(&mut self, command: C) -> &mut Self {
+        self.commands.push(command);
+        self
+    }
+
+    /// Add draw commands.
+    pub fn with_commands(mut self, commands: impl IntoIterator<Item = C>) -> Self {
+        self.commands.extend(commands);
+        self
+    }
+
+    /// Store number of commands after them to use the buffer as count buffer.
+    pub fn with_count(mut self, with_count: bool) -> Self {
+        self.set_count(with_count);
+        self
+    }
+
+    /// Store number of commands after them to use the buffer as count buffer.
+    pub fn set_count(&mut self, with_count: bool) -> &mut Self {
+        self.with_count = with_count;
+        self
+    }
+
+    /// Number of commands added.
+    pub fn len(&self) -> u32 {
+        self.commands.len() as u32
+    }
+
+    /// Check if no commands were added.
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Distance in bytes between commands in the buffer.
+    pub fn stride(&self) -> u32 {
+        size_of::<C>() as u32
+    }
+
+    /// Offset of the count in the buffer.
+    pub fn count_offset(&self) -> Option<u64> {
+        if self.with_count {
+            Some(self.stride() as u64 * self.len() as u64)
+        } else {
+            None
+        }
+    }
+
+    /// Content of the buffer.
+    pub fn bytes(&self) -> Vec<u8> {
+        let mut bytes = cast_slice(&self.commands).to_vec();
+        if self.with_count {
+            bytes.extend_from_slice(&self.len().to_ne_bytes());
+        }
+        bytes
+    }
+
+    /// Create buffer and upload commands into it.
+    /// Buffer is ready to be read by indirect draw commands on `queue`.
+    pub fn build<B>(
+        &self,
+        queue: QueueId,
+        factory: &Factory<B>,
+    ) -> Result<IndirectBuffer<B, C>, UploadError>
+    where
+        B: rendy_core::hal::Backend,
+    {
+        let bytes = self.bytes();
+        let buffer = factory
+            .create_buffer(
+                BufferInfo {
+                    size: bytes.len() as u64,
+                    usage: rendy_core::hal::buffer::Usage::INDIRECT
+                        | rendy_core::hal::buffer::Usage::TRANSFER_DST,
+                },
+                Data,
+            )
+            .map_err(UploadError::Create)?;
+
+        unsafe {
+            // New buffer can't be touched by device yet.
+            factory.upload_buffer(
+                &buffer,
+                0,
+                &bytes,
+                None,
+                BufferState::for_usage(queue, rendy_core::hal::buffer::Usage::INDIRECT),
+            )?;
+        }
+
+        Ok(IndirectBuffer {
+            buffer,
+            count: self.len(),
+            count_offset: self.count_offset(),
+            marker: PhantomData,
+        })
+    }
+}
+
+/// Buffer with indirect draw commands of type `C`.
+#[derive(Debug)]
+pub struct IndirectBuffer<B: rendy_core::hal::Backend, C> {
+    buffer: Escape<Buffer<B>>,
+    count: u32,
+    count_offset: Option<u64>,
+    marker: PhantomData<fn() -> C>,
+}
+
+impl<B, C> IndirectBuffer<B, C>
+where
+    B: rendy_core::hal::Backend,
+    C: IndirectCommand,
+{
+    /// Create builder of indirect buffer.
+    pub fn builder() -> IndirectBufferBuilder<C> {
+        IndirectBufferBuilder::new()
+    }
+
+    /// Buffer with commands.
+    pub fn buffer(&self) -> &Buffer<B> {
+        &self.buffer
+    }
+
+    /// Number of commands in the buffer.
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// Distance in bytes between commands in the buffer.
+    pub fn stride(&self) -> u32 {
+        size_of::<C>() as u32
+    }
+
+    /// Offset of the count in the buffer if it was built with count.
+    pub fn count_offset(&self) -> Option<u64> {
+        self.count_offset
+    }
+
+    /// Draw all commands from the buffer.
+    ///
+    /// # Safety
+    ///
+    /// Commands must be valid for resources bound to the `encoder`.
+    pub unsafe fn draw(&self, encoder: &mut RenderPassEncoder<'_, B>) {
+        C::draw_indirect(encoder, self.buffer.raw(), 0, self.count, self.stride())
+    }
+}
+
+#[test]
+fn test_indexed_bytes() {
+    let builder = IndirectBufferBuilder::new()
+        .with_command(DrawIndexedCommand {
+            index_count: 6,
+            instance_count: 1,
+            first_index: 0,
+            vertex_offset: 0,
+            first_instance: 0,
+        })
+        .with_command(DrawIndexedCommand {
+            index_count: 36,
+            instance_count: 4,
+            first_index: 6,
+            vertex_offset: -2,
+            first_instance: 1,
+        })
+        .with_count(true);
+
+    assert_eq!(builder.stride(), 20);
+    assert_eq!(builder.count_offset(), Some(40));
+
+    let expected: Vec<u8> = [6u32, 1, 0, 0, 0, 36, 4, 6, -2i32 as u32, 1, 2]
+        .iter()
+        .flat_map(|word| word.to_ne_bytes().to_vec())
+        .collect();
+    assert_eq!(builder.bytes(), expected);
+    assert_eq!(builder.clone().with_count(false).bytes(), &expected[..40]);
+}
@@ -0,0 +1,165 @@
+//!
+//! Growable buffers of per-instance vertex data.
+//!
+
+use crate::{
+    command::{EncoderCommon, Graphics, Supports},
+    core::types::vertex::{AsVertex, VertexFormat},
+    factory::{Factory, UploadError},
+    memory::Dynamic,
+    resource::{Buffer, BufferInfo, Escape},
+};
+use std::{marker::PhantomData, mem::size_of};
+
+/// Smallest number of instances buffer is allocated for.
+const MIN_CAPACITY: u64 = 16;
+
+/// Number of instances buffer with `capacity` must grow to in order to fit `required`.
+/// Capacity is doubled to amortize reallocations.
+fn grow_capacity(capacity: u64, required: u64) -> u64 {
+    if required <= capacity {
+        capacity
+    } else {
+        required.next_power_of_two().max(MIN_CAPACITY)
+    }
+}
+
+/// Host-visible buffers with per-instance vertex data of type `T`.
+///
+/// Holds separate buffer for each frame in flight,
+/// so instances for next frame can be written while device reads previous ones.
+/// Buffer is reallocated when it can't fit new instances.
+#[derive(Debug)]
+pub struct InstanceBuffer<B: rendy_core::hal::Backend, T> {
+    frames: Vec<InstanceFrame<B>>,
+    marker: PhantomData<fn() -> T>,
+}
+
+#[derive(Debug)]
+struct InstanceFrame<B: rendy_core::hal::Backend> {
+    buffer: Option<Escape<Buffer<B>>>,
+    capacity: u64,
+    count: u32,
+}
+
+impl<B, T> InstanceBuffer<B, T>
+where
+    B: rendy_core::hal::Backend,
+    T: AsVertex,
+{
+    /// Create instance buffer for specified number of frames in flight.
+    /// No memory is allocated until instances are set.
+    pub fn new(frames: usize) -> Self {
+        InstanceBuffer {
+            frames: (0..frames)
+                .map(|_| InstanceFrame {
+                    buffer: None,
+                    capacity: 0,
+                    count: 0,
+                })
+                .collect(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Vertex format of the instance data.
+    /// Should be used with `VertexInputRate::Instance(1)`.
+    pub fn format() -> VertexFormat {
+        T::vertex()
+    }
+
+    /// Write instances for the frame with `index`.
+    /// Buffer of the frame is reallocated if it is too small.
+    ///
+    /// # Safety
+    ///
+    /// Device must not use buffer of the frame with `index`,
+    /// e.g. frame that used it last time must be complete.
+    pub unsafe fn set_instances(
+        &mut self,
+        factory: &Factory<B>,
+        index: usize,
+        instances: &[T],
+    ) -> Result<(), UploadError> {
+        let frame = &mut self.frames[index];
+        let required = instances.len() as u64;
+
+        if frame.buffer.is_none() || required > frame.capacity {
+            let capacity = grow_capacity(frame.capacity, required.max(1));
+            let buffer = factory
+                .create_buffer(
+                    BufferInfo {
+                        size: capacity * size_of::<T>() as u64,
+                        usage: rendy_core::hal::buffer::Usage::VERTEX,
+                    },
+                    Dynamic,
+                )
+                .map_err(UploadError::Create)?;
+            log::trace!(
+                "Instance buffer for frame {} grows from {} to {} instances",
+                index,
+                frame.capacity,
+                capacity
+            );
+            frame.buffer = Some(buffer);
+            frame.capacity = capacity;
+        }
+
+        if !instances.is_empty() {
+            factory
+                .upload_visible_buffer(frame.buffer.as_mut().unwrap(), 0, instances)
+                .map_err(UploadError::Map)?;
+        }
+        frame.count = instances.len() as u32;
+        Ok(())
+    }
+
+    /// Buffer of the frame with `index`.
+    /// Returns `None` if instances were never set for the frame.
+    pub fn buffer(&self, index: usize) -> Option<&Buffer<B>> {
+        self.frames[index].buffer.as_deref()
+    }
+
+    /// Number of instances set for the frame with `index`.
+    pub fn count(&self, index: usize) -> u32 {
+        self.frames[index].count
+    }
+
+    /// Number of instances buffer of the frame with `index` can hold without reallocation.
+    pub fn capacity(&self, index: usize) -> u64 {
+        self.frames[index].capacity
+    }
+
+    /// Bind buffer of the frame with `index` to the vertex `binding`.
+    /// Returns `false` if there is nothing to bind.
+    ///
+    /// # Safety
+    ///
+    /// Buffer must not be written until commands recorded with `encoder` are complete.
+    pub unsafe fn bind<C>(
+        &self,
+        index: usize,
+        binding: u32,
+        encoder: &mut EncoderCommon<'_, B, C>,
+    ) -> bool
+    where
+        C: Supports<Graphics>,
+    {
+        match self.buffer(index) {
+            Some(buffer) => {
+                encoder.bind_vertex_buffers(binding, Some((buffer.raw(), 0)));
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[test]
+fn test_grow_capacity() {
+    assert_eq!(grow_capacity(0, 0), 0);
+    assert_eq!(grow_capacity(0, 1), MIN_CAPACITY);
+    assert_eq!(grow_capacity(16, 16), 16);
+    assert_eq!(grow_capacity(16, 17), 32);
+    assert_eq!(grow_capacity(32, 100), 128);
+}
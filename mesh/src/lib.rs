@@ -23,7 +23,9 @@ use rendy_memory as memory;
 use rendy_resource as resource;
 
 mod format;
+mod indirect;
+mod instance;
 mod mesh;
 
-pub use crate::{format::*, mesh::*};
+pub use crate::{format::*, indirect::*, instance::*, mesh::*};
 pub use rendy_core::types::vertex::*;
@@ -4,7 +4,7 @@
 
 use crate::{
     command::{EncoderCommon, Graphics, QueueId, RenderPassEncoder, Supports},
-    core::cast_cow,
+    core::{cast_cow, cast_slice},
     factory::{BufferState, Factory, UploadError},
     memory::{Data, Upload, Write},
     resource::{Buffer, BufferInfo, Escape},
@@ -85,6 +85,8 @@ pub struct MeshBuilder<'a> {
     #[cfg_attr(feature = "serde", serde(borrow))]
     indices: Option<RawIndices<'a>>,
     prim: rendy_core::hal::pso::Primitive,
+    #[cfg_attr(feature = "serde", serde(default))]
+    compute_bounding_sphere: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -117,6 +119,7 @@ impl<'a> MeshBuilder<'a> {
             vertices: smallvec::SmallVec::new(),
             indices: None,
             prim: rendy_core::hal::pso::Primitive::TriangleList,
+            compute_bounding_sphere: false,
         }
     }
 
@@ -137,6 +140,7 @@ impl<'a> MeshBuilder<'a> {
                 index_type: i.index_type,
             }),
             prim: self.prim,
+            compute_bounding_sphere: self.compute_bounding_sphere,
         }
     }
 
@@ -207,6 +211,342 @@ impl<'a> MeshBuilder<'a> {
         self
     }
 
+    /// Primitive type of the mesh.
+    pub fn prim_type(&self) -> rendy_core::hal::pso::Primitive {
+        self.prim
+    }
+
+    /// Compute bounding sphere of the mesh when it is built.
+    ///
+    /// Disabled by default.
+    pub fn with_bounding_sphere(mut self, enable: bool) -> Self {
+        self.compute_bounding_sphere = enable;
+        self
+    }
+
+    /// Compute bounding sphere of the mesh when it is built.
+    ///
+    /// Disabled by default.
+    pub fn set_bounding_sphere(&mut self, enable: bool) -> &mut Self {
+        self.compute_bounding_sphere = enable;
+        self
+    }
+
+    /// Bounding sphere of the vertices as center and radius.
+    ///
+    /// Positions are read from `position` attribute of `Rgb32Sfloat` format.
+    /// Sphere is found with Ritter's algorithm and then shrunk
+    /// to the farthest vertex from its center, so it is not minimal
+    /// but bounds all vertices, including ones not referenced by indices.
+    ///
+    /// Returns `None` if there is no vertices or no positions.
+    pub fn bounding_sphere(&self) -> Option<([f32; 3], f32)> {
+        use crate::{AsAttribute, Position};
+
+        let (buffer, offset) = self.find_attribute(Position::NAME, Position::FORMAT)?;
+        let positions: Vec<[f32; 3]> = (0..self.vertex_count())
+            .map(|index| read_vec3(&self.vertices[buffer], offset, index))
+            .collect();
+        bounding_sphere(&positions)
+    }
+
+    /// Merge bit-exact duplicate vertices and reference unique ones via index buffer.
+    ///
+    /// Vertices are compared byte-wise across all vertex buffers,
+    /// so vertices with NaN components are merged only when bit patterns are equal.
+    /// Existing index buffer is remapped, otherwise new one is created.
+    ///
+    /// Returns compression ratio: number of vertices before divided by number after.
+    pub fn deduplicate(&mut self) -> f32 {
+        let len = self
+            .vertices
+            .iter()
+            .map(|v| v.vertices.len() / v.format.stride as usize)
+            .min()
+            .unwrap_or(0);
+
+        if len == 0 {
+            return 1.0;
+        }
+
+        let mut unique = Vec::new();
+        let mut remap = Vec::with_capacity(len);
+        let mut lookup = std::collections::HashMap::with_capacity(len);
+        let mut key = Vec::new();
+
+        for index in 0..len {
+            key.clear();
+            for v in &self.vertices {
+                let stride = v.format.stride as usize;
+                key.extend_from_slice(&v.vertices[index * stride..(index + 1) * stride]);
+            }
+            let next = unique.len() as u32;
+            let mapped = *lookup.entry(key.clone()).or_insert(next);
+            if mapped == next {
+                unique.push(index);
+            }
+            remap.push(mapped);
+        }
+
+        self.select_vertices(&unique);
+
+        let indices: Vec<u32> = match &self.indices {
+            None => remap,
+            Some(RawIndices {
+                indices,
+                index_type: rendy_core::hal::IndexType::U16,
+            }) => indices
+                .chunks_exact(2)
+                .map(|i| remap[u16::from_ne_bytes([i[0], i[1]]) as usize])
+                .collect(),
+            Some(RawIndices {
+                indices,
+                index_type: rendy_core::hal::IndexType::U32,
+            }) => indices
+                .chunks_exact(4)
+                .map(|i| remap[u32::from_ne_bytes([i[0], i[1], i[2], i[3]]) as usize])
+                .collect(),
+        };
+
+        self.replace_indices(indices, unique.len());
+
+        let ratio = len as f32 / unique.len() as f32;
+        log::trace!(
+            "Deduplicated {} vertices into {} (ratio {})",
+            len,
+            unique.len(),
+            ratio
+        );
+        ratio
+    }
+
+    /// Compute vertex normals from triangles of the mesh.
+    ///
+    /// With `smoothing_angle` of `None` face normals weighted by face area are averaged
+    /// for each vertex without changing vertex count.
+    ///
+    /// With `Some(angle)` in radians normals of faces sharing vertex position are averaged
+    /// only when angle between the faces doesn't exceed `angle`.
+    /// Vertices on hard edges are split, so each side of the edge gets its own normal
+    /// and mesh gets index buffer.
+    ///
+    /// Normals are written into existing `normal` attribute of `Rgb32Sfloat` format
+    /// or into new vertex buffer. Degenerate triangles don't contribute to normals.
+    /// Only triangle lists are supported.
+    pub fn generate_normals(
+        &mut self,
+        smoothing_angle: Option<f32>,
+    ) -> Result<&mut Self, GenerateNormalsError> {
+        use crate::{AsAttribute, Normal, Position};
+
+        if self.prim != rendy_core::hal::pso::Primitive::TriangleList {
+            return Err(GenerateNormalsError::UnsupportedPrimitive(self.prim));
+        }
+
+        let (position_buffer, position_offset) = self
+            .find_attribute(Position::NAME, Position::FORMAT)
+            .ok_or(GenerateNormalsError::NoPositions)?;
+
+        let len = self.vertex_count();
+        let positions: Vec<[f32; 3]> = (0..len)
+            .map(|index| read_vec3(&self.vertices[position_buffer], position_offset, index))
+            .collect();
+
+        let indices = self.index_list(len);
+        if let Some(&index) = indices.iter().find(|&&index| index as usize >= len) {
+            return Err(GenerateNormalsError::IndexOutOfBounds(index));
+        }
+
+        let faces: Vec<Option<[f32; 3]>> = indices
+            .chunks_exact(3)
+            .map(|tri| {
+                let [a, b, c] = [
+                    positions[tri[0] as usize],
+                    positions[tri[1] as usize],
+                    positions[tri[2] as usize],
+                ];
+                let normal = cross(sub(b, a), sub(c, a));
+                if length(normal) > f32::EPSILON {
+                    Some(normal)
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        let normals = match smoothing_angle {
+            None => {
+                let mut normals = vec![[0.0; 3]; len];
+                for (tri, face) in indices.chunks_exact(3).zip(&faces) {
+                    if let Some(face) = face {
+                        for &index in tri {
+                            normals[index as usize] = add(normals[index as usize], *face);
+                        }
+                    }
+                }
+                normals.into_iter().map(normalize).collect()
+            }
+            Some(angle) => {
+                let threshold = angle.cos();
+                let units: Vec<Option<[f32; 3]>> =
+                    faces.iter().map(|face| face.map(normalize)).collect();
+
+                let mut by_position = std::collections::HashMap::new();
+                for (face, tri) in indices.chunks_exact(3).enumerate() {
+                    if faces[face].is_some() {
+                        for &index in tri {
+                            by_position
+                                .entry(vec3_bits(positions[index as usize]))
+                                .or_insert_with(Vec::new)
+                                .push(face);
+                        }
+                    }
+                }
+
+                let mut sources = Vec::new();
+                let mut normals = Vec::new();
+                let mut lookup = std::collections::HashMap::new();
+                let mut split_indices = Vec::with_capacity(indices.len());
+
+                for (face, tri) in indices.chunks_exact(3).enumerate() {
+                    for &index in tri {
+                        let adjacent = by_position
+                            .get(&vec3_bits(positions[index as usize]))
+                            .map_or(&[][..], |faces| &faces[..]);
+
+                        let normal = normalize(
+                            adjacent
+                                .iter()
+                                .filter(|&&other| match (units[face], units[other]) {
+                                    (Some(own), Some(other)) => dot(own, other) >= threshold,
+                                    // Degenerate face takes smooth normal of its vertex.
+                                    _ => true,
+                                })
+                                .filter_map(|&other| faces[other])
+                                .fold([0.0; 3], add),
+                        );
+
+                        let next = sources.len() as u32;
+                        let mapped = *lookup.entry((index, vec3_bits(normal))).or_insert(next);
+                        if mapped == next {
+                            sources.push(index as usize);
+                            normals.push(normal);
+                        }
+                        split_indices.push(mapped);
+                    }
+                }
+
+                log::trace!("Split {} vertices into {}", len, sources.len());
+                self.select_vertices(&sources);
+                self.replace_indices(split_indices, sources.len());
+                normals
+            }
+        };
+
+        match self.find_attribute(Normal::NAME, Normal::FORMAT) {
+            Some((buffer, offset)) => {
+                let raw = &mut self.vertices[buffer];
+                let stride = raw.format.stride as usize;
+                let vertices = raw.vertices.to_mut();
+                for (index, normal) in normals.into_iter().enumerate() {
+                    let start = index * stride + offset;
+                    vertices[start..start + 12].copy_from_slice(cast_slice(&normal));
+                }
+            }
+            None => {
+                let normals: Vec<Normal> = normals.into_iter().map(Normal).collect();
+                self.vertices.push(RawVertices {
+                    vertices: cast_cow(Cow::Owned(normals)),
+                    format: Normal::vertex(),
+                });
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Number of vertices that all vertex buffers have.
+    fn vertex_count(&self) -> usize {
+        self.vertices
+            .iter()
+            .map(|v| v.vertices.len() / v.format.stride as usize)
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Find vertex buffer and offset of the attribute.
+    fn find_attribute(
+        &self,
+        name: &str,
+        format: rendy_core::hal::format::Format,
+    ) -> Option<(usize, usize)> {
+        self.vertices.iter().enumerate().find_map(|(buffer, v)| {
+            v.format
+                .attributes
+                .iter()
+                .find(|attr| attr.name() == name && attr.element().format == format)
+                .map(|attr| (buffer, attr.element().offset as usize))
+        })
+    }
+
+    /// Indices of the mesh or sequential indices if there is no index buffer.
+    fn index_list(&self, len: usize) -> Vec<u32> {
+        match &self.indices {
+            None => (0..len as u32).collect(),
+            Some(RawIndices {
+                indices,
+                index_type: rendy_core::hal::IndexType::U16,
+            }) => indices
+                .chunks_exact(2)
+                .map(|i| u16::from_ne_bytes([i[0], i[1]]) as u32)
+                .collect(),
+            Some(RawIndices {
+                indices,
+                index_type: rendy_core::hal::IndexType::U32,
+            }) => indices
+                .chunks_exact(4)
+                .map(|i| u32::from_ne_bytes([i[0], i[1], i[2], i[3]]))
+                .collect(),
+        }
+    }
+
+    /// Keep only vertices with specified indices in all vertex buffers.
+    fn select_vertices(&mut self, selected: &[usize]) {
+        for v in &mut self.vertices {
+            let stride = v.format.stride as usize;
+            let mut vertices = Vec::with_capacity(selected.len() * stride);
+            for &index in selected {
+                vertices.extend_from_slice(&v.vertices[index * stride..(index + 1) * stride]);
+            }
+            v.vertices = Cow::Owned(vertices);
+        }
+    }
+
+    /// Replace index buffer keeping its index type
+    /// or choosing smallest one that can address `vertex_count` vertices.
+    /// 16-bit indices are widened if `vertex_count` doesn't fit them.
+    fn replace_indices(&mut self, indices: Vec<u32>, vertex_count: usize) {
+        let wide = vertex_count > u16::MAX as usize + 1
+            || self
+                .indices
+                .as_ref()
+                .is_some_and(|raw| raw.index_type == rendy_core::hal::IndexType::U32);
+
+        self.indices = Some(if wide {
+            RawIndices {
+                indices: cast_cow(Cow::Owned(indices)),
+                index_type: rendy_core::hal::IndexType::U32,
+            }
+        } else {
+            RawIndices {
+                indices: cast_cow(Cow::Owned(
+                    indices.into_iter().map(|i| i as u16).collect::<Vec<u16>>(),
+                )),
+                index_type: rendy_core::hal::IndexType::U16,
+            }
+        });
+    }
+
     /// Builds and returns the new mesh.
     ///
     /// A mesh expects all vertex buffers to have the same number of elements.
@@ -219,12 +559,13 @@ impl<'a> MeshBuilder<'a> {
         B: rendy_core::hal::Backend,
     {
         let align = factory.physical().limits().non_coherent_atom_size;
-        let mut len = self
+        let vertex_count = self
             .vertices
             .iter()
             .map(|v| v.vertices.len() as u32 / v.format.stride)
             .min()
             .unwrap_or(0);
+        let mut len = vertex_count;
 
         let buffer_size = self
             .vertices
@@ -307,9 +648,7 @@ impl<'a> MeshBuilder<'a> {
                         0,
                         &indices,
                         None,
-                        BufferState::new(queue)
-                            .with_access(rendy_core::hal::buffer::Access::INDEX_BUFFER_READ)
-                            .with_stage(rendy_core::hal::pso::PipelineStage::VERTEX_INPUT),
+                        BufferState::for_usage(queue, rendy_core::hal::buffer::Usage::INDEX),
                     )?;
                 }
 
@@ -324,21 +663,232 @@ impl<'a> MeshBuilder<'a> {
                     0,
                     staging,
                     None,
-                    BufferState::new(queue)
-                        .with_access(rendy_core::hal::buffer::Access::VERTEX_BUFFER_READ)
-                        .with_stage(rendy_core::hal::pso::PipelineStage::VERTEX_INPUT),
+                    BufferState::for_usage(queue, rendy_core::hal::buffer::Usage::VERTEX),
                 )
                 .map_err(UploadError::Upload)?;
         }
 
+        if is_list(self.prim) && primitive_count(self.prim, len) * primitive_len(self.prim) != len {
+            log::warn!(
+                "Mesh with {} vertices doesn't form whole primitives of type {:?}",
+                len,
+                self.prim
+            );
+        }
+
+        let bounding_sphere = if self.compute_bounding_sphere {
+            self.bounding_sphere()
+        } else {
+            None
+        };
+
         Ok(Mesh {
             vertex_layouts,
             index_buffer,
             vertex_buffer: buffer,
             prim: self.prim,
             len,
+            vertex_count,
+            bounding_sphere,
+            queue,
         })
     }
+
+    /// Wrap vertex and index data that already resides in device buffers into a `Mesh`
+    /// without uploading anything, e.g. geometry generated by a compute pass.
+    ///
+    /// The mesh takes ownership of the buffers.
+    /// `layout` lists offset of vertex data of each format in `vertex_buffer`.
+    /// `indices` is index buffer with type and number of indices.
+    ///
+    /// Buffers must be created with `VERTEX` and `INDEX` usage respectively
+    /// and should be accessed with `queue` only.
+    /// Bounding sphere is not available for such meshes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if vertex data of any format or indices don't fit into the buffer.
+    pub fn from_buffers<B>(
+        queue: QueueId,
+        prim: rendy_core::hal::pso::Primitive,
+        vertex_buffer: Escape<Buffer<B>>,
+        vertex_count: u32,
+        layout: impl IntoIterator<Item = (u64, VertexFormat)>,
+        indices: Option<(Escape<Buffer<B>>, rendy_core::hal::IndexType, u32)>,
+    ) -> Mesh<B>
+    where
+        B: rendy_core::hal::Backend,
+    {
+        let vertex_layouts = prebuilt_layouts(layout, vertex_count, vertex_buffer.size());
+
+        let (index_buffer, len) = match indices {
+            None => (None, vertex_count),
+            Some((buffer, index_type, count)) => {
+                assert!(
+                    count as u64 * index_stride(index_type) as u64 <= buffer.size(),
+                    "{} indices of type {:?} don't fit into buffer of size {}",
+                    count,
+                    index_type,
+                    buffer.size()
+                );
+                (Some(IndexBuffer { buffer, index_type }), count)
+            }
+        };
+
+        Mesh {
+            vertex_layouts,
+            index_buffer,
+            vertex_buffer,
+            prim,
+            len,
+            vertex_count,
+            bounding_sphere: None,
+            queue,
+        }
+    }
+}
+
+/// Validate and sort layouts of vertex data in prebuilt buffer.
+fn prebuilt_layouts(
+    layout: impl IntoIterator<Item = (u64, VertexFormat)>,
+    vertex_count: u32,
+    buffer_size: u64,
+) -> Vec<VertexBufferLayout> {
+    let mut vertex_layouts: Vec<_> = layout
+        .into_iter()
+        .map(|(offset, format)| {
+            assert!(
+                offset + format.stride as u64 * vertex_count as u64 <= buffer_size,
+                "{} vertices of format {:?} at offset {} don't fit into buffer of size {}",
+                vertex_count,
+                format,
+                offset,
+                buffer_size
+            );
+            VertexBufferLayout { offset, format }
+        })
+        .collect();
+
+    vertex_layouts.sort_unstable_by(|a, b| a.format.cmp(&b.format));
+    vertex_layouts
+}
+
+/// Number of vertices in single primitive of the list.
+fn primitive_len(prim: rendy_core::hal::pso::Primitive) -> u32 {
+    use rendy_core::hal::pso::Primitive;
+    match prim {
+        Primitive::PointList => 1,
+        Primitive::LineList | Primitive::LineStrip => 2,
+        Primitive::TriangleList | Primitive::TriangleStrip => 3,
+        Primitive::PatchList(size) => size as u32,
+    }
+}
+
+fn is_list(prim: rendy_core::hal::pso::Primitive) -> bool {
+    use rendy_core::hal::pso::Primitive;
+    !matches!(prim, Primitive::LineStrip | Primitive::TriangleStrip)
+}
+
+fn primitive_count(prim: rendy_core::hal::pso::Primitive, len: u32) -> u32 {
+    let primitive_len = primitive_len(prim);
+    match primitive_len {
+        0 => 0,
+        _ if is_list(prim) => len / primitive_len,
+        _ => len.saturating_sub(primitive_len - 1),
+    }
+}
+
+fn read_vec3(raw: &RawVertices<'_>, offset: usize, index: usize) -> [f32; 3] {
+    let start = index * raw.format.stride as usize + offset;
+    let mut vec = [0.0; 3];
+    for (i, c) in raw.vertices[start..start + 12].chunks_exact(4).enumerate() {
+        vec[i] = f32::from_ne_bytes([c[0], c[1], c[2], c[3]]);
+    }
+    vec
+}
+
+/// Ritter's bounding sphere, shrunk to the farthest point afterwards.
+fn bounding_sphere(positions: &[[f32; 3]]) -> Option<([f32; 3], f32)> {
+    let farthest = |from: [f32; 3]| {
+        positions
+            .iter()
+            .copied()
+            .max_by(|&a, &b| {
+                let a = length(sub(a, from));
+                let b = length(sub(b, from));
+                a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .unwrap_or(from)
+    };
+
+    let x = *positions.first()?;
+    let y = farthest(x);
+    let z = farthest(y);
+
+    let mut center = [
+        (y[0] + z[0]) * 0.5,
+        (y[1] + z[1]) * 0.5,
+        (y[2] + z[2]) * 0.5,
+    ];
+    let mut radius = length(sub(z, y)) * 0.5;
+
+    for &p in positions {
+        let d = length(sub(p, center));
+        if d > radius {
+            let grow = (d - radius) * 0.5;
+            let dir = sub(p, center);
+            center = add(
+                center,
+                [dir[0] * grow / d, dir[1] * grow / d, dir[2] * grow / d],
+            );
+            radius += grow;
+        }
+    }
+
+    let radius = positions
+        .iter()
+        .map(|&p| length(sub(p, center)))
+        .fold(0.0, f32::max);
+
+    Some((center, radius))
+}
+
+fn vec3_bits(v: [f32; 3]) -> [u32; 3] {
+    [v[0].to_bits(), v[1].to_bits(), v[2].to_bits()]
+}
+
+fn add(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn length(v: [f32; 3]) -> f32 {
+    dot(v, v).sqrt()
+}
+
+/// Normalize vector. Zero vector is left as is.
+fn normalize(v: [f32; 3]) -> [f32; 3] {
+    let length = length(v);
+    if length > 0.0 {
+        [v[0] / length, v[1] / length, v[2] / length]
+    } else {
+        v
+    }
 }
 
 fn align_by(align: usize, value: usize) -> usize {
@@ -354,6 +904,9 @@ pub struct Mesh<B: rendy_core::hal::Backend> {
     index_buffer: Option<IndexBuffer<B>>,
     prim: rendy_core::hal::pso::Primitive,
     len: u32,
+    vertex_count: u32,
+    bounding_sphere: Option<([f32; 3], f32)>,
+    queue: QueueId,
 }
 
 impl<B> Mesh<B>
@@ -365,11 +918,30 @@ where
         MeshBuilder::new()
     }
 
+    /// Bounding sphere of the mesh vertices as center and radius.
+    ///
+    /// Computed only if enabled with `MeshBuilder::with_bounding_sphere`.
+    /// `None` for meshes without vertices or positions.
+    pub fn bounding_sphere(&self) -> Option<([f32; 3], f32)> {
+        self.bounding_sphere
+    }
+
     /// rendy_core::hal::pso::Primitive type of the `Mesh`
     pub fn primitive(&self) -> rendy_core::hal::pso::Primitive {
         self.prim
     }
 
+    /// Input assembler state for pipelines that draw this mesh.
+    pub fn input_assembler(&self) -> rendy_core::hal::pso::InputAssemblerDesc {
+        rendy_core::hal::pso::InputAssemblerDesc::new(self.prim)
+    }
+
+    /// Returns the number of primitives that will be drawn.
+    /// Vertices that don't form whole primitive are not counted.
+    pub fn primitive_count(&self) -> u32 {
+        primitive_count(self.prim, self.len)
+    }
+
     /// Returns the number of vertices that will be drawn
     /// in the mesh.  For a mesh with no index buffer,
     /// this is the same as the number of vertices, or for
@@ -379,6 +951,119 @@ where
         self.len
     }
 
+    /// Returns the number of vertices stored in the mesh.
+    pub fn vertex_count(&self) -> u32 {
+        self.vertex_count
+    }
+
+    /// Buffer that stores vertex data of all formats of the mesh.
+    /// Use [`vertex_offset`] to find where data of particular format begins.
+    ///
+    /// [`vertex_offset`]: #method.vertex_offset
+    pub fn vertex_buffer(&self) -> &Buffer<B> {
+        &self.vertex_buffer
+    }
+
+    /// Returns offset in bytes from the start of [`vertex_buffer`]
+    /// to vertex data compatible with `format`.
+    /// Returns `None` if mesh has no compatible vertex data.
+    ///
+    /// [`vertex_buffer`]: #method.vertex_buffer
+    pub fn vertex_offset(&self, format: &VertexFormat) -> Option<u64> {
+        find_compatible_buffer(&self.vertex_layouts, format)
+            .map(|index| self.vertex_layouts[index].offset)
+    }
+
+    /// Returns index buffer, index type and number of indices.
+    /// Returns `None` if mesh has no indices.
+    pub fn index_buffer(&self) -> Option<(&Buffer<B>, rendy_core::hal::IndexType, u32)> {
+        self.index_buffer
+            .as_ref()
+            .map(|index_buffer| (&*index_buffer.buffer, index_buffer.index_type, self.len))
+    }
+
+    /// Overwrite range of vertices of the format `V` starting from `offset`
+    /// without reallocating the vertex buffer.
+    ///
+    /// Update will be submitted upon next [`Factory::flush_uploads`] or [`Factory::maintain`]
+    /// before commands submitted after that point.
+    ///
+    /// [`Factory::flush_uploads`]: ../rendy_factory/struct.Factory.html#method.flush_uploads
+    /// [`Factory::maintain`]: ../rendy_factory/struct.Factory.html#method.maintain
+    ///
+    /// # Safety
+    ///
+    /// `factory` must be the one used to build the mesh.
+    /// If vertex buffer is host-visible it is written immediately,
+    /// so device must not use the mesh until then.
+    pub unsafe fn update_vertices<V>(
+        &mut self,
+        factory: &Factory<B>,
+        offset: u32,
+        vertices: &[V],
+    ) -> Result<(), MeshUpdateError>
+    where
+        V: AsVertex + 'static,
+    {
+        let range = vertex_update_range(
+            &self.vertex_layouts,
+            self.vertex_count,
+            &V::vertex(),
+            offset,
+            vertices.len(),
+        )?;
+        upload_range(
+            factory,
+            &mut self.vertex_buffer,
+            range.start,
+            vertices,
+            BufferState::for_usage(self.queue, rendy_core::hal::buffer::Usage::VERTEX),
+        )
+    }
+
+    /// Overwrite range of indices starting from `offset`
+    /// without reallocating the index buffer.
+    /// Indices must be of the same type the mesh was built with.
+    ///
+    /// # Safety
+    ///
+    /// See [`update_vertices`].
+    ///
+    /// [`update_vertices`]: #method.update_vertices
+    pub unsafe fn update_indices<'a>(
+        &mut self,
+        factory: &Factory<B>,
+        offset: u32,
+        indices: impl Into<Indices<'a>>,
+    ) -> Result<(), MeshUpdateError> {
+        let index_buffer = self
+            .index_buffer
+            .as_mut()
+            .ok_or(MeshUpdateError::IndexTypeMismatch)?;
+        let (bytes, index_type) = match indices.into() {
+            Indices::None => return Ok(()),
+            Indices::U16(i) => (cast_cow(i), rendy_core::hal::IndexType::U16),
+            Indices::U32(i) => (cast_cow(i), rendy_core::hal::IndexType::U32),
+        };
+        if index_type != index_buffer.index_type {
+            return Err(MeshUpdateError::IndexTypeMismatch);
+        }
+
+        let stride = index_stride(index_type) as u64;
+        let start = offset as u64 * stride;
+        if start + bytes.len() as u64 > index_buffer.buffer.size() {
+            return Err(MeshUpdateError::OutOfBounds);
+        }
+
+        upload_range(
+            factory,
+            &mut index_buffer.buffer,
+            start,
+            &bytes,
+            BufferState::for_usage(self.queue, rendy_core::hal::buffer::Usage::INDEX),
+        )
+    }
+
     fn get_vertex_iter<'a>(
         &'a self,
         formats: &[VertexFormat],
@@ -465,6 +1150,133 @@ where
     }
 }
 
+/// Error returned by `Mesh::update_vertices` and `Mesh::update_indices`.
+#[derive(Debug)]
+pub enum MeshUpdateError {
+    /// Mesh has no vertex buffer with requested format.
+    Incompatible(Incompatible),
+    /// Mesh has no index buffer or it has different index type.
+    IndexTypeMismatch,
+    /// Updated range doesn't fit into the buffer.
+    OutOfBounds,
+    /// Failed to map host-visible buffer.
+    Map(rendy_core::hal::device::MapError),
+    /// Failed to upload through staging buffer.
+    Upload(UploadError),
+}
+
+impl std::fmt::Display for MeshUpdateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MeshUpdateError::Incompatible(err) => write!(f, "Failed to update mesh: {}", err),
+            MeshUpdateError::IndexTypeMismatch => {
+                write!(f, "Failed to update mesh: index type mismatch")
+            }
+            MeshUpdateError::OutOfBounds => {
+                write!(f, "Failed to update mesh: range is out of bounds")
+            }
+            MeshUpdateError::Map(err) => write!(f, "Failed to update mesh: {:?}", err),
+            MeshUpdateError::Upload(err) => write!(f, "Failed to update mesh: {:?}", err),
+        }
+    }
+}
+
+impl std::error::Error for MeshUpdateError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MeshUpdateError::Incompatible(err) => Some(err),
+            MeshUpdateError::IndexTypeMismatch | MeshUpdateError::OutOfBounds => None,
+            MeshUpdateError::Map(err) => Some(err),
+            MeshUpdateError::Upload(err) => Some(err),
+        }
+    }
+}
+
+/// Error returned by `MeshBuilder::generate_normals`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GenerateNormalsError {
+    /// Mesh has no `position` attribute of `Rgb32Sfloat` format.
+    NoPositions,
+    /// Normals can be generated only for triangle lists.
+    UnsupportedPrimitive(rendy_core::hal::pso::Primitive),
+    /// Index buffer references vertex that doesn't exist.
+    IndexOutOfBounds(u32),
+}
+
+impl std::fmt::Display for GenerateNormalsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GenerateNormalsError::NoPositions => {
+                write!(f, "Failed to generate normals: mesh has no positions")
+            }
+            GenerateNormalsError::UnsupportedPrimitive(prim) => write!(
+                f,
+                "Failed to generate normals: primitive {:?} is not supported",
+                prim
+            ),
+            GenerateNormalsError::IndexOutOfBounds(index) => write!(
+                f,
+                "Failed to generate normals: index {} is out of bounds",
+                index
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GenerateNormalsError {}
+
+/// Find byte range of vertex buffer occupied by `count` vertices of `format` starting from `offset`.
+fn vertex_update_range(
+    vertex_layouts: &[VertexBufferLayout],
+    vertex_count: u32,
+    format: &VertexFormat,
+    offset: u32,
+    count: usize,
+) -> Result<std::ops::Range<u64>, MeshUpdateError> {
+    let layout = vertex_layouts
+        .iter()
+        .find(|layout| layout.format == *format)
+        .ok_or_else(|| {
+            MeshUpdateError::Incompatible(Incompatible {
+                not_found: format.clone(),
+                in_formats: vertex_layouts.iter().map(|l| l.format.clone()).collect(),
+            })
+        })?;
+
+    if offset as u64 + count as u64 > vertex_count as u64 {
+        return Err(MeshUpdateError::OutOfBounds);
+    }
+
+    let stride = format.stride as u64;
+    let start = layout.offset + offset as u64 * stride;
+    Ok(start..start + count as u64 * stride)
+}
+
+/// Write `content` to the buffer directly if it is host-visible or through staging buffer otherwise.
+unsafe fn upload_range<B, T>(
+    factory: &Factory<B>,
+    buffer: &mut Buffer<B>,
+    offset: u64,
+    content: &[T],
+    state: BufferState,
+) -> Result<(), MeshUpdateError>
+where
+    B: rendy_core::hal::Backend,
+    T: 'static + Copy,
+{
+    if content.is_empty() {
+        Ok(())
+    } else if buffer.visible() {
+        factory
+            .upload_visible_buffer(buffer, offset, content)
+            .map_err(MeshUpdateError::Map)
+    } else {
+        factory
+            .upload_buffer(buffer, offset, content, Some(state), state)
+            .map_err(MeshUpdateError::Upload)
+    }
+}
+
 /// Error type returned by `Mesh::bind` in case of mesh's vertex buffers are incompatible with requested vertex formats.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Incompatible {
@@ -574,3 +1386,232 @@ macro_rules! impl_builder_from_vec {
 }
 
 impl_builder_from_vec!(A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{AsAttribute, Normal, Position};
+
+    #[test]
+    fn test_deduplicate() {
+        let a = Position([0.0, 0.0, 0.0]);
+        let b = Position([1.0, 0.0, 0.0]);
+        let c = Position([0.0, 1.0, 0.0]);
+        let d = Position([1.0, 1.0, 0.0]);
+
+        let mut builder = MeshBuilder::new().with_vertices(vec![a, b, c, c, b, d]);
+        let ratio = builder.deduplicate();
+
+        assert_eq!(ratio, 1.5);
+        assert_eq!(
+            builder.vertices[0].vertices.len(),
+            4 * size_of::<Position>()
+        );
+
+        let indices = builder.indices.as_ref().unwrap();
+        assert_eq!(indices.index_type, rendy_core::hal::IndexType::U16);
+        let indices: Vec<u16> = indices
+            .indices
+            .chunks_exact(2)
+            .map(|i| u16::from_ne_bytes([i[0], i[1]]))
+            .collect();
+        assert_eq!(indices, vec![0, 1, 2, 2, 1, 3]);
+    }
+
+    fn cube() -> MeshBuilder<'static> {
+        let positions: Vec<Position> = (0..8)
+            .map(|i| Position([(i & 1) as f32, (i >> 1 & 1) as f32, (i >> 2 & 1) as f32]))
+            .collect();
+        MeshBuilder::new()
+            .with_vertices(positions)
+            .with_indices(vec![
+                0u16, 2, 3, 0, 3, 1, 4, 5, 7, 4, 7, 6, 0, 1, 5, 0, 5, 4, 2, 6, 7, 2, 7, 3, 0, 4, 6,
+                0, 6, 2, 1, 3, 7, 1, 7, 5,
+            ])
+    }
+
+    #[test]
+    fn test_bounding_sphere() {
+        let t = (1.0 + 5f32.sqrt()) / 2.0;
+        let icosahedron: Vec<Position> = [
+            [-1.0, t, 0.0],
+            [1.0, t, 0.0],
+            [-1.0, -t, 0.0],
+            [1.0, -t, 0.0],
+            [0.0, -1.0, t],
+            [0.0, 1.0, t],
+            [0.0, -1.0, -t],
+            [0.0, 1.0, -t],
+            [t, 0.0, -1.0],
+            [t, 0.0, 1.0],
+            [-t, 0.0, -1.0],
+            [-t, 0.0, 1.0],
+        ]
+        .iter()
+        .map(|&v| Position(normalize(v)))
+        .collect();
+
+        let builder = MeshBuilder::new().with_vertices(icosahedron.clone());
+        let (center, radius) = builder.bounding_sphere().unwrap();
+        assert!(length(center) < 1e-3);
+        assert!((radius - 1.0).abs() < 1e-3);
+        for p in icosahedron {
+            assert!(length(sub(p.0, center)) <= radius);
+        }
+
+        assert_eq!(MeshBuilder::new().bounding_sphere(), None);
+        assert_eq!(
+            MeshBuilder::new()
+                .with_vertices(Vec::<Position>::new())
+                .bounding_sphere(),
+            None
+        );
+    }
+
+    fn normals(builder: &MeshBuilder<'_>) -> Vec<[f32; 3]> {
+        let (buffer, offset) = builder.find_attribute("normal", Normal::FORMAT).unwrap();
+        (0..builder.vertex_count())
+            .map(|index| read_vec3(&builder.vertices[buffer], offset, index))
+            .collect()
+    }
+
+    #[test]
+    fn test_generate_normals_hard_edges() {
+        let mut builder = cube();
+        builder.generate_normals(Some(30f32.to_radians())).unwrap();
+
+        assert_eq!(builder.vertex_count(), 24);
+        assert_eq!(builder.index_list(24).len(), 36);
+        for normal in normals(&builder) {
+            assert_eq!(normal.iter().map(|c| c.abs()).sum::<f32>(), 1.0);
+        }
+    }
+
+    #[test]
+    fn test_generate_normals_smooth() {
+        let mut builder = cube().with_indices(vec![
+            0u16, 2, 3, 0, 3, 1, 4, 5, 7, 4, 7, 6, 0, 1, 5, 0, 5, 4, 2, 6, 7, 2, 7, 3, 0, 4, 6, 0,
+            6, 2, 1, 3, 7, 1, 7, 5, 0, 1, 1,
+        ]);
+        builder.generate_normals(None).unwrap();
+
+        assert_eq!(builder.vertex_count(), 8);
+        for (index, normal) in normals(&builder).into_iter().enumerate() {
+            assert!((length(normal) - 1.0).abs() < 1e-6);
+            // Points away from the cube center.
+            let corner = [
+                (index & 1) as f32 - 0.5,
+                (index >> 1 & 1) as f32 - 0.5,
+                (index >> 2 & 1) as f32 - 0.5,
+            ];
+            assert!(dot(normal, corner) > 0.0);
+        }
+
+        assert_eq!(
+            MeshBuilder::new()
+                .with_vertices(vec![Normal([0.0; 3])])
+                .generate_normals(None)
+                .unwrap_err(),
+            GenerateNormalsError::NoPositions
+        );
+    }
+
+    #[test]
+    fn test_replace_indices_widens() {
+        let mut builder = MeshBuilder::new().with_indices(vec![0u16, 1, 2]);
+        builder.replace_indices(vec![0, 1, 70000], 70001);
+
+        let indices = builder.indices.as_ref().unwrap();
+        assert_eq!(indices.index_type, rendy_core::hal::IndexType::U32);
+        assert_eq!(builder.index_list(70001), vec![0, 1, 70000]);
+
+        let mut builder = MeshBuilder::new().with_indices(vec![0u16, 1, 2]);
+        builder.replace_indices(vec![2, 1, 0], 3);
+        let indices = builder.indices.as_ref().unwrap();
+        assert_eq!(indices.index_type, rendy_core::hal::IndexType::U16);
+    }
+
+    #[test]
+    fn test_primitive_count() {
+        use rendy_core::hal::pso::Primitive;
+
+        assert_eq!(primitive_count(Primitive::PointList, 5), 5);
+        assert_eq!(primitive_count(Primitive::LineList, 5), 2);
+        assert_eq!(primitive_count(Primitive::LineStrip, 5), 4);
+        assert_eq!(primitive_count(Primitive::LineStrip, 1), 0);
+        assert_eq!(primitive_count(Primitive::TriangleList, 7), 2);
+        assert_eq!(primitive_count(Primitive::TriangleStrip, 5), 3);
+        assert_eq!(primitive_count(Primitive::PatchList(4), 8), 2);
+        assert_eq!(primitive_count(Primitive::PatchList(0), 8), 0);
+
+        let builder = MeshBuilder::new();
+        assert_eq!(builder.prim_type(), Primitive::TriangleList);
+        assert_eq!(
+            builder.with_prim_type(Primitive::LineList).prim_type(),
+            Primitive::LineList
+        );
+    }
+
+    #[test]
+    fn test_vertex_update_range() {
+        use crate::{Color, PosColor};
+
+        let layouts = vec![
+            VertexBufferLayout {
+                offset: 0,
+                format: Position::vertex(),
+            },
+            VertexBufferLayout {
+                offset: 4 * size_of::<Position>() as u64,
+                format: Color::vertex(),
+            },
+        ];
+
+        let stride = size_of::<Color>() as u64;
+        assert_eq!(
+            vertex_update_range(&layouts, 4, &Color::vertex(), 1, 2).unwrap(),
+            4 * size_of::<Position>() as u64 + stride
+                ..4 * size_of::<Position>() as u64 + 3 * stride
+        );
+
+        assert!(matches!(
+            vertex_update_range(&layouts, 4, &Color::vertex(), 3, 2),
+            Err(MeshUpdateError::OutOfBounds)
+        ));
+        assert!(matches!(
+            vertex_update_range(&layouts, 4, &PosColor::vertex(), 0, 1),
+            Err(MeshUpdateError::Incompatible(_))
+        ));
+    }
+
+    #[test]
+    fn test_prebuilt_layouts() {
+        use crate::Color;
+
+        let colors = 4 * size_of::<Position>() as u64;
+        let size = colors + 4 * size_of::<Color>() as u64;
+        let layouts = prebuilt_layouts(
+            vec![(colors, Color::vertex()), (0, Position::vertex())],
+            4,
+            size,
+        );
+        assert_eq!(
+            find_compatible_buffer(&layouts, &Position::vertex()),
+            Some(0)
+        );
+        assert_eq!(layouts[0].offset, 0);
+        assert_eq!(layouts[1].offset, colors);
+        assert!(is_slice_sorted_by_key(&layouts, |l| &l.format));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_prebuilt_layouts_out_of_bounds() {
+        let size = 4 * size_of::<Position>() as u64;
+        prebuilt_layouts(
+            vec![(size_of::<Position>() as u64, Position::vertex())],
+            4,
+            size,
+        );
+    }
+}
@@ -48,7 +48,7 @@ lazy_static::lazy_static! {
         ShaderKind::Fragment,
         SourceLanguage::GLSL,
         "main",
-    ).precompile().unwrap();
+    ).with_prelude(format!("#define MAX_LIGHTS {}", MAX_LIGHTS)).precompile().unwrap();
 
     static ref SHADERS: rendy::shader::ShaderSetBuilder = rendy::shader::ShaderSetBuilder::default()
         .with_vertex(&*VERTEX).unwrap()
@@ -201,7 +201,7 @@ where
         assert!(images.is_empty());
         assert_eq!(set_layouts.len(), 1);
 
-        let frames = ctx.frames_in_flight as _;
+        let frames = ctx.frames_in_flight;
         let align = factory
             .physical()
             .limits()
@@ -219,12 +219,11 @@ where
             )
             .unwrap();
 
-        let mut sets = Vec::new();
-        for index in 0..frames {
+        let sets: Vec<_> = factory
+            .create_descriptor_sets(set_layouts[0].clone(), frames)
+            .unwrap();
+        for (index, set) in sets.iter().enumerate() {
             unsafe {
-                let set = factory
-                    .create_descriptor_set(set_layouts[0].clone())
-                    .unwrap();
                 factory.write_descriptor_sets(Some(hal::pso::DescriptorSetWrite {
                     set: set.raw(),
                     binding: 0,
@@ -237,7 +236,6 @@ where
                         },
                     )),
                 }));
-                sets.push(set);
             }
         }
 
@@ -406,10 +404,7 @@ fn main() {
                 .into_pass()
                 .with_surface(
                     surface,
-                    hal::window::Extent2D {
-                        width: size.width as _,
-                        height: size.height as _,
-                    },
+                    rendy::core::window_extent(size.width, size.height),
                     Some(hal::command::ClearValue {
                         color: hal::command::ClearColor {
                             float32: [1.0, 1.0, 1.0, 1.0],
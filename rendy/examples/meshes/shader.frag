@@ -21,7 +21,7 @@ layout(std140, set = 0, binding = 0) uniform Args {
     int pad1;
     int pad2;
     int pad3;
-    Light lights[32];
+    Light lights[MAX_LIGHTS];
 };
 
 void main() {
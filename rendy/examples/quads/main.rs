@@ -489,7 +489,7 @@ where
                         flags: hal::pso::PipelineCreationFlags::empty(),
                         parent: hal::pso::BasePipeline::None,
                     },
-                    None,
+                    factory.pipeline_cache(),
                 )
                 .map_err(NodeBuildError::Pipeline)?
         };
@@ -537,14 +537,18 @@ where
             {
                 let (stages, barriers) = gfx_acquire_barriers(ctx, &*buffers, None);
                 log::info!("Acquire {:?} : {:#?}", stages, barriers);
-                encoder.pipeline_barrier(stages, hal::memory::Dependencies::empty(), barriers);
+                if !barriers.is_empty() {
+                    encoder.pipeline_barrier(stages, hal::memory::Dependencies::empty(), barriers);
+                }
             }
             encoder.dispatch(QUADS, 1, 1);
 
             {
                 let (stages, barriers) = gfx_release_barriers(ctx, &*buffers, None);
                 log::info!("Release {:?} : {:#?}", stages, barriers);
-                encoder.pipeline_barrier(stages, hal::memory::Dependencies::empty(), barriers);
+                if !barriers.is_empty() {
+                    encoder.pipeline_barrier(stages, hal::memory::Dependencies::empty(), barriers);
+                }
             }
         }
 
@@ -600,10 +604,7 @@ fn build_graph<B: hal::Backend>(
             .into_pass()
             .with_surface(
                 surface,
-                hal::window::Extent2D {
-                    width: size.width as _,
-                    height: size.height as _,
-                },
+                rendy::core::window_extent(size.width, size.height),
                 Some(hal::command::ClearValue {
                     color: hal::command::ClearColor {
                         float32: [1.0, 1.0, 1.0, 1.0],
@@ -628,6 +629,10 @@ fn main() {
     let rendy = AnyWindowedRendy::init_auto(&config, window, &event_loop).unwrap();
     rendy::with_any_windowed_rendy!((rendy)
         (mut factory, mut families, surface, window) => {
+            // Pipelines are compiled faster when driver finds them in the cache from previous run.
+            let pipeline_cache_path = std::env::temp_dir().join("rendy-quads-pipeline-cache.bin");
+            factory.load_pipeline_cache(&pipeline_cache_path).unwrap();
+
             let mut graph = Some(build_graph(&mut factory, &mut families, surface, &window));
 
             let started = std::time::Instant::now();
@@ -674,6 +679,10 @@ fn main() {
                     );
 
                     graph.take().unwrap().dispose(&mut factory, &());
+
+                    if let Err(err) = factory.save_pipeline_cache(&pipeline_cache_path) {
+                        log::warn!("{}", err);
+                    }
                 }
             });
         }
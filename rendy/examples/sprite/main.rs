@@ -169,12 +169,7 @@ where
 
         let texture = texture_builder
             .build(
-                ImageState {
-                    queue,
-                    stage: hal::pso::PipelineStage::FRAGMENT_SHADER,
-                    access: hal::image::Access::SHADER_READ,
-                    layout: hal::image::Layout::ShaderReadOnlyOptimal,
-                },
+                ImageState::for_layout(queue, hal::image::Layout::ShaderReadOnlyOptimal),
                 factory,
             )
             .unwrap();
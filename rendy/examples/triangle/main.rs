@@ -265,10 +265,7 @@ fn main() {
                     .into_pass()
                     .with_surface(
                         surface,
-                        hal::window::Extent2D {
-                            width,
-                            height,
-                        },
+                        rendy::core::window_extent(width, height),
                         Some(hal::command::ClearValue {
                             color: hal::command::ClearColor {
                                 float32: [1.0, 1.0, 1.0, 1.0],
@@ -0,0 +1,151 @@
+//!
+//! Clears color image outside of render pass and reads it back to the host.
+//! Skipped when no device is available.
+//!
+
+use rendy::{
+    command::{Execute, Families},
+    factory::{Config, Factory},
+    hal::{self, Backend},
+    init::AnyRendy,
+    memory::{Data, Download},
+    resource::{BufferInfo, ImageInfo},
+};
+
+const SIDE: u32 = 4;
+const VALUE: f32 = 0.25;
+
+fn clear_and_read<B: Backend>(factory: &mut Factory<B>, families: &mut Families<B>) {
+    use hal::{
+        command::{BufferImageCopy, ClearColor},
+        image::{Access, Layout, Offset, SubresourceLayers, SubresourceRange},
+        memory::{Barrier, Dependencies},
+        pso::PipelineStage,
+    };
+
+    let image = factory
+        .create_image(
+            ImageInfo {
+                kind: hal::image::Kind::D2(SIDE, SIDE, 1, 1),
+                levels: 1,
+                format: hal::format::Format::R32Sfloat,
+                tiling: hal::image::Tiling::Optimal,
+                view_caps: hal::image::ViewCapabilities::empty(),
+                usage: hal::image::Usage::TRANSFER_DST | hal::image::Usage::TRANSFER_SRC,
+            },
+            Data,
+        )
+        .unwrap();
+
+    let size = (SIDE * SIDE) as u64 * std::mem::size_of::<f32>() as u64;
+    let mut buffer = factory
+        .create_buffer(
+            BufferInfo {
+                size,
+                usage: hal::buffer::Usage::TRANSFER_DST,
+            },
+            Download,
+        )
+        .unwrap();
+
+    let family = families
+        .with_capability::<Execute>()
+        .expect("Device must have graphics or compute queue");
+    let range = SubresourceRange {
+        aspects: hal::format::Aspects::COLOR,
+        levels: 0..1,
+        layers: 0..1,
+    };
+
+    unsafe {
+        factory
+            .execute_oneshot(families, family, |encoder| {
+                encoder.pipeline_barrier(
+                    PipelineStage::TOP_OF_PIPE..PipelineStage::TRANSFER,
+                    Dependencies::empty(),
+                    Some(Barrier::Image {
+                        states: (Access::empty(), Layout::Undefined)
+                            ..(Access::TRANSFER_WRITE, Layout::TransferDstOptimal),
+                        target: image.raw(),
+                        families: None,
+                        range: range.clone(),
+                    }),
+                );
+
+                encoder.clear_color_image(
+                    image.raw(),
+                    Layout::TransferDstOptimal,
+                    ClearColor {
+                        float32: [VALUE, 0.0, 0.0, 0.0],
+                    },
+                    Some(range.clone()),
+                );
+
+                encoder.pipeline_barrier(
+                    PipelineStage::TRANSFER..PipelineStage::TRANSFER,
+                    Dependencies::empty(),
+                    Some(Barrier::Image {
+                        states: (Access::TRANSFER_WRITE, Layout::TransferDstOptimal)
+                            ..(Access::TRANSFER_READ, Layout::TransferSrcOptimal),
+                        target: image.raw(),
+                        families: None,
+                        range: range.clone(),
+                    }),
+                );
+
+                encoder.copy_image_to_buffer(
+                    image.raw(),
+                    Layout::TransferSrcOptimal,
+                    buffer.raw(),
+                    Some(BufferImageCopy {
+                        buffer_offset: 0,
+                        buffer_width: SIDE,
+                        buffer_height: SIDE,
+                        image_layers: SubresourceLayers {
+                            aspects: hal::format::Aspects::COLOR,
+                            level: 0,
+                            layers: 0..1,
+                        },
+                        image_offset: Offset::ZERO,
+                        image_extent: hal::image::Extent {
+                            width: SIDE,
+                            height: SIDE,
+                            depth: 1,
+                        },
+                    }),
+                );
+
+                encoder.pipeline_barrier(
+                    PipelineStage::TRANSFER..PipelineStage::HOST,
+                    Dependencies::empty(),
+                    Some(Barrier::Buffer {
+                        states: hal::buffer::Access::TRANSFER_WRITE..hal::buffer::Access::HOST_READ,
+                        target: buffer.raw(),
+                        families: None,
+                        range: hal::buffer::SubRange::WHOLE,
+                    }),
+                );
+            })
+            .unwrap();
+    }
+
+    let mut mapping = buffer.map(factory.device(), 0..size).unwrap();
+    let texels = unsafe { mapping.read::<f32>(factory.device(), 0..size).unwrap() };
+    assert_eq!(texels, &[VALUE; (SIDE * SIDE) as usize][..]);
+}
+
+#[test]
+fn clear_r32f_image() {
+    let config: Config = Default::default();
+    let rendy = match AnyRendy::init_auto(&config) {
+        Ok(rendy) => rendy,
+        Err(err) => {
+            eprintln!("No device to run the test on: {:?}", err);
+            return;
+        }
+    };
+
+    rendy::with_any_rendy!((rendy) (mut factory, mut families) => {
+        clear_and_read(&mut factory, &mut families);
+    });
+}
@@ -0,0 +1,84 @@
+//!
+//! Allocates many descriptor sets with different layouts in one call.
+//! Skipped when no device is available.
+//!
+
+use rendy::{
+    factory::{Config, Factory},
+    hal::{self, Backend},
+    init::AnyRendy,
+    resource::{DescriptorSet, DescriptorSetLayout, Escape, Handle},
+};
+
+const COUNT: usize = 16;
+
+fn layout<B: Backend>(
+    factory: &Factory<B>,
+    ty: hal::pso::BufferDescriptorType,
+) -> Handle<DescriptorSetLayout<B>> {
+    factory
+        .create_descriptor_set_layout(vec![hal::pso::DescriptorSetLayoutBinding {
+            binding: 0,
+            ty: hal::pso::DescriptorType::Buffer {
+                ty,
+                format: hal::pso::BufferDescriptorFormat::Structured {
+                    dynamic_offset: false,
+                },
+            },
+            count: 1,
+            stage_flags: hal::pso::ShaderStageFlags::GRAPHICS,
+            immutable_samplers: false,
+        }])
+        .unwrap()
+        .into()
+}
+
+fn allocate_sets<B: Backend>(factory: &Factory<B>) {
+    let uniform = layout(factory, hal::pso::BufferDescriptorType::Uniform);
+    let storage = layout(
+        factory,
+        hal::pso::BufferDescriptorType::Storage { read_only: true },
+    );
+
+    // Two runs of the same layout followed by alternating layouts.
+    let layouts: Vec<_> = (0..COUNT)
+        .map(|index| match index {
+            0..=5 => uniform.clone(),
+            6..=9 => storage.clone(),
+            _ if index % 2 == 0 => uniform.clone(),
+            _ => storage.clone(),
+        })
+        .collect();
+
+    let sets: Vec<Escape<DescriptorSet<B>>> = factory
+        .create_descriptor_sets_with_layouts(layouts.iter().cloned())
+        .unwrap();
+    assert_eq!(sets.len(), COUNT);
+
+    for (set, layout) in sets.iter().zip(&layouts) {
+        assert!(
+            std::ptr::eq::<DescriptorSetLayout<B>>(&**set.layout(), &**layout),
+            "Sets must be returned in order of layouts"
+        );
+    }
+
+    let raw: std::collections::HashSet<String> =
+        sets.iter().map(|set| format!("{:?}", set.raw())).collect();
+    assert_eq!(raw.len(), COUNT, "Allocated sets must be distinct");
+}
+
+#[test]
+fn descriptor_sets_with_layouts() {
+    let config: Config = Default::default();
+    let rendy = match AnyRendy::init_auto(&config) {
+        Ok(rendy) => rendy,
+        Err(err) => {
+            eprintln!("No device to run the test on: {:?}", err);
+            return;
+        }
+    };
+
+    rendy::with_any_rendy!((rendy) (factory, _families) => {
+        allocate_sets(&factory);
+    });
+}
@@ -0,0 +1,85 @@
+//!
+//! Sets and waits events from the host and from command buffers.
+//! Skipped when no device is available or backend doesn't implement events.
+//!
+
+use rendy::{
+    command::{supports_events, EventCreationError, Execute, Families},
+    factory::{Config, Factory},
+    hal::{pso::PipelineStage, Backend},
+    init::AnyRendy,
+};
+
+fn set_and_wait<B: Backend>(factory: &mut Factory<B>, families: &mut Families<B>) {
+    let backend = rendy::core::Backend::which::<B>();
+    let event = match factory.create_event() {
+        Ok(event) => event,
+        Err(EventCreationError::Unsupported(_)) => {
+            assert!(!supports_events(backend));
+            return;
+        }
+        Err(err) => panic!("Failed to create event: {}", err),
+    };
+    assert!(supports_events(backend));
+    assert!(!event.is_set(factory.device()).unwrap());
+
+    event.set(factory.device()).unwrap();
+    assert!(event.is_set(factory.device()).unwrap());
+    unsafe {
+        event.reset(factory.device()).unwrap();
+    }
+    assert!(!event.is_set(factory.device()).unwrap());
+
+    let family = families
+        .with_capability::<Execute>()
+        .expect("Device must have graphics or compute queue");
+
+    // Set by the device.
+    unsafe {
+        factory
+            .execute_oneshot(families, family, |encoder| {
+                encoder.set_event(&event, PipelineStage::BOTTOM_OF_PIPE);
+            })
+            .unwrap();
+    }
+    assert!(event.is_set(factory.device()).unwrap());
+
+    // Set by the host before submission, waited and reset by the device.
+    unsafe {
+        event.reset(factory.device()).unwrap();
+    }
+    event.set(factory.device()).unwrap();
+    unsafe {
+        factory
+            .execute_oneshot(families, family, |encoder| {
+                encoder.wait_events(
+                    &[&event],
+                    PipelineStage::HOST..PipelineStage::TOP_OF_PIPE,
+                    None,
+                );
+                encoder.reset_event(&event, PipelineStage::BOTTOM_OF_PIPE);
+            })
+            .unwrap();
+    }
+    assert!(!event.is_set(factory.device()).unwrap());
+
+    unsafe {
+        factory.destroy_event(event);
+    }
+}
+
+#[test]
+fn events() {
+    let config: Config = Default::default();
+    let rendy = match AnyRendy::init_auto(&config) {
+        Ok(rendy) => rendy,
+        Err(err) => {
+            eprintln!("No device to run the test on: {:?}", err);
+            return;
+        }
+    };
+
+    rendy::with_any_rendy!((rendy) (mut factory, mut families) => {
+        set_and_wait(&mut factory, &mut families);
+    });
+}
@@ -0,0 +1,76 @@
+//!
+//! Submits command buffers of consecutive frames from `FrameCommandPools`
+//! alternating families and reclaims pools as frames complete.
+//! Skipped when no device is available.
+//!
+
+use rendy::{
+    command::{Families, FamilyId, OneShot, Submission},
+    factory::{Config, Factory},
+    frame::{Fences, FrameCommandPools, Frames},
+    hal::Backend,
+    init::AnyRendy,
+};
+
+const FRAMES: usize = 6;
+
+fn submit_frames<B: Backend>(factory: &mut Factory<B>, families: &mut Families<B>) {
+    let ids: Vec<FamilyId> = families
+        .as_slice()
+        .iter()
+        .map(|family| family.id())
+        .collect();
+    let mut frames = Frames::new();
+    let mut pools = FrameCommandPools::<B>::new();
+
+    for index in 0..FRAMES {
+        let id = ids[index % ids.len()];
+        let frame = frames.next();
+
+        let buffer = pools.buffer(frame, factory, families.family(id)).unwrap();
+        // Pool reclaimed from frame of another family must not be reused.
+        assert_eq!(buffer.family_id(), id);
+
+        let (submit, buffer) = buffer.begin(OneShot, ()).finish().submit_once();
+        let mut fence = factory.create_fence(false).unwrap();
+        unsafe {
+            families.family_mut(id).queue_mut(0).submit(
+                Some(Submission::new().submits(Some(submit))),
+                Some(&mut fence),
+            );
+            pools.submitted(frame, buffer);
+        }
+
+        let mut fences = Fences::new();
+        fences.push(fence);
+        frames.advance(fences);
+
+        let complete = frames.wait_complete(frame, factory, |fences| {
+            fences
+                .into_iter()
+                .for_each(|fence| factory.destroy_fence(fence))
+        });
+        pools.reclaim(&complete);
+    }
+
+    unsafe {
+        pools.dispose(factory);
+    }
+    frames.dispose(factory);
+}
+
+#[test]
+fn frame_command_pools() {
+    let config: Config = Default::default();
+    let rendy = match AnyRendy::init_auto(&config) {
+        Ok(rendy) => rendy,
+        Err(err) => {
+            eprintln!("No device to run the test on: {:?}", err);
+            return;
+        }
+    };
+
+    rendy::with_any_rendy!((rendy) (mut factory, mut families) => {
+        submit_frames(&mut factory, &mut families);
+    });
+}
@@ -0,0 +1,74 @@
+//!
+//! Clears images with `ClearNode` and reads them back with debug capture.
+//! Skipped when no device is available.
+//!
+
+use rendy::{
+    command::Families,
+    factory::{Config, Factory},
+    graph::{utility::ClearNode, GraphBuilder},
+    hal::{self, Backend},
+    init::AnyRendy,
+};
+
+const SIDE: u32 = 16;
+
+fn clear_node<B: Backend>(factory: &mut Factory<B>, families: &mut Families<B>) {
+    let kind = hal::image::Kind::D2(SIDE, SIDE, 1, 1);
+    let depth_format = hal::format::Format::D32Sfloat;
+
+    let mut builder = GraphBuilder::<B, ()>::new().with_debug_capture(true);
+    let color = builder.create_image(kind, 1, hal::format::Format::Rgba8Unorm, None);
+    let depth = builder.create_image(kind, 1, depth_format, None);
+    builder.add_node(ClearNode::<B>::builder(
+        color,
+        hal::command::ClearValue {
+            color: hal::command::ClearColor {
+                float32: [1.0, 0.0, 1.0, 1.0],
+            },
+        },
+    ));
+    builder.add_node(ClearNode::<B>::builder(
+        depth,
+        hal::command::ClearValue {
+            depth_stencil: hal::command::ClearDepthStencil {
+                depth: 0.25,
+                stencil: 0,
+            },
+        },
+    ));
+
+    let mut graph = builder.build(factory, families, &()).unwrap();
+    graph.run(factory, families, &());
+
+    let captured = graph.debug_capture(factory, families, color).unwrap();
+    assert_eq!(captured.data.len(), (SIDE * SIDE * 4) as usize);
+    for texel in captured.data.chunks(4) {
+        assert_eq!(texel, [255, 0, 255, 255]);
+    }
+
+    let captured = graph.debug_capture(factory, families, depth).unwrap();
+    assert_eq!(captured.format, depth_format);
+    assert_eq!(captured.data.len(), (SIDE * SIDE * 4) as usize);
+    for texel in captured.data.chunks(4) {
+        assert_eq!(texel, 0.25f32.to_ne_bytes());
+    }
+
+    graph.dispose(factory, &());
+}
+
+#[test]
+fn graph_clear_node() {
+    let config: Config = Default::default();
+    let rendy = match AnyRendy::init_auto(&config) {
+        Ok(rendy) => rendy,
+        Err(err) => {
+            eprintln!("No device to run the test on: {:?}", err);
+            return;
+        }
+    };
+
+    rendy::with_any_rendy!((rendy) (mut factory, mut families) => {
+        clear_node(&mut factory, &mut families);
+    });
+}
@@ -0,0 +1,348 @@
+//!
+//! Fills buffer with compute node and reads it back through graph API and `ReadbackNode`.
+//! Skipped when no device is available.
+//!
+
+use rendy::{
+    command::{
+        CommandBuffer, CommandPool, Compute, ExecutableState, Families, Family, MultiShot,
+        PendingState, SimultaneousUse, Submit,
+    },
+    factory::{Config, Factory},
+    frame::Frames,
+    graph::{
+        gfx_acquire_barriers, gfx_release_barriers, readback::ReadbackNode, BufferAccess,
+        GraphBuilder, GraphContext, Node, NodeBuffer, NodeBuildError, NodeDesc, NodeImage,
+        NodeSubmittable, OutputBufferError,
+    },
+    hal::{self, device::Device as _, Backend},
+    init::AnyRendy,
+    resource::{DescriptorSet, Escape, Handle},
+};
+
+/// Number of `u32` values written by `FillDesc` node.
+const COUNT: u32 = 64;
+
+/// Compute shader writing `index + 1` to each element of the buffer.
+///
+/// ```glsl
+/// #version 450
+/// layout(local_size_x = 1) in;
+/// layout(std430, set = 0, binding = 0) buffer Data { uint data[]; };
+/// void main() { data[gl_GlobalInvocationID.x] = gl_GlobalInvocationID.x + 1; }
+/// ```
+const FILL_SHADER: [u32; 137] = [
+    0x07230203, 0x00010000, 0x00000000, 0x00000017, 0x00000000, 0x00020011, 0x00000001, 0x0003000e,
+    0x00000000, 0x00000001, 0x0006000f, 0x00000005, 0x00000001, 0x6e69616d, 0x00000000, 0x00000002,
+    0x00060010, 0x00000001, 0x00000011, 0x00000001, 0x00000001, 0x00000001, 0x00040047, 0x00000002,
+    0x0000000b, 0x0000001c, 0x00040047, 0x00000003, 0x00000006, 0x00000004, 0x00050048, 0x00000004,
+    0x00000000, 0x00000023, 0x00000000, 0x00030047, 0x00000004, 0x00000003, 0x00040047, 0x00000005,
+    0x00000022, 0x00000000, 0x00040047, 0x00000005, 0x00000021, 0x00000000, 0x00020013, 0x00000006,
+    0x00030021, 0x00000007, 0x00000006, 0x00040015, 0x00000008, 0x00000020, 0x00000000, 0x00040017,
+    0x00000009, 0x00000008, 0x00000003, 0x00040020, 0x0000000a, 0x00000001, 0x00000009, 0x0004003b,
+    0x0000000a, 0x00000002, 0x00000001, 0x0003001d, 0x00000003, 0x00000008, 0x0003001e, 0x00000004,
+    0x00000003, 0x00040020, 0x0000000b, 0x00000002, 0x00000004, 0x0004003b, 0x0000000b, 0x00000005,
+    0x00000002, 0x00040020, 0x0000000c, 0x00000001, 0x00000008, 0x00040020, 0x0000000d, 0x00000002,
+    0x00000008, 0x00040015, 0x0000000e, 0x00000020, 0x00000001, 0x0004002b, 0x0000000e, 0x0000000f,
+    0x00000000, 0x0004002b, 0x00000008, 0x00000010, 0x00000000, 0x0004002b, 0x00000008, 0x00000011,
+    0x00000001, 0x00050036, 0x00000006, 0x00000001, 0x00000000, 0x00000007, 0x000200f8, 0x00000012,
+    0x00050041, 0x0000000c, 0x00000013, 0x00000002, 0x00000010, 0x0004003d, 0x00000008, 0x00000014,
+    0x00000013, 0x00050080, 0x00000008, 0x00000015, 0x00000014, 0x00000011, 0x00060041, 0x0000000d,
+    0x00000016, 0x00000005, 0x0000000f, 0x00000014, 0x0003003e, 0x00000016, 0x00000015, 0x000100fd,
+    0x00010038,
+];
+
+/// Content `FillDesc` node writes.
+fn expected() -> Vec<u32> {
+    (1..=COUNT).collect()
+}
+
+fn as_u32(bytes: &[u8]) -> Vec<u32> {
+    bytes
+        .chunks_exact(4)
+        .map(|chunk| u32::from_ne_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect()
+}
+
+#[derive(Debug)]
+struct Fill<B: Backend> {
+    pipeline_layout: B::PipelineLayout,
+    pipeline: B::ComputePipeline,
+    descriptor_set: Escape<DescriptorSet<B>>,
+    command_pool: CommandPool<B, Compute>,
+    command_buffer:
+        CommandBuffer<B, Compute, PendingState<ExecutableState<MultiShot<SimultaneousUse>>>>,
+    submit: Submit<B, SimultaneousUse>,
+}
+
+impl<'a, B> NodeSubmittable<'a, B> for Fill<B>
+where
+    B: Backend,
+{
+    type Submittable = &'a Submit<B, SimultaneousUse>;
+    type Submittables = &'a [Submit<B, SimultaneousUse>];
+}
+
+impl<B, T> Node<B, T> for Fill<B>
+where
+    B: Backend,
+    T: ?Sized,
+{
+    type Capability = Compute;
+
+    fn run<'a>(
+        &'a mut self,
+        _ctx: &GraphContext<B>,
+        _factory: &Factory<B>,
+        _aux: &T,
+        _frames: &'a Frames<B>,
+    ) -> &'a [Submit<B, SimultaneousUse>] {
+        std::slice::from_ref(&self.submit)
+    }
+
+    unsafe fn dispose(mut self, factory: &mut Factory<B>, _aux: &T) {
+        drop(self.submit);
+        self.command_pool
+            .free_buffers(Some(self.command_buffer.mark_complete()));
+        factory.destroy_command_pool(self.command_pool);
+        factory.device().destroy_compute_pipeline(self.pipeline);
+        factory
+            .device()
+            .destroy_pipeline_layout(self.pipeline_layout);
+        drop(self.descriptor_set);
+    }
+}
+
+/// Node writing `COUNT` values to the buffer with compute shader.
+#[derive(Debug, Default)]
+struct FillDesc;
+
+impl<B, T> NodeDesc<B, T> for FillDesc
+where
+    B: Backend,
+    T: ?Sized,
+{
+    type Node = Fill<B>;
+
+    fn buffers(&self) -> Vec<BufferAccess> {
+        vec![BufferAccess {
+            access: hal::buffer::Access::SHADER_WRITE,
+            stages: hal::pso::PipelineStage::COMPUTE_SHADER,
+            usage: hal::buffer::Usage::STORAGE,
+        }]
+    }
+
+    fn build<'a>(
+        self,
+        ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        family: &mut Family<B>,
+        _queue: usize,
+        _aux: &T,
+        buffers: Vec<NodeBuffer>,
+        images: Vec<NodeImage>,
+    ) -> Result<Self::Node, NodeBuildError> {
+        assert!(images.is_empty());
+        assert_eq!(buffers.len(), 1);
+
+        let buffer = ctx.get_buffer(buffers[0].id).unwrap();
+
+        let set_layout = Handle::from(
+            factory
+                .create_descriptor_set_layout(vec![hal::pso::DescriptorSetLayoutBinding {
+                    binding: 0,
+                    ty: hal::pso::DescriptorType::Buffer {
+                        ty: hal::pso::BufferDescriptorType::Storage { read_only: false },
+                        format: hal::pso::BufferDescriptorFormat::Structured {
+                            dynamic_offset: false,
+                        },
+                    },
+                    count: 1,
+                    stage_flags: hal::pso::ShaderStageFlags::COMPUTE,
+                    immutable_samplers: false,
+                }])
+                .map_err(NodeBuildError::OutOfMemory)?,
+        );
+
+        let (pipeline_layout, pipeline) = unsafe {
+            let module = factory
+                .device()
+                .create_shader_module(&FILL_SHADER)
+                .map_err(hal::pso::CreationError::Shader)
+                .map_err(NodeBuildError::Pipeline)?;
+
+            let pipeline_layout = factory
+                .device()
+                .create_pipeline_layout(
+                    std::iter::once(set_layout.raw()),
+                    std::iter::empty::<(hal::pso::ShaderStageFlags, std::ops::Range<u32>)>(),
+                )
+                .map_err(NodeBuildError::OutOfMemory)?;
+
+            let pipeline = factory.device().create_compute_pipeline(
+                &hal::pso::ComputePipelineDesc {
+                    shader: hal::pso::EntryPoint {
+                        entry: "main",
+                        module: &module,
+                        specialization: hal::pso::Specialization::EMPTY,
+                    },
+                    layout: &pipeline_layout,
+                    flags: hal::pso::PipelineCreationFlags::empty(),
+                    parent: hal::pso::BasePipeline::None,
+                },
+                factory.pipeline_cache(),
+            );
+            factory.device().destroy_shader_module(module);
+            (pipeline_layout, pipeline.map_err(NodeBuildError::Pipeline)?)
+        };
+
+        let descriptor_set = factory
+            .create_descriptor_set(set_layout.clone())
+            .map_err(NodeBuildError::OutOfMemory)?;
+
+        unsafe {
+            factory
+                .device()
+                .write_descriptor_sets(std::iter::once(hal::pso::DescriptorSetWrite {
+                    set: descriptor_set.raw(),
+                    binding: 0,
+                    array_offset: 0,
+                    descriptors: std::iter::once(hal::pso::Descriptor::Buffer(
+                        buffer.raw(),
+                        hal::buffer::SubRange::WHOLE,
+                    )),
+                }));
+        }
+
+        let mut command_pool = factory
+            .create_command_pool(family)
+            .map_err(NodeBuildError::OutOfMemory)?
+            .with_capability::<Compute>()
+            .expect("Graph builder must provide family with Compute capability");
+        let initial = command_pool.allocate_buffers(1).remove(0);
+        let mut recording = initial.begin(MultiShot(SimultaneousUse), ());
+        let mut encoder = recording.encoder();
+        encoder.bind_compute_pipeline(&pipeline);
+        unsafe {
+            encoder.bind_compute_descriptor_sets(
+                &pipeline_layout,
+                0,
+                std::iter::once(descriptor_set.raw()),
+                std::iter::empty::<u32>(),
+            );
+
+            let (stages, barriers) = gfx_acquire_barriers(ctx, &*buffers, None);
+            if !barriers.is_empty() {
+                encoder.pipeline_barrier(stages, hal::memory::Dependencies::empty(), barriers);
+            }
+
+            encoder.dispatch(COUNT, 1, 1);
+
+            let (stages, barriers) = gfx_release_barriers(ctx, &*buffers, None);
+            if !barriers.is_empty() {
+                encoder.pipeline_barrier(stages, hal::memory::Dependencies::empty(), barriers);
+            }
+        }
+
+        let (submit, command_buffer) = recording.finish().submit();
+
+        Ok(Fill {
+            pipeline_layout,
+            pipeline,
+            descriptor_set,
+            command_pool,
+            command_buffer,
+            submit,
+        })
+    }
+}
+
+fn output_buffer<B: Backend>(factory: &mut Factory<B>, families: &mut Families<B>) {
+    let size = COUNT as u64 * std::mem::size_of::<u32>() as u64;
+
+    let mut builder = GraphBuilder::<B, ()>::new();
+    let buffer = builder.create_buffer(size);
+    let unused = builder.create_buffer(size);
+    builder.add_node(FillDesc.builder().with_buffer(buffer));
+    builder.mark_output_buffer(buffer);
+    builder.mark_output_buffer(unused);
+
+    let mut graph = builder.build(factory, families, &()).unwrap();
+
+    for _ in 0..2 {
+        graph.run(factory, families, &());
+        let content = graph.download_output_buffer(factory, buffer).unwrap();
+        assert_eq!(as_u32(&content), expected());
+    }
+
+    // No node accesses this buffer, so graph never allocates it.
+    assert_eq!(
+        graph.download_output_buffer(factory, unused),
+        Err(OutputBufferError::NotOutput(unused))
+    );
+
+    graph.dispose(factory, &());
+}
+
+fn readback_node<B: Backend>(factory: &mut Factory<B>, families: &mut Families<B>) {
+    let size = COUNT as u64 * std::mem::size_of::<u32>() as u64;
+    let copies = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+
+    let mut builder = GraphBuilder::<B, ()>::new();
+    let buffer = builder.create_buffer(size);
+    let fill = builder.add_node(FillDesc.builder().with_buffer(buffer));
+    let sink = copies.clone();
+    builder.add_node(
+        ReadbackNode::<B>::builder(buffer, move |data| sink.lock().unwrap().push(data.to_vec()))
+            .with_dependency(fill),
+    );
+
+    let mut graph = builder.build(factory, families, &()).unwrap();
+
+    // More frames than can be in flight, so every slot is reused after its copy is delivered.
+    let frames = 5;
+    for _ in 0..frames {
+        graph.run(factory, families, &());
+    }
+
+    // Copies of frames still in flight are delivered on dispose.
+    graph.dispose(factory, &());
+
+    let copies = copies.lock().unwrap();
+    assert_eq!(copies.len(), frames);
+    for copy in copies.iter() {
+        assert_eq!(as_u32(copy), expected());
+    }
+}
+
+#[test]
+fn graph_output_buffer() {
+    let config: Config = Default::default();
+    let rendy = match AnyRendy::init_auto(&config) {
+        Ok(rendy) => rendy,
+        Err(err) => {
+            eprintln!("No device to run the test on: {:?}", err);
+            return;
+        }
+    };
+
+    rendy::with_any_rendy!((rendy) (mut factory, mut families) => {
+        output_buffer(&mut factory, &mut families);
+    });
+}
+
+#[test]
+fn graph_readback_node() {
+    let config: Config = Default::default();
+    let rendy = match AnyRendy::init_auto(&config) {
+        Ok(rendy) => rendy,
+        Err(err) => {
+            eprintln!("No device to run the test on: {:?}", err);
+            return;
+        }
+    };
+
+    rendy::with_any_rendy!((rendy) (mut factory, mut families) => {
+        readback_node(&mut factory, &mut families);
+    });
+}
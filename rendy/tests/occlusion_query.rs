@@ -0,0 +1,252 @@
+//!
+//! Draws a triangle within occlusion query and reads back number of samples that passed.
+//! Skipped when no device is available.
+//!
+
+use rendy::{
+    command::{Families, Graphics, QueryType},
+    factory::{Config, Factory},
+    hal::{self, device::Device as _, Backend},
+    init::AnyRendy,
+    memory::{Data, Dynamic},
+    resource::{BufferInfo, ImageInfo, ImageViewInfo},
+};
+
+const SIDE: u32 = 16;
+const FORMAT: hal::format::Format = hal::format::Format::Rgba8Unorm;
+
+/// Vertex shader passing 2D position from vertex buffer through.
+///
+/// ```glsl
+/// #version 450
+/// layout(location = 0) in vec2 pos;
+/// void main() { gl_Position = vec4(pos, 0.0, 1.0); }
+/// ```
+const VERTEX_SHADER: [u32; 98] = [
+    0x07230203, 0x00010000, 0x00000000, 0x00000012, 0x00000000, 0x00020011, 0x00000001, 0x0003000e,
+    0x00000000, 0x00000001, 0x0007000f, 0x00000000, 0x00000001, 0x6e69616d, 0x00000000, 0x00000002,
+    0x00000003, 0x00040047, 0x00000002, 0x0000001e, 0x00000000, 0x00040047, 0x00000003, 0x0000000b,
+    0x00000000, 0x00020013, 0x00000004, 0x00030021, 0x00000005, 0x00000004, 0x00030016, 0x00000006,
+    0x00000020, 0x00040017, 0x00000007, 0x00000006, 0x00000002, 0x00040017, 0x00000008, 0x00000006,
+    0x00000004, 0x00040020, 0x00000009, 0x00000001, 0x00000007, 0x00040020, 0x0000000a, 0x00000003,
+    0x00000008, 0x0004003b, 0x00000009, 0x00000002, 0x00000001, 0x0004003b, 0x0000000a, 0x00000003,
+    0x00000003, 0x0004002b, 0x00000006, 0x0000000b, 0x00000000, 0x0004002b, 0x00000006, 0x0000000c,
+    0x3f800000, 0x00050036, 0x00000004, 0x00000001, 0x00000000, 0x00000005, 0x000200f8, 0x0000000d,
+    0x0004003d, 0x00000007, 0x0000000e, 0x00000002, 0x00050051, 0x00000006, 0x0000000f, 0x0000000e,
+    0x00000000, 0x00050051, 0x00000006, 0x00000010, 0x0000000e, 0x00000001, 0x00070050, 0x00000008,
+    0x00000011, 0x0000000f, 0x00000010, 0x0000000b, 0x0000000c, 0x0003003e, 0x00000003, 0x00000011,
+    0x000100fd, 0x00010038,
+];
+
+/// Triangle covering the whole framebuffer.
+const TRIANGLE: [[f32; 2]; 3] = [[-1.0, -1.0], [3.0, -1.0], [-1.0, 3.0]];
+
+fn draw_and_count<B: Backend>(factory: &mut Factory<B>, families: &mut Families<B>) {
+    use hal::{
+        image::{Layout, SubresourceRange},
+        pass::{Attachment, AttachmentOps, Subpass, SubpassDesc},
+        pso,
+        query::ResultFlags,
+    };
+
+    let family = match families.with_capability::<Graphics>() {
+        Some(family) => family,
+        None => {
+            eprintln!("Device has no graphics queue");
+            return;
+        }
+    };
+
+    let pool = factory
+        .create_query_pool(QueryType::Occlusion { precise: false }, 1)
+        .unwrap();
+
+    let image = factory
+        .create_image(
+            ImageInfo {
+                kind: hal::image::Kind::D2(SIDE, SIDE, 1, 1),
+                levels: 1,
+                format: FORMAT,
+                tiling: hal::image::Tiling::Optimal,
+                view_caps: hal::image::ViewCapabilities::empty(),
+                usage: hal::image::Usage::COLOR_ATTACHMENT,
+            },
+            Data,
+        )
+        .unwrap();
+    let view = factory
+        .create_image_view(
+            image.into(),
+            ImageViewInfo {
+                view_kind: hal::image::ViewKind::D2,
+                format: FORMAT,
+                swizzle: hal::format::Swizzle::NO,
+                range: SubresourceRange {
+                    aspects: hal::format::Aspects::COLOR,
+                    levels: 0..1,
+                    layers: 0..1,
+                },
+            },
+        )
+        .unwrap();
+
+    let mut vertices = factory
+        .create_buffer(
+            BufferInfo {
+                size: std::mem::size_of_val(&TRIANGLE) as u64,
+                usage: hal::buffer::Usage::VERTEX,
+            },
+            Dynamic,
+        )
+        .unwrap();
+    unsafe {
+        factory
+            .upload_visible_buffer(&mut vertices, 0, &TRIANGLE)
+            .unwrap();
+    }
+
+    let rect = pso::Rect {
+        x: 0,
+        y: 0,
+        w: SIDE as i16,
+        h: SIDE as i16,
+    };
+
+    unsafe {
+        let render_pass = factory
+            .device()
+            .create_render_pass(
+                Some(Attachment {
+                    format: Some(FORMAT),
+                    samples: 1,
+                    ops: AttachmentOps::DONT_CARE,
+                    stencil_ops: AttachmentOps::DONT_CARE,
+                    layouts: Layout::Undefined..Layout::ColorAttachmentOptimal,
+                }),
+                Some(SubpassDesc {
+                    colors: &[(0, Layout::ColorAttachmentOptimal)],
+                    depth_stencil: None,
+                    inputs: &[],
+                    resolves: &[],
+                    preserves: &[],
+                }),
+                std::iter::empty::<hal::pass::SubpassDependency>(),
+            )
+            .unwrap();
+        let framebuffer = factory
+            .device()
+            .create_framebuffer(
+                &render_pass,
+                Some(view.raw()),
+                hal::image::Extent {
+                    width: SIDE,
+                    height: SIDE,
+                    depth: 1,
+                },
+            )
+            .unwrap();
+
+        let module = factory
+            .device()
+            .create_shader_module(&VERTEX_SHADER)
+            .unwrap();
+        let layout = factory
+            .device()
+            .create_pipeline_layout(
+                std::iter::empty::<B::DescriptorSetLayout>(),
+                std::iter::empty::<(pso::ShaderStageFlags, std::ops::Range<u32>)>(),
+            )
+            .unwrap();
+
+        let mut desc = pso::GraphicsPipelineDesc::new(
+            pso::GraphicsShaderSet {
+                vertex: pso::EntryPoint {
+                    entry: "main",
+                    module: &module,
+                    specialization: pso::Specialization::EMPTY,
+                },
+                hull: None,
+                domain: None,
+                geometry: None,
+                fragment: None,
+            },
+            pso::Primitive::TriangleList,
+            pso::Rasterizer::FILL,
+            &layout,
+            Subpass {
+                index: 0,
+                main_pass: &render_pass,
+            },
+        );
+        desc.vertex_buffers.push(pso::VertexBufferDesc {
+            binding: 0,
+            stride: std::mem::size_of::<[f32; 2]>() as u32,
+            rate: pso::VertexInputRate::Vertex,
+        });
+        desc.attributes.push(pso::AttributeDesc {
+            location: 0,
+            binding: 0,
+            element: pso::Element {
+                format: hal::format::Format::Rg32Sfloat,
+                offset: 0,
+            },
+        });
+        desc.blender.targets.push(pso::ColorBlendDesc {
+            mask: pso::ColorMask::empty(),
+            blend: None,
+        });
+        desc.baked_states.viewport = Some(pso::Viewport {
+            rect,
+            depth: 0.0..1.0,
+        });
+        desc.baked_states.scissor = Some(rect);
+        let pipeline = factory
+            .device()
+            .create_graphics_pipeline(&desc, None)
+            .unwrap();
+
+        factory
+            .execute_oneshot(families, family, |encoder| {
+                encoder.reset_query_pool(&pool, 0..1);
+                encoder.begin_query(&pool, 0);
+                {
+                    let mut pass =
+                        encoder.begin_render_pass_inline(&render_pass, &framebuffer, rect, &[]);
+                    pass.bind_graphics_pipeline(&pipeline);
+                    pass.bind_vertex_buffers(0, Some((vertices.raw(), 0)));
+                    pass.draw(0..3, 0..1);
+                }
+                encoder.end_query(&pool, 0);
+            })
+            .unwrap();
+
+        let results = factory
+            .get_query_results(&pool, 0..1, ResultFlags::WAIT)
+            .unwrap()
+            .expect("Results must be ready after waiting");
+        assert!(results[0] > 0, "No samples passed");
+
+        factory.device().destroy_graphics_pipeline(pipeline);
+        factory.device().destroy_pipeline_layout(layout);
+        factory.device().destroy_shader_module(module);
+        factory.device().destroy_framebuffer(framebuffer);
+        factory.device().destroy_render_pass(render_pass);
+        factory.destroy_query_pool(pool);
+    }
+}
+
+#[test]
+fn occlusion_query() {
+    let config: Config = Default::default();
+    let rendy = match AnyRendy::init_auto(&config) {
+        Ok(rendy) => rendy,
+        Err(err) => {
+            eprintln!("No device to run the test on: {:?}", err);
+            return;
+        }
+    };
+
+    rendy::with_any_rendy!((rendy) (mut factory, mut families) => {
+        draw_and_count(&mut factory, &mut families);
+    });
+}
@@ -0,0 +1,236 @@
+//!
+//! Renders a subpass recorded into secondary command buffers and reads back the result.
+//! Skipped when no device is available.
+//!
+
+use rendy::{
+    command::{Families, QueueId, RenderPassEncoder},
+    factory::{Config, Factory},
+    graph::{render::*, GraphBuilder, GraphContext, NodeBuffer, NodeImage},
+    hal::{self, Backend},
+    init::AnyRendy,
+    memory::Dynamic,
+    resource::{Buffer, BufferInfo, DescriptorSetLayout, Escape, Handle},
+    shader::{ShaderSet, ShaderSetBuilder, SpirvShader},
+};
+
+const SIDE: u32 = 16;
+const FORMAT: hal::format::Format = hal::format::Format::Rgba8Unorm;
+
+/// Vertex shader passing 2D position from vertex buffer through.
+///
+/// ```glsl
+/// #version 450
+/// layout(location = 0) in vec2 pos;
+/// void main() { gl_Position = vec4(pos, 0.0, 1.0); }
+/// ```
+const VERTEX_SHADER: [u32; 98] = [
+    0x07230203, 0x00010000, 0x00000000, 0x00000012, 0x00000000, 0x00020011, 0x00000001, 0x0003000e,
+    0x00000000, 0x00000001, 0x0007000f, 0x00000000, 0x00000001, 0x6e69616d, 0x00000000, 0x00000002,
+    0x00000003, 0x00040047, 0x00000002, 0x0000001e, 0x00000000, 0x00040047, 0x00000003, 0x0000000b,
+    0x00000000, 0x00020013, 0x00000004, 0x00030021, 0x00000005, 0x00000004, 0x00030016, 0x00000006,
+    0x00000020, 0x00040017, 0x00000007, 0x00000006, 0x00000002, 0x00040017, 0x00000008, 0x00000006,
+    0x00000004, 0x00040020, 0x00000009, 0x00000001, 0x00000007, 0x00040020, 0x0000000a, 0x00000003,
+    0x00000008, 0x0004003b, 0x00000009, 0x00000002, 0x00000001, 0x0004003b, 0x0000000a, 0x00000003,
+    0x00000003, 0x0004002b, 0x00000006, 0x0000000b, 0x00000000, 0x0004002b, 0x00000006, 0x0000000c,
+    0x3f800000, 0x00050036, 0x00000004, 0x00000001, 0x00000000, 0x00000005, 0x000200f8, 0x0000000d,
+    0x0004003d, 0x00000007, 0x0000000e, 0x00000002, 0x00050051, 0x00000006, 0x0000000f, 0x0000000e,
+    0x00000000, 0x00050051, 0x00000006, 0x00000010, 0x0000000e, 0x00000001, 0x00070050, 0x00000008,
+    0x00000011, 0x0000000f, 0x00000010, 0x0000000b, 0x0000000c, 0x0003003e, 0x00000003, 0x00000011,
+    0x000100fd, 0x00010038,
+];
+
+/// Fragment shader filling everything with green.
+///
+/// ```glsl
+/// #version 450
+/// layout(location = 0) out vec4 color;
+/// void main() { color = vec4(0.0, 1.0, 0.0, 1.0); }
+/// ```
+const FRAGMENT_SHADER: [u32; 70] = [
+    0x07230203, 0x00010000, 0x00000000, 0x0000000c, 0x00000000, 0x00020011, 0x00000001, 0x0003000e,
+    0x00000000, 0x00000001, 0x0006000f, 0x00000004, 0x00000001, 0x6e69616d, 0x00000000, 0x00000002,
+    0x00030010, 0x00000001, 0x00000007, 0x00040047, 0x00000002, 0x0000001e, 0x00000000, 0x00020013,
+    0x00000003, 0x00030021, 0x00000004, 0x00000003, 0x00030016, 0x00000005, 0x00000020, 0x00040017,
+    0x00000006, 0x00000005, 0x00000004, 0x00040020, 0x00000007, 0x00000003, 0x00000006, 0x0004003b,
+    0x00000007, 0x00000002, 0x00000003, 0x0004002b, 0x00000005, 0x00000008, 0x00000000, 0x0004002b,
+    0x00000005, 0x00000009, 0x3f800000, 0x0007002c, 0x00000006, 0x0000000a, 0x00000008, 0x00000009,
+    0x00000008, 0x00000009, 0x00050036, 0x00000003, 0x00000001, 0x00000000, 0x00000004, 0x000200f8,
+    0x0000000b, 0x0003003e, 0x00000002, 0x0000000a, 0x000100fd, 0x00010038,
+];
+
+/// Triangle covering the whole framebuffer.
+const TRIANGLE: [[f32; 2]; 3] = [[-1.0, -1.0], [3.0, -1.0], [-1.0, 3.0]];
+
+const CLEAR: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+const GREEN: [u8; 4] = [0, 255, 0, 255];
+
+#[derive(Debug, Default)]
+struct FillDesc;
+
+#[derive(Debug)]
+struct Fill<B: Backend> {
+    vertices: Escape<Buffer<B>>,
+}
+
+impl<B, T> SimpleGraphicsPipelineDesc<B, T> for FillDesc
+where
+    B: Backend,
+    T: ?Sized,
+{
+    type Pipeline = Fill<B>;
+
+    fn colors(&self) -> Vec<hal::pso::ColorBlendDesc> {
+        vec![hal::pso::ColorBlendDesc {
+            mask: hal::pso::ColorMask::ALL,
+            blend: None,
+        }]
+    }
+
+    fn depth_stencil(&self) -> Option<hal::pso::DepthStencilDesc> {
+        None
+    }
+
+    fn vertices(
+        &self,
+    ) -> Vec<(
+        Vec<hal::pso::Element<hal::format::Format>>,
+        hal::pso::ElemStride,
+        hal::pso::VertexInputRate,
+    )> {
+        vec![(
+            vec![hal::pso::Element {
+                format: hal::format::Format::Rg32Sfloat,
+                offset: 0,
+            }],
+            std::mem::size_of::<[f32; 2]>() as hal::pso::ElemStride,
+            hal::pso::VertexInputRate::Vertex,
+        )]
+    }
+
+    fn load_shader_set(&self, factory: &mut Factory<B>, _aux: &T) -> ShaderSet<B> {
+        let vertex = SpirvShader::new(
+            VERTEX_SHADER.to_vec(),
+            hal::pso::ShaderStageFlags::VERTEX,
+            "main",
+        );
+        let fragment = SpirvShader::new(
+            FRAGMENT_SHADER.to_vec(),
+            hal::pso::ShaderStageFlags::FRAGMENT,
+            "main",
+        );
+
+        ShaderSetBuilder::default()
+            .with_vertex(&vertex)
+            .unwrap()
+            .with_fragment(&fragment)
+            .unwrap()
+            .build(factory, Default::default())
+            .unwrap()
+    }
+
+    fn build<'a>(
+        self,
+        _ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        _queue: QueueId,
+        _aux: &T,
+        buffers: Vec<NodeBuffer>,
+        images: Vec<NodeImage>,
+        set_layouts: &[Handle<DescriptorSetLayout<B>>],
+    ) -> Result<Fill<B>, hal::pso::CreationError> {
+        assert!(buffers.is_empty());
+        assert!(images.is_empty());
+        assert!(set_layouts.is_empty());
+
+        let mut vertices = factory
+            .create_buffer(
+                BufferInfo {
+                    size: std::mem::size_of_val(&TRIANGLE) as u64,
+                    usage: hal::buffer::Usage::VERTEX,
+                },
+                Dynamic,
+            )
+            .unwrap();
+        unsafe {
+            factory
+                .upload_visible_buffer(&mut vertices, 0, &TRIANGLE)
+                .unwrap();
+        }
+
+        Ok(Fill { vertices })
+    }
+}
+
+impl<B, T> SimpleGraphicsPipeline<B, T> for Fill<B>
+where
+    B: Backend,
+    T: ?Sized,
+{
+    type Desc = FillDesc;
+
+    fn draw(
+        &mut self,
+        _layout: &B::PipelineLayout,
+        mut encoder: RenderPassEncoder<'_, B>,
+        _index: usize,
+        _aux: &T,
+    ) {
+        unsafe {
+            encoder.bind_vertex_buffers(0, Some((self.vertices.raw(), 0)));
+            encoder.draw(0..3, 0..1);
+        }
+    }
+
+    fn dispose(self, _factory: &mut Factory<B>, _aux: &T) {}
+}
+
+fn render_secondary<B: Backend>(factory: &mut Factory<B>, families: &mut Families<B>) {
+    let mut builder = GraphBuilder::<B, ()>::new().with_debug_capture(true);
+    let color = builder.create_image(
+        hal::image::Kind::D2(SIDE, SIDE, 1, 1),
+        1,
+        FORMAT,
+        Some(hal::command::ClearValue {
+            color: hal::command::ClearColor { float32: CLEAR },
+        }),
+    );
+    builder.add_node(
+        FillDesc
+            .builder()
+            .into_subpass()
+            .with_color(color)
+            .with_secondary_buffers()
+            .into_pass(),
+    );
+
+    let mut graph = builder.build(factory, families, &()).unwrap();
+
+    // Run more frames than can be in flight so secondary buffers are reset and recorded again.
+    for _ in 0..4 {
+        graph.run(factory, families, &());
+        let captured = graph.debug_capture(factory, families, color).unwrap();
+        assert_eq!(captured.data.len(), (SIDE * SIDE) as usize * GREEN.len());
+        for texel in captured.data.chunks(GREEN.len()) {
+            assert_eq!(texel, GREEN);
+        }
+    }
+
+    graph.dispose(factory, &());
+}
+
+#[test]
+fn render_pass_secondary() {
+    let config: Config = Default::default();
+    let rendy = match AnyRendy::init_auto(&config) {
+        Ok(rendy) => rendy,
+        Err(err) => {
+            eprintln!("No device to run the test on: {:?}", err);
+            return;
+        }
+    };
+
+    rendy::with_any_rendy!((rendy) (mut factory, mut families) => {
+        render_secondary(&mut factory, &mut families);
+    });
+}
@@ -0,0 +1,125 @@
+//!
+//! Grows buffer preserving its content and reads it back to the host.
+//! Skipped when no device is available.
+//!
+
+use rendy::{
+    command::{Execute, Families, QueueId},
+    factory::{BufferResize, BufferState, Config, Factory},
+    hal::{self, Backend},
+    init::AnyRendy,
+    memory::{Download, Dynamic},
+    resource::BufferInfo,
+};
+
+const CONTENT: [u32; 4] = [1, 2, 3, 4];
+
+fn grow_and_read<B: Backend>(factory: &mut Factory<B>, families: &mut Families<B>) {
+    use hal::{
+        buffer::{Access, SubRange, Usage},
+        command::BufferCopy,
+        memory::{Barrier, Dependencies},
+        pso::PipelineStage,
+    };
+
+    let size = std::mem::size_of_val(&CONTENT) as u64;
+    let mut buffer = factory
+        .create_buffer(
+            BufferInfo {
+                size,
+                usage: Usage::TRANSFER_SRC | Usage::TRANSFER_DST,
+            },
+            Dynamic,
+        )
+        .unwrap();
+    unsafe {
+        factory
+            .upload_visible_buffer(&mut buffer, 0, &CONTENT)
+            .unwrap();
+    }
+
+    let family = families
+        .with_capability::<Execute>()
+        .expect("Device must have graphics or compute queue");
+    let queue = QueueId { family, index: 0 };
+
+    unsafe {
+        buffer
+            .resize(
+                factory,
+                size * 4,
+                true,
+                None,
+                BufferState::for_usage(queue, Usage::TRANSFER_SRC),
+            )
+            .unwrap();
+    }
+    assert_eq!(buffer.size(), size * 4);
+    factory.wait_for_uploads(families).unwrap();
+
+    let mut download = factory
+        .create_buffer(
+            BufferInfo {
+                size,
+                usage: Usage::TRANSFER_DST,
+            },
+            Download,
+        )
+        .unwrap();
+
+    unsafe {
+        factory
+            .execute_oneshot(families, family, |encoder| {
+                encoder.copy_buffer(
+                    buffer.raw(),
+                    download.raw(),
+                    Some(BufferCopy {
+                        src: 0,
+                        dst: 0,
+                        size,
+                    }),
+                );
+
+                encoder.pipeline_barrier(
+                    PipelineStage::TRANSFER..PipelineStage::HOST,
+                    Dependencies::empty(),
+                    Some(Barrier::Buffer {
+                        states: Access::TRANSFER_WRITE..Access::HOST_READ,
+                        target: download.raw(),
+                        families: None,
+                        range: SubRange::WHOLE,
+                    }),
+                );
+            })
+            .unwrap();
+    }
+
+    let mut mapping = download.map(factory.device(), 0..size).unwrap();
+    let content = unsafe { mapping.read::<u32>(factory.device(), 0..size).unwrap() };
+    assert_eq!(content, &CONTENT[..]);
+    drop(mapping);
+
+    // Shrinking without preserving content only changes the size.
+    unsafe {
+        buffer
+            .resize(factory, size / 2, false, None, BufferState::new(queue))
+            .unwrap();
+    }
+    assert_eq!(buffer.size(), size / 2);
+}
+
+#[test]
+fn resize_buffer_preserve() {
+    let config: Config = Default::default();
+    let rendy = match AnyRendy::init_auto(&config) {
+        Ok(rendy) => rendy,
+        Err(err) => {
+            eprintln!("No device to run the test on: {:?}", err);
+            return;
+        }
+    };
+
+    rendy::with_any_rendy!((rendy) (mut factory, mut families) => {
+        grow_and_read(&mut factory, &mut families);
+    });
+}
@@ -0,0 +1,171 @@
+//!
+//! Uploads batch of textures with generated mip levels and reads every level back.
+//! Skipped when no device is available.
+//!
+
+use rendy::{
+    command::{Families, Graphics, QueueId},
+    factory::{Config, Factory, ImageState},
+    hal::{self, Backend},
+    init::AnyRendy,
+    memory::{Data, Download},
+    resource::{BufferInfo, Handle, Image, ImageInfo},
+};
+
+const SIDE: u32 = 8;
+const LEVELS: u8 = 4;
+
+/// Solid color of each uploaded texture.
+/// Every mip level of solid image must have the same color regardless of filtering.
+const COLORS: [[u8; 4]; 3] = [[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255]];
+
+fn upload_and_read<B: Backend>(factory: &mut Factory<B>, families: &mut Families<B>) {
+    use hal::{
+        command::BufferImageCopy,
+        image::{Layout, Offset, SubresourceLayers},
+        memory::{Barrier, Dependencies},
+        pso::PipelineStage,
+    };
+
+    let family = match families.with_capability::<Graphics>() {
+        Some(family) => family,
+        None => {
+            eprintln!("Device has no graphics queue");
+            return;
+        }
+    };
+
+    if !factory.supports_blit(hal::format::Format::Rgba8Unorm) {
+        eprintln!("Device can't blit Rgba8Unorm images");
+        return;
+    }
+
+    let next = ImageState::for_layout(QueueId { family, index: 0 }, Layout::TransferSrcOptimal);
+
+    let images: Vec<_> = COLORS
+        .iter()
+        .map(|color| {
+            let image: Handle<Image<B>> = factory
+                .create_image(
+                    ImageInfo {
+                        kind: hal::image::Kind::D2(SIDE, SIDE, 1, 1),
+                        levels: LEVELS,
+                        format: hal::format::Format::Rgba8Unorm,
+                        tiling: hal::image::Tiling::Optimal,
+                        view_caps: hal::image::ViewCapabilities::empty(),
+                        usage: hal::image::Usage::TRANSFER_DST | hal::image::Usage::TRANSFER_SRC,
+                    },
+                    Data,
+                )
+                .unwrap()
+                .into();
+
+            let content = vec![*color; (SIDE * SIDE) as usize];
+            unsafe {
+                factory
+                    .upload_image_with_mips(
+                        image.clone(),
+                        SIDE,
+                        SIDE,
+                        &content,
+                        hal::image::Filter::Linear,
+                        next,
+                    )
+                    .unwrap();
+            }
+            image
+        })
+        .collect();
+
+    // All three uploads are submitted together.
+    factory.wait_for_uploads(families).unwrap();
+
+    // Levels are copied one after another, each tightly packed.
+    let level_sides: Vec<u32> = (0..LEVELS).map(|level| SIDE >> level).collect();
+    let size: u64 = level_sides
+        .iter()
+        .map(|side| (side * side * 4) as u64)
+        .sum();
+
+    for (image, color) in images.iter().zip(&COLORS) {
+        let mut buffer = factory
+            .create_buffer(
+                BufferInfo {
+                    size,
+                    usage: hal::buffer::Usage::TRANSFER_DST,
+                },
+                Download,
+            )
+            .unwrap();
+
+        unsafe {
+            factory
+                .execute_oneshot(families, family, |encoder| {
+                    let mut offset = 0;
+                    let regions = level_sides.iter().enumerate().map(|(level, &side)| {
+                        let region = BufferImageCopy {
+                            buffer_offset: offset,
+                            buffer_width: side,
+                            buffer_height: side,
+                            image_layers: SubresourceLayers {
+                                aspects: hal::format::Aspects::COLOR,
+                                level: level as u8,
+                                layers: 0..1,
+                            },
+                            image_offset: Offset::ZERO,
+                            image_extent: hal::image::Extent {
+                                width: side,
+                                height: side,
+                                depth: 1,
+                            },
+                        };
+                        offset += (side * side * 4) as u64;
+                        region
+                    });
+
+                    encoder.copy_image_to_buffer(
+                        image.raw(),
+                        Layout::TransferSrcOptimal,
+                        buffer.raw(),
+                        regions,
+                    );
+
+                    encoder.pipeline_barrier(
+                        PipelineStage::TRANSFER..PipelineStage::HOST,
+                        Dependencies::empty(),
+                        Some(Barrier::Buffer {
+                            states: hal::buffer::Access::TRANSFER_WRITE
+                                ..hal::buffer::Access::HOST_READ,
+                            target: buffer.raw(),
+                            families: None,
+                            range: hal::buffer::SubRange::WHOLE,
+                        }),
+                    );
+                })
+                .unwrap();
+        }
+
+        let mut mapping = buffer.map(factory.device(), 0..size).unwrap();
+        let texels = unsafe { mapping.read::<[u8; 4]>(factory.device(), 0..size).unwrap() };
+        assert_eq!(texels.len() as u64 * 4, size);
+        for texel in texels {
+            assert_eq!(texel, color);
+        }
+    }
+}
+
+#[test]
+fn upload_image_with_mips() {
+    let config: Config = Default::default();
+    let rendy = match AnyRendy::init_auto(&config) {
+        Ok(rendy) => rendy,
+        Err(err) => {
+            eprintln!("No device to run the test on: {:?}", err);
+            return;
+        }
+    };
+
+    rendy::with_any_rendy!((rendy) (mut factory, mut families) => {
+        upload_and_read(&mut factory, &mut families);
+    });
+}
@@ -5,11 +5,13 @@ pub use rendy_core::hal::buffer::*;
 use {
     crate::{
         core::{device_owned, Device, DeviceId},
+        escape::Handle,
+        fits_requirements,
         memory::{Block, Heaps, MappedRange, MemoryBlock, MemoryUsage},
         CreationError,
     },
     relevant::Relevant,
-    rendy_core::hal::{device::Device as _, Backend},
+    rendy_core::hal::{device::Device as _, memory::Requirements, Backend},
 };
 
 /// Buffer info.
@@ -40,6 +42,83 @@ device_owned!(Buffer<B>);
 /// Alias for the error to create a buffer.
 pub type BufferCreationError = CreationError<rendy_core::hal::buffer::CreationError>;
 
+/// Buffer without memory bound to it.
+///
+/// Memory block satisfying [`requirements`] can be allocated separately
+/// and bound with [`Buffer::bind`].
+///
+/// [`requirements`]: #method.requirements
+/// [`Buffer::bind`]: struct.Buffer.html#method.bind
+#[derive(Debug)]
+pub struct UnboundBuffer<B: Backend> {
+    device: DeviceId,
+    raw: B::Buffer,
+    info: BufferInfo,
+    requirements: Requirements,
+    relevant: Relevant,
+}
+
+device_owned!(UnboundBuffer<B>);
+
+impl<B> UnboundBuffer<B>
+where
+    B: Backend,
+{
+    /// Create buffer without allocating memory for it.
+    ///
+    /// # Safety
+    ///
+    /// Usage of hal methods must be always valid.
+    pub unsafe fn create(
+        device: &Device<B>,
+        info: BufferInfo,
+    ) -> Result<Self, BufferCreationError> {
+        log::trace!("{:#?}", info);
+        assert_ne!(info.size, 0);
+
+        let raw = device
+            .create_buffer(info.size, info.usage)
+            .map_err(CreationError::Create)?;
+        let requirements = device.get_buffer_requirements(&raw);
+
+        Ok(UnboundBuffer {
+            device: device.id(),
+            raw,
+            info,
+            requirements,
+            relevant: Relevant,
+        })
+    }
+
+    /// Destroy buffer that was never bound.
+    ///
+    /// # Safety
+    ///
+    /// Buffer must not be referenced anywhere.
+    pub unsafe fn dispose(self, device: &Device<B>) {
+        self.assert_device_owner(device);
+        device.destroy_buffer(self.raw);
+        self.relevant.dispose();
+    }
+
+    /// Get reference to raw buffer resource
+    pub fn raw(&self) -> &B::Buffer {
+        &self.raw
+    }
+
+    /// Get buffer info.
+    pub fn info(&self) -> &BufferInfo {
+        &self.info
+    }
+
+    /// Get memory requirements of the buffer.
+    /// Memory block bound to the buffer must be of allowed type,
+    /// properly aligned and large enough.
+    pub fn requirements(&self) -> &Requirements {
+        &self.requirements
+    }
+}
+
 impl<B> Buffer<B>
 where
     B: Backend,
@@ -60,12 +139,9 @@ where
         memory_usage: impl MemoryUsage,
     ) -> Result<Self, BufferCreationError> {
         log::trace!("{:#?}@{:#?}", info, memory_usage);
-        assert_ne!(info.size, 0);
 
-        let mut buf = device
-            .create_buffer(info.size, info.usage)
-            .map_err(CreationError::Create)?;
-        let reqs = device.get_buffer_requirements(&buf);
+        let unbound = UnboundBuffer::create(device, info)?;
+        let reqs = unbound.requirements;
         let block = heaps
             .allocate(
                 device,
@@ -76,16 +152,60 @@ where
             )
             .map_err(CreationError::Allocate)?;
 
+        Self::bind(device, unbound, block, 0)
+    }
+
+    /// Bind memory `block` at `offset` to the `unbound` buffer.
+    ///
+    /// # Safety
+    ///
+    /// `block` must be allocated from `Heaps` instance
+    /// the buffer will be disposed with.
+    /// Memory bound to the buffer must not be used by other resources
+    /// unless they alias it intentionally.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `block` at `offset` doesn't satisfy
+    /// buffer's memory requirements.
+    pub unsafe fn bind(
+        device: &Device<B>,
+        unbound: UnboundBuffer<B>,
+        block: MemoryBlock<B>,
+        offset: u64,
+    ) -> Result<Self, BufferCreationError> {
+        unbound.assert_device_owner(device);
+        assert!(
+            fits_requirements(
+                &unbound.requirements,
+                block.memory_type(),
+                block.range(),
+                offset
+            ),
+            "Memory block {:?} at offset {} doesn't satisfy buffer requirements {:?}",
+            block,
+            offset,
+            unbound.requirements,
+        );
+
+        let UnboundBuffer {
+            device: device_id,
+            mut raw,
+            info,
+            relevant,
+            ..
+        } = unbound;
+
         device
-            .bind_buffer_memory(block.memory(), block.range().start, &mut buf)
+            .bind_buffer_memory(block.memory(), block.range().start + offset, &mut raw)
             .map_err(CreationError::Bind)?;
 
         Ok(Buffer {
-            device: device.id(),
-            raw: buf,
+            device: device_id,
+            raw,
             block,
             info,
-            relevant: Relevant,
+            relevant,
         })
     }
 
@@ -148,3 +268,137 @@ where
         self.info().size
     }
 }
+
+/// Buffer view info
+#[derive(Clone, Debug)]
+pub struct BufferViewInfo {
+    /// Format of texels in the view
+    pub format: rendy_core::hal::format::Format,
+    /// Range of the buffer to view
+    pub range: SubRange,
+}
+
+/// Format features required for buffer view over buffer with specified usage.
+/// Uniform texel buffers require `UNIFORM_TEXEL` and
+/// storage texel buffers require `STORAGE_TEXEL` feature.
+pub fn texel_buffer_features(usage: Usage) -> rendy_core::hal::format::BufferFeature {
+    let mut features = rendy_core::hal::format::BufferFeature::empty();
+    if usage.contains(Usage::UNIFORM_TEXEL) {
+        features |= rendy_core::hal::format::BufferFeature::UNIFORM_TEXEL;
+    }
+    if usage.contains(Usage::STORAGE_TEXEL) {
+        features |= rendy_core::hal::format::BufferFeature::STORAGE_TEXEL;
+    }
+    features
+}
+
+/// Generic buffer view resource wrapper.
+/// Used to bind buffer as uniform or storage texel buffer.
+#[derive(Debug)]
+pub struct BufferView<B: Backend> {
+    raw: B::BufferView,
+    buffer: Handle<Buffer<B>>,
+    info: BufferViewInfo,
+    relevant: Relevant,
+}
+
+device_owned!(BufferView<B> @ |view: &Self| view.buffer.device_id());
+/// Alias for the error to create a buffer view.
+pub type BufferViewCreationError = CreationError<ViewCreationError>;
+
+impl<B> BufferView<B>
+where
+    B: Backend,
+{
+    /// Create a buffer view.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if buffer was created
+    /// without `UNIFORM_TEXEL` or `STORAGE_TEXEL` usage.
+    pub fn create(
+        device: &Device<B>,
+        info: BufferViewInfo,
+        buffer: Handle<Buffer<B>>,
+    ) -> Result<Self, BufferViewCreationError> {
+        log::trace!("{:#?}@{:#?}", info, buffer);
+
+        buffer.assert_device_owner(device);
+
+        assert!(
+            buffer
+                .info()
+                .usage
+                .intersects(Usage::UNIFORM_TEXEL | Usage::STORAGE_TEXEL),
+            "Buffer must be created with texel buffer usage"
+        );
+
+        let view = unsafe {
+            device
+                .create_buffer_view(buffer.raw(), Some(info.format), info.range.clone())
+                .map_err(CreationError::Create)?
+        };
+
+        Ok(BufferView {
+            raw: view,
+            buffer,
+            info,
+            relevant: Relevant,
+        })
+    }
+
+    /// Destroy buffer view resource.
+    ///
+    /// # Safety
+    ///
+    /// Buffer view must not be used by any pending commands or referenced anywhere.
+    pub unsafe fn dispose(self, device: &Device<B>) {
+        device.destroy_buffer_view(self.raw);
+        drop(self.buffer);
+        self.relevant.dispose();
+    }
+
+    /// Get reference to raw buffer view resource.
+    pub fn raw(&self) -> &B::BufferView {
+        &self.raw
+    }
+
+    /// Get mutable reference to raw buffer view resource.
+    ///
+    /// # Safety
+    ///
+    /// Raw buffer view must not be replaced or destroyed.
+    pub unsafe fn raw_mut(&mut self) -> &mut B::BufferView {
+        &mut self.raw
+    }
+
+    /// Get buffer view info.
+    pub fn info(&self) -> &BufferViewInfo {
+        &self.info
+    }
+
+    /// Get buffer of this view.
+    pub fn buffer(&self) -> &Handle<Buffer<B>> {
+        &self.buffer
+    }
+
+    /// Get descriptor to write this view into descriptor set.
+    pub fn descriptor(&self) -> rendy_core::hal::pso::Descriptor<'_, B> {
+        rendy_core::hal::pso::Descriptor::TexelBuffer(&self.raw)
+    }
+}
+
+#[test]
+fn test_texel_buffer_features() {
+    use rendy_core::hal::format::BufferFeature;
+
+    assert_eq!(
+        texel_buffer_features(Usage::STORAGE | Usage::STORAGE_TEXEL),
+        BufferFeature::STORAGE_TEXEL
+    );
+    assert_eq!(
+        texel_buffer_features(Usage::UNIFORM_TEXEL | Usage::STORAGE_TEXEL),
+        BufferFeature::UNIFORM_TEXEL | BufferFeature::STORAGE_TEXEL
+    );
+    assert!(texel_buffer_features(Usage::STORAGE).is_empty());
+}
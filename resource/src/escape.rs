@@ -18,7 +18,7 @@ use {
         mem::ManuallyDrop,
         ops::{Deref, DerefMut},
         ptr::{drop_in_place, read},
-        sync::Arc,
+        sync::{Arc, Weak},
     },
 };
 
@@ -152,7 +152,17 @@ impl<T> Drop for Terminal<T> {
 /// Allows values to "escape" dropping by sending them to the `Terminal`.
 /// Permit sharing unlike [`Escape`]
 ///
+/// Cloning `Handle` doesn't clone the value, it creates another reference to the same value,
+/// just like cloning `Arc` does.
+/// The value escapes to the `Terminal` only when the last `Handle` is dropped,
+/// so any clone retained somewhere keeps the resource alive.
+/// [`Handle::strong_count`] can be used to find such leaks.
+///
+/// [`Escape`] on the other hand is unique owner of the value
+/// and escapes it as soon as dropped.
+///
 /// [`Escape`]: ./struct.Escape.html
+/// [`Handle::strong_count`]: ./struct.Handle.html#method.strong_count
 #[derive(Debug)]
 pub struct Handle<T> {
     inner: Arc<Escape<T>>,
@@ -174,6 +184,31 @@ impl<T> From<Escape<T>> for Handle<T> {
     }
 }
 
+impl<T> Handle<T> {
+    /// Create weak reference to the shared value.
+    /// Weak reference doesn't keep value from escaping
+    /// and can detect that it was sent to the `Terminal`.
+    pub fn downgrade(handle: &Self) -> WeakHandle<T> {
+        WeakHandle {
+            inner: Arc::downgrade(&handle.inner),
+        }
+    }
+
+    /// Number of `Handle`s that share the value, including this one.
+    /// Value escapes to the `Terminal` when it drops to zero.
+    ///
+    /// Intended for debugging. Other threads may clone or drop handles concurrently
+    /// so the value may be outdated by the time it is returned.
+    pub fn strong_count(handle: &Self) -> usize {
+        Arc::strong_count(&handle.inner)
+    }
+
+    /// Number of `WeakHandle`s pointing to the value.
+    pub fn weak_count(handle: &Self) -> usize {
+        Arc::weak_count(&handle.inner)
+    }
+}
+
 impl<T> Deref for Handle<T> {
     type Target = T;
 
@@ -181,3 +216,73 @@ impl<T> Deref for Handle<T> {
         &**self.inner
     }
 }
+
+/// Weak reference to the value shared by [`Handle`]s.
+/// Value escapes to the `Terminal` when last `Handle` is dropped,
+/// after that point `WeakHandle` can't be upgraded.
+///
+/// [`Handle`]: ./struct.Handle.html
+#[derive(Debug)]
+pub struct WeakHandle<T> {
+    inner: Weak<Escape<T>>,
+}
+
+impl<T> Clone for WeakHandle<T> {
+    fn clone(&self) -> Self {
+        WeakHandle {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T> WeakHandle<T> {
+    /// Get `Handle` to the value if it is still alive.
+    pub fn upgrade(&self) -> Option<Handle<T>> {
+        self.inner.upgrade().map(|inner| Handle { inner })
+    }
+
+    /// Check if value is still alive.
+    /// Returns `false` once all `Handle`s are dropped and value escaped to the `Terminal`.
+    pub fn is_alive(&self) -> bool {
+        self.inner.strong_count() > 0
+    }
+}
+
+#[test]
+fn test_weak_handle_detects_escaped_value() {
+    let mut terminal = Terminal::new();
+    let handle = Escape::share(terminal.escape(42));
+    let weak = Handle::downgrade(&handle);
+
+    assert!(weak.is_alive());
+    assert_eq!(weak.upgrade().map(|handle| *handle), Some(42));
+
+    drop(handle);
+    assert_eq!(terminal.drain().collect::<Vec<_>>(), vec![42]);
+
+    let recycled = Escape::share(terminal.escape(43));
+    assert!(!weak.is_alive());
+    assert!(weak.upgrade().is_none());
+    drop(recycled);
+    assert_eq!(terminal.drain().collect::<Vec<_>>(), vec![43]);
+}
+
+#[test]
+fn test_handle_strong_count() {
+    let mut terminal = Terminal::new();
+    let handle = Escape::share(terminal.escape(42));
+    assert_eq!(Handle::strong_count(&handle), 1);
+
+    let alias = handle.clone();
+    let weak = Handle::downgrade(&alias);
+    assert_eq!(Handle::strong_count(&handle), 2);
+    assert_eq!(Handle::weak_count(&handle), 1);
+
+    drop(alias);
+    assert_eq!(Handle::strong_count(&handle), 1);
+    assert_eq!(terminal.drain().count(), 0);
+
+    drop(handle);
+    drop(weak);
+    assert_eq!(terminal.drain().collect::<Vec<_>>(), vec![42]);
+}
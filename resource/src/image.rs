@@ -6,11 +6,12 @@ use {
     crate::{
         core::{device_owned, Device, DeviceId},
         escape::Handle,
+        fits_requirements,
         memory::{Block, Heaps, MemoryBlock, MemoryUsage},
         CreationError,
     },
     relevant::Relevant,
-    rendy_core::hal::{device::Device as _, format, Backend},
+    rendy_core::hal::{device::Device as _, format, memory::Requirements, Backend},
 };
 
 /// Image info.
@@ -53,25 +54,34 @@ device_owned!(Image<B>);
 /// Alias for the error to create an image.
 pub type ImageCreationError = CreationError<rendy_core::hal::image::CreationError>;
 
-impl<B> Image<B>
+/// Image without memory bound to it.
+///
+/// Memory block satisfying [`requirements`] can be allocated separately
+/// and bound with [`Image::bind`].
+///
+/// [`requirements`]: #method.requirements
+/// [`Image::bind`]: struct.Image.html#method.bind
+#[derive(Debug)]
+pub struct UnboundImage<B: Backend> {
+    device: DeviceId,
+    raw: B::Image,
+    info: ImageInfo,
+    requirements: Requirements,
+    relevant: Relevant,
+}
+
+device_owned!(UnboundImage<B>);
+
+impl<B> UnboundImage<B>
 where
     B: Backend,
 {
-    /// Create image.
+    /// Create image without allocating memory for it.
     ///
     /// # Safety
     ///
-    /// In order to guarantee that `Heap::allocate` will return
-    /// memory range owned by this `Device`,
-    /// this `Heaps` instance must always be used with this `Device` instance.
-    ///
-    /// Otherwise usage of hal methods must be always valid.
-    pub unsafe fn create(
-        device: &Device<B>,
-        heaps: &mut Heaps<B>,
-        info: ImageInfo,
-        memory_usage: impl MemoryUsage,
-    ) -> Result<Self, ImageCreationError> {
+    /// Usage of hal methods must be always valid.
+    pub unsafe fn create(device: &Device<B>, info: ImageInfo) -> Result<Self, ImageCreationError> {
         assert!(
             info.levels <= info.kind.num_levels(),
             "Number of mip leves ({}) cannot be greater than {} for given kind {:?}",
@@ -80,9 +90,9 @@ where
             info.kind,
         );
 
-        log::trace!("{:#?}@{:#?}", info, memory_usage);
+        log::trace!("{:#?}", info);
 
-        let mut img = device
+        let raw = device
             .create_image(
                 info.kind,
                 info.levels,
@@ -92,7 +102,105 @@ where
                 info.view_caps,
             )
             .map_err(CreationError::Create)?;
-        let reqs = device.get_image_requirements(&img);
+        let requirements = device.get_image_requirements(&raw);
+
+        Ok(UnboundImage {
+            device: device.id(),
+            raw,
+            info,
+            requirements,
+            relevant: Relevant,
+        })
+    }
+
+    /// Destroy image that was never bound.
+    ///
+    /// # Safety
+    ///
+    /// Image must not be referenced anywhere.
+    pub unsafe fn dispose(self, device: &Device<B>) {
+        self.assert_device_owner(device);
+        device.destroy_image(self.raw);
+        self.relevant.dispose();
+    }
+
+    /// Get reference for raw image resource.
+    pub fn raw(&self) -> &B::Image {
+        &self.raw
+    }
+
+    /// Get image info.
+    pub fn info(&self) -> &ImageInfo {
+        &self.info
+    }
+
+    /// Get memory requirements of the image.
+    /// Memory block bound to the image must be of allowed type,
+    /// properly aligned and large enough.
+    pub fn requirements(&self) -> &Requirements {
+        &self.requirements
+    }
+}
+
+/// Error returned when sparse (partially resident) images can't be used.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SparseImageError {
+    /// Device features required for sparse images of requested kind are not enabled.
+    MissingFeatures(rendy_core::hal::Features),
+
+    /// Sparse memory binding isn't available.
+    /// gfx-hal 0.5 reports sparse features but doesn't expose
+    /// `vkQueueBindSparse` or its equivalent on any backend.
+    Unsupported,
+}
+
+impl std::fmt::Display for SparseImageError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SparseImageError::MissingFeatures(features) => write!(
+                fmt,
+                "Sparse images require features {:?} that are not enabled",
+                features
+            ),
+            SparseImageError::Unsupported => write!(fmt, "Sparse binding is not supported"),
+        }
+    }
+}
+
+impl std::error::Error for SparseImageError {}
+
+/// Device features required for sparse binding of image with specified kind.
+pub fn sparse_image_features(kind: Kind) -> rendy_core::hal::Features {
+    rendy_core::hal::Features::SPARSE_BINDING
+        | match kind {
+            Kind::D3(_, _, _) => rendy_core::hal::Features::SPARSE_RESIDENCY_IMAGE_3D,
+            _ => rendy_core::hal::Features::SPARSE_RESIDENCY_IMAGE_2D,
+        }
+}
+
+impl<B> Image<B>
+where
+    B: Backend,
+{
+    /// Create image.
+    ///
+    /// # Safety
+    ///
+    /// In order to guarantee that `Heap::allocate` will return
+    /// memory range owned by this `Device`,
+    /// this `Heaps` instance must always be used with this `Device` instance.
+    ///
+    /// Otherwise usage of hal methods must be always valid.
+    pub unsafe fn create(
+        device: &Device<B>,
+        heaps: &mut Heaps<B>,
+        info: ImageInfo,
+        memory_usage: impl MemoryUsage,
+    ) -> Result<Self, ImageCreationError> {
+        log::trace!("{:#?}@{:#?}", info, memory_usage);
+
+        let unbound = UnboundImage::create(device, info)?;
+        let reqs = unbound.requirements;
         let block = heaps
             .allocate(
                 device,
@@ -103,16 +211,60 @@ where
             )
             .map_err(CreationError::Allocate)?;
 
+        Self::bind(device, unbound, block, 0)
+    }
+
+    /// Bind memory `block` at `offset` to the `unbound` image.
+    ///
+    /// # Safety
+    ///
+    /// `block` must be allocated from `Heaps` instance
+    /// the image will be disposed with.
+    /// Memory bound to the image must not be used by other resources
+    /// unless they alias it intentionally.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `block` at `offset` doesn't satisfy
+    /// image's memory requirements.
+    pub unsafe fn bind(
+        device: &Device<B>,
+        unbound: UnboundImage<B>,
+        block: MemoryBlock<B>,
+        offset: u64,
+    ) -> Result<Self, ImageCreationError> {
+        unbound.assert_device_owner(device);
+        assert!(
+            fits_requirements(
+                &unbound.requirements,
+                block.memory_type(),
+                block.range(),
+                offset
+            ),
+            "Memory block {:?} at offset {} doesn't satisfy image requirements {:?}",
+            block,
+            offset,
+            unbound.requirements,
+        );
+
+        let UnboundImage {
+            device: device_id,
+            mut raw,
+            info,
+            relevant,
+            ..
+        } = unbound;
+
         device
-            .bind_image_memory(block.memory(), block.range().start, &mut img)
+            .bind_image_memory(block.memory(), block.range().start + offset, &mut raw)
             .map_err(CreationError::Bind)?;
 
         Ok(Image {
-            device: device.id(),
-            raw: img,
+            device: device_id,
+            raw,
             block: Some(block),
             info,
-            relevant: Relevant,
+            relevant,
         })
     }
 
@@ -312,3 +464,17 @@ fn match_kind(kind: Kind, view_kind: ViewKind, view_caps: ViewCapabilities) -> b
         }
     }
 }
+
+#[test]
+fn test_sparse_image_features() {
+    use rendy_core::hal::Features;
+
+    assert_eq!(
+        sparse_image_features(Kind::D2(256, 256, 1, 1)),
+        Features::SPARSE_BINDING | Features::SPARSE_RESIDENCY_IMAGE_2D
+    );
+    assert_eq!(
+        sparse_image_features(Kind::D3(64, 64, 64)),
+        Features::SPARSE_BINDING | Features::SPARSE_RESIDENCY_IMAGE_3D
+    );
+}
@@ -49,6 +49,21 @@ where
     }
 }
 
+/// Check that memory of type `memory_type` occupying `range`
+/// can be bound at `offset` within the range to a resource with `requirements`.
+fn fits_requirements(
+    requirements: &rendy_core::hal::memory::Requirements,
+    memory_type: u32,
+    range: std::ops::Range<u64>,
+    offset: u64,
+) -> bool {
+    let start = range.start + offset;
+    requirements.type_mask & (1 << memory_type) != 0
+        && start.is_multiple_of(requirements.alignment)
+        && start <= range.end
+        && range.end - start >= requirements.size
+}
+
 impl<E> std::error::Error for CreationError<E>
 where
     E: std::error::Error + 'static,
@@ -61,3 +76,18 @@ where
         }
     }
 }
+
+#[test]
+fn test_fits_requirements() {
+    let requirements = rendy_core::hal::memory::Requirements {
+        size: 256,
+        alignment: 64,
+        type_mask: 0b0110,
+    };
+    assert!(fits_requirements(&requirements, 1, 0..256, 0));
+    assert!(fits_requirements(&requirements, 2, 64..512, 192));
+    assert!(!fits_requirements(&requirements, 0, 0..256, 0));
+    assert!(!fits_requirements(&requirements, 1, 0..256, 32));
+    assert!(!fits_requirements(&requirements, 1, 0..256, 64));
+    assert!(!fits_requirements(&requirements, 1, 0..128, 256));
+}
@@ -10,6 +10,12 @@ use {
 };
 
 /// Descriptor set layout info.
+///
+/// Descriptor binding flags from descriptor indexing
+/// (`PARTIALLY_BOUND`, `UPDATE_AFTER_BIND`, `VARIABLE_DESCRIPTOR_COUNT`)
+/// are not supported, as gfx-hal 0.5 can't pass them to the backend.
+/// All descriptors of the set must be valid when it is used,
+/// bound sets must not be updated, and sets are allocated with full binding sizes.
 #[derive(Clone, Debug)]
 pub struct DescriptorSetInfo {
     /// Bindings.
@@ -158,7 +164,7 @@ where
     }
 
     /// Get layout of descriptor set.
-    pub fn layout(&mut self) -> &Handle<DescriptorSetLayout<B>> {
+    pub fn layout(&self) -> &Handle<DescriptorSetLayout<B>> {
         &self.layout
     }
 }
@@ -0,0 +1,224 @@
+use rendy_core::hal::Features;
+
+const MAGIC: u32 = 0x0723_0203;
+const HEADER_LEN: usize = 5;
+const OP_CAPABILITY: u32 = 17;
+
+/// Capability declared by SPIR-V module with `OpCapability`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SpirvCapability(pub u32);
+
+impl SpirvCapability {
+    /// Name of the capability as in SPIR-V specification, if known.
+    pub fn name(&self) -> Option<&'static str> {
+        Some(match self.0 {
+            0 => "Matrix",
+            1 => "Shader",
+            2 => "Geometry",
+            3 => "Tessellation",
+            10 => "Float64",
+            11 => "Int64",
+            12 => "Int64Atomics",
+            22 => "Int16",
+            23 => "TessellationPointSize",
+            24 => "GeometryPointSize",
+            25 => "ImageGatherExtended",
+            27 => "StorageImageMultisample",
+            28 => "UniformBufferArrayDynamicIndexing",
+            29 => "SampledImageArrayDynamicIndexing",
+            30 => "StorageBufferArrayDynamicIndexing",
+            31 => "StorageImageArrayDynamicIndexing",
+            32 => "ClipDistance",
+            33 => "CullDistance",
+            34 => "ImageCubeArray",
+            35 => "SampleRateShading",
+            41 => "SparseResidency",
+            42 => "MinLod",
+            44 => "SampledCubeArray",
+            49 => "StorageImageExtendedFormats",
+            55 => "StorageImageReadWithoutFormat",
+            56 => "StorageImageWriteWithoutFormat",
+            57 => "MultiViewport",
+            61 => "GroupNonUniform",
+            62 => "GroupNonUniformVote",
+            63 => "GroupNonUniformArithmetic",
+            64 => "GroupNonUniformBallot",
+            65 => "GroupNonUniformShuffle",
+            66 => "GroupNonUniformShuffleRelative",
+            67 => "GroupNonUniformClustered",
+            68 => "GroupNonUniformQuad",
+            4427 => "DrawParameters",
+            5301 => "ShaderNonUniform",
+            5302 => "RuntimeDescriptorArray",
+            5307 => "SampledImageArrayNonUniformIndexing",
+            5309 => "StorageImageArrayNonUniformIndexing",
+            _ => return None,
+        })
+    }
+
+    /// Device features that must be enabled to use the capability.
+    /// Returns `None` if gfx-hal doesn't expose a feature to enable the capability.
+    /// Capabilities that don't require any feature yield empty set.
+    pub fn required_features(&self) -> Option<Features> {
+        Some(match self.0 {
+            2 => Features::GEOMETRY_SHADER,
+            3 => Features::TESSELLATION_SHADER,
+            10 => Features::SHADER_FLOAT64,
+            11 => Features::SHADER_INT64,
+            22 => Features::SHADER_INT16,
+            23 | 24 => Features::SHADER_TESSELLATION_AND_GEOMETRY_POINT_SIZE,
+            25 => Features::SHADER_IMAGE_GATHER_EXTENDED,
+            27 => Features::SHADER_STORAGE_IMAGE_MULTISAMPLE,
+            28 => Features::SHADER_UNIFORM_BUFFER_ARRAY_DYNAMIC_INDEXING,
+            29 => Features::SHADER_SAMPLED_IMAGE_ARRAY_DYNAMIC_INDEXING,
+            30 => Features::SHADER_STORAGE_BUFFER_ARRAY_DYNAMIC_INDEXING,
+            31 => Features::SHADER_STORAGE_IMAGE_ARRAY_DYNAMIC_INDEXING,
+            32 => Features::SHADER_CLIP_DISTANCE,
+            33 => Features::SHADER_CULL_DISTANCE,
+            34 | 44 => Features::IMAGE_CUBE_ARRAY,
+            35 => Features::SAMPLE_RATE_SHADING,
+            41 => Features::SHADER_RESOURCE_RESIDENCY,
+            42 => Features::SHADER_RESOURCE_MIN_LOD,
+            49 => Features::SHADER_STORAGE_IMAGE_EXTENDED_FORMATS,
+            55 => Features::SHADER_STORAGE_IMAGE_READ_WITHOUT_FORMAT,
+            56 => Features::SHADER_STORAGE_IMAGE_WRITE_WITHOUT_FORMAT,
+            57 => Features::MULTI_VIEWPORTS,
+            5301 | 5307 => Features::SAMPLED_TEXTURE_DESCRIPTOR_INDEXING,
+            5302 => Features::UNSIZED_DESCRIPTOR_ARRAY,
+            5309 => Features::STORAGE_TEXTURE_DESCRIPTOR_INDEXING,
+            12 | 61..=68 => return None,
+            _ => Features::empty(),
+        })
+    }
+}
+
+impl std::fmt::Display for SpirvCapability {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.name() {
+            Some(name) => fmt.write_str(name),
+            None => write!(fmt, "Capability({})", self.0),
+        }
+    }
+}
+
+/// Error validating SPIR-V capabilities against enabled device features.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CapabilityError {
+    /// Bytecode is not a valid SPIR-V module.
+    Malformed,
+
+    /// Capability requires features that are not enabled.
+    MissingFeatures(SpirvCapability, Features),
+
+    /// Capability cannot be enabled through gfx-hal.
+    Unsupported(SpirvCapability),
+}
+
+impl std::fmt::Display for CapabilityError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CapabilityError::Malformed => write!(fmt, "Malformed SPIR-V module"),
+            CapabilityError::MissingFeatures(capability, features) => write!(
+                fmt,
+                "Shader uses SPIR-V capability {} which requires device features {:?} to be enabled",
+                capability, features
+            ),
+            CapabilityError::Unsupported(capability) => write!(
+                fmt,
+                "Shader uses SPIR-V capability {} which cannot be enabled on the device",
+                capability
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CapabilityError {}
+
+/// Collect capabilities declared in SPIR-V module.
+pub fn spirv_capabilities(spirv: &[u32]) -> Result<Vec<SpirvCapability>, CapabilityError> {
+    if spirv.len() < HEADER_LEN || spirv[0] != MAGIC {
+        return Err(CapabilityError::Malformed);
+    }
+
+    let mut capabilities = Vec::new();
+    let mut offset = HEADER_LEN;
+    while offset < spirv.len() {
+        let word_count = (spirv[offset] >> 16) as usize;
+        let opcode = spirv[offset] & 0xFFFF;
+        if word_count == 0 || offset + word_count > spirv.len() {
+            return Err(CapabilityError::Malformed);
+        }
+        match opcode {
+            OP_CAPABILITY if word_count == 2 => {
+                capabilities.push(SpirvCapability(spirv[offset + 1]))
+            }
+            OP_CAPABILITY => return Err(CapabilityError::Malformed),
+            // Capabilities are declared first, followed by extensions and imports
+            // (opcodes 10, 11), memory model (14) and the rest of the module.
+            10 | 11 | 14 => {}
+            _ => break,
+        }
+        offset += word_count;
+    }
+    Ok(capabilities)
+}
+
+/// Check that all capabilities declared in SPIR-V module
+/// are usable with `features` enabled on the device.
+pub fn check_capabilities(spirv: &[u32], features: Features) -> Result<(), CapabilityError> {
+    for capability in spirv_capabilities(spirv)? {
+        let required = capability
+            .required_features()
+            .ok_or(CapabilityError::Unsupported(capability))?;
+        if !features.contains(required) {
+            return Err(CapabilityError::MissingFeatures(
+                capability,
+                required - features,
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn module(capabilities: &[u32]) -> Vec<u32> {
+        let mut spirv = vec![MAGIC, 0x0001_0000, 0, 1, 0];
+        for &capability in capabilities {
+            spirv.push(2 << 16 | OP_CAPABILITY);
+            spirv.push(capability);
+        }
+        // OpMemoryModel Logical GLSL450
+        spirv.extend_from_slice(&[3 << 16 | 14, 0, 1]);
+        // OpEntryPoint stops the scan
+        spirv.extend_from_slice(&[1 << 16 | 15]);
+        spirv
+    }
+
+    #[test]
+    fn test_check_capabilities() {
+        let spirv = module(&[1, 10]);
+        assert_eq!(
+            spirv_capabilities(&spirv),
+            Ok(vec![SpirvCapability(1), SpirvCapability(10)])
+        );
+        assert_eq!(
+            check_capabilities(&spirv, Features::empty()),
+            Err(CapabilityError::MissingFeatures(
+                SpirvCapability(10),
+                Features::SHADER_FLOAT64
+            ))
+        );
+        assert_eq!(check_capabilities(&spirv, Features::SHADER_FLOAT64), Ok(()));
+        assert_eq!(
+            check_capabilities(&module(&[1, 61]), Features::all()),
+            Err(CapabilityError::Unsupported(SpirvCapability(61)))
+        );
+        assert_eq!(
+            check_capabilities(&[0, 1, 2], Features::all()),
+            Err(CapabilityError::Malformed)
+        );
+    }
+}
@@ -0,0 +1,172 @@
+//! Human-readable dump of SPIR-V modules.
+
+#[cfg(feature = "spirv-disassembly")]
+const MAGIC: u32 = 0x0723_0203;
+#[cfg(feature = "spirv-disassembly")]
+const HEADER_LEN: usize = 5;
+
+/// Error disassembling SPIR-V module.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DisassembleError {
+    /// Crate is built without `spirv-disassembly` feature.
+    FeatureNotEnabled,
+
+    /// Bytecode is not a valid SPIR-V module.
+    Malformed,
+}
+
+impl std::fmt::Display for DisassembleError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DisassembleError::FeatureNotEnabled => write!(
+                fmt,
+                "SPIR-V disassembly requires `spirv-disassembly` feature to be enabled"
+            ),
+            DisassembleError::Malformed => write!(fmt, "Malformed SPIR-V module"),
+        }
+    }
+}
+
+impl std::error::Error for DisassembleError {}
+
+/// Produce textual representation of SPIR-V module.
+///
+/// Each instruction is printed on its own line as opcode mnemonic followed by operands.
+/// Operands are printed as raw numbers except for literal strings.
+/// This is not a replacement for `spirv-dis` but enough to inspect a module
+/// without external tools.
+///
+/// Returns `DisassembleError::FeatureNotEnabled` if crate is built without `spirv-disassembly` feature.
+#[cfg(feature = "spirv-disassembly")]
+pub fn disassemble(spirv: &[u32]) -> Result<String, DisassembleError> {
+    use {num_traits::FromPrimitive as _, spirv_headers::Op, std::fmt::Write as _};
+
+    if spirv.len() < HEADER_LEN || spirv[0] != MAGIC {
+        return Err(DisassembleError::Malformed);
+    }
+
+    let mut out = String::new();
+    writeln!(out, "; SPIR-V").unwrap();
+    writeln!(
+        out,
+        "; Version: {}.{}",
+        (spirv[1] >> 16) & 0xFF,
+        (spirv[1] >> 8) & 0xFF
+    )
+    .unwrap();
+    writeln!(out, "; Generator: {:#010x}", spirv[2]).unwrap();
+    writeln!(out, "; Bound: {}", spirv[3]).unwrap();
+    writeln!(out, "; Schema: {}", spirv[4]).unwrap();
+
+    let mut offset = HEADER_LEN;
+    while offset < spirv.len() {
+        let word_count = (spirv[offset] >> 16) as usize;
+        let opcode = spirv[offset] & 0xFFFF;
+        if word_count == 0 || offset + word_count > spirv.len() {
+            return Err(DisassembleError::Malformed);
+        }
+        let operands = &spirv[offset + 1..offset + word_count];
+        offset += word_count;
+
+        let op = Op::from_u32(opcode);
+        match op {
+            Some(op) => write!(out, "Op{:?}", op).unwrap(),
+            None => write!(out, "Op<{}>", opcode).unwrap(),
+        }
+
+        let string_at = op.and_then(string_operand);
+        let mut index = 0;
+        while index < operands.len() {
+            if Some(index) == string_at {
+                let (string, words) = decode_string(&operands[index..]);
+                write!(out, " {:?}", string).unwrap();
+                index += words;
+            } else {
+                write!(out, " {}", operands[index]).unwrap();
+                index += 1;
+            }
+        }
+        out.push('\n');
+    }
+
+    Ok(out)
+}
+
+/// Produce textual representation of SPIR-V module.
+///
+/// Returns `DisassembleError::FeatureNotEnabled` if crate is built without `spirv-disassembly` feature.
+#[cfg(not(feature = "spirv-disassembly"))]
+pub fn disassemble(spirv: &[u32]) -> Result<String, DisassembleError> {
+    let _ = spirv;
+    Err(DisassembleError::FeatureNotEnabled)
+}
+
+/// Index of the literal string operand of the instruction if any.
+#[cfg(feature = "spirv-disassembly")]
+fn string_operand(op: spirv_headers::Op) -> Option<usize> {
+    use spirv_headers::Op;
+
+    match op {
+        Op::SourceExtension | Op::Extension | Op::ModuleProcessed => Some(0),
+        Op::Name | Op::String | Op::ExtInstImport => Some(1),
+        Op::MemberName | Op::EntryPoint => Some(2),
+        Op::Source => Some(3),
+        _ => None,
+    }
+}
+
+/// Decode nul-terminated UTF-8 string packed into words.
+/// Returns the string and number of words it occupies.
+#[cfg(feature = "spirv-disassembly")]
+fn decode_string(words: &[u32]) -> (String, usize) {
+    let mut bytes = Vec::new();
+    for (index, word) in words.iter().enumerate() {
+        for &byte in &word.to_le_bytes() {
+            if byte == 0 {
+                return (String::from_utf8_lossy(&bytes).into_owned(), index + 1);
+            }
+            bytes.push(byte);
+        }
+    }
+    (String::from_utf8_lossy(&bytes).into_owned(), words.len())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[cfg(feature = "spirv-disassembly")]
+    #[test]
+    fn test_disassemble() {
+        let spirv = [
+            MAGIC,
+            0x0001_0000,
+            0,
+            2,
+            0,
+            // OpCapability Shader
+            2 << 16 | 17,
+            1,
+            // OpName %1 "main"
+            4 << 16 | 5,
+            1,
+            u32::from_le_bytes(*b"main"),
+            0,
+        ];
+        let text = disassemble(&spirv).unwrap();
+        assert!(text.contains("; Version: 1.0"));
+        assert!(text.contains("OpCapability 1\n"));
+        assert!(text.contains("OpName 1 \"main\"\n"));
+
+        assert_eq!(disassemble(&spirv[..9]), Err(DisassembleError::Malformed));
+    }
+
+    #[cfg(not(feature = "spirv-disassembly"))]
+    #[test]
+    fn test_disassemble_disabled() {
+        assert_eq!(
+            disassemble(&[0x0723_0203, 0x0001_0000, 0, 1, 0]),
+            Err(DisassembleError::FeatureNotEnabled)
+        );
+    }
+}
@@ -11,6 +11,10 @@
     unused_qualifications
 )]
 
+mod capability;
+mod disassemble;
+mod prelude;
+
 #[cfg(feature = "shader-compiler")]
 mod shaderc;
 
@@ -18,11 +22,16 @@ mod shaderc;
 #[allow(dead_code)]
 mod reflect;
 
+pub use self::{capability::*, disassemble::*, prelude::*};
+
 #[cfg(feature = "shader-compiler")]
 pub use self::shaderc::*;
 
 #[cfg(feature = "spirv-reflection")]
-pub use self::reflect::{ReflectError, ReflectTypeError, RetrievalKind, SpirvReflection};
+pub use self::reflect::{
+    check_vertex_compatibility, BindingNames, BlockMember, ReflectError, ReflectTypeError,
+    RetrievalKind, SpirvReflection, VertexMismatch,
+};
 
 use rendy_core::hal::{pso::ShaderStageFlags, Backend};
 use std::collections::HashMap;
@@ -84,13 +93,16 @@ pub struct SpirvShader {
     entry: String,
 }
 
+/// Spir-V words are serialized as little-endian bytes regardless of host byte order.
+/// On deserialization byte order is detected from the magic number,
+/// so bytes written in either order are read back correctly.
 #[cfg(feature = "serde")]
 mod serde_spirv {
     pub fn serialize<S>(data: &Vec<u32>, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
     {
-        serializer.serialize_bytes(rendy_core::cast_slice(&data))
+        serializer.serialize_bytes(&to_bytes(data))
     }
 
     pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<u32>, D::Error>
@@ -102,6 +114,41 @@ mod serde_spirv {
         rendy_core::hal::pso::read_spirv(std::io::Cursor::new(bytes))
             .map_err(serde::de::Error::custom)
     }
+
+    fn to_bytes(data: &[u32]) -> Vec<u8> {
+        data.iter()
+            .flat_map(|word| word.to_le_bytes().to_vec())
+            .collect()
+    }
+
+    #[cfg(test)]
+    mod test {
+        use serde::de::value::{BorrowedBytesDeserializer, Error};
+
+        const SPIRV: [u32; 5] = [0x0723_0203, 0x0001_0000, 0, 1, 0];
+
+        #[test]
+        fn test_spirv_round_trip() {
+            let bytes = super::to_bytes(&SPIRV);
+            assert_eq!(&bytes[..4], &[0x03, 0x02, 0x23, 0x07]);
+
+            let words =
+                super::deserialize(BorrowedBytesDeserializer::<Error>::new(&bytes)).unwrap();
+            assert_eq!(words, SPIRV);
+        }
+
+        #[test]
+        fn test_spirv_big_endian_bytes() {
+            let bytes: Vec<u8> = SPIRV
+                .iter()
+                .flat_map(|word| word.to_be_bytes().to_vec())
+                .collect();
+
+            let words =
+                super::deserialize(BorrowedBytesDeserializer::<Error>::new(&bytes)).unwrap();
+            assert_eq!(words, SPIRV);
+        }
+    }
 }
 
 impl SpirvShader {
@@ -128,6 +175,12 @@ impl SpirvShader {
             entrypoint,
         ))
     }
+
+    /// Produce human-readable dump of the Spir-V bytecode.
+    /// Requires `spirv-disassembly` feature.
+    pub fn disassemble(&self) -> Result<String, DisassembleError> {
+        disassemble(&self.spirv)
+    }
 }
 
 impl Shader for SpirvShader {
@@ -177,6 +230,10 @@ impl<B: Backend> ShaderSet<B> {
     }
 
     /// Returns the `GraphicsShaderSet` structure to provide all the runtime information needed to use the shaders in this set in rendy_core::hal.
+    ///
+    /// Mesh shading pipelines can't be created on gfx-hal 0.5.
+    /// It has neither task and mesh stages in `GraphicsShaderSet` nor a command to draw mesh tasks,
+    /// so vertex shader is always required.
     pub fn raw<'a>(
         &'a self,
     ) -> Result<rendy_core::hal::pso::GraphicsShaderSet<'a, B>, ShaderError> {
@@ -406,6 +463,25 @@ impl ShaderSetBuilder {
         Ok(self)
     }
 
+    /// Produce human-readable dump of the Spir-V bytecode of each shader in the set.
+    /// Requires `spirv-disassembly` feature.
+    pub fn disassemble(&self) -> Result<Vec<(ShaderStageFlags, String)>, DisassembleError> {
+        let stages = [
+            (ShaderStageFlags::VERTEX, &self.vertex),
+            (ShaderStageFlags::FRAGMENT, &self.fragment),
+            (ShaderStageFlags::GEOMETRY, &self.geometry),
+            (ShaderStageFlags::HULL, &self.hull),
+            (ShaderStageFlags::DOMAIN, &self.domain),
+            (ShaderStageFlags::COMPUTE, &self.compute),
+        ];
+
+        stages
+            .iter()
+            .filter_map(|(stage, shader)| shader.as_ref().map(|shader| (*stage, shader)))
+            .map(|(stage, shader)| Ok((stage, disassemble(&shader.0)?)))
+            .collect()
+    }
+
     #[cfg(feature = "spirv-reflection")]
     /// This function processes all shaders provided to the builder and computes and stores full reflection information on the shader.
     /// This includes names, attributes, descriptor sets and push constants used by the shaders, as well as compiling local caches for performance.
@@ -461,10 +537,20 @@ impl<B: Backend> ShaderStorage<B> {
     }
 
     /// Compile the SPIRV code with the backend and store the reference to the module inside this structure.
+    ///
+    /// Unless `no-slow-safety-checks` feature is enabled, capabilities declared by the SPIR-V module
+    /// are checked against features enabled on the device first
+    /// so that missing features are reported instead of passing the module to the driver.
     pub unsafe fn compile(
         &mut self,
         factory: &rendy_factory::Factory<B>,
     ) -> Result<(), rendy_core::hal::device::ShaderError> {
+        rendy_core::rendy_with_slow_safety_checks!(
+            check_capabilities(&self.spirv, factory.features()).map_err(|err| {
+                rendy_core::hal::device::ShaderError::CompilationFailed(err.to_string())
+            })?;
+        );
+
         self.module = Some(rendy_core::hal::device::Device::create_shader_module(
             factory.device().raw(),
             &self.spirv,
@@ -0,0 +1,64 @@
+/// Insert `prelude` into shader `source` so that it is shared between shaders
+/// without editing each one, e.g. to define constants like `MAX_LIGHTS` in one place.
+///
+/// Prelude is inserted after leading `#version` and `#extension` directives
+/// since they must precede any other code.
+/// `#line` directive is emitted after the prelude so that compiler diagnostics
+/// keep referring to lines of the original source.
+pub fn insert_prelude(source: &str, prelude: &str) -> String {
+    let mut header_lines = 0;
+    for (index, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with("#version") || line.starts_with("#extension") {
+            header_lines = index + 1;
+        } else if !line.is_empty() && !line.starts_with("//") {
+            break;
+        }
+    }
+
+    let mut result = String::with_capacity(source.len() + prelude.len() + 16);
+    let mut lines = source.split_inclusive('\n');
+    for line in lines.by_ref().take(header_lines) {
+        result.push_str(line);
+    }
+    if !result.is_empty() && !result.ends_with('\n') {
+        result.push('\n');
+    }
+
+    result.push_str(prelude);
+    if !prelude.ends_with('\n') {
+        result.push('\n');
+    }
+    result.push_str(&format!("#line {}\n", header_lines + 1));
+
+    for line in lines {
+        result.push_str(line);
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::insert_prelude;
+
+    #[test]
+    fn after_version_and_extensions() {
+        let source = "// Mesh shader\n#version 450\n#extension GL_ARB_separate_shader_objects : enable\n\nvoid main() {}\n";
+        assert_eq!(
+            insert_prelude(source, "#define MAX_LIGHTS 32"),
+            "// Mesh shader\n#version 450\n#extension GL_ARB_separate_shader_objects : enable\n#define MAX_LIGHTS 32\n#line 4\n\nvoid main() {}\n"
+        );
+    }
+
+    #[test]
+    fn without_version() {
+        assert_eq!(
+            insert_prelude("void main() {}", "#define A 1\n"),
+            "#define A 1\n#line 1\nvoid main() {}"
+        );
+        assert_eq!(
+            insert_prelude("#version 450", "#define A 1"),
+            "#version 450\n#define A 1\n#line 2\n"
+        );
+    }
+}
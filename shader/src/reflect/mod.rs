@@ -5,8 +5,10 @@ use std::collections::HashMap;
 use std::ops::{Bound, Range, RangeBounds};
 
 pub(crate) mod types;
+mod vertex;
 pub use types::ReflectTypeError;
 use types::*;
+pub use vertex::{check_vertex_compatibility, VertexMismatch};
 
 /// The item kind that couldn't be retrieved from spirv-reflect.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -97,6 +99,30 @@ pub(crate) struct SpirvCachedGfxDescription {
     pub layout: Layout,
 }
 
+/// Debug names of a descriptor binding taken from `OpName` and `OpMemberName`.
+/// Names are `None` when shader was stripped of debug info.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BindingNames {
+    /// Name of the variable, e.g. `u_texture`.
+    pub name: Option<String>,
+    /// Name of the variable type, e.g. `Camera` for uniform block.
+    pub type_name: Option<String>,
+    /// Members of uniform or storage block.
+    /// Members of nested structures follow their parent with dot-separated names.
+    pub members: Vec<BlockMember>,
+}
+
+/// Member of uniform or storage block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockMember {
+    /// Name of the member, e.g. `view_proj`, or `light.color` for nested structures.
+    pub name: Option<String>,
+    /// Offset of the member from the start of the block in bytes.
+    pub offset: u32,
+    /// Size of the member in bytes.
+    pub size: u32,
+}
+
 /// Contains intermediate structured data of reflected shader information.
 #[derive(Clone, Debug)]
 pub struct SpirvReflection {
@@ -106,6 +132,8 @@ pub struct SpirvReflection {
     pub input_attributes: HashMap<(String, u8), rendy_core::hal::pso::AttributeDesc>,
     /// Hashmap of output variables with names.
     pub descriptor_sets: Vec<Vec<rendy_core::hal::pso::DescriptorSetLayoutBinding>>,
+    /// Debug names of descriptor bindings by set and binding index.
+    pub descriptor_names: HashMap<(u32, u32), BindingNames>,
     /// Stage flag of this shader
     pub stage_flag: ShaderStageFlags,
     /// Push Constants
@@ -124,6 +152,7 @@ impl Default for SpirvReflection {
             output_attributes: HashMap::new(),
             input_attributes: HashMap::new(),
             descriptor_sets: Vec::new(),
+            descriptor_names: HashMap::new(),
             stage_flag: ShaderStageFlags::VERTEX,
             push_constants: Vec::new(),
             entrypoints: Vec::new(),
@@ -147,6 +176,7 @@ impl SpirvReflection {
             output_attributes,
             input_attributes,
             descriptor_sets,
+            descriptor_names: HashMap::new(),
             stage_flag,
             push_constants,
             entrypoints,
@@ -207,11 +237,17 @@ impl SpirvReflection {
                         ReflectError::Retrieval(RetrievalKind::OutputAttrib, e.to_string())
                     })?);
 
-                let descriptor_sets: Result<Vec<_>, _> = module
-                    .enumerate_descriptor_sets(None)
-                    .map_err(|e| {
-                        ReflectError::Retrieval(RetrievalKind::DescriptorSets, e.to_string())
-                    })?
+                let reflected_sets = module.enumerate_descriptor_sets(None).map_err(|e| {
+                    ReflectError::Retrieval(RetrievalKind::DescriptorSets, e.to_string())
+                })?;
+
+                let descriptor_names = reflected_sets
+                    .iter()
+                    .flat_map(|set| set.bindings.iter())
+                    .map(|binding| ((binding.set, binding.binding), binding_names(binding)))
+                    .collect();
+
+                let descriptor_sets: Result<Vec<_>, _> = reflected_sets
                     .iter()
                     .map(ReflectInto::<Vec<rendy_core::hal::pso::DescriptorSetLayoutBinding>>::reflect_into)
                     .collect();
@@ -235,7 +271,7 @@ impl SpirvReflection {
 
                 let entrypoint = if let Some(e) = entrypoint { e } else { "main" };
 
-                Self::new(
+                let mut reflection = Self::new(
                     stage_flag,
                     Some(entrypoint.to_string()),
                     vec![(stage_flag, module.get_entry_point_name())],
@@ -247,7 +283,9 @@ impl SpirvReflection {
                     })?,
                     descriptor_sets_final,
                     push_constants?,
-                )
+                )?;
+                reflection.descriptor_names = descriptor_names;
+                Ok(reflection)
             }
             Err(e) => Err(ReflectError::General(e.to_string())),
         }
@@ -316,6 +354,31 @@ impl SpirvReflection {
             .clone())
     }
 
+    /// Returns name of the descriptor variable at `binding` of `set`, e.g. `u_texture`.
+    /// Returns `None` if there is no such binding or shader lacks debug info.
+    pub fn descriptor_name(&self, set: u32, binding: u32) -> Option<&str> {
+        self.descriptor_names
+            .get(&(set, binding))
+            .and_then(|names| names.name.as_deref())
+    }
+
+    /// Returns name of the type of descriptor variable at `binding` of `set`,
+    /// e.g. `Camera` for uniform block.
+    /// Returns `None` if there is no such binding or shader lacks debug info.
+    pub fn descriptor_type_name(&self, set: u32, binding: u32) -> Option<&str> {
+        self.descriptor_names
+            .get(&(set, binding))
+            .and_then(|names| names.type_name.as_deref())
+    }
+
+    /// Returns members of uniform or storage block at `binding` of `set` with their offsets.
+    /// Returns empty slice if there is no such binding or it isn't a block.
+    pub fn block_members(&self, set: u32, binding: u32) -> &[BlockMember] {
+        self.descriptor_names
+            .get(&(set, binding))
+            .map_or(&[], |names| &names.members[..])
+    }
+
     /// Returns the combined stages of shaders which are in this set in the form of a `ShaderStageFlags` bitflag.
     #[inline]
     pub fn stage(&self) -> ShaderStageFlags {
@@ -352,8 +415,13 @@ pub(crate) fn merge(reflections: &[SpirvReflection]) -> Result<SpirvReflection,
     let mut set_stage_flags = ShaderStageFlags::empty();
     let mut set_entry_points = Vec::new();
     let mut input_attributes = HashMap::new();
+    let mut descriptor_names = HashMap::new();
 
     for s in reflections.iter() {
+        for (&key, names) in &s.descriptor_names {
+            descriptor_names.entry(key).or_insert_with(|| names.clone());
+        }
+
         let current_layout = &s.descriptor_sets;
 
         set_stage_flags.insert(s.stage());
@@ -385,7 +453,7 @@ pub(crate) fn merge(reflections: &[SpirvReflection]) -> Result<SpirvReflection,
         }
     }
 
-    SpirvReflection::new(
+    let mut reflection = SpirvReflection::new(
         set_stage_flags,
         None,
         set_entry_points,
@@ -393,7 +461,9 @@ pub(crate) fn merge(reflections: &[SpirvReflection]) -> Result<SpirvReflection,
         HashMap::new(),
         descriptor_sets,
         set_push_constants,
-    )
+    )?;
+    reflection.descriptor_names = descriptor_names;
+    Ok(reflection)
 }
 
 /// This enum provides logical comparison results for descriptor sets. Because shaders can share bindings,
@@ -314,6 +314,48 @@ pub(crate) fn convert_stage(
     bits
 }
 
+fn debug_name(name: &str) -> Option<String> {
+    if name.is_empty() {
+        None
+    } else {
+        Some(name.to_owned())
+    }
+}
+
+// Members of nested structures are named only if names along the whole path are known.
+fn collect_block_members(
+    block: &ReflectBlockVariable,
+    prefix: Option<&str>,
+    members: &mut Vec<super::BlockMember>,
+) {
+    for member in &block.members {
+        let name = debug_name(&member.name).and_then(|name| match prefix {
+            None => Some(name),
+            Some("") => None,
+            Some(prefix) => Some(format!("{}.{}", prefix, name)),
+        });
+        members.push(super::BlockMember {
+            name: name.clone(),
+            offset: member.absolute_offset,
+            size: member.size,
+        });
+        collect_block_members(member, Some(name.as_deref().unwrap_or("")), members);
+    }
+}
+
+pub(crate) fn binding_names(binding: &ReflectDescriptorBinding) -> super::BindingNames {
+    let mut members = Vec::new();
+    collect_block_members(&binding.block, None, &mut members);
+    super::BindingNames {
+        name: debug_name(&binding.name),
+        type_name: binding
+            .type_description
+            .as_ref()
+            .and_then(|ty| debug_name(&ty.type_name)),
+        members,
+    }
+}
+
 pub(crate) fn generate_attributes(
     attributes: Vec<ReflectInterfaceVariable>,
 ) -> Result<HashMap<(String, u8), rendy_core::hal::pso::AttributeDesc>, ReflectTypeError> {
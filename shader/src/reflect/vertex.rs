@@ -0,0 +1,269 @@
+use super::SpirvReflection;
+use rendy_core::{
+    hal::format::{BaseFormat, ChannelType, Format, SurfaceType},
+    types::vertex::VertexFormat,
+};
+
+/// Mismatch between vertex formats of the mesh and vertex inputs of the shader.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VertexMismatch {
+    /// Shader input is not provided by vertex formats.
+    Missing {
+        /// Location of the shader input.
+        location: u32,
+        /// Name of the shader input.
+        name: String,
+        /// Format expected by shader.
+        expected: Format,
+    },
+    /// Vertex attribute format differs from format of shader input at the same location.
+    Format {
+        /// Location of the shader input.
+        location: u32,
+        /// Name of the shader input.
+        name: String,
+        /// Format expected by shader.
+        expected: Format,
+        /// Format of the vertex attribute.
+        actual: Format,
+    },
+    /// Vertex attribute doesn't fit into vertex stride.
+    Offset {
+        /// Location of the vertex attribute.
+        location: u32,
+        /// Offset of the vertex attribute.
+        offset: u32,
+        /// Stride of the vertex format.
+        stride: u32,
+    },
+}
+
+impl std::error::Error for VertexMismatch {}
+impl std::fmt::Display for VertexMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VertexMismatch::Missing {
+                location,
+                name,
+                expected,
+            } => write!(
+                f,
+                "vertex input {} at location {} expects {:?} but no attribute is provided",
+                name, location, expected
+            ),
+            VertexMismatch::Format {
+                location,
+                name,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "vertex input {} at location {} expects {:?} but attribute has {:?}",
+                name, location, expected, actual
+            ),
+            VertexMismatch::Offset {
+                location,
+                offset,
+                stride,
+            } => write!(
+                f,
+                "vertex attribute at location {} with offset {} doesn't fit into stride {}",
+                location, offset, stride
+            ),
+        }
+    }
+}
+
+/// Check if vertex attribute of `actual` format can feed shader input of `expected` format.
+///
+/// Besides exact match, floating-point inputs accept narrower formats
+/// that are read as floating-point values, e.g. half-floats or normalized integers,
+/// with at least as many components as input has.
+/// Extra components are ignored by the shader.
+pub fn attribute_format_compatible(expected: Format, actual: Format) -> bool {
+    if expected == actual {
+        return true;
+    }
+
+    let BaseFormat(expected_surface, expected_channel) = expected.base_format();
+    let BaseFormat(actual_surface, actual_channel) = actual.base_format();
+    let float_input =
+        expected_channel == ChannelType::Sfloat && component_bits(expected_surface) == Some(32);
+    let narrow = match (actual_channel, component_bits(actual_surface)) {
+        (ChannelType::Sfloat, Some(16)) => true,
+        (ChannelType::Unorm, _)
+        | (ChannelType::Snorm, _)
+        | (ChannelType::Uscaled, _)
+        | (ChannelType::Sscaled, _) => true,
+        _ => false,
+    };
+
+    float_input
+        && narrow
+        && component_count(actual_surface) >= component_count(expected_surface)
+        && component_count(expected_surface) > 0
+}
+
+/// Size of the largest component of color surface, `None` for other surfaces.
+fn component_bits(surface: SurfaceType) -> Option<u8> {
+    use SurfaceType::*;
+    match surface {
+        R8 | R8_G8 | R8_G8_B8 | B8_G8_R8 | R8_G8_B8_A8 | B8_G8_R8_A8 | A8_B8_G8_R8 => Some(8),
+        A2_R10_G10_B10 | A2_B10_G10_R10 => Some(10),
+        R16 | R16_G16 | R16_G16_B16 | R16_G16_B16_A16 => Some(16),
+        R32 | R32_G32 | R32_G32_B32 | R32_G32_B32_A32 => Some(32),
+        R64 | R64_G64 | R64_G64_B64 | R64_G64_B64_A64 => Some(64),
+        _ => None,
+    }
+}
+
+fn component_count(surface: SurfaceType) -> usize {
+    use SurfaceType::*;
+    match surface {
+        R8 | R16 | R32 | R64 => 1,
+        R8_G8 | R16_G16 | R32_G32 | R64_G64 => 2,
+        R8_G8_B8 | B8_G8_R8 | R16_G16_B16 | R32_G32_B32 | R64_G64_B64 => 3,
+        R8_G8_B8_A8 | B8_G8_R8_A8 | A8_B8_G8_R8 | A2_R10_G10_B10 | A2_B10_G10_R10
+        | R16_G16_B16_A16 | R32_G32_B32_A32 | R64_G64_B64_A64 => 4,
+        _ => 0,
+    }
+}
+
+/// Check that vertex formats provide all vertex inputs of the shader.
+///
+/// Formats are bound to consecutive vertex buffers and their attributes
+/// are assigned consecutive locations, the same way `SimpleGraphicsPipeline` does.
+/// Attributes at locations not used by the shader are ignored.
+pub fn check_vertex_compatibility(
+    formats: &[VertexFormat],
+    reflection: &SpirvReflection,
+) -> Result<(), VertexMismatch> {
+    let mut provided = Vec::new();
+    for format in formats {
+        for attribute in &format.attributes {
+            let location = provided.len() as u32;
+            let element = attribute.element();
+            let size = element.format.surface_desc().bits as u32 / 8;
+            if element.offset + size > format.stride {
+                return Err(VertexMismatch::Offset {
+                    location,
+                    offset: element.offset,
+                    stride: format.stride,
+                });
+            }
+            provided.push(element.format);
+        }
+    }
+
+    let mut inputs: Vec<_> = reflection.input_attributes.iter().collect();
+    inputs.sort_by_key(|(_, desc)| desc.location);
+
+    for ((name, _), desc) in inputs {
+        let expected = desc.element.format;
+        match provided.get(desc.location as usize) {
+            None => {
+                return Err(VertexMismatch::Missing {
+                    location: desc.location,
+                    name: name.clone(),
+                    expected,
+                })
+            }
+            Some(&actual) if !attribute_format_compatible(expected, actual) => {
+                return Err(VertexMismatch::Format {
+                    location: desc.location,
+                    name: name.clone(),
+                    expected,
+                    actual,
+                })
+            }
+            Some(_) => {}
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn reflection(inputs: &[(&str, u32, Format)]) -> SpirvReflection {
+        let mut reflection = SpirvReflection::default();
+        for &(name, location, format) in inputs {
+            reflection.input_attributes.insert(
+                (name.to_string(), 0),
+                rendy_core::hal::pso::AttributeDesc {
+                    location,
+                    binding: 0,
+                    element: rendy_core::hal::pso::Element { format, offset: 0 },
+                },
+            );
+        }
+        reflection
+    }
+
+    #[test]
+    fn test_check_vertex_compatibility() {
+        let formats = [
+            VertexFormat::new(vec![
+                (Format::Rgb32Sfloat, "position".to_string()),
+                (Format::Rg32Sfloat, "tex_coord".to_string()),
+            ]),
+            VertexFormat::new(vec![(Format::Rgba32Sfloat, "color".to_string())]),
+        ];
+
+        let shader = reflection(&[
+            ("a_pos", 0, Format::Rgb32Sfloat),
+            ("a_color", 2, Format::Rgba32Sfloat),
+        ]);
+        assert_eq!(check_vertex_compatibility(&formats, &shader), Ok(()));
+
+        let shader = reflection(&[("a_uv", 1, Format::Rgb32Sfloat)]);
+        assert_eq!(
+            check_vertex_compatibility(&formats, &shader),
+            Err(VertexMismatch::Format {
+                location: 1,
+                name: "a_uv".to_string(),
+                expected: Format::Rgb32Sfloat,
+                actual: Format::Rg32Sfloat,
+            })
+        );
+
+        let packed = [VertexFormat::new(vec![
+            (Format::Rgba16Sfloat, "position".to_string()),
+            (Format::A2b10g10r10Snorm, "normal".to_string()),
+            (Format::Rg16Sfloat, "tex_coord".to_string()),
+        ])];
+        let shader = reflection(&[
+            ("a_pos", 0, Format::Rgb32Sfloat),
+            ("a_normal", 1, Format::Rgb32Sfloat),
+            ("a_uv", 2, Format::Rg32Sfloat),
+        ]);
+        assert_eq!(check_vertex_compatibility(&packed, &shader), Ok(()));
+
+        let shader = reflection(&[("a_uv", 2, Format::Rgb32Sfloat)]);
+        assert_eq!(
+            check_vertex_compatibility(&packed, &shader),
+            Err(VertexMismatch::Format {
+                location: 2,
+                name: "a_uv".to_string(),
+                expected: Format::Rgb32Sfloat,
+                actual: Format::Rg16Sfloat,
+            })
+        );
+        assert!(!attribute_format_compatible(
+            Format::Rgba32Uint,
+            Format::Rgba16Uint
+        ));
+
+        let shader = reflection(&[("a_normal", 3, Format::Rgb32Sfloat)]);
+        assert_eq!(
+            check_vertex_compatibility(&formats, &shader),
+            Err(VertexMismatch::Missing {
+                location: 3,
+                name: "a_normal".to_string(),
+                expected: Format::Rgb32Sfloat,
+            })
+        );
+    }
+}
@@ -1,7 +1,7 @@
 // This module is gated under "shader-compiler" feature
 use super::Shader;
-use crate::SpirvShader;
-pub use shaderc::{self, ShaderKind, SourceLanguage};
+use crate::{insert_prelude, SpirvShader};
+pub use shaderc::{self, OptimizationLevel, ShaderKind, SourceLanguage};
 
 macro_rules! vk_make_version {
     ($major: expr, $minor: expr, $patch: expr) => {{
@@ -49,13 +49,42 @@ impl From<::shaderc::Error> for ShaderCError {
     }
 }
 
+/// Optimization level used by default.
+/// `Zero` in debug builds so GPU debuggers can map SPIR-V back to the source,
+/// `Performance` in release builds.
+pub fn default_optimization_level() -> OptimizationLevel {
+    if cfg!(debug_assertions) {
+        OptimizationLevel::Zero
+    } else {
+        OptimizationLevel::Performance
+    }
+}
+
+fn compile_options(
+    lang: SourceLanguage,
+    debug_info: bool,
+    optimization: OptimizationLevel,
+) -> Result<shaderc::CompileOptions<'static>, ShaderCError> {
+    let mut ops = shaderc::CompileOptions::new().ok_or(ShaderCError::Init)?;
+    ops.set_target_env(shaderc::TargetEnv::Vulkan, vk_make_version!(1, 0, 0));
+    ops.set_source_language(lang);
+    if debug_info {
+        ops.set_generate_debug_info();
+    }
+    ops.set_optimization_level(optimization);
+    Ok(ops)
+}
+
 /// Info necessary to compile a shader from source code stored in the filesystem.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub struct FileShaderInfo<P, E> {
     path: P,
     kind: ShaderKind,
     lang: SourceLanguage,
     entry: E,
+    prelude: Option<String>,
+    debug_info: bool,
+    optimization: OptimizationLevel,
 }
 
 impl<P, E> FileShaderInfo<P, E> {
@@ -66,8 +95,38 @@ impl<P, E> FileShaderInfo<P, E> {
             kind,
             lang,
             entry,
+            prelude: None,
+            debug_info: cfg!(debug_assertions),
+            optimization: default_optimization_level(),
         }
     }
+
+    /// Insert `prelude` into the source before compilation.
+    /// See [`insert_prelude`] for details.
+    ///
+    /// [`insert_prelude`]: fn.insert_prelude.html
+    pub fn with_prelude(mut self, prelude: impl Into<String>) -> Self {
+        self.prelude = Some(prelude.into());
+        self
+    }
+
+    /// Generate debug info so that GPU debuggers can show the source.
+    ///
+    /// Enabled by default in debug builds.
+    pub fn with_debug_info(mut self, debug_info: bool) -> Self {
+        self.debug_info = debug_info;
+        self
+    }
+
+    /// Set optimization level.
+    ///
+    /// See [`default_optimization_level`] for default.
+    ///
+    /// [`default_optimization_level`]: fn.default_optimization_level.html
+    pub fn with_optimization_level(mut self, optimization: OptimizationLevel) -> Self {
+        self.optimization = optimization;
+        self
+    }
 }
 
 impl<P, E> FileShaderInfo<P, E>
@@ -95,7 +154,10 @@ where
     type Error = ShaderCError;
 
     fn spirv(&self) -> Result<std::borrow::Cow<'static, [u32]>, ShaderCError> {
-        let code = std::fs::read_to_string(&self.path)?;
+        let mut code = std::fs::read_to_string(&self.path)?;
+        if let Some(prelude) = &self.prelude {
+            code = insert_prelude(&code, prelude);
+        }
 
         let artifact = shaderc::Compiler::new()
             .ok_or(ShaderCError::Init)?
@@ -107,15 +169,11 @@ where
                     .to_str()
                     .ok_or_else(|| ShaderCError::NonUtf8Path(self.path.as_ref().to_owned()))?,
                 self.entry.as_ref(),
-                Some({
-                    let mut ops = shaderc::CompileOptions::new().ok_or(ShaderCError::Init)?;
-                    ops.set_target_env(shaderc::TargetEnv::Vulkan, vk_make_version!(1, 0, 0));
-                    ops.set_source_language(self.lang);
-                    ops.set_generate_debug_info();
-                    ops.set_optimization_level(shaderc::OptimizationLevel::Performance);
-                    ops
-                })
-                .as_ref(),
+                Some(&compile_options(
+                    self.lang,
+                    self.debug_info,
+                    self.optimization,
+                )?),
             )?;
 
         Ok(std::borrow::Cow::Owned(artifact.as_binary().into()))
@@ -131,13 +189,16 @@ where
 }
 
 /// Info necessary to compile a shader from provided source code.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub struct SourceCodeShaderInfo<P, E, S> {
     source: S,
     path: P,
     kind: ShaderKind,
     lang: SourceLanguage,
     entry: E,
+    prelude: Option<String>,
+    debug_info: bool,
+    optimization: OptimizationLevel,
 }
 
 impl<P, E, S> SourceCodeShaderInfo<P, E, S> {
@@ -150,8 +211,38 @@ impl<P, E, S> SourceCodeShaderInfo<P, E, S> {
             kind,
             lang,
             entry,
+            prelude: None,
+            debug_info: cfg!(debug_assertions),
+            optimization: default_optimization_level(),
         }
     }
+
+    /// Insert `prelude` into the source before compilation.
+    /// See [`insert_prelude`] for details.
+    ///
+    /// [`insert_prelude`]: fn.insert_prelude.html
+    pub fn with_prelude(mut self, prelude: impl Into<String>) -> Self {
+        self.prelude = Some(prelude.into());
+        self
+    }
+
+    /// Generate debug info so that GPU debuggers can show the source.
+    ///
+    /// Enabled by default in debug builds.
+    pub fn with_debug_info(mut self, debug_info: bool) -> Self {
+        self.debug_info = debug_info;
+        self
+    }
+
+    /// Set optimization level.
+    ///
+    /// See [`default_optimization_level`] for default.
+    ///
+    /// [`default_optimization_level`]: fn.default_optimization_level.html
+    pub fn with_optimization_level(mut self, optimization: OptimizationLevel) -> Self {
+        self.optimization = optimization;
+        self
+    }
 }
 
 impl<P, E, S> SourceCodeShaderInfo<P, E, S>
@@ -180,25 +271,26 @@ where
     type Error = ShaderCError;
 
     fn spirv(&self) -> Result<std::borrow::Cow<'static, [u32]>, ShaderCError> {
+        let code = match &self.prelude {
+            Some(prelude) => std::borrow::Cow::Owned(insert_prelude(self.source.as_ref(), prelude)),
+            None => std::borrow::Cow::Borrowed(self.source.as_ref()),
+        };
+
         let artifact = shaderc::Compiler::new()
             .ok_or(ShaderCError::Init)?
             .compile_into_spirv(
-                self.source.as_ref(),
+                &code,
                 self.kind,
                 self.path
                     .as_ref()
                     .to_str()
                     .ok_or_else(|| ShaderCError::NonUtf8Path(self.path.as_ref().to_owned()))?,
                 self.entry.as_ref(),
-                Some({
-                    let mut ops = shaderc::CompileOptions::new().ok_or(ShaderCError::Init)?;
-                    ops.set_target_env(shaderc::TargetEnv::Vulkan, vk_make_version!(1, 0, 0));
-                    ops.set_source_language(self.lang);
-                    ops.set_generate_debug_info();
-                    ops.set_optimization_level(shaderc::OptimizationLevel::Performance);
-                    ops
-                })
-                .as_ref(),
+                Some(&compile_options(
+                    self.lang,
+                    self.debug_info,
+                    self.optimization,
+                )?),
             )?;
 
         Ok(std::borrow::Cow::Owned(artifact.as_binary().into()))
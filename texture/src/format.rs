@@ -1,6 +1,7 @@
 //! Exports the image and palette modules if the features
 //! are enabled
 
+pub mod dds;
 #[cfg(feature = "image")]
 pub mod image;
 #[cfg(feature = "palette")]
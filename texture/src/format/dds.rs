@@ -0,0 +1,534 @@
+//! Module that turns a DDS file into a `Texture`.
+//!
+//! Mip levels and array layers embedded in the file are uploaded as is.
+
+use crate::{mip_level_size, MipLevels, TextureBuilder};
+
+use {
+    rendy_core::hal::{
+        format::{Component, Format, Swizzle},
+        image::{Kind, ViewKind},
+    },
+    std::num::NonZeroU8,
+};
+
+const MAGIC: &[u8; 4] = b"DDS ";
+const HEADER_SIZE: u32 = 124;
+const DX10_HEADER_SIZE: usize = 20;
+
+const DDSD_MIPMAPCOUNT: u32 = 0x2_0000;
+const DDSD_DEPTH: u32 = 0x80_0000;
+
+const DDPF_ALPHAPIXELS: u32 = 0x1;
+const DDPF_FOURCC: u32 = 0x4;
+const DDPF_RGB: u32 = 0x40;
+const DDPF_LUMINANCE: u32 = 0x2_0000;
+
+const DDSCAPS2_CUBEMAP: u32 = 0x200;
+const DDSCAPS2_CUBEMAP_ALLFACES: u32 = 0xFC00;
+const DDSCAPS2_VOLUME: u32 = 0x20_0000;
+
+const D3D10_RESOURCE_DIMENSION_TEXTURE1D: u32 = 2;
+const D3D10_RESOURCE_DIMENSION_TEXTURE2D: u32 = 3;
+const D3D10_RESOURCE_DIMENSION_TEXTURE3D: u32 = 4;
+const D3D10_RESOURCE_MISC_TEXTURECUBE: u32 = 0x4;
+
+/// A description how to interpret loaded DDS file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct DdsTextureConfig {
+    /// Use sRGB variant of the format for files without DX10 header.
+    /// Files with DX10 header specify color space explicitly.
+    pub srgb: bool,
+    /// Sampler parameters. Can be created from `SamplerInfo` with `into()`.
+    pub sampler_info: rendy_core::hal::image::SamplerDesc,
+    /// Image tiling. When `None`, optimal tiling is preferred
+    /// and linear tiling is used if format doesn't support required features otherwise.
+    /// See `pick_tiling`.
+    pub tiling: Option<rendy_core::hal::image::Tiling>,
+}
+
+impl Default for DdsTextureConfig {
+    fn default() -> Self {
+        DdsTextureConfig {
+            srgb: true,
+            sampler_info: rendy_core::hal::image::SamplerDesc::new(
+                rendy_core::hal::image::Filter::Linear,
+                rendy_core::hal::image::WrapMode::Clamp,
+            ),
+            tiling: None,
+        }
+    }
+}
+
+/// Error that can occur while loading DDS file.
+#[derive(Debug)]
+pub enum DdsError {
+    /// Failed to read the file.
+    Io(std::io::Error),
+    /// File doesn't start with DDS magic number.
+    Magic,
+    /// Header is malformed or describes unsupported texture.
+    Header(&'static str),
+    /// Compressed format identified by FourCC code is not supported.
+    UnsupportedFourCC([u8; 4]),
+    /// Format from DX10 header is not supported.
+    UnsupportedDxgiFormat(u32),
+    /// Uncompressed pixel format is not supported.
+    UnsupportedPixelFormat {
+        /// Bits per pixel.
+        bit_count: u32,
+        /// Red, green, blue and alpha masks.
+        masks: [u32; 4],
+    },
+    /// File contains less data than header describes.
+    Truncated {
+        /// Size of all layers and mip levels in bytes.
+        expected: usize,
+        /// Size of data in the file.
+        actual: usize,
+    },
+}
+
+impl std::fmt::Display for DdsError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DdsError::Io(err) => write!(fmt, "Failed to read DDS file: {}", err),
+            DdsError::Magic => write!(fmt, "Not a DDS file"),
+            DdsError::Header(msg) => write!(fmt, "Invalid DDS header: {}", msg),
+            DdsError::UnsupportedFourCC(fourcc) => write!(
+                fmt,
+                "Unsupported DDS FourCC: {:?}",
+                String::from_utf8_lossy(fourcc)
+            ),
+            DdsError::UnsupportedDxgiFormat(format) => {
+                write!(fmt, "Unsupported DXGI format: {}", format)
+            }
+            DdsError::UnsupportedPixelFormat { bit_count, masks } => write!(
+                fmt,
+                "Unsupported DDS pixel format: {} bits with masks {:#x?}",
+                bit_count, masks
+            ),
+            DdsError::Truncated { expected, actual } => write!(
+                fmt,
+                "DDS data is truncated: {} bytes expected, {} found",
+                expected, actual
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DdsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DdsError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for DdsError {
+    fn from(err: std::io::Error) -> Self {
+        DdsError::Io(err)
+    }
+}
+
+/// Attempts to load a Texture from a DDS file.
+///
+/// All mip levels, array layers and cube faces stored in the file are preserved.
+/// Block-compressed formats (BC1 through BC7) are uploaded without decompression,
+/// `TextureBuilder::build` fails with `BuildError::Format` if device doesn't support them.
+pub fn load_from_dds<R>(
+    mut reader: R,
+    config: DdsTextureConfig,
+) -> Result<TextureBuilder<'static>, DdsError>
+where
+    R: std::io::Read,
+{
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+
+    let image = parse(&bytes, config.srgb)?;
+    bytes.truncate(image.offset + image.size);
+    bytes.drain(..image.offset);
+
+    let extent = image.kind.extent();
+    Ok(TextureBuilder::new()
+        .with_raw_data(bytes, image.format)
+        .with_swizzle(image.swizzle)
+        .with_data_width(extent.width)
+        .with_data_height(extent.height)
+        .with_mip_levels(MipLevels::Provided(image.levels))
+        .with_kind(image.kind)
+        .with_tiling(config.tiling)
+        .with_view_kind(image.view_kind)
+        .with_sampler_info(config.sampler_info))
+}
+
+/// Image described by DDS headers.
+#[derive(Debug)]
+struct DdsImage {
+    format: Format,
+    swizzle: Swizzle,
+    kind: Kind,
+    view_kind: ViewKind,
+    levels: NonZeroU8,
+    /// Offset of the data in the file.
+    offset: usize,
+    /// Size of all layers and mip levels.
+    size: usize,
+}
+
+fn parse(bytes: &[u8], srgb: bool) -> Result<DdsImage, DdsError> {
+    if bytes.len() < MAGIC.len() || &bytes[..MAGIC.len()] != MAGIC {
+        return Err(DdsError::Magic);
+    }
+    if bytes.len() < 4 + HEADER_SIZE as usize {
+        return Err(DdsError::Header("Header is truncated"));
+    }
+
+    let header = |offset: usize| read_u32(bytes, 4 + offset);
+    if header(0) != HEADER_SIZE {
+        return Err(DdsError::Header("Wrong header size"));
+    }
+
+    let flags = header(4);
+    let height = header(8);
+    let width = header(12);
+    let depth = header(20);
+    let mip_count = header(24);
+    let pf_flags = header(76);
+    let fourcc = [bytes[84], bytes[85], bytes[86], bytes[87]];
+    let bit_count = header(84);
+    let masks = [header(88), header(92), header(96), header(100)];
+    let caps2 = header(108);
+
+    let mut offset = 4 + HEADER_SIZE as usize;
+
+    let (format, swizzle, kind, view_kind) = if pf_flags & DDPF_FOURCC != 0 && &fourcc == b"DX10" {
+        if bytes.len() < offset + DX10_HEADER_SIZE {
+            return Err(DdsError::Header("DX10 header is truncated"));
+        }
+        let dxgi_format = read_u32(bytes, offset);
+        let dimension = read_u32(bytes, offset + 4);
+        let misc_flags = read_u32(bytes, offset + 8);
+        let array_size = read_u32(bytes, offset + 12).max(1);
+        offset += DX10_HEADER_SIZE;
+
+        let format = dxgi_format_to_format(dxgi_format)
+            .ok_or(DdsError::UnsupportedDxgiFormat(dxgi_format))?;
+        let (kind, view_kind) = match dimension {
+            D3D10_RESOURCE_DIMENSION_TEXTURE1D => kind_1d(width, array_size)?,
+            D3D10_RESOURCE_DIMENSION_TEXTURE2D => kind_2d(
+                width,
+                height,
+                array_size,
+                misc_flags & D3D10_RESOURCE_MISC_TEXTURECUBE != 0,
+            )?,
+            D3D10_RESOURCE_DIMENSION_TEXTURE3D => kind_3d(width, height, depth, array_size)?,
+            _ => return Err(DdsError::Header("Unknown resource dimension")),
+        };
+        (format, Swizzle::NO, kind, view_kind)
+    } else {
+        let (format, swizzle) = if pf_flags & DDPF_FOURCC != 0 {
+            let format =
+                fourcc_to_format(fourcc, srgb).ok_or(DdsError::UnsupportedFourCC(fourcc))?;
+            (format, Swizzle::NO)
+        } else {
+            masks_to_format(pf_flags, bit_count, masks, srgb)
+                .ok_or(DdsError::UnsupportedPixelFormat { bit_count, masks })?
+        };
+
+        let cube = caps2 & DDSCAPS2_CUBEMAP != 0;
+        if cube && caps2 & DDSCAPS2_CUBEMAP_ALLFACES != DDSCAPS2_CUBEMAP_ALLFACES {
+            return Err(DdsError::Header(
+                "Cube maps with missing faces are not supported",
+            ));
+        }
+        let (kind, view_kind) = if caps2 & DDSCAPS2_VOLUME != 0 && flags & DDSD_DEPTH != 0 {
+            kind_3d(width, height, depth, 1)?
+        } else {
+            kind_2d(width, height, 1, cube)?
+        };
+        (format, swizzle, kind, view_kind)
+    };
+
+    let extent = kind.extent();
+    let levels = if flags & DDSD_MIPMAPCOUNT != 0 {
+        mip_count.max(1)
+    } else {
+        1
+    };
+    let max_levels = 32
+        - extent
+            .width
+            .max(extent.height)
+            .max(extent.depth)
+            .leading_zeros();
+    if levels > max_levels || levels > rendy_core::hal::image::MAX_LEVEL as u32 {
+        return Err(DdsError::Header("Too many mip levels"));
+    }
+    let levels = NonZeroU8::new(levels as u8).unwrap();
+
+    let expected = (0..levels.get())
+        .map(|level| mip_level_size(format, extent.at_level(level)))
+        .sum::<usize>()
+        * kind.num_layers() as usize;
+    let actual = bytes.len() - offset;
+    if actual < expected {
+        return Err(DdsError::Truncated { expected, actual });
+    }
+
+    Ok(DdsImage {
+        format,
+        swizzle,
+        kind,
+        view_kind,
+        levels,
+        offset,
+        size: expected,
+    })
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        bytes[offset],
+        bytes[offset + 1],
+        bytes[offset + 2],
+        bytes[offset + 3],
+    ])
+}
+
+fn check_extent(width: u32, height: u32) -> Result<(), DdsError> {
+    if width == 0 || height == 0 {
+        Err(DdsError::Header("Image is empty"))
+    } else {
+        Ok(())
+    }
+}
+
+fn array_layers(layers: u32) -> Result<u16, DdsError> {
+    if layers > u16::MAX as u32 {
+        Err(DdsError::Header("Too many array layers"))
+    } else {
+        Ok(layers as u16)
+    }
+}
+
+fn kind_1d(width: u32, array_size: u32) -> Result<(Kind, ViewKind), DdsError> {
+    check_extent(width, 1)?;
+    let view_kind = if array_size > 1 {
+        ViewKind::D1Array
+    } else {
+        ViewKind::D1
+    };
+    Ok((Kind::D1(width, array_layers(array_size)?), view_kind))
+}
+
+fn kind_2d(
+    width: u32,
+    height: u32,
+    array_size: u32,
+    cube: bool,
+) -> Result<(Kind, ViewKind), DdsError> {
+    check_extent(width, height)?;
+    let (layers, view_kind) = match (cube, array_size > 1) {
+        (false, false) => (array_size, ViewKind::D2),
+        (false, true) => (array_size, ViewKind::D2Array),
+        (true, false) => (6, ViewKind::Cube),
+        (true, true) => (array_size.saturating_mul(6), ViewKind::CubeArray),
+    };
+    Ok((Kind::D2(width, height, array_layers(layers)?, 1), view_kind))
+}
+
+fn kind_3d(
+    width: u32,
+    height: u32,
+    depth: u32,
+    array_size: u32,
+) -> Result<(Kind, ViewKind), DdsError> {
+    check_extent(width, height)?;
+    if array_size > 1 {
+        return Err(DdsError::Header("Arrays of 3D textures are not supported"));
+    }
+    Ok((Kind::D3(width, height, depth.max(1)), ViewKind::D3))
+}
+
+fn fourcc_to_format(fourcc: [u8; 4], srgb: bool) -> Option<Format> {
+    let pick = |unorm, srgb_format| if srgb { srgb_format } else { unorm };
+    Some(match &fourcc {
+        b"DXT1" => pick(Format::Bc1RgbaUnorm, Format::Bc1RgbaSrgb),
+        b"DXT2" | b"DXT3" => pick(Format::Bc2Unorm, Format::Bc2Srgb),
+        b"DXT4" | b"DXT5" => pick(Format::Bc3Unorm, Format::Bc3Srgb),
+        b"ATI1" | b"BC4U" => Format::Bc4Unorm,
+        b"BC4S" => Format::Bc4Snorm,
+        b"ATI2" | b"BC5U" => Format::Bc5Unorm,
+        b"BC5S" => Format::Bc5Snorm,
+        // Legacy D3DFORMAT values stored in place of FourCC.
+        _ => match u32::from_le_bytes(fourcc) {
+            36 => Format::Rgba16Unorm,
+            110 => Format::Rgba16Snorm,
+            111 => Format::R16Sfloat,
+            112 => Format::Rg16Sfloat,
+            113 => Format::Rgba16Sfloat,
+            114 => Format::R32Sfloat,
+            115 => Format::Rg32Sfloat,
+            116 => Format::Rgba32Sfloat,
+            _ => return None,
+        },
+    })
+}
+
+fn masks_to_format(
+    pf_flags: u32,
+    bit_count: u32,
+    masks: [u32; 4],
+    srgb: bool,
+) -> Option<(Format, Swizzle)> {
+    let pick = |unorm, srgb_format| if srgb { srgb_format } else { unorm };
+    let [r, g, b, a] = masks;
+    let alpha = pf_flags & DDPF_ALPHAPIXELS != 0 && a != 0;
+    let opaque = Swizzle(Component::R, Component::G, Component::B, Component::One);
+    let rgb_swizzle = if alpha { Swizzle::NO } else { opaque };
+
+    if pf_flags & DDPF_RGB != 0 {
+        match (bit_count, r, g, b) {
+            (32, 0xff, 0xff00, 0xff_0000) => {
+                Some((pick(Format::Rgba8Unorm, Format::Rgba8Srgb), rgb_swizzle))
+            }
+            (32, 0xff_0000, 0xff00, 0xff) => {
+                Some((pick(Format::Bgra8Unorm, Format::Bgra8Srgb), rgb_swizzle))
+            }
+            (32, 0x3ff, 0xf_fc00, 0x3ff0_0000) => Some((Format::A2b10g10r10Unorm, rgb_swizzle)),
+            (32, 0xffff, 0xffff_0000, 0) => Some((
+                Format::Rg16Unorm,
+                Swizzle(Component::R, Component::G, Component::Zero, Component::One),
+            )),
+            (16, 0xf800, 0x7e0, 0x1f) => Some((Format::R5g6b5Unorm, opaque)),
+            _ => None,
+        }
+    } else if pf_flags & DDPF_LUMINANCE != 0 {
+        let luminance = |alpha| Swizzle(Component::R, Component::R, Component::R, alpha);
+        match (bit_count, r, alpha) {
+            (8, 0xff, false) => Some((Format::R8Unorm, luminance(Component::One))),
+            (16, 0xffff, false) => Some((Format::R16Unorm, luminance(Component::One))),
+            (16, 0xff, true) if a == 0xff00 => Some((Format::Rg8Unorm, luminance(Component::G))),
+            _ => None,
+        }
+    } else {
+        None
+    }
+}
+
+fn dxgi_format_to_format(dxgi_format: u32) -> Option<Format> {
+    Some(match dxgi_format {
+        2 => Format::Rgba32Sfloat,
+        10 => Format::Rgba16Sfloat,
+        11 => Format::Rgba16Unorm,
+        13 => Format::Rgba16Snorm,
+        16 => Format::Rg32Sfloat,
+        24 => Format::A2b10g10r10Unorm,
+        26 => Format::B10g11r11Ufloat,
+        28 => Format::Rgba8Unorm,
+        29 => Format::Rgba8Srgb,
+        31 => Format::Rgba8Snorm,
+        34 => Format::Rg16Sfloat,
+        35 => Format::Rg16Unorm,
+        41 => Format::R32Sfloat,
+        49 => Format::Rg8Unorm,
+        54 => Format::R16Sfloat,
+        56 => Format::R16Unorm,
+        61 => Format::R8Unorm,
+        67 => Format::E5b9g9r9Ufloat,
+        71 => Format::Bc1RgbaUnorm,
+        72 => Format::Bc1RgbaSrgb,
+        74 => Format::Bc2Unorm,
+        75 => Format::Bc2Srgb,
+        77 => Format::Bc3Unorm,
+        78 => Format::Bc3Srgb,
+        80 => Format::Bc4Unorm,
+        81 => Format::Bc4Snorm,
+        83 => Format::Bc5Unorm,
+        84 => Format::Bc5Snorm,
+        87 => Format::Bgra8Unorm,
+        91 => Format::Bgra8Srgb,
+        95 => Format::Bc6hUfloat,
+        96 => Format::Bc6hSfloat,
+        98 => Format::Bc7Unorm,
+        99 => Format::Bc7Srgb,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(width: u32, height: u32, mips: u32, fourcc: &[u8; 4], caps2: u32) -> Vec<u8> {
+        let mut header = vec![0u32; 31];
+        header[0] = HEADER_SIZE;
+        header[1] = 0x1007 | DDSD_MIPMAPCOUNT;
+        header[2] = height;
+        header[3] = width;
+        header[6] = mips;
+        header[18] = 32;
+        header[19] = DDPF_FOURCC;
+        header[20] = u32::from_le_bytes(*fourcc);
+        header[27] = caps2;
+
+        let mut bytes = MAGIC.to_vec();
+        for value in header {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_load_dxt1_mips() {
+        // 8x8, 4x4, 2x2 and 1x1 levels take 4, 1, 1 and 1 blocks respectively.
+        let mut bytes = header(8, 8, 4, b"DXT1", 0);
+        bytes.extend_from_slice(&[0xAB; 7 * 8]);
+
+        let image = parse(&bytes, true).unwrap();
+        assert_eq!(image.format, Format::Bc1RgbaSrgb);
+        assert_eq!(image.kind, Kind::D2(8, 8, 1, 1));
+        assert_eq!(image.levels.get(), 4);
+        assert_eq!((image.offset, image.size), (128, 7 * 8));
+
+        bytes.truncate(bytes.len() - 1);
+        match parse(&bytes, true) {
+            Err(DdsError::Truncated { expected, actual }) => {
+                assert_eq!((expected, actual), (7 * 8, 7 * 8 - 1))
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_load_dx10_cube() {
+        let mut bytes = header(4, 4, 1, b"DX10", 0);
+        for value in &[98u32, D3D10_RESOURCE_DIMENSION_TEXTURE2D, 0x4, 1, 0] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.extend_from_slice(&[0; 6 * 16]);
+
+        let image = parse(&bytes, true).unwrap();
+        assert_eq!(image.format, Format::Bc7Unorm);
+        assert_eq!(image.kind, Kind::D2(4, 4, 6, 1));
+        assert_eq!(image.view_kind, ViewKind::Cube);
+        assert_eq!((image.offset, image.size), (148, 6 * 16));
+    }
+
+    #[test]
+    fn test_unsupported_fourcc() {
+        let bytes = header(4, 4, 1, b"ETC1", 0);
+        match load_from_dds(&bytes[..], DdsTextureConfig::default()) {
+            Err(DdsError::UnsupportedFourCC(fourcc)) => assert_eq!(&fourcc, b"ETC1"),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+}
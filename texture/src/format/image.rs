@@ -1,6 +1,6 @@
 //! Module that turns an image into a `Texture`
 
-use crate::{pixel, MipLevels, TextureBuilder};
+use crate::{pixel, LayersError, MipLevels, TextureBuilder};
 
 use std::num::NonZeroU8;
 
@@ -97,6 +97,7 @@ pub struct ImageTextureConfig {
     pub format: Option<ImageFormat>,
     pub repr: Repr,
     pub kind: TextureKind,
+    /// Sampler parameters. Can be created from `SamplerInfo` with `into()`.
     pub sampler_info: rendy_core::hal::image::SamplerDesc,
     /// Automatically generate mipmaps for this image
     pub generate_mips: bool,
@@ -104,6 +105,10 @@ pub struct ImageTextureConfig {
     /// means an image stored with non-premultiplied alpha will become premultiplied,
     /// rather than indicating that the supplied image is premultiplied to begin with.
     pub premultiply_alpha: bool,
+    /// Image tiling. When `None`, optimal tiling is preferred
+    /// and linear tiling is used if format doesn't support required features otherwise.
+    /// See `pick_tiling`.
+    pub tiling: Option<rendy_core::hal::image::Tiling>,
 }
 
 impl Default for ImageTextureConfig {
@@ -118,6 +123,7 @@ impl Default for ImageTextureConfig {
             ),
             generate_mips: false,
             premultiply_alpha: false,
+            tiling: None,
         }
     }
 }
@@ -306,6 +312,33 @@ where
         .with_mip_levels(mips)
         .with_kind(kind)
         .with_premultiplied_alpha(config.premultiply_alpha)
+        .with_tiling(config.tiling)
         .with_view_kind(config.kind.view_kind())
         .with_sampler_info(config.sampler_info))
 }
+
+/// Attempts to load an array texture from same-sized images.
+/// Each image becomes one layer of `D2Array` texture.
+/// `config.kind` is ignored.
+pub fn load_array_from_images<R>(
+    readers: impl IntoIterator<Item = R>,
+    config: ImageTextureConfig,
+) -> Result<TextureBuilder<'static>, image::ImageError>
+where
+    R: std::io::BufRead + std::io::Seek,
+{
+    let config = ImageTextureConfig {
+        kind: TextureKind::D2,
+        ..config
+    };
+
+    let layers = readers
+        .into_iter()
+        .map(|reader| load_from_image(reader, config.clone()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    TextureBuilder::from_layers(layers).map_err(|err| match err {
+        LayersError::Extent(_) => image::ImageError::DimensionError,
+        err => image::ImageError::FormatError(err.to_string()),
+    })
+}
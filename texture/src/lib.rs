@@ -19,6 +19,7 @@ use rendy_resource as resource;
 
 mod format;
 pub mod pixel;
+mod sampler;
 mod texture;
 
-pub use crate::{format::*, pixel::Rgba8Unorm, texture::*};
+pub use crate::{format::*, pixel::Rgba8Unorm, sampler::*, texture::*};
@@ -0,0 +1,185 @@
+//! Sampler configuration for textures.
+use rendy_core::hal::{
+    image::{Filter, Lod, PackedColor, SamplerDesc, WrapMode},
+    pso::Comparison,
+    Features, Limits,
+};
+
+/// Sampler parameters of the texture.
+/// Converts into `SamplerDesc` accepted by `TextureBuilder` and `ImageTextureConfig`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SamplerInfo {
+    /// Filter used when texture is magnified.
+    pub mag_filter: Filter,
+    /// Filter used when texture is minified.
+    pub min_filter: Filter,
+    /// Filter used between mip levels.
+    pub mip_filter: Filter,
+    /// Addressing mode for U coordinate.
+    pub address_u: WrapMode,
+    /// Addressing mode for V coordinate.
+    pub address_v: WrapMode,
+    /// Addressing mode for W coordinate.
+    pub address_w: WrapMode,
+    /// Bias added to computed mip level.
+    pub lod_bias: f32,
+    /// Range to clamp computed mip level to.
+    pub lod_range: std::ops::Range<f32>,
+    /// Maximum anisotropy. `None` disables anisotropic filtering.
+    /// Requires `SAMPLER_ANISOTROPY` feature.
+    pub anisotropy: Option<u8>,
+    /// Comparison function for depth textures.
+    pub comparison: Option<Comparison>,
+    /// Border color for `WrapMode::Border`.
+    pub border: PackedColor,
+}
+
+impl Default for SamplerInfo {
+    fn default() -> Self {
+        SamplerInfo::new(Filter::Linear, WrapMode::Clamp)
+    }
+}
+
+impl SamplerInfo {
+    /// Sampler with the same filter for all operations and the same addressing mode for all axes.
+    pub fn new(filter: Filter, wrap: WrapMode) -> Self {
+        SamplerInfo {
+            mag_filter: filter,
+            min_filter: filter,
+            mip_filter: filter,
+            address_u: wrap,
+            address_v: wrap,
+            address_w: wrap,
+            lod_bias: 0.0,
+            lod_range: Lod::RANGE.start.0..Lod::RANGE.end.0,
+            anisotropy: None,
+            comparison: None,
+            border: PackedColor(0),
+        }
+    }
+
+    /// Sampler that uses nearest filtering, suitable for pixel-art.
+    pub fn nearest(wrap: WrapMode) -> Self {
+        SamplerInfo::new(Filter::Nearest, wrap)
+    }
+
+    /// Get raw sampler description.
+    pub fn desc(&self) -> SamplerDesc {
+        SamplerDesc {
+            min_filter: self.min_filter,
+            mag_filter: self.mag_filter,
+            mip_filter: self.mip_filter,
+            wrap_mode: (self.address_u, self.address_v, self.address_w),
+            lod_bias: Lod(self.lod_bias),
+            lod_range: Lod(self.lod_range.start)..Lod(self.lod_range.end),
+            comparison: self.comparison,
+            border: self.border,
+            normalized: true,
+            anisotropy_clamp: self.anisotropy,
+        }
+    }
+}
+
+impl From<SamplerInfo> for SamplerDesc {
+    fn from(info: SamplerInfo) -> Self {
+        info.desc()
+    }
+}
+
+impl From<&SamplerInfo> for SamplerDesc {
+    fn from(info: &SamplerInfo) -> Self {
+        info.desc()
+    }
+}
+
+/// Error validating sampler parameters against device.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SamplerError {
+    /// Anisotropic filtering was requested but `SAMPLER_ANISOTROPY` feature is not enabled.
+    AnisotropyUnsupported(u8),
+    /// LOD range is empty.
+    InvalidLodRange(std::ops::Range<f32>),
+}
+
+impl std::fmt::Display for SamplerError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SamplerError::AnisotropyUnsupported(anisotropy) => write!(
+                fmt,
+                "Anisotropy {} requested but `SAMPLER_ANISOTROPY` feature is not enabled",
+                anisotropy
+            ),
+            SamplerError::InvalidLodRange(range) => {
+                write!(fmt, "LOD range {:?} is empty", range)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SamplerError {}
+
+/// Validate sampler description against device features and limits.
+/// Anisotropy above `max_sampler_anisotropy` limit is clamped to the limit.
+pub fn validate_sampler_desc(
+    mut desc: SamplerDesc,
+    features: Features,
+    limits: &Limits,
+) -> Result<SamplerDesc, SamplerError> {
+    if desc.lod_range.start.0 > desc.lod_range.end.0 {
+        return Err(SamplerError::InvalidLodRange(
+            desc.lod_range.start.0..desc.lod_range.end.0,
+        ));
+    }
+
+    if let Some(anisotropy) = desc.anisotropy_clamp {
+        if !features.contains(Features::SAMPLER_ANISOTROPY) {
+            return Err(SamplerError::AnisotropyUnsupported(anisotropy));
+        }
+        let max = limits.max_sampler_anisotropy.max(1.0).min(u8::MAX as f32) as u8;
+        if anisotropy > max {
+            log::warn!(
+                "Sampler anisotropy {} exceeds device limit and is clamped to {}",
+                anisotropy,
+                max
+            );
+            desc.anisotropy_clamp = Some(max);
+        }
+    }
+
+    Ok(desc)
+}
+
+#[test]
+fn test_validate_sampler_desc() {
+    let limits = Limits {
+        max_sampler_anisotropy: 8.0,
+        ..Limits::default()
+    };
+
+    let info = SamplerInfo {
+        anisotropy: Some(16),
+        ..SamplerInfo::nearest(WrapMode::Tile)
+    };
+    assert_eq!(
+        validate_sampler_desc(info.desc(), Features::empty(), &limits),
+        Err(SamplerError::AnisotropyUnsupported(16))
+    );
+
+    let desc = validate_sampler_desc(info.desc(), Features::SAMPLER_ANISOTROPY, &limits).unwrap();
+    assert_eq!(desc.anisotropy_clamp, Some(8));
+    assert_eq!(desc.mag_filter, Filter::Nearest);
+    assert_eq!(
+        desc.wrap_mode,
+        (WrapMode::Tile, WrapMode::Tile, WrapMode::Tile)
+    );
+
+    let info = SamplerInfo {
+        lod_range: 4.0..1.0,
+        ..SamplerInfo::default()
+    };
+    assert_eq!(
+        validate_sampler_desc(info.desc(), Features::empty(), &limits),
+        Err(SamplerError::InvalidLodRange(4.0..1.0))
+    );
+}
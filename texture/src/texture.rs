@@ -9,9 +9,11 @@ use {
             Escape, Handle, Image, ImageCreationError, ImageInfo, ImageView,
             ImageViewCreationError, ImageViewInfo, Sampler,
         },
+        sampler::{validate_sampler_desc, SamplerError},
     },
     rendy_core::hal::{
-        format::{Component, Format, Swizzle},
+        adapter::PhysicalDevice as _,
+        format::{Component, Format, ImageFeature, Swizzle},
         image, Backend,
     },
     std::num::NonZeroU8,
@@ -71,6 +73,10 @@ pub enum MipLevels {
     /// Create the image with raw mip levels but without blitting the main
     /// texture data into them
     Levels(NonZeroU8),
+    /// Data contains all mip levels, none are generated.
+    /// For each layer levels follow each other from the largest to the smallest,
+    /// each level taking `mip_level_size` bytes.
+    Provided(NonZeroU8),
 }
 
 /// Calculate the number of mip levels for a 2D image with given dimensions
@@ -78,25 +84,101 @@ pub fn mip_levels_from_dims(width: u32, height: u32) -> u8 {
     ((32 - width.max(height).leading_zeros()).max(1) as u8).min(rendy_core::hal::image::MAX_LEVEL)
 }
 
+/// Size in bytes of tightly packed data of a single layer of a mip level
+/// with given extent.
+/// Extent of compressed formats is rounded up to whole blocks.
+pub fn mip_level_size(format: Format, extent: image::Extent) -> usize {
+    let desc = format.surface_desc();
+    let (block_width, block_height) = (desc.dim.0 as u32, desc.dim.1 as u32);
+    let blocks = extent.width.div_ceil(block_width) as usize
+        * extent.height.div_ceil(block_height) as usize
+        * extent.depth as usize;
+    blocks * (desc.bits as usize / 8)
+}
+
+/// Image features required to build a texture sampled with `sampler_info`.
+///
+/// Texture is always sampled. Linear filtering in sampler requires `SAMPLED_LINEAR`
+/// and mip generation requires linear blits within the image.
+pub fn required_image_features(
+    sampler_info: &rendy_core::hal::image::SamplerDesc,
+    generate_mips: bool,
+) -> ImageFeature {
+    let mut features = ImageFeature::SAMPLED;
+    if sampler_info.min_filter == image::Filter::Linear
+        || sampler_info.mag_filter == image::Filter::Linear
+        || sampler_info.mip_filter == image::Filter::Linear
+    {
+        features |= ImageFeature::SAMPLED_LINEAR;
+    }
+    if generate_mips {
+        features |= ImageFeature::BLIT_SRC | ImageFeature::BLIT_DST | ImageFeature::SAMPLED_LINEAR;
+    }
+    features
+}
+
+/// Pick tiling which supports `required` features of the format.
+/// Optimal tiling is preferred.
+/// Returns `None` if neither tiling supports all `required` features.
+pub fn pick_tiling(
+    properties: &rendy_core::hal::format::Properties,
+    required: ImageFeature,
+) -> Option<image::Tiling> {
+    if properties.optimal_tiling.contains(required) {
+        Some(image::Tiling::Optimal)
+    } else if properties.linear_tiling.contains(required) {
+        Some(image::Tiling::Linear)
+    } else {
+        None
+    }
+}
+
 #[derive(Debug)]
 pub enum BuildError {
     Format(Format),
+    Tiling {
+        format: Format,
+        tiling: image::Tiling,
+        required: ImageFeature,
+        supported: ImageFeature,
+    },
     Image(ImageCreationError),
     Upload(UploadError),
     ImageView(ImageViewCreationError),
     Mipmap(rendy_core::hal::device::OutOfMemory),
     Sampler(rendy_core::hal::device::AllocationError),
+    SamplerInfo(SamplerError),
+    Data {
+        expected: usize,
+        actual: usize,
+    },
 }
 
 impl std::fmt::Display for BuildError {
     fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             BuildError::Format(format) => write!(fmt, "Format unsupported: {:?}", format),
+            BuildError::Tiling {
+                format,
+                tiling,
+                required,
+                supported,
+            } => write!(
+                fmt,
+                "Format {:?} with {:?} tiling supports only {:?} features, but {:?} are required",
+                format, tiling, supported, required
+            ),
             BuildError::Image(err) => write!(fmt, "Texture build failed: {:?}", err),
             BuildError::Upload(err) => write!(fmt, "Texture build failed: {:?}", err),
             BuildError::ImageView(err) => write!(fmt, "Texture build failed: {:?}", err),
             BuildError::Mipmap(err) => write!(fmt, "Texture build failed: {:?}", err),
             BuildError::Sampler(err) => write!(fmt, "Texture build failed: {:?}", err),
+            BuildError::SamplerInfo(err) => write!(fmt, "Texture build failed: {}", err),
+            BuildError::Data { expected, actual } => write!(
+                fmt,
+                "Texture data is too short: {} bytes expected, {} provided",
+                expected, actual
+            ),
         }
     }
 }
@@ -105,19 +187,67 @@ impl std::error::Error for BuildError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             BuildError::Format(_) => None,
+            BuildError::Tiling { .. } => None,
             BuildError::Image(err) => Some(err),
             BuildError::Upload(err) => Some(err),
             BuildError::ImageView(err) => Some(err),
             BuildError::Mipmap(err) => Some(err),
             BuildError::Sampler(err) => Some(err),
+            BuildError::SamplerInfo(err) => Some(err),
+            BuildError::Data { .. } => None,
         }
     }
 }
 
+/// Error combining texture layers into an array texture.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LayersError {
+    /// No layers were provided.
+    Empty,
+    /// Layer is not a single layer 2D image.
+    Kind(usize),
+    /// Layer extent differs from the first layer's.
+    Extent(usize),
+    /// Layer format or swizzle differs from the first layer's.
+    Format(usize),
+}
+
+impl std::fmt::Display for LayersError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LayersError::Empty => write!(fmt, "Array texture requires at least one layer"),
+            LayersError::Kind(index) => {
+                write!(fmt, "Layer {} is not a single layer 2D image", index)
+            }
+            LayersError::Extent(index) => {
+                write!(fmt, "Layer {} extent differs from the first layer's", index)
+            }
+            LayersError::Format(index) => {
+                write!(fmt, "Layer {} format differs from the first layer's", index)
+            }
+        }
+    }
+}
+
+impl std::error::Error for LayersError {}
+
 /// Generics-free texture builder.
 #[derive(Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// Struct for staging data in preparation of building a `Texture`
+///
+/// Builder keeps decoded texel data and [`build`] doesn't consume it,
+/// so the same builder can build texture with several factories
+/// or rebuild it after device loss without decoding the source again.
+/// With `serde-1` feature builder can be serialized together with the data,
+/// e.g. to cache decoded assets.
+///
+/// Retained data takes as much memory as all mip levels and layers
+/// it provides, uncompressed unless the format itself is block-compressed.
+/// Cloning builder that owns its data copies the data,
+/// drop builders once textures are built if they are not going to be rebuilt.
+///
+/// [`build`]: #method.build
 pub struct TextureBuilder<'a> {
     kind: image::Kind,
     view_kind: image::ViewKind,
@@ -128,6 +258,7 @@ pub struct TextureBuilder<'a> {
     sampler_info: rendy_core::hal::image::SamplerDesc,
     swizzle: Swizzle,
     mip_levels: MipLevels,
+    tiling: Option<image::Tiling>,
     premultiplied: bool,
 }
 
@@ -143,6 +274,7 @@ impl<'a> std::fmt::Debug for TextureBuilder<'a> {
             .field("sampler_info", &self.sampler_info)
             .field("swizzle", &self.swizzle)
             .field("mip_levels", &self.mip_levels)
+            .field("tiling", &self.tiling)
             .field("premultiplied", &self.premultiplied)
             .finish()
     }
@@ -164,10 +296,28 @@ impl<'a> TextureBuilder<'a> {
             ),
             swizzle: Swizzle::NO,
             mip_levels: MipLevels::Levels(NonZeroU8::new(1).unwrap()),
+            tiling: None,
             premultiplied: false,
         }
     }
 
+    /// Make builder own its data, copying borrowed data if necessary.
+    pub fn into_owned(self) -> TextureBuilder<'static> {
+        TextureBuilder {
+            data: std::borrow::Cow::Owned(self.data.into_owned()),
+            kind: self.kind,
+            view_kind: self.view_kind,
+            format: self.format,
+            data_width: self.data_width,
+            data_height: self.data_height,
+            sampler_info: self.sampler_info,
+            swizzle: self.swizzle,
+            mip_levels: self.mip_levels,
+            tiling: self.tiling,
+            premultiplied: self.premultiplied,
+        }
+    }
+
     /// Set whether the image has premultiplied alpha
     pub fn set_premultiplied_alpha(&mut self, premultiplied: bool) -> &mut Self {
         self.premultiplied = premultiplied;
@@ -253,6 +403,22 @@ impl<'a> TextureBuilder<'a> {
         self
     }
 
+    /// Set image tiling.
+    /// When `None` (default), optimal tiling is used if format supports
+    /// required features with it, and linear tiling otherwise.
+    pub fn with_tiling(mut self, tiling: Option<image::Tiling>) -> Self {
+        self.set_tiling(tiling);
+        self
+    }
+
+    /// Set image tiling.
+    /// When `None` (default), optimal tiling is used if format supports
+    /// required features with it, and linear tiling otherwise.
+    pub fn set_tiling(&mut self, tiling: Option<image::Tiling>) -> &mut Self {
+        self.tiling = tiling;
+        self
+    }
+
     /// Set image extent.
     pub fn with_kind(mut self, kind: image::Kind) -> Self {
         self.set_kind(kind);
@@ -278,17 +444,26 @@ impl<'a> TextureBuilder<'a> {
     }
 
     /// With image sampler info.
-    pub fn with_sampler_info(mut self, sampler_info: rendy_core::hal::image::SamplerDesc) -> Self {
+    /// Accepts either raw `SamplerDesc` or [`SamplerInfo`].
+    ///
+    /// [`SamplerInfo`]: struct.SamplerInfo.html
+    pub fn with_sampler_info(
+        mut self,
+        sampler_info: impl Into<rendy_core::hal::image::SamplerDesc>,
+    ) -> Self {
         self.set_sampler_info(sampler_info);
         self
     }
 
     /// Set image sampler info.
+    /// Accepts either raw `SamplerDesc` or [`SamplerInfo`].
+    ///
+    /// [`SamplerInfo`]: struct.SamplerInfo.html
     pub fn set_sampler_info(
         &mut self,
-        sampler_info: rendy_core::hal::image::SamplerDesc,
+        sampler_info: impl Into<rendy_core::hal::image::SamplerDesc>,
     ) -> &mut Self {
-        self.sampler_info = sampler_info;
+        self.sampler_info = sampler_info.into();
         self
     }
 
@@ -304,16 +479,57 @@ impl<'a> TextureBuilder<'a> {
         self
     }
 
+    /// Combine same-sized 2D layers into a `D2Array` texture.
+    /// Layer data is uploaded with a single staging copy when the texture is built.
+    /// Mip levels, sampler and alpha mode are taken from the first layer.
+    pub fn from_layers<'b>(
+        layers: impl IntoIterator<Item = TextureBuilder<'b>>,
+    ) -> Result<Self, LayersError> {
+        let mut layers = layers.into_iter();
+        let first = layers.next().ok_or(LayersError::Empty)?;
+        let (width, height) = match first.kind {
+            image::Kind::D2(width, height, 1, 1) => (width, height),
+            _ => return Err(LayersError::Kind(0)),
+        };
+
+        let mut data = first.data.into_owned();
+        let mut count = 1u16;
+        for (index, layer) in (1..).zip(layers) {
+            match layer.kind {
+                image::Kind::D2(w, h, 1, 1) if (w, h) == (width, height) => {}
+                image::Kind::D2(_, _, 1, 1) => return Err(LayersError::Extent(index)),
+                _ => return Err(LayersError::Kind(index)),
+            }
+            if (layer.data_width, layer.data_height) != (first.data_width, first.data_height) {
+                return Err(LayersError::Extent(index));
+            }
+            if layer.format != first.format || layer.swizzle != first.swizzle {
+                return Err(LayersError::Format(index));
+            }
+            data.extend_from_slice(&layer.data);
+            count += 1;
+        }
+
+        Ok(TextureBuilder {
+            kind: image::Kind::D2(width, height, count, 1),
+            view_kind: image::ViewKind::D2Array,
+            data: std::borrow::Cow::Owned(data),
+            ..first
+        })
+    }
+
     /// Build texture.
     ///
     /// ## Parameters
     /// * `next_state`: The next state that this texture will be used in.
     ///     It will get transitioned to this state after uploading.
     /// * `factory`: Factory to use to build the texture
+    ///
+    /// Builder is not consumed, so it can build texture again, e.g. after device loss.
     pub fn build<B>(
         &self,
         next_state: ImageState,
-        factory: &'a mut Factory<B>,
+        factory: &mut Factory<B>,
     ) -> Result<Texture<B>, BuildError>
     where
         B: Backend,
@@ -333,7 +549,7 @@ impl<'a> TextureBuilder<'a> {
 
         let (mip_levels, generate_mips) = match self.mip_levels {
             MipLevels::GenerateLevels(val) => (val.get(), true),
-            MipLevels::Levels(val) => (val.get(), false),
+            MipLevels::Levels(val) | MipLevels::Provided(val) => (val.get(), false),
             MipLevels::GenerateAuto => match self.kind {
                 rendy_core::hal::image::Kind::D1(_, _) => (1, false),
                 rendy_core::hal::image::Kind::D2(w, h, _, _) => (mip_levels_from_dims(w, h), true),
@@ -341,20 +557,48 @@ impl<'a> TextureBuilder<'a> {
             },
         };
 
-        let (info, transform, transform_swizzle) = find_compatible_format(
+        let required = required_image_features(&self.sampler_info, generate_mips && mip_levels > 1);
+        let found = find_compatible_format(
             factory,
             ImageInfo {
                 kind: self.kind,
                 levels: mip_levels,
                 format: self.format,
-                tiling: rendy_core::hal::image::Tiling::Optimal,
+                tiling: self.tiling.unwrap_or(image::Tiling::Optimal),
                 view_caps,
                 usage: rendy_core::hal::image::Usage::SAMPLED
                     | rendy_core::hal::image::Usage::TRANSFER_DST
                     | rendy_core::hal::image::Usage::TRANSFER_SRC,
             },
-        )
-        .ok_or(BuildError::Format(self.format))?;
+        );
+        let (mut info, transform, transform_swizzle) =
+            found.ok_or(BuildError::Format(self.format))?;
+
+        let properties = factory.format_properties(info.format);
+        if self.tiling.is_none() {
+            if let Some(image::Tiling::Linear) = pick_tiling(&properties, required) {
+                let linear = ImageInfo {
+                    tiling: image::Tiling::Linear,
+                    ..info
+                };
+                if let Some(linear) = image_format_supported(factory, linear) {
+                    info = linear;
+                }
+            }
+        }
+
+        let supported = match info.tiling {
+            image::Tiling::Optimal => properties.optimal_tiling,
+            image::Tiling::Linear => properties.linear_tiling,
+        };
+        if !supported.contains(required) {
+            return Err(BuildError::Tiling {
+                format: info.format,
+                tiling: info.tiling,
+                required,
+                supported,
+            });
+        }
 
         let image: Handle<Image<B>> = factory
             .create_image(info, Data)
@@ -384,64 +628,88 @@ impl<'a> TextureBuilder<'a> {
             }
         };
 
-        let mip_state = ImageState {
-            queue: next_state.queue,
-            stage: rendy_core::hal::pso::PipelineStage::TRANSFER,
-            access: image::Access::TRANSFER_READ,
-            layout: image::Layout::TransferSrcOptimal,
-        };
-
-        let undef_state = ImageState {
-            queue: next_state.queue,
-            stage: rendy_core::hal::pso::PipelineStage::TOP_OF_PIPE,
-            access: image::Access::empty(),
-            layout: image::Layout::Undefined,
-        };
-
-        // The reason that factory.upload_image is unsafe is that the image being uploaded
+        // The reason that factory upload functions are unsafe is that the image being uploaded
         // must have been created by the same factory and that it is not in use; we guarantee
         // that here because we just created the image on the same factory right before.
-        unsafe {
-            profile_scope!("upload_image");
-
-            factory
-                .upload_image(
-                    image.clone(),
-                    self.data_width,
-                    self.data_height,
-                    image::SubresourceLayers {
-                        aspects: info.format.surface_desc().aspects,
-                        level: 0,
-                        layers: 0..info.kind.num_layers(),
-                    },
-                    image::Offset::ZERO,
-                    info.kind.extent(),
-                    buffer,
-                    image::Layout::Undefined,
-                    if !generate_mips || mip_levels == 1 {
-                        next_state
-                    } else {
-                        mip_state
-                    },
-                )
-                .map_err(BuildError::Upload)?;
-        }
-
         if mip_levels > 1 && generate_mips {
-            profile_scope!("fill_mips");
+            profile_scope!("upload_image_with_mips");
+
+            // Copy and mip blits are batched with other images into one command buffer.
             unsafe {
                 factory
-                    .blitter()
-                    .fill_mips(
-                        factory.device(),
+                    .upload_image_with_mips(
                         image.clone(),
+                        self.data_width,
+                        self.data_height,
+                        buffer,
                         image::Filter::Linear,
-                        std::iter::once(mip_state).chain(std::iter::repeat(undef_state)),
-                        std::iter::repeat(next_state),
+                        next_state,
+                    )
+                    .map_err(BuildError::Upload)?;
+            }
+        } else if let MipLevels::Provided(_) = self.mip_levels {
+            profile_scope!("upload_mip_levels");
+
+            let desc = info.format.surface_desc();
+            let (block_width, block_height) = (desc.dim.0 as u32, desc.dim.1 as u32);
+            let mut offset = 0;
+            for layer in 0..info.kind.num_layers() {
+                for level in 0..mip_levels {
+                    let extent = info.kind.extent().at_level(level);
+                    let size = mip_level_size(info.format, extent);
+                    let data = buffer.get(offset..offset + size).ok_or(BuildError::Data {
+                        expected: mip_level_size_total(&info),
+                        actual: buffer.len(),
+                    })?;
+                    offset += size;
+
+                    unsafe {
+                        factory
+                            .upload_image(
+                                image.clone(),
+                                extent.width.div_ceil(block_width) * block_width,
+                                extent.height.div_ceil(block_height) * block_height,
+                                image::SubresourceLayers {
+                                    aspects: desc.aspects,
+                                    level,
+                                    layers: layer..layer + 1,
+                                },
+                                image::Offset::ZERO,
+                                extent,
+                                data,
+                                image::Layout::Undefined,
+                                next_state,
+                            )
+                            .map_err(BuildError::Upload)?;
+                    }
+                }
+            }
+        } else {
+            profile_scope!("upload_image");
+
+            unsafe {
+                factory
+                    .upload_image(
+                        image.clone(),
+                        self.data_width,
+                        self.data_height,
+                        image::SubresourceLayers {
+                            aspects: info.format.surface_desc().aspects,
+                            level: 0,
+                            layers: 0..info.kind.num_layers(),
+                        },
+                        image::Offset::ZERO,
+                        info.kind.extent(),
+                        buffer,
+                        image::Layout::Undefined,
+                        next_state,
                     )
-                    .map_err(BuildError::Mipmap)?;
+                    .map_err(BuildError::Upload)?;
             }
-        } else if mip_levels > 1 && !generate_mips {
+        }
+
+        let provided = matches!(self.mip_levels, MipLevels::Provided(_));
+        if mip_levels > 1 && !generate_mips && !provided {
             unsafe {
                 factory.transition_image(
                     image.clone(),
@@ -475,8 +743,15 @@ impl<'a> TextureBuilder<'a> {
                 .map_err(BuildError::ImageView)?
         };
 
+        let sampler_info = validate_sampler_desc(
+            self.sampler_info.clone(),
+            factory.features(),
+            &factory.physical().limits(),
+        )
+        .map_err(BuildError::SamplerInfo)?;
+
         let sampler = factory
-            .get_sampler(self.sampler_info.clone())
+            .get_sampler(sampler_info)
             .map_err(BuildError::Sampler)?;
 
         Ok(Texture {
@@ -516,6 +791,14 @@ fn double_swizzle(src: Swizzle, overlay: Swizzle) -> Swizzle {
     )
 }
 
+fn mip_level_size_total(info: &ImageInfo) -> usize {
+    let extent = info.kind.extent();
+    (0..info.levels)
+        .map(|level| mip_level_size(info.format, extent.at_level(level)))
+        .sum::<usize>()
+        * info.kind.num_layers() as usize
+}
+
 fn find_compatible_format<B: Backend>(
     factory: &Factory<B>,
     info: ImageInfo,
@@ -666,6 +949,7 @@ fn image_format_supported<B: Backend>(
         .image_format_properties(info)
         .filter(|props| {
             props.max_layers >= info.kind.num_layers()
+                && props.max_levels >= info.levels
                 && props.max_extent.width >= info.kind.extent().width
                 && props.max_extent.height >= info.kind.extent().height
                 && props.max_extent.depth >= info.kind.extent().depth
@@ -701,3 +985,78 @@ fn buf_add_padding(buffer: &[u8], dst_slice: &mut [u8], stride: usize, padding:
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_layers() {
+        let layer = |value: u8| {
+            TextureBuilder::new()
+                .with_raw_data(vec![value; 2 * 2 * 4], Format::Rgba8Unorm)
+                .with_data_width(2)
+                .with_data_height(2)
+                .with_kind(image::Kind::D2(2, 2, 1, 1))
+        };
+
+        let array = TextureBuilder::from_layers(vec![layer(0), layer(1), layer(2)]).unwrap();
+        assert_eq!(array.kind, image::Kind::D2(2, 2, 3, 1));
+        assert_eq!(array.view_kind, image::ViewKind::D2Array);
+
+        let layer_size = 2 * 2 * 4;
+        assert!(array.data[layer_size..2 * layer_size]
+            .iter()
+            .all(|&b| b == 1));
+
+        let mismatch = layer(3).with_kind(image::Kind::D2(4, 1, 1, 1));
+        assert_eq!(
+            TextureBuilder::from_layers(vec![layer(0), mismatch]).unwrap_err(),
+            LayersError::Extent(1)
+        );
+    }
+
+    #[test]
+    fn test_into_owned() {
+        let data = vec![7u8; 2 * 2 * 4];
+        let borrowed = TextureBuilder::new()
+            .with_raw_data(&data[..], Format::Rgba8Unorm)
+            .with_data_width(2)
+            .with_kind(image::Kind::D2(2, 2, 1, 1));
+        let owned = borrowed.clone().into_owned();
+        drop(data);
+
+        assert!(matches!(owned.data, std::borrow::Cow::Owned(_)));
+        assert_eq!(&owned.data[..], &[7u8; 2 * 2 * 4][..]);
+        assert_eq!(owned.kind, image::Kind::D2(2, 2, 1, 1));
+        assert_eq!(owned.data_width, 2);
+    }
+
+    #[test]
+    fn test_pick_tiling() {
+        let sampler = image::SamplerDesc::new(image::Filter::Linear, image::WrapMode::Clamp);
+        let required = required_image_features(&sampler, false);
+        assert_eq!(
+            required,
+            ImageFeature::SAMPLED | ImageFeature::SAMPLED_LINEAR
+        );
+
+        let properties = rendy_core::hal::format::Properties {
+            linear_tiling: ImageFeature::SAMPLED | ImageFeature::SAMPLED_LINEAR,
+            optimal_tiling: ImageFeature::SAMPLED,
+            buffer_features: Default::default(),
+        };
+        assert_eq!(
+            pick_tiling(&properties, required),
+            Some(image::Tiling::Linear)
+        );
+        assert_eq!(
+            pick_tiling(&properties, ImageFeature::SAMPLED),
+            Some(image::Tiling::Optimal)
+        );
+        assert_eq!(
+            pick_tiling(&properties, required_image_features(&sampler, true)),
+            None
+        );
+    }
+}
@@ -20,6 +20,7 @@ use {
     },
     rendy_core::{
         device_owned, instance_owned, Device, DeviceId, HasRawWindowHandle, Instance, InstanceId,
+        RawHandle, RawWindowHandle,
     },
     rendy_resource::{Image, ImageInfo},
 };
@@ -33,6 +34,13 @@ pub enum SwapchainError {
     BadPresentMode(rendy_core::hal::window::PresentMode),
     /// Image count is not supported.
     BadImageCount(rendy_core::hal::window::SwapImageIndex),
+    /// Image usage is not supported.
+    BadUsage {
+        /// Requested usage flags.
+        requested: rendy_core::hal::image::Usage,
+        /// Usage flags supported by the surface.
+        supported: rendy_core::hal::image::Usage,
+    },
 }
 
 impl std::fmt::Display for SwapchainError {
@@ -53,6 +61,14 @@ impl std::fmt::Display for SwapchainError {
                 "Failed to create swapchain because requested image count is not supported: {:?}",
                 image_count
             ),
+            SwapchainError::BadUsage {
+                requested,
+                supported,
+            } => write!(
+                fmt,
+                "Failed to create swapchain because requested image usage {:?} is not supported. Supported: {:?}",
+                requested, supported
+            ),
         }
     }
 }
@@ -63,13 +79,193 @@ impl std::error::Error for SwapchainError {
             SwapchainError::Create(err) => Some(err),
             SwapchainError::BadPresentMode(_) => None,
             SwapchainError::BadImageCount(_) => None,
+            SwapchainError::BadUsage { .. } => None,
         }
     }
 }
+/// Error acquiring or presenting swapchain image
+/// when swapchain is recreated on demand.
+#[derive(Debug)]
+pub enum RecreateError {
+    /// Failed to acquire image.
+    Acquire(rendy_core::hal::window::AcquireError),
+    /// Failed to present image.
+    Present(rendy_core::hal::window::PresentError),
+    /// Failed to wait for device to become idle before recreation.
+    OutOfMemory(rendy_core::hal::device::OutOfMemory),
+    /// Failed to recreate swapchain.
+    Swapchain(SwapchainError),
+    /// Surface is lost and no swapchain can be created for it.
+    /// Surface must be recreated from the window with [`Target::recreate_surface`].
+    ///
+    /// Of gfx-hal 0.5 backends only Vulkan reports lost surfaces
+    /// (`VK_ERROR_SURFACE_LOST_KHR`), e.g. when Wayland or Android destroys
+    /// the native window or the display is unplugged.
+    ///
+    /// [`Target::recreate_surface`]: struct.Target.html#method.recreate_surface
+    SurfaceLost(rendy_core::hal::device::SurfaceLost),
+    /// Failed to create new surface for the window.
+    Surface(rendy_core::hal::window::InitError),
+}
+
+impl RecreateError {
+    /// Check if error is caused by lost surface.
+    pub fn is_surface_lost(&self) -> bool {
+        matches!(self, RecreateError::SurfaceLost(_))
+    }
+}
+
+impl From<rendy_core::hal::window::AcquireError> for RecreateError {
+    fn from(err: rendy_core::hal::window::AcquireError) -> Self {
+        match err {
+            rendy_core::hal::window::AcquireError::SurfaceLost(lost) => {
+                RecreateError::SurfaceLost(lost)
+            }
+            err => RecreateError::Acquire(err),
+        }
+    }
+}
+
+impl From<rendy_core::hal::window::PresentError> for RecreateError {
+    fn from(err: rendy_core::hal::window::PresentError) -> Self {
+        match err {
+            rendy_core::hal::window::PresentError::SurfaceLost(lost) => {
+                RecreateError::SurfaceLost(lost)
+            }
+            err => RecreateError::Present(err),
+        }
+    }
+}
+
+impl From<SwapchainError> for RecreateError {
+    fn from(err: SwapchainError) -> Self {
+        match err {
+            SwapchainError::Create(rendy_core::hal::window::CreationError::SurfaceLost(lost)) => {
+                RecreateError::SurfaceLost(lost)
+            }
+            err => RecreateError::Swapchain(err),
+        }
+    }
+}
+
+impl std::fmt::Display for RecreateError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecreateError::Acquire(err) => write!(fmt, "Failed to acquire image: {:?}", err),
+            RecreateError::Present(err) => write!(fmt, "Failed to present image: {:?}", err),
+            RecreateError::OutOfMemory(err) => {
+                write!(fmt, "Failed to recreate swapchain: {:?}", err)
+            }
+            RecreateError::Swapchain(err) => write!(fmt, "Failed to recreate swapchain: {}", err),
+            RecreateError::SurfaceLost(err) => {
+                write!(fmt, "Surface is lost and must be recreated: {}", err)
+            }
+            RecreateError::Surface(err) => write!(fmt, "Failed to recreate surface: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for RecreateError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RecreateError::Acquire(err) => Some(err),
+            RecreateError::Present(err) => Some(err),
+            RecreateError::OutOfMemory(err) => Some(err),
+            RecreateError::Swapchain(err) => Some(err),
+            RecreateError::SurfaceLost(err) => Some(err),
+            RecreateError::Surface(err) => Some(err),
+        }
+    }
+}
+
+/// State of the swapchain after acquire or present
+/// that recreates swapchain on demand.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SwapchainStatus {
+    /// Swapchain matches the surface.
+    Optimal,
+
+    /// Swapchain doesn't match the surface exactly, but can still be used.
+    /// It is recreated on present only if surface extent changed.
+    Suboptimal,
+
+    /// Swapchain was recreated.
+    /// Resources referencing previous swapchain images must be recreated.
+    Recreated,
+}
+
+/// Tradeoff between presentation latency and throughput
+/// made by picking number of images in the swapchain.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum SwapchainMode {
+    /// Two images. Frame is displayed soon after it is rendered,
+    /// but rendering may stall waiting for an image to be released by presentation engine.
+    LowLatency,
+
+    /// Three images. Rendering can proceed while previous frames wait for presentation
+    /// at the cost of a frame of latency.
+    #[default]
+    Throughput,
+}
+
+impl SwapchainMode {
+    /// Number of swapchain images requested in this mode.
+    /// Use [`clamp_image_count`] to fit it into range supported by the surface.
+    ///
+    /// [`clamp_image_count`]: fn.clamp_image_count.html
+    pub fn image_count(&self) -> u32 {
+        match self {
+            SwapchainMode::LowLatency => 2,
+            SwapchainMode::Throughput => 3,
+        }
+    }
+}
+
+/// Fit requested number of swapchain images into range supported by the surface.
+///
+/// If surface requires more images than requested the minimum is used
+/// and a warning is logged, as the swapchain will add latency that wasn't asked for.
+///
+/// Number of images in the swapchain doesn't limit frames in flight of the graph,
+/// but rendering more frames in flight than there are images to acquire will stall.
+pub fn clamp_image_count(capabilities: &SurfaceCapabilities, image_count: u32) -> u32 {
+    let (min, max) = (
+        *capabilities.image_count.start(),
+        *capabilities.image_count.end(),
+    );
+    if image_count < min {
+        log::warn!(
+            "Surface requires at least {} swapchain images, {} requested",
+            min,
+            image_count
+        );
+        min
+    } else {
+        image_count.min(max)
+    }
+}
+
 /// Rendering target bound to window.
+///
+/// Capabilities and formats of the surface are queried once and cached.
+/// Cache is invalidated automatically when surface is recreated
+/// and when swapchain is created or recreated for it.
+/// Call [`invalidate_caps`] to force a refresh in other cases,
+/// e.g. after window was moved to another display.
+/// Cached values are valid only for the physical device they were queried with.
+///
+/// [`invalidate_caps`]: #method.invalidate_caps
 pub struct Surface<B: Backend> {
     raw: B::Surface,
     instance: InstanceId,
+    cache: std::sync::Mutex<SurfaceCache>,
+}
+
+/// Last queried capabilities and formats of the surface.
+#[derive(Debug, Default)]
+struct SurfaceCache {
+    capabilities: Option<SurfaceCapabilities>,
+    formats: Option<Option<Vec<Format>>>,
 }
 
 impl<B> std::fmt::Debug for Surface<B>
@@ -98,9 +294,26 @@ where
         Ok(Surface {
             raw,
             instance: instance.id(),
+            cache: Default::default(),
         })
     }
 
+    /// Create surface for the window from raw handle,
+    /// e.g. constructed manually for windowing library that doesn't implement `HasRawWindowHandle`.
+    ///
+    /// Fails with `InitError::UnsupportedWindowHandle`
+    /// if backend can't create surface for this kind of handle.
+    ///
+    /// # Safety
+    ///
+    /// `handle` must refer to a valid window that outlives created surface.
+    pub unsafe fn from_raw_handle(
+        instance: &Instance<B>,
+        handle: RawWindowHandle,
+    ) -> Result<Self, rendy_core::hal::window::InitError> {
+        Self::new(instance, &RawHandle::new(handle))
+    }
+
     /// Create surface from `instance`.
     ///
     /// # Safety
@@ -113,6 +326,7 @@ where
         Surface {
             raw: f(instance.raw()),
             instance: instance.id(),
+            cache: Default::default(),
         }
     }
 
@@ -121,6 +335,7 @@ where
         Surface {
             raw: surface,
             instance,
+            cache: Default::default(),
         }
     }
 }
@@ -135,13 +350,19 @@ where
     }
 
     /// Get current extent of the surface.
+    ///
+    /// Extent changes with window size, so it is always queried
+    /// and refreshes cached capabilities.
     pub unsafe fn extent(&self, physical_device: &B::PhysicalDevice) -> Option<Extent2D> {
-        self.capabilities(physical_device).current_extent
+        let capabilities = self.raw.capabilities(physical_device);
+        let extent = capabilities.current_extent;
+        self.cache().capabilities = Some(capabilities);
+        extent
     }
 
     /// Get surface ideal format.
     pub unsafe fn format(&self, physical_device: &B::PhysicalDevice) -> Format {
-        if let Some(formats) = self.raw.supported_formats(physical_device) {
+        if let Some(formats) = self.supported_formats(physical_device) {
             *formats
                 .iter()
                 .max_by_key(|format| {
@@ -159,7 +380,9 @@ where
         }
     }
 
-    /// Get formats supported by surface
+    /// Get formats supported by surface.
+    ///
+    /// Returns cached formats if they were queried before.
     ///
     /// ## Safety
     ///
@@ -168,16 +391,48 @@ where
         &self,
         physical_device: &B::PhysicalDevice,
     ) -> Option<Vec<Format>> {
-        self.raw.supported_formats(physical_device)
+        self.cache()
+            .formats
+            .get_or_insert_with(|| self.raw.supported_formats(physical_device))
+            .clone()
     }
 
-    /// Get formats supported by surface
+    /// Get capabilities of the surface.
+    ///
+    /// Returns cached capabilities if they were queried before.
+    /// `current_extent` of cached capabilities may be stale after window resize,
+    /// use [`extent`] to get actual one.
+    ///
+    /// Surface transform (display rotation on mobile devices) is not reported.
+    /// gfx-hal 0.5 doesn't expose it and swapchains are created with identity pre-transform,
+    /// so on rotated displays the compositor rotates presented images at additional cost.
     ///
     /// ## Safety
     ///
     /// - `physical_device` must be created from same `Instance` as the `Surface`
+    ///
+    /// [`extent`]: #method.extent
     pub unsafe fn capabilities(&self, physical_device: &B::PhysicalDevice) -> SurfaceCapabilities {
-        self.raw.capabilities(physical_device)
+        self.cache()
+            .capabilities
+            .get_or_insert_with(|| self.raw.capabilities(physical_device))
+            .clone()
+    }
+
+    /// Drop cached capabilities and formats.
+    /// They will be queried again on next access.
+    ///
+    /// Must be called when surface properties may have changed
+    /// in a way that isn't tracked by the surface itself,
+    /// e.g. window was moved to another display or display settings were changed.
+    pub fn invalidate_caps(&self) {
+        *self.cache() = SurfaceCache::default();
+    }
+
+    fn cache(&self) -> std::sync::MutexGuard<'_, SurfaceCache> {
+        self.cache
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
     }
 
     /// Cast surface into render target.
@@ -218,6 +473,23 @@ where
         })
     }
 
+    /// Replace lost surface with new one created for the window.
+    ///
+    /// # Safety
+    ///
+    /// Surface must be not in use and must have no swapchain.
+    pub unsafe fn recreate(
+        &mut self,
+        instance: &Instance<B>,
+        handle: &impl HasRawWindowHandle,
+    ) -> Result<(), rendy_core::hal::window::InitError> {
+        self.assert_instance_owner(instance);
+        let raw = instance.create_surface(handle)?;
+        instance.destroy_surface(std::mem::replace(&mut self.raw, raw));
+        self.invalidate_caps();
+        Ok(())
+    }
+
     /// Dispose of Surface.
     ///
     /// # Safety
@@ -238,6 +510,8 @@ unsafe fn create_swapchain<B: Backend>(
     present_mode: rendy_core::hal::window::PresentMode,
     usage: rendy_core::hal::image::Usage,
 ) -> Result<(B::Swapchain, Vec<Image<B>>, Extent2D), SwapchainError> {
+    // Swapchain is configured with fresh properties of the surface.
+    surface.invalidate_caps();
     let capabilities = surface.capabilities(physical_device);
     let format = surface.format(physical_device);
 
@@ -275,12 +549,17 @@ unsafe fn create_swapchain<B: Backend>(
         image_count
     );
 
-    assert!(
-        capabilities.usage.contains(usage),
-        "Surface supports {:?}, but {:?} was requested",
-        capabilities.usage,
-        usage
-    );
+    if !capabilities.usage.contains(usage) {
+        log::warn!(
+            "Image usage not supported. Supported: {:#?}, requested: {:#?}",
+            capabilities.usage,
+            usage
+        );
+        return Err(SwapchainError::BadUsage {
+            requested: usage,
+            supported: capabilities.usage,
+        });
+    }
 
     let extent = capabilities.current_extent.unwrap_or(suggest_extent);
 
@@ -407,6 +686,12 @@ where
     ) -> Result<(), SwapchainError> {
         self.assert_device_owner(device);
 
+        let image_count = self.destroy_swapchain(device);
+        self.create_swapchain(physical_device, device, suggest_extent, image_count)
+    }
+
+    /// Destroy swapchain and its images returning number of images.
+    unsafe fn destroy_swapchain(&mut self, device: &Device<B>) -> u32 {
         let image_count = match self.backbuffer.take() {
             Some(images) => {
                 let count = images.len();
@@ -422,12 +707,22 @@ where
             device.destroy_swapchain(s)
         }
 
+        image_count as u32
+    }
+
+    unsafe fn create_swapchain(
+        &mut self,
+        physical_device: &B::PhysicalDevice,
+        device: &Device<B>,
+        suggest_extent: Extent2D,
+        image_count: u32,
+    ) -> Result<(), SwapchainError> {
         let (swapchain, backbuffer, extent) = create_swapchain(
             &mut self.surface,
             physical_device,
             device,
             suggest_extent,
-            image_count as u32,
+            image_count,
             self.present_mode,
             self.usage,
         )?;
@@ -439,6 +734,50 @@ where
         Ok(())
     }
 
+    /// Recreate lost surface for the window and swapchain for it.
+    ///
+    /// Old swapchain is destroyed first, as window can't have two swapchains at once.
+    /// On failure target is left without swapchain and recreation can be retried.
+    ///
+    /// # Safety
+    ///
+    /// Current swapchain must be not in use.
+    pub unsafe fn recreate_surface(
+        &mut self,
+        instance: &Instance<B>,
+        physical_device: &B::PhysicalDevice,
+        device: &Device<B>,
+        handle: &impl HasRawWindowHandle,
+        suggest_extent: Extent2D,
+    ) -> Result<(), RecreateError> {
+        self.assert_device_owner(device);
+        self.surface.assert_instance_owner(instance);
+
+        let image_count = self.destroy_swapchain(device);
+        let mut surface = Surface::new(instance, handle).map_err(RecreateError::Surface)?;
+        let (swapchain, backbuffer, extent) = match create_swapchain(
+            &mut surface,
+            physical_device,
+            device,
+            suggest_extent,
+            image_count,
+            self.present_mode,
+            self.usage,
+        ) {
+            Ok(created) => created,
+            Err(err) => {
+                surface.dispose(instance);
+                return Err(err.into());
+            }
+        };
+
+        std::mem::replace(&mut self.surface, surface).dispose(instance);
+        self.swapchain = Some(swapchain);
+        self.backbuffer = Some(backbuffer);
+        self.extent = extent;
+        Ok(())
+    }
+
     /// Get swapchain impl trait.
     ///
     /// # Safety
@@ -485,6 +824,111 @@ where
             targets: std::iter::once((&*self, index)).collect(),
         })
     }
+
+    /// Acquire next image recreating swapchain when it is out of date.
+    ///
+    /// Acquisition is retried once after recreation.
+    /// `OutOfDate` error is returned if recreated swapchain is out of date as well.
+    /// Suboptimal swapchain isn't recreated as image is already acquired,
+    /// `present_or_recreate` will recreate it after image is presented if surface was resized.
+    ///
+    /// # Safety
+    ///
+    /// Swapchain images must be not in use if swapchain is recreated.
+    pub unsafe fn acquire_or_recreate(
+        &mut self,
+        physical_device: &B::PhysicalDevice,
+        device: &Device<B>,
+        suggest_extent: Extent2D,
+        signal: &B::Semaphore,
+    ) -> Result<(u32, SwapchainStatus), RecreateError> {
+        let mut status = SwapchainStatus::Optimal;
+        loop {
+            let result = match self.swapchain.as_mut() {
+                Some(swapchain) => rendy_core::hal::window::Swapchain::acquire_image(
+                    swapchain,
+                    !0,
+                    Some(signal),
+                    None,
+                ),
+                None => Err(rendy_core::hal::window::AcquireError::OutOfDate),
+            };
+
+            match result {
+                Ok((index, None)) => return Ok((index, status)),
+                Ok((index, Some(_))) if status == SwapchainStatus::Optimal => {
+                    return Ok((index, SwapchainStatus::Suboptimal))
+                }
+                Ok((index, Some(_))) => return Ok((index, status)),
+                Err(rendy_core::hal::window::AcquireError::OutOfDate)
+                    if status == SwapchainStatus::Optimal =>
+                {
+                    log::debug!("Swapchain is out of date on acquire. Recreate");
+                    self.recreate_idle(physical_device, device, suggest_extent)?;
+                    status = SwapchainStatus::Recreated;
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
+    /// Present image acquired by `acquire_or_recreate`.
+    /// Swapchain is recreated if it is out of date,
+    /// or if it is suboptimal and surface extent differs from the swapchain extent.
+    /// Recreation waits for the device to become idle,
+    /// so suboptimal swapchain of the same extent is kept and `Suboptimal` is returned.
+    ///
+    /// # Safety
+    ///
+    /// `index` must be acquired from current swapchain.
+    /// `queue` must belong to the `device`.
+    pub unsafe fn present_or_recreate(
+        &mut self,
+        physical_device: &B::PhysicalDevice,
+        device: &Device<B>,
+        suggest_extent: Extent2D,
+        queue: &mut impl rendy_core::hal::queue::CommandQueue<B>,
+        index: u32,
+        wait: Option<&B::Semaphore>,
+    ) -> Result<SwapchainStatus, RecreateError> {
+        let result = queue.present(
+            std::iter::once((
+                self.swapchain.as_ref().expect("Swapchain already disposed"),
+                index,
+            )),
+            wait,
+        );
+
+        match result {
+            Ok(None) => Ok(SwapchainStatus::Optimal),
+            Ok(Some(_)) => match self.surface.extent(physical_device) {
+                Some(extent) if extent != self.extent => {
+                    log::debug!("Swapchain is suboptimal and surface was resized. Recreate");
+                    self.recreate_idle(physical_device, device, suggest_extent)?;
+                    Ok(SwapchainStatus::Recreated)
+                }
+                _ => Ok(SwapchainStatus::Suboptimal),
+            },
+            Err(rendy_core::hal::window::PresentError::OutOfDate) => {
+                log::debug!("Swapchain is out of date on present. Recreate");
+                self.recreate_idle(physical_device, device, suggest_extent)?;
+                Ok(SwapchainStatus::Recreated)
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    // TODO: use retired swapchains once available in hal and remove the wait.
+    unsafe fn recreate_idle(
+        &mut self,
+        physical_device: &B::PhysicalDevice,
+        device: &Device<B>,
+        suggest_extent: Extent2D,
+    ) -> Result<(), RecreateError> {
+        device.wait_idle().map_err(RecreateError::OutOfMemory)?;
+        self.recreate(physical_device, device, suggest_extent)
+            .map_err(RecreateError::from)
+    }
 }
 
 /// Represents acquire frames that will be presented next.
@@ -540,3 +984,49 @@ where
         &self.targets[index].1
     }
 }
+
+#[test]
+fn test_surface_lost_errors() {
+    use rendy_core::hal::{device::SurfaceLost, window};
+
+    assert!(RecreateError::from(window::AcquireError::SurfaceLost(SurfaceLost)).is_surface_lost());
+    assert!(RecreateError::from(window::PresentError::SurfaceLost(SurfaceLost)).is_surface_lost());
+    assert!(
+        RecreateError::from(SwapchainError::Create(window::CreationError::SurfaceLost(
+            SurfaceLost
+        )))
+        .is_surface_lost()
+    );
+    assert!(!RecreateError::from(window::AcquireError::NotReady).is_surface_lost());
+    assert!(!RecreateError::from(SwapchainError::BadImageCount(0)).is_surface_lost());
+}
+
+#[test]
+fn test_clamp_image_count() {
+    use rendy_core::hal::{
+        image::Usage,
+        window::{CompositeAlphaMode, Extent2D, PresentMode, SurfaceCapabilities},
+    };
+
+    let extent = Extent2D {
+        width: 800,
+        height: 600,
+    };
+    let caps = |image_count| SurfaceCapabilities {
+        image_count,
+        current_extent: Some(extent),
+        extents: extent..=extent,
+        max_image_layers: 1,
+        usage: Usage::COLOR_ATTACHMENT,
+        present_modes: PresentMode::FIFO,
+        composite_alpha_modes: CompositeAlphaMode::OPAQUE,
+    };
+
+    let low = SwapchainMode::LowLatency.image_count();
+    let high = SwapchainMode::Throughput.image_count();
+    assert_eq!(clamp_image_count(&caps(1..=8), low), 2);
+    assert_eq!(clamp_image_count(&caps(1..=8), high), 3);
+    assert_eq!(clamp_image_count(&caps(1..=2), high), 2);
+    // Minimum required by the surface is honored.
+    assert_eq!(clamp_image_count(&caps(3..=8), low), 3);
+}